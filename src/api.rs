@@ -1,74 +1,272 @@
-use std::{fmt, env, borrow::Borrow, time::Duration, collections::HashSet, net::Ipv4Addr};
-use once_cell::sync::Lazy;
+//! The Sakura Cloud api. Resources are plain structs fetched by id or name, and changed with the builders
+//! (`ServerInfo::builder()` and so on). Every call reads `SACLOUD_ZONE`, `SACLOUD_ACCESS_TOKEN` and
+//! `SACLOUD_SECRET_TOKEN` from the environment, unless another [`ApiEndpoint`] is given with [`ApiEndpoint::scope`].
+//! The same named resources are an error unless a [`ResourceChooser`] is given with [`ResourceChooser::scope`].
+
+use std::{fmt, env, str::FromStr, borrow::Borrow, sync::Arc, time::{Duration, Instant}, net::Ipv4Addr, future::Future, io::{self, Write, IsTerminal}};
+use once_cell::sync::OnceCell;
+use base64::{Engine, prelude::BASE64_STANDARD};
 use url::Url;
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
 use serde_json::{self, Value, json};
 use reqwest::{Method, StatusCode};
 use tokio::time::sleep;
 
-pub(crate) static ZONE: Lazy<String> = Lazy::new(|| { env::var("SACLOUD_ZONE").unwrap() });
+pub mod monitor;
+pub mod billing;
 
-static ACCESS_TOKEN: Lazy<String> = Lazy::new(|| { env::var("SACLOUD_ACCESS_TOKEN").unwrap() });
-static SECRET_TOKEN: Lazy<String> = Lazy::new(|| { env::var("SACLOUD_SECRET_TOKEN").unwrap() });
-static API_BASE_URL: Lazy<Url> = Lazy::new(|| { Url::parse(format!("https://secure.sakura.ad.jp/cloud/zone/{}/api/cloud/1.1/", &*ZONE).as_str()).unwrap() });
+/// the zones to look for resources in, without the sandbox tk1v
+pub const ZONES: [&str; 4] = ["is1a", "is1b", "tk1a", "tk1b"];
 
-#[derive(Debug, Serialize)]
-pub(crate) enum Error {
+static DEFAULT_ENDPOINT: OnceCell<ApiEndpoint> = OnceCell::new();
+
+tokio::task_local! {
+    static ENDPOINT: ApiEndpoint;
+}
+
+/// where and how the api is called, e.g. a local server in tests
+#[derive(Debug, Clone)]
+pub struct ApiEndpoint {
+    base_url: Url,
+    system_base_url: Url,
+    access_token: String,
+    secret_token: String,
+    poll_interval: Duration,
+    client: reqwest::Client,
+}
+
+impl ApiEndpoint {
+    /// an endpoint of the base urls of the cloud api and the system api, with the tokens
+    pub fn new(base_url: Url, system_base_url: Url, access_token: impl Into<String>, secret_token: impl Into<String>) -> Self {
+        Self {
+            base_url,
+            system_base_url,
+            access_token: access_token.into(),
+            secret_token: secret_token.into(),
+            poll_interval: Duration::from_secs(2),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// the tokens in SACLOUD_ZONE, the endpoint of the calls out of any scope
+    pub fn from_env() -> Result<Self, Error> {
+        Self::for_zone(zone()?)
+    }
+
+    /// the same tokens in another zone than SACLOUD_ZONE
+    pub fn for_zone(zone: impl AsRef<str>) -> Result<Self, Error> {
+        let zone = zone.as_ref();
+        Ok(Self::new(
+            Url::parse(format!("https://secure.sakura.ad.jp/cloud/zone/{}/api/cloud/1.1/", zone).as_str()).unwrap(),
+            Url::parse(format!("https://secure.sakura.ad.jp/cloud/zone/{}/api/system/1.0/", zone).as_str()).unwrap(),
+            env_var("SACLOUD_ACCESS_TOKEN")?,
+            env_var("SACLOUD_SECRET_TOKEN")?,
+        ))
+    }
+
+    /// the interval of the wait loops
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// sets the http client of the calls, e.g. one with a timeout
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// the api calls in the future use this endpoint, except in the tasks spawned from it
+    pub async fn scope<F: Future>(self, f: F) -> F::Output {
+        ENDPOINT.scope(self, f).await
+    }
+
+    /// the endpoint of the running scope, e.g. to carry it into another runtime
+    pub fn current() -> Result<Self, Error> {
+        match ENDPOINT.try_with(|endpoint| endpoint.clone()) {
+            Ok(endpoint) => Ok(endpoint),
+            Err(_) => DEFAULT_ENDPOINT.get_or_try_init(Self::from_env).cloned(),
+        }
+    }
+}
+
+/// the zone of the environments, the other zones are only called through `ApiEndpoint::for_zone`
+pub fn zone() -> Result<String, Error> {
+    env_var("SACLOUD_ZONE")
+}
+
+fn env_var(name: &str) -> Result<String, Error> {
+    env::var(name).map_err(|_| Error::EnvVarNotSet(name.to_string()))
+}
+
+/// the request bodies and the responses are left to the json error format
+#[derive(Debug, Serialize, thiserror::Error)]
+pub enum Error {
+    /// the environment variable of the tokens or the zone isn't set, with its name
+    #[error("{0} is not set")]
+    EnvVarNotSet(String),
+    /// no resource of the name or the tags, with the kind
+    #[error("{0} not found")]
     ResourceNotFound(String),
-    TooManyResources(String, usize),
+    /// several resources of the name and no chooser to pick one, with the kind and their ids
+    #[error("{0} {1:?} found for one, pass --id to choose")]
+    TooManyResources(String, Vec<String>),
+    /// the power api returned no status
+    #[error("the instance status is unknown")]
     ResourceUnknownInstanceStatus,
+    /// the id isn't digits
+    #[error("invalid resource id {0:?}, must be digits like 113000000000")]
+    InvalidResourceId(String),
+    /// the info couldn't be turned into the request json, with the kind and the serde error
+    #[error("couldn't serialize the {0:?}: {1}")]
     ResourceSerializationFailed(ResourceKind, String),
+    /// the response json isn't of the resource, with the kind and the serde error
+    #[error("couldn't deserialize the {0:?}: {1}")]
     ResourceDeserializationFailed(ResourceKind, String),
+    /// the response has no object under the json name of the kind, with the path and the request body
+    #[error("{0}: the response has no resource object")]
     ResourceApiInvalidResourceObject(String, Option<Value>),
+    /// the Success or is_ok field is neither a bool nor a string of Success, with the field, the response, the path and the request body
+    #[error("{2}: unexpected status {0} of {1}")]
     ResourceApiInvalidStatusDataType(Value, Value, String, Option<Value>),
+    /// the Success or is_ok field is false or not Accepted, with the response, the path and the request body
+    #[error("{1}: the api returned {0} false")]
     ResourceApiInvalidStatusFalse(Value, String, Option<Value>),
+    /// the polled resource has no Availability or Instance.Status, with the path and the last fetched resource
+    #[error("{0}: no status in the resource while waiting")]
     ResourceApiWaitStatusNotFound(String, Value),
+    /// the polled resource became failed, with the path and the last fetched resource
+    #[error("{0}: the resource failed while waiting")]
     ResourceApiWaitStatusFailed(String, Value),
+    /// the polled resource is in a status not known here, with the status, the path and the last fetched resource
+    #[error("{1}: unknown status {0} while waiting")]
     ResourceApiWaitStatusUnknown(String, String, Value),
+    /// the polled resource didn't get there in time, with the path, the seconds waited and the last fetched resource
+    #[error("{0}: still waiting after {1}s")]
+    ResourceApiWaitTimedOut(String, u64, Value),
+    /// the request couldn't be sent or its body read, with the reqwest error, the path and the request body
+    #[error("{1}: request failed: {0}")]
     RequestFailed(String, String, Option<Value>),
+    /// the response body isn't json, with the json error, the path and the request body
+    #[error("{1}: invalid response json: {0}")]
     InvalidResponseJson(String, String, Option<Value>),
+    /// 400 from the api, with the path and the request body
+    #[error("{0}: 400 bad request")]
     ApiBadRequest(String, Option<Value>),
+    /// 401 from the api, the tokens are wrong, with the path and the request body
+    #[error("{0}: 401 unauthorized, check SACLOUD_ACCESS_TOKEN and SACLOUD_SECRET_TOKEN")]
     ApiUnauthorized(String, Option<Value>),
+    /// 403 from the api, the tokens lack the permission, with the path and the request body
+    #[error("{0}: 403 forbidden")]
     ApiForbidden(String, Option<Value>),
+    /// 404 from the api, with the path and the request body
+    #[error("{0}: 404 not found")]
     ApiNotFound(String, Option<Value>),
+    /// 405 from the api, with the path and the request body
+    #[error("{0}: 405 method not allowed")]
     ApiMethodNotAllowed(String, Option<Value>),
+    /// 406 from the api, with the path and the request body
+    #[error("{0}: 406 not acceptable")]
     ApiNotAcceptable(String, Option<Value>),
+    /// 408 from the api, with the path and the request body
+    #[error("{0}: 408 request timeout")]
     ApiRequestTimeout(String, Option<Value>),
+    /// 409 from the api, e.g. a power operation to the current state, with the path and the request body
+    #[error("{0}: 409 conflict")]
     ApiConflict(String, Option<Value>),
+    /// 411 from the api, with the path and the request body
+    #[error("{0}: 411 length required")]
     ApiLengthRequired(String, Option<Value>),
+    /// 413 from the api, with the path and the request body
+    #[error("{0}: 413 payload too large")]
     ApiPayloadTooLarge(String, Option<Value>),
+    /// 415 from the api, with the path and the request body
+    #[error("{0}: 415 unsupported media type")]
     ApiUnsupportedMediaType(String, Option<Value>),
+    /// 500 from the api, with the path and the request body
+    #[error("{0}: 500 internal server error")]
     ApiInternalServerError(String, Option<Value>),
+    /// 503 from the api, e.g. under maintenance, with the path and the request body
+    #[error("{0}: 503 service unavailable")]
     ApiServiceUnavailable(String, Option<Value>),
+    /// a status code not handled above, with the code, the path and the request body
+    #[error("{1}: unknown status code {0}")]
     ApiUnknownStatusCode(u16, String, Option<Value>),
+    /// the Total of the search response isn't a number, with the path and the query
+    #[error("{0}: invalid total count in the search response")]
     SearchApiInvalidTotalCount(String, Value),
+    /// the From of the search response is missing or not the one requested, with it, the path and the query
+    #[error("{1}: invalid index from {0:?} in the search response")]
     SearchApiInvalidIndexFrom(Option<u64>, String, Value),
+    /// the Count of the search response isn't a number, with the path and the query
+    #[error("{0}: invalid resource count in the search response")]
     SearchApiInvalidResourceCount(String, Value),
+    /// the resources of the search response aren't an array, with them, the path and the query
+    #[error("{1}: invalid resource array {0} in the search response")]
     SearchApiInvalidResourceArray(Value, String, Value),
+    /// the appliance json has no Interfaces
+    #[error("the appliance has no interface info")]
     ApplianceDoesntHaveInterfaceInfo,
+    /// an interface json of the appliance has no Switch
+    #[error("the appliance interface has no connected switch info")]
     ApplianceInterfaceDoesntHaveConnectedSwitchInfo,
+    /// the switch json of an appliance interface has no Scope
+    #[error("the connected switch of the appliance interface has no scope info")]
     ApplianceInterfaceConnectedSwitchDoesntHaveScopeInfo,
+    /// the interface on the shared switch has no IPAddress
+    #[error("the shared scope interface of the appliance has no ip address")]
     ApplianceInterfaceHasSharedScopeButDoesntHaveIpAddress,
+    /// no interface of the appliance is on the shared switch
+    #[error("the appliance has no shared scope interface")]
     ApplianceHasNoSharedScopeInterface,
+    /// more than one interface of the appliance is on the shared switch
+    #[error("the appliance has multiple shared scope interfaces")]
     ApplianceHasMultipleSharedScopeInterfaces,
 }
 
+/// the kinds of the resources, which tell the paths and the json names in the api
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
-pub(crate) enum ResourceKind {
+pub enum ResourceKind {
+    /// the servers
     Server,
+    /// the disks
     Disk,
+    /// the ssh public keys
     SshPublicKey,
+    /// the switches
     Switch,
+    /// the appliances, e.g. the vpc routers
     Appliance,
+    /// the archives, e.g. the public os images
     Archive,
+    /// the server plans
     ServerPlan,
     // DiskPlan, commented out because it's not used
+    /// the notes, e.g. the startup scripts
     Note,
+    /// the power status of a server or an appliance
     Instance,
+    /// the monitor samples
+    Monitor,
+    /// the bills in the system api
+    Bill,
+    /// the prices of the service classes
+    ServiceClassPrice,
+    /// the common service items, e.g. the simple monitors and the dns zones
+    CommonServiceItem,
+    /// the iso images
+    Cdrom,
+    /// the icons
+    Icon,
+    /// the private hosts
+    PrivateHost,
+    /// the interfaces of the servers
+    Interface,
 }
 
 impl ResourceKind {
-    pub(crate) fn single_name(&self) -> &'static str {
+    /// the name of a resource in the request and response json, e.g. "Server"
+    pub fn single_name(&self) -> &'static str {
         match self {
             Self::Server => "Server",
             Self::Disk => "Disk",
@@ -80,10 +278,19 @@ impl ResourceKind {
             // Self::DiskPlan => "DiskPlan",
             Self::Note => "Note",
             Self::Instance => "Instance",
+            Self::Monitor => "Data",
+            Self::Bill => "Bill",
+            Self::ServiceClassPrice => "ServiceClass",
+            Self::CommonServiceItem => "CommonServiceItem",
+            Self::Cdrom => "CDROM",
+            Self::Icon => "Icon",
+            Self::PrivateHost => "PrivateHost",
+            Self::Interface => "Interface",
         }
     }
 
-    pub(crate) fn prural_name(&self) -> &'static str {
+    /// the name of the resources in the search responses, e.g. "Servers"
+    pub fn prural_name(&self) -> &'static str {
         match self {
             Self::Server => "Servers",
             Self::Disk => "Disks",
@@ -95,10 +302,19 @@ impl ResourceKind {
             // Self::DiskPlan => "DiskPlans",
             Self::Note => "Notes",
             Self::Instance => "Instances",
+            Self::Monitor => "Data",
+            Self::Bill => "Bills",
+            Self::ServiceClassPrice => "ServiceClasses",
+            Self::CommonServiceItem => "CommonServiceItems",
+            Self::Cdrom => "CDROMs",
+            Self::Icon => "Icons",
+            Self::PrivateHost => "PrivateHosts",
+            Self::Interface => "Interfaces",
         }
     }
 
-    pub(crate) fn path(&self) -> &'static str {
+    /// the path of the resources of the kind, it panics for the kinds without one
+    pub fn path(&self) -> &'static str {
         match self {
             Self::Server => "server",
             Self::Disk => "disk",
@@ -110,52 +326,73 @@ impl ResourceKind {
             // Self::DiskPlan => "product/disk",
             Self::Note => "note",
             Self::Instance => panic!("ResourceKind::Instance has no root path"),
+            Self::Monitor => panic!("ResourceKind::Monitor has no root path"),
+            Self::Bill => panic!("ResourceKind::Bill is in the system api"),
+            Self::ServiceClassPrice => "public/price",
+            Self::CommonServiceItem => "commonserviceitem",
+            Self::Cdrom => "cdrom",
+            Self::Icon => "icon",
+            Self::PrivateHost => "privatehost",
+            Self::Interface => "interface",
         }
     }
 
-    pub(crate) async fn search_by_name(&self, name: impl AsRef<str>) -> Result<Option<Value>, Error> {
+    /// searches the resource of the kind by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn search_by_name(&self, name: impl AsRef<str>, scope: ResourceScope) -> Result<Option<Value>, Error> {
         let name = name.as_ref();
-        let path = self.path();
-        let resource_name = self.prural_name();
-        let filter = json!({ "Name": [ name ] });
-        search_single_resource(path, filter, resource_name).await
+        let query = SearchQuery::new().name(name);
+        search_single_resource(self.path(), &query, self.prural_name(), Some(name), scope).await
     }
 
-    pub(crate) async fn search_one_by_tags(&self, tags: Vec<&str>) -> Result<Option<Value>, Error> {
-        let path = self.path();
-        let resource_name = self.prural_name();
-        let tags = tags.iter().map(|s| Value::from(s.to_string())).collect::<Vec<_>>();
-        let filter = json!({ "Tags": tags });
-        search_single_resource(path, filter, resource_name).await
+    /// searches all the resources of the kind whose names contain the name
+    pub async fn search_all_by_name(&self, name: impl AsRef<str>, scope: ResourceScope) -> Result<Vec<Value>, Error> {
+        let resource_values = self.search_all(&SearchQuery::new().name(name)).await?;
+        Ok(resource_values.into_iter().filter(|resource_value| scope.matches(resource_value)).collect())
+    }
+
+    /// searches the resource of the kind having all the tags, none if there is none
+    pub async fn search_one_by_tags(&self, tags: Vec<&str>, scope: ResourceScope) -> Result<Option<Value>, Error> {
+        let query = SearchQuery::new().tags(tags);
+        search_single_resource(self.path(), &query, self.prural_name(), None, scope).await
     }
 
-    pub(crate) async fn get(&self, resource_id: impl AsRef<str>) -> Result<Value, Error> {
+    /// fetches the resource of the kind by id
+    pub async fn get(&self, resource_id: impl AsRef<str>) -> Result<Value, Error> {
         let resource_id = resource_id.as_ref();
         let path = format!("{}/{}", self.path(), resource_id);
         let resource_name = self.single_name();
         fetch(path, resource_name).await
     }
 
-    pub(crate) async fn create(&self, resource_value: Value) -> Result<Value, Error> {
+    /// creates the resource of the kind
+    pub async fn create(&self, resource_value: Value) -> Result<Value, Error> {
         let path = self.path();
         let resource_name = self.single_name();
         create(path, json!({ resource_name: resource_value }), resource_name).await
     }
 
-    pub(crate) async fn update(&self, resource_id: impl AsRef<str>, resource_value: Value) -> Result<(), Error> {
+    /// updates the fields of the resource given in the json
+    pub async fn update(&self, resource_id: impl AsRef<str>, resource_value: Value) -> Result<(), Error> {
         let resource_id = resource_id.as_ref();
         let path = format!("{}/{}", self.path(), resource_id);
         let resource_name = self.single_name();
         update(path, Some(json!({ resource_name: resource_value }))).await
     }
 
-    pub(crate) async fn delete(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
+    /// deletes the resource of the kind
+    pub async fn delete(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
         let resource_id = resource_id.as_ref();
         let path = format!("{}/{}", self.path(), resource_id);
         delete(path, None).await
     }
 
-    pub(crate) async fn instance_status(&self, resource_id: impl AsRef<str>) -> Result<InstanceStatus, Error> {
+    /// the other fields are left as they are
+    pub async fn rename(&self, resource_id: impl AsRef<str>, name: impl AsRef<str>) -> Result<(), Error> {
+        self.update(resource_id, json!({ "Name": name.as_ref() })).await
+    }
+
+    /// the power status of the server or the appliance
+    pub async fn power_status(&self, resource_id: impl AsRef<str>) -> Result<InstanceStatus, Error> {
         let resource_id = resource_id.as_ref();
         let path = format!("{}/{}/power", self.path(), resource_id);
         let resource_name = ResourceKind::Instance.single_name();
@@ -168,55 +405,128 @@ impl ResourceKind {
         }
     }
 
-    /*
-    pub(crate) async fn search_all(&self) -> Result<Vec<Value>, Error> {
-        let path = self.path();
-        let resource_name = self.prural_name();
+    /// searches the resources of the kind through all the pages
+    pub async fn search_all(&self, query: &SearchQuery) -> Result<Vec<Value>, Error> {
+        search(self.path(), self.prural_name(), query).await
+    }
 
-        search(path, resource_name, None, None, None, 50).await
+    /// searches the resources of the kind as the summaries for a list
+    pub async fn search_summaries(&self, query: &SearchQuery) -> Result<Vec<ResourceSummary>, Error> {
+        let resource_values = self.search_all(query).await?;
+        resource_values.into_iter().map(|resource_value| ResourceSummary::from_value(*self, resource_value)).collect()
     }
-    */
 
-    pub(crate) async fn up_resource(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
+    /// the power operations succeed if the resource is already in the state, e.g. booted by someone else in between
+    pub async fn up_resource(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
         let resource_id = resource_id.as_ref();
-        update(format!("{}/{}/power", self.path(), resource_id), None).await
+        let result = update(format!("{}/{}/power", self.path(), resource_id), None).await;
+        self.tolerate_power_conflict(resource_id, result, |status| status == InstanceStatus::Up).await
     }
 
-    pub(crate) async fn down_resource(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
+    /// shuts the resource down without a body, see `shutdown_resource` to choose the force
+    pub async fn down_resource(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
         let resource_id = resource_id.as_ref();
-        delete(format!("{}/{}/power", self.path(), resource_id), None).await
+        let result = delete(format!("{}/{}/power", self.path(), resource_id), None).await;
+        self.tolerate_power_conflict(resource_id, result, |status| status != InstanceStatus::Up).await
     }
 
-    pub(crate) async fn wait_available(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
+    /// force: false sends the acpi shutdown, the os may ignore it
+    pub async fn shutdown_resource(&self, resource_id: impl AsRef<str>, force: bool) -> Result<(), Error> {
         let resource_id = resource_id.as_ref();
-        let path = format!("{}/{}", self.path(), resource_id);
-        wait_resource_available(&path, self.single_name()).await
+        let result = delete(format!("{}/{}/power", self.path(), resource_id), Some(json!({ "Force": force }))).await;
+        self.tolerate_power_conflict(resource_id, result, |status| status != InstanceStatus::Up).await
+    }
+
+    // 409 is returned for the operation to the current state, which is regarded as done
+    async fn tolerate_power_conflict(&self, resource_id: &str, result: Result<(), Error>, reached: impl Fn(InstanceStatus) -> bool) -> Result<(), Error> {
+        match result {
+            Err(Error::ApiConflict(path, body)) => {
+                let status = self.power_status(resource_id).await?;
+                if !reached(status) {
+                    return Err(Error::ApiConflict(path, body));
+                }
+                log::debug!("[POWER] {} conflicted, already {}", path, status);
+                Ok(())
+            },
+            result => result,
+        }
+    }
+
+    /// waits until the resource is available, reporting the progress on stderr
+    pub async fn wait_available(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
+        wait_for(*self, resource_id, check_available, None).await.map(|_: WaitStatuses| ())
+    }
+
+    /// same as wait_available but hands each poll to on_progress instead of reporting it on stderr
+    pub async fn wait_available_with_progress(&self, resource_id: impl AsRef<str>, on_progress: impl FnMut(&WaitProgress)) -> Result<(), Error> {
+        let path = format!("{}/{}", self.path(), resource_id.as_ref());
+        wait_resource(&path, *self, check_available, None, on_progress).await.map(|_: WaitStatuses| ())
+    }
+
+    /// waits until the resource is up
+    pub async fn wait_up(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
+        wait_for(*self, resource_id, check_up, None).await.map(|_: WaitStatuses| ())
     }
 
-    pub(crate) async fn wait_up(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
+    /// waits until the resource is down
+    pub async fn wait_down(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
+        wait_for(*self, resource_id, check_down, None).await.map(|_: WaitStatuses| ())
+    }
+
+    /// waits until the resource is not found any more
+    pub async fn wait_delete(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
         let resource_id = resource_id.as_ref();
         let path = format!("{}/{}", self.path(), resource_id);
-        wait_resource_up(&path, self.single_name()).await
+        fetch_until_not_found(&path).await
     }
 
-    pub(crate) async fn wait_down(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
+    /// boots and waits unless already up, true if booted here
+    /// a conflict is regarded as the boot by someone else, and the wait tells the result
+    pub async fn ensure_up(&self, resource_id: impl AsRef<str>) -> Result<bool, Error> {
         let resource_id = resource_id.as_ref();
-        let path = format!("{}/{}", self.path(), resource_id);
-        wait_resource_down(&path, self.single_name()).await
+        match self.power_status(resource_id).await? {
+            InstanceStatus::Up => return Ok(false),
+            // the last shutdown must finish before the boot
+            InstanceStatus::Cleaning => self.wait_down(resource_id).await?,
+            InstanceStatus::Down => {},
+        }
+        match self.up_resource(resource_id).await {
+            Ok(()) => {},
+            Err(Error::ApiConflict(path, _)) => log::debug!("[POWER] {} conflicted, waiting for the other boot", path),
+            Err(e) => return Err(e),
+        }
+        self.wait_up(resource_id).await?;
+        Ok(true)
     }
 
-    pub(crate) async fn wait_delete(&self, resource_id: impl AsRef<str>) -> Result<(), Error> {
+    /// shuts down and waits unless already down, true if shut down here
+    pub async fn ensure_down(&self, resource_id: impl AsRef<str>, force: bool) -> Result<bool, Error> {
         let resource_id = resource_id.as_ref();
-        let path = format!("{}/{}", self.path(), resource_id);
-        fetch_until_not_found(&path).await
+        match self.power_status(resource_id).await? {
+            InstanceStatus::Down => return Ok(false),
+            InstanceStatus::Cleaning => {
+                self.wait_down(resource_id).await?;
+                return Ok(false);
+            },
+            InstanceStatus::Up => {},
+        }
+        match self.shutdown_resource(resource_id, force).await {
+            Ok(()) => {},
+            Err(Error::ApiConflict(path, _)) => log::debug!("[POWER] {} conflicted, waiting for the other shutdown", path),
+            Err(e) => return Err(e),
+        }
+        self.wait_down(resource_id).await?;
+        Ok(true)
     }
 }
 
-// string id and integer id are both is OK in SakuraCloud API
+/// string id and integer id are both is OK in SakuraCloud API
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
-pub(crate) enum ResourceId {
+pub enum ResourceId {
+    /// an id in a string, as the most of the api returns
     String(String),
+    /// an id in a number
     Integer(u64),
 }
 
@@ -247,10 +557,105 @@ impl From<u64> for ResourceId {
     }
 }
 
+// for the ids given by hand, the ids of the resources are all digits, kept as a string as the api returns
+impl FromStr for ResourceId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+            return Err(Error::InvalidResourceId(s.to_string()));
+        }
+        Ok(Self::String(s.to_string()))
+    }
+}
+
+/// common fields to show any kind of resources in a list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceSummary {
+    #[serde(skip_deserializing)]
+    kind: Option<ResourceKind>,
+
+    #[serde(rename = "ID")]
+    id: ResourceId,
+
+    #[serde(rename = "Name", default)]
+    name: Option<String>,
+
+    #[serde(rename = "Tags", default)]
+    tags: Vec<String>,
+
+    #[serde(rename = "Scope", default)]
+    scope: Option<String>,
+
+    #[serde(rename = "Availability", default)]
+    availability: Option<String>,
+
+    #[serde(rename = "Instance", default)]
+    instance: Option<Value>,
+
+    #[serde(rename = "ServiceClass", default)]
+    service_class: Option<String>,
+}
+
+impl ResourceSummary {
+    /// the resource summary from the json of the api
+    pub fn from_value(kind: ResourceKind, value: Value) -> Result<Self, Error> {
+        let mut summary: Self = serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(kind, e.to_string()))?;
+        summary.kind = Some(kind);
+        Ok(summary)
+    }
+
+    /// the kind of the resource summary
+    pub fn kind(&self) -> Option<ResourceKind> {
+        self.kind
+    }
+
+    /// the id of the resource summary
+    pub fn id(&self) -> &ResourceId {
+        &self.id
+    }
+
+    /// the name of the resource summary
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// the tags of the resource summary
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// shared resources (e.g. public archives) have "shared" scope, scope less resources are regarded as user's
+    pub fn is_user_scope(&self) -> bool {
+        ResourceScope::User.matches_scope(self.scope.as_deref())
+    }
+
+    /// the service class of the resource summary
+    pub fn service_class(&self) -> Option<&str> {
+        self.service_class.as_deref()
+    }
+
+    /// instance status for server like resources, availability for the others
+    pub fn status(&self) -> Option<&str> {
+        self.instance_status().or(self.availability())
+    }
+
+    /// the instance status of the resource summary
+    pub fn instance_status(&self) -> Option<&str> {
+        self.instance.as_ref().and_then(|instance| instance["Status"].as_str())
+    }
+
+    /// the availability of the resource summary
+    pub fn availability(&self) -> Option<&str> {
+        self.availability.as_deref()
+    }
+}
+
 // Archive
 
+/// the id of an archive
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ArchiveId(pub ResourceId);
+pub struct ArchiveId(pub ResourceId);
 
 impl fmt::Display for ArchiveId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -264,47 +669,243 @@ impl From<String> for ArchiveId {
     }
 }
 
+impl FromStr for ArchiveId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
+/// an archive referred to by id
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ArchiveRef {
+pub struct ArchiveRef {
     #[serde(rename = "ID")]
     id: ArchiveId,
 }
 
+/// an archive, an os image or a backup of a disk
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct Archive {
+pub struct Archive {
     #[serde(rename = "ID")]
     id: ArchiveId,
+
+    #[serde(rename = "Availability", skip_serializing_if = "Option::is_none")]
+    availability: Option<String>,
+
+    // e.g. 2024-01-01T00:00:00+09:00
+    #[serde(rename = "CreatedAt", skip_serializing_if = "Option::is_none")]
+    created_at: Option<String>,
+
+    #[serde(flatten)]
+    info: ArchiveInfo,
 }
 
 impl Archive {
-    pub(crate) async fn latest_public_ubuntu() -> Result<Archive, Error> {
-        let resource_value = ResourceKind::Archive.search_one_by_tags(vec!["ubuntu-22.04-latest"]).await?;
+    /// fetches the archive found by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::Archive.search_by_name(name, ResourceScope::User).await?;
+        resource_value.map(Self::from_value).transpose()
+    }
+
+    /// searches the archives whose names contain the name
+    pub async fn search_by_name(name: impl AsRef<str>) -> Result<Vec<Self>, Error> {
+        let resource_values = ResourceKind::Archive.search_all_by_name(name, ResourceScope::User).await?;
+        resource_values.into_iter().map(Self::from_value).collect()
+    }
+
+    /// a snapshot of the disk if the info has the source disk, otherwise blank and the ftp server is opened to upload the image
+    pub async fn create(info: ArchiveInfo) -> Result<(Archive, Option<FtpServer>), Error> {
+        let kind = ResourceKind::Archive;
+        let mut info_value = info.to_value()?;
+        tag_managed(&mut info_value);
+        let mut res_value = request_api_for_resource(Method::POST, kind.path(), None, Some(json!({ kind.single_name(): info_value }))).await?;
+        let archive = Archive::from_value(res_value[kind.single_name()].take())?;
+        let ftp_server = match res_value["FTPServer"].take() {
+            Value::Null => None,
+            ftp_server => Some(FtpServer::from_value(ftp_server)?),
+        };
+        Ok((archive, ftp_server))
+    }
+
+    /// the image is downloadable while it's open, close it to make the archive available again
+    pub async fn open_ftp(archive_id: impl Borrow<ArchiveId>) -> Result<FtpServer, Error> {
+        let archive_id = archive_id.borrow();
+        let path = format!("{}/{}/ftp", ResourceKind::Archive.path(), archive_id);
+        let res_value = request_api_for_resource(Method::PUT, path, Some("FTPServer"), Some(json!({ "ChangePassword": false }))).await?;
+        FtpServer::from_value(res_value)
+    }
+
+    /// closes the ftp server of the archive
+    pub async fn close_ftp(archive_id: impl Borrow<ArchiveId>) -> Result<(), Error> {
+        let archive_id = archive_id.borrow();
+        delete(format!("{}/{}/ftp", ResourceKind::Archive.path(), archive_id), None).await
+    }
+
+    /// waits until the archive is available
+    pub async fn wait_available(archive_id: impl Borrow<ArchiveId>) -> Result<(), Error> {
+        let archive_id = archive_id.borrow();
+        ResourceKind::Archive.wait_available(archive_id.to_string()).await
+    }
+
+    /// deletes the archive
+    pub async fn delete(archive_id: impl Borrow<ArchiveId>) -> Result<(), Error> {
+        let archive_id = archive_id.borrow();
+        ResourceKind::Archive.delete(archive_id.to_string()).await
+    }
+
+    /// waits until the archive is deleted
+    pub async fn wait_delete(archive_id: impl Borrow<ArchiveId>) -> Result<(), Error> {
+        let archive_id = archive_id.borrow();
+        ResourceKind::Archive.wait_delete(archive_id.to_string()).await
+    }
+
+    /// the latest public ubuntu 22.04 archive
+    pub async fn latest_public_ubuntu() -> Result<Archive, Error> {
+        let resource_value = ResourceKind::Archive.search_one_by_tags(vec!["ubuntu-22.04-latest"], ResourceScope::Shared).await?;
         let Some(resource_value) = resource_value else {
             return Err(Error::ResourceNotFound("Archive".to_string()));
         };
         Archive::from_value(resource_value)
     }
 
-    pub(crate) fn from_value(value: Value) -> Result<Self, Error> {
+    /// the archive from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
         serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Archive, e.to_string()))
     }
 
     /* commented out because it's not used
-    pub(crate) fn kind() -> ResourceKind {
+    pub fn kind() -> ResourceKind {
         ResourceKind::Archive
     }
     */
 
-    pub(crate) fn id(&self) -> &ArchiveId {
+    /// the id of the archive
+    pub fn id(&self) -> &ArchiveId {
         &self.id
     }
+
+    /// the name of the archive
+    pub fn name(&self) -> Option<&str> {
+        self.info.name.as_deref()
+    }
+
+    /// when the archive was created, as the api returns it
+    pub fn created_at(&self) -> Option<&str> {
+        self.created_at.as_deref()
+    }
+
+    /// the size in MB of the archive
+    pub fn size_mb(&self) -> Option<u64> {
+        self.info.size_mb
+    }
+
+    /// the availability of the archive
+    pub fn availability(&self) -> Option<Availability> {
+        self.availability.as_deref().and_then(|availability| availability.parse().ok())
+    }
+
+    /// whether the archive is available
+    pub fn is_available(&self) -> bool {
+        self.availability() == Some(Availability::Available)
+    }
+}
+
+/// the fields of an archive to create
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveInfo {
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    #[serde(rename = "Description", skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    #[serde(rename = "Tags", skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+
+    // only for a blank archive, the snapshot has the size of the disk
+    #[serde(rename = "SizeMB", skip_serializing_if = "Option::is_none")]
+    size_mb: Option<u64>,
+
+    #[serde(rename = "SourceDisk", skip_serializing_if = "Option::is_none")]
+    source_disk: Option<DiskRef>,
+}
+
+impl ArchiveInfo {
+    /// a builder of the archive info
+    pub fn builder() -> ArchiveInfoBuilder {
+        ArchiveInfoBuilder::new()
+    }
+
+    /// the json of the archive info as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
+        serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::Archive, e.to_string()))
+    }
+}
+
+/// a builder of [`ArchiveInfo`]
+#[derive(Debug)]
+pub struct ArchiveInfoBuilder {
+    name: Option<String>,
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+    size_mb: Option<u64>,
+    source_disk: Option<DiskRef>,
+}
+
+impl ArchiveInfoBuilder {
+    fn new() -> Self {
+        Self {
+            name: None,
+            description: None,
+            tags: None,
+            size_mb: None,
+            source_disk: None,
+        }
+    }
+
+    /// sets the name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// sets the description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// sets the size in MB
+    pub fn size_mb(mut self, size_mb: u64) -> Self {
+        self.size_mb = Some(size_mb);
+        self
+    }
+
+    /// sets the source disk id
+    pub fn source_disk_id(mut self, disk_id: DiskId) -> Self {
+        self.source_disk = Some(DiskRef { id: disk_id });
+        self
+    }
+
+    /// builds the archive info
+    pub fn build(self) -> ArchiveInfo {
+        ArchiveInfo {
+            name: self.name,
+            description: self.description,
+            tags: self.tags,
+            size_mb: self.size_mb,
+            source_disk: self.source_disk,
+        }
+    }
 }
 
 
 // Server
 
+/// the id of a server
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ServerId(pub ResourceId);
+pub struct ServerId(pub ResourceId);
 
 impl fmt::Display for ServerId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -318,147 +919,342 @@ impl From<String> for ServerId {
     }
 }
 
+impl FromStr for ServerId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
+/// a server referred to by id
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ServerRef {
+pub struct ServerRef {
     #[serde(rename = "ID")]
     id: ServerId,
 }
 
+/// a server
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct Server {
+pub struct Server {
     #[serde(rename = "ID")]
     id: ServerId,
 
+    #[serde(rename = "Availability", skip_serializing_if = "Option::is_none")]
+    availability: Option<String>,
+
+    // e.g. 2024-01-01T00:00:00+09:00
+    #[serde(rename = "CreatedAt", skip_serializing_if = "Option::is_none")]
+    created_at: Option<String>,
+
     #[serde(rename = "Instance", skip_serializing_if = "Option::is_none")]
     instance: Option<Instance>,
 
+    #[serde(rename = "Interfaces", skip_serializing_if = "Option::is_none")]
+    interfaces: Option<Vec<Interface>>,
+
+    #[serde(rename = "Disks", skip_serializing_if = "Option::is_none")]
+    disks: Option<Vec<DiskRef>>,
+
     #[serde(flatten)]
     info: ServerInfo,
 }
 
 impl Server {
-    pub(crate) async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
-        let resource_value = ResourceKind::Server.search_by_name(name).await?;
+    /// fetches the server by id
+    pub async fn get(server_id: impl Borrow<ServerId>) -> Result<Self, Error> {
+        let server_id = server_id.borrow();
+        let resource_value = ResourceKind::Server.get(server_id.to_string()).await?;
+        Self::from_value(resource_value)
+    }
+
+    /// fetches the server found by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::Server.search_by_name(name, ResourceScope::User).await?;
         resource_value.map(|resource_value| Self::from_value(resource_value)).transpose()
     }
 
-    pub(crate) async fn create(info: ServerInfo) -> Result<Server, Error> {
+    /// searches the servers whose names contain the name
+    pub async fn search_by_name(name: impl AsRef<str>) -> Result<Vec<Self>, Error> {
+        let resource_values = ResourceKind::Server.search_all_by_name(name, ResourceScope::User).await?;
+        resource_values.into_iter().map(Self::from_value).collect()
+    }
+
+    /// creates the server
+    pub async fn create(info: ServerInfo) -> Result<Server, Error> {
         let req_value = info.to_value()?;
         let res_value = ResourceKind::Server.create(req_value).await?;
         Server::from_value(res_value)
     }
 
-    pub(crate) async fn delete(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
+    /// deletes the server
+    pub async fn delete(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
         let server_id = server_id.borrow();
         ResourceKind::Server.delete(server_id.to_string()).await
     } 
 
-    pub(crate) async fn wait_delete(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
+    /// waits until the server is deleted
+    pub async fn wait_delete(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
         let server_id = server_id.borrow();
         ResourceKind::Server.wait_delete(server_id.to_string()).await
     }
 
-    pub(crate) async fn is_connected_to_switch(server_id: impl Borrow<ServerId>, switch_id: impl Borrow<SwitchId>) -> Result<bool, Error> {
+    /// whether the server has an interface connected to the switch
+    pub async fn is_connected_to_switch(server_id: impl Borrow<ServerId>, switch_id: impl Borrow<SwitchId>) -> Result<bool, Error> {
         let server_id = server_id.borrow();
         let switch_id = switch_id.borrow();
         let servers = Switch::connected_servers(switch_id).await?;
         Ok(servers.iter().any(|server| server.id() == server_id))
     }
 
-    pub(crate) async fn wait_available(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
+    /// waits until the server is available
+    pub async fn wait_available(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
         let server_id = server_id.borrow();
         ResourceKind::Server.wait_available(server_id.to_string()).await
     }
 
-    pub(crate) async fn instance_status(server_id: impl Borrow<ServerId>) -> Result<InstanceStatus, Error> {
+    /// the power status of the server
+    pub async fn power_status(server_id: impl Borrow<ServerId>) -> Result<InstanceStatus, Error> {
         let server_id = server_id.borrow();
-        ResourceKind::Server.instance_status(server_id.to_string()).await
+        ResourceKind::Server.power_status(server_id.to_string()).await
     }
 
-    pub(crate) async fn is_up(server_id: impl Borrow<ServerId>) -> Result<bool, Error> {
+    /// whether the server is up
+    pub async fn is_up(server_id: impl Borrow<ServerId>) -> Result<bool, Error> {
+        Ok(Self::power_status(server_id).await? == InstanceStatus::Up)
+    }
+
+    /// true if booted here
+    pub async fn ensure_up(server_id: impl Borrow<ServerId>) -> Result<bool, Error> {
         let server_id = server_id.borrow();
-        match ResourceKind::Server.instance_status(server_id.to_string()).await? {
-            InstanceStatus::Up => Ok(true),
-            _ => Ok(false),
-        }
+        ResourceKind::Server.ensure_up(server_id.to_string()).await
+    }
+
+    /// the hard stop like down, true if stopped here
+    pub async fn ensure_down(server_id: impl Borrow<ServerId>) -> Result<bool, Error> {
+        let server_id = server_id.borrow();
+        ResourceKind::Server.ensure_down(server_id.to_string(), true).await
     }
 
-    pub(crate) async fn up(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
+    /// boots the server without waiting
+    pub async fn up(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
         let server_id = server_id.borrow();
         ResourceKind::Server.up_resource(server_id.to_string()).await
     }
 
-    pub(crate) async fn wait_up(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
+    /// waits until the server is up
+    pub async fn wait_up(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
         let server_id = server_id.borrow();
         ResourceKind::Server.wait_up(server_id.to_string()).await
     }
 
-    pub(crate) async fn down(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
+    /// stops the server by force without waiting
+    pub async fn down(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
+        let server_id = server_id.borrow();
+        ResourceKind::Server.shutdown_resource(server_id.to_string(), true).await
+    }
+
+    /// sends the acpi shutdown to the server without waiting
+    pub async fn shutdown(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
         let server_id = server_id.borrow();
-        ResourceKind::Server.down_resource(server_id.to_string()).await
+        ResourceKind::Server.shutdown_resource(server_id.to_string(), false).await
     }
 
-    pub(crate) async fn wait_down(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
+    /// waits until the server is down
+    pub async fn wait_down(server_id: impl Borrow<ServerId>) -> Result<(), Error> {
         let server_id = server_id.borrow();
         ResourceKind::Server.wait_down(server_id.to_string()).await
     }
 
-    pub(crate) fn from_value(value: Value) -> Result<Self, Error> {
+    /// the server must be down, and the server id is changed by the plan change
+    /// the generation is left to the api if not given
+    pub async fn change_plan(server_id: impl Borrow<ServerId>, cpu: u32, memory_mb: u64, generation: Option<u32>, commitment: ServerPlanCommitment) -> Result<Server, Error> {
+        let server_id = server_id.borrow();
+        let path = format!("{}/{}/plan", ResourceKind::Server.path(), server_id);
+        let mut body = json!({ "CPU": cpu, "MemoryMB": memory_mb, "ServerPlanCommitment": commitment });
+        if let Some(generation) = generation {
+            body["ServerPlanGeneration"] = json!(generation);
+        }
+        let res_value = request_api_for_resource(Method::PUT, path, Some(ResourceKind::Server.single_name()), Some(body)).await?;
+        Server::from_value(res_value)
+    }
+
+    /// the vnc proxy to the console of the server
+    pub async fn vnc_proxy(server_id: impl Borrow<ServerId>) -> Result<VncProxy, Error> {
+        let server_id = server_id.borrow();
+        let res_value = request_api_for_resource(Method::GET, format!("{}/{}/vnc/proxy", ResourceKind::Server.path(), server_id), None, None).await?;
+        VncProxy::from_value(res_value)
+    }
+
+    /// sends the key combination to the console of the server
+    pub async fn send_shortcut_key(server_id: impl Borrow<ServerId>, shortcut_key: ShortcutKey) -> Result<(), Error> {
+        let server_id = server_id.borrow();
+        update(format!("{}/{}/keyboard", ResourceKind::Server.path(), server_id), Some(json!({ "Shortcut": shortcut_key }))).await
+    }
+
+    /// inserts the iso image into the server
+    pub async fn insert_cdrom(server_id: impl Borrow<ServerId>, cdrom_id: impl Borrow<CdromId>) -> Result<(), Error> {
+        let server_id = server_id.borrow();
+        let cdrom_id = cdrom_id.borrow();
+        let cdrom_ref = CdromRef { id: cdrom_id.clone() };
+        update(format!("{}/{}/cdrom", ResourceKind::Server.path(), server_id), Some(json!({ ResourceKind::Cdrom.single_name(): cdrom_ref }))).await
+    }
+
+    /// ejects the iso image from the server
+    pub async fn eject_cdrom(server_id: impl Borrow<ServerId>, cdrom_id: impl Borrow<CdromId>) -> Result<(), Error> {
+        let server_id = server_id.borrow();
+        let cdrom_id = cdrom_id.borrow();
+        let cdrom_ref = CdromRef { id: cdrom_id.clone() };
+        delete(format!("{}/{}/cdrom", ResourceKind::Server.path(), server_id), Some(json!({ ResourceKind::Cdrom.single_name(): cdrom_ref }))).await
+    }
+
+    /// the server from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
         serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Server, e.to_string()))
     }
 
     /* commented out because it's not used
-    pub(crate) fn kind() -> ResourceKind {
+    pub fn kind() -> ResourceKind {
         ResourceKind::Server
     }
     */
 
-    pub(crate) fn id(&self) -> &ServerId {
+    /// the id of the server
+    pub fn id(&self) -> &ServerId {
         &self.id
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ServerInfo {
-    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
-    name: Option<String>,
+    /// the interface ids of the server
+    pub fn interface_ids(&self) -> Vec<&InterfaceId> {
+        self.interfaces.iter().flatten().map(|interface| &interface.id).collect()
+    }
 
-    #[serde(rename = "ServerPlan", skip_serializing_if = "Option::is_none")]
-    server_plan: Option<ServerPlanRef>,
+    /// in the order of the nics, the first one is eth0
+    pub fn interfaces(&self) -> &[Interface] {
+        self.interfaces.as_deref().unwrap_or_default()
+    }
 
-    #[serde(rename = "Description", skip_serializing_if = "Option::is_none")]
-    description: Option<String>,
+    /// the ip set by the disk edit, on the interface connected to the switch
+    pub fn user_ip_address_on(&self, switch_id: impl Borrow<SwitchId>) -> Option<Ipv4Addr> {
+        let switch_id = switch_id.borrow();
+        self.interfaces().iter().find(|interface| interface.switch_id() == Some(switch_id)).and_then(|interface| interface.user_ip_address())
+    }
 
-    #[serde(rename = "HostName", skip_serializing_if = "Option::is_none")]
-    host_name: Option<String>,
+    /// (cpu, memory_mb)
+    pub fn plan_size(&self) -> Option<(u32, u64)> {
+        let server_plan = self.info.server_plan.as_ref()?;
+        Some((server_plan.cpu?, server_plan.memory_mb?))
+    }
 
-    #[serde(rename = "Tags", skip_serializing_if = "Option::is_none")]
-    tags: Option<Vec<String>>,
+    /// the plan generation of the server
+    pub fn plan_generation(&self) -> Option<u32> {
+        self.info.server_plan.as_ref()?.generation
+    }
 
-    #[serde(rename = "InterfaceDriver", skip_serializing_if = "Option::is_none")]
-    interface_driver: Option<InterfaceDriver>,
+    /// the plan commitment of the server
+    pub fn plan_commitment(&self) -> Option<ServerPlanCommitment> {
+        self.info.server_plan.as_ref()?.commitment
+    }
 
-    // XXX probably used follows only creation time
+    /// the id of the iso image inserted in the server
+    pub fn inserted_cdrom_id(&self) -> Option<&CdromId> {
+        self.instance.as_ref().and_then(|instance| instance.cdrom.as_ref()).map(|cdrom| &cdrom.id)
+    }
 
-    #[serde(rename = "ConnectedSwitches", skip_serializing_if = "Option::is_none")]
-    connected_switches: Option<Vec<ConnectedSwitch>>,
+    /// the name of the server
+    pub fn name(&self) -> Option<&str> {
+        self.info.name.as_deref()
+    }
+
+    /// the description of the server
+    pub fn description(&self) -> Option<&str> {
+        self.info.description.as_deref()
+    }
+
+    /// the host name of the server
+    pub fn host_name(&self) -> Option<&str> {
+        self.info.host_name.as_deref()
+    }
+
+    /// the tags of the server
+    pub fn tags(&self) -> &[String] {
+        self.info.tags.as_deref().unwrap_or_default()
+    }
+
+    /// when the server was created, as the api returns it
+    pub fn created_at(&self) -> Option<&str> {
+        self.created_at.as_deref()
+    }
+
+    /// the availability of the server
+    pub fn availability(&self) -> Option<Availability> {
+        self.availability.as_deref().and_then(|availability| availability.parse().ok())
+    }
+
+    /// the instance status of the server
+    pub fn instance_status(&self) -> Option<InstanceStatus> {
+        self.instance.as_ref()?.status
+    }
+
+    /// in the connection order, the first one is the boot disk
+    pub fn disk_ids(&self) -> Vec<&DiskId> {
+        self.disks.iter().flatten().map(|disk| &disk.id).collect()
+    }
+}
+
+/// the fields of a server to create
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerInfo {
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    #[serde(rename = "ServerPlan", skip_serializing_if = "Option::is_none")]
+    server_plan: Option<ServerPlanRef>,
+
+    #[serde(rename = "Description", skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    #[serde(rename = "HostName", skip_serializing_if = "Option::is_none")]
+    host_name: Option<String>,
+
+    #[serde(rename = "Tags", skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+
+    #[serde(rename = "InterfaceDriver", skip_serializing_if = "Option::is_none")]
+    interface_driver: Option<InterfaceDriver>,
+
+    #[serde(rename = "Icon", skip_serializing_if = "Option::is_none")]
+    icon: Option<IconRef>,
+
+    #[serde(rename = "PrivateHost", skip_serializing_if = "Option::is_none")]
+    private_host: Option<PrivateHostRef>,
+
+    // XXX probably used follows only creation time
+
+    #[serde(rename = "ConnectedSwitches", skip_serializing_if = "Option::is_none")]
+    connected_switches: Option<Vec<ConnectedSwitch>>,
 
     #[serde(rename = "WaitDiskMigration", skip_serializing_if = "Option::is_none")]
     wait_disk_migration: Option<bool>,
 }
 
+/// where a server interface is connected
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) enum ConnectedSwitch {
+pub enum ConnectedSwitch {
+    /// the shared switch of the internet
     Shared,
+    /// a switch of the user
     Switch(SwitchRef),
 }
 
 impl ServerInfo {
-    pub(crate) fn builder() -> ServerInfoBuilder {
+    /// a builder of the server info
+    pub fn builder() -> ServerInfoBuilder {
         ServerInfoBuilder::new()
     }
 
-    pub(crate) fn to_value(&self) -> Result<Value, Error> {
+    /// the json of the server info as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
         serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::ServerPlan, e.to_string()))
     }
 }
@@ -493,20 +1289,23 @@ impl<'de> Deserialize<'de> for ConnectedSwitch {
     }
 }
 
+/// a builder of [`ServerInfo`]
 #[derive(Debug)]
-pub(crate) struct ServerInfoBuilder {
+pub struct ServerInfoBuilder {
     name: Option<String>,
     server_plan: Option<ServerPlanRef>,
     description: Option<String>,
     host_name: Option<String>,
     tags: Option<Vec<String>>,
     interface_driver: Option<InterfaceDriver>,
+    icon: Option<IconRef>,
+    private_host: Option<PrivateHostRef>,
     connected_switches: Option<Vec<ConnectedSwitch>>,
     wait_disk_migration: Option<bool>,
 }
 
 impl ServerInfoBuilder {
-    pub(crate) fn new() -> Self {
+    fn new() -> Self {
         Self {
             name: None,
             server_plan: None,
@@ -514,54 +1313,82 @@ impl ServerInfoBuilder {
             host_name: None,
             tags: None,
             interface_driver: None,
+            icon: None,
+            private_host: None,
             connected_switches: None,
             wait_disk_migration: None,
         }
     }
 
-    pub(crate) fn name(mut self, name: impl Into<String>) -> Self {
+    /// sets the name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
         self.name = Some(name.into());
         self
     }
 
-    pub(crate) fn server_plan(mut self, server_plan_id: ServerPlanId) -> Self {
-        self.server_plan = Some(ServerPlanRef { id: server_plan_id });
+    /// sets the server plan
+    pub fn server_plan(mut self, server_plan_id: ServerPlanId) -> Self {
+        self.server_plan = Some(ServerPlanRef { id: server_plan_id, cpu: None, memory_mb: None, generation: None, commitment: None });
         self
     }
 
-    pub(crate) fn description(mut self, description: impl Into<String>) -> Self {
+    /// sets the description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
         self.description = Some(description.into());
         self
     }
 
-    pub(crate) fn host_name(mut self, host_name: impl Into<String>) -> Self {
+    /// sets the host name
+    pub fn host_name(mut self, host_name: impl Into<String>) -> Self {
         self.host_name = Some(host_name.into());
         self
     }
 
-    pub(crate) fn interface_driver(mut self, interface_driver: InterfaceDriver) -> Self {
+    /// sets the tags
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    /// sets the interface driver
+    pub fn interface_driver(mut self, interface_driver: InterfaceDriver) -> Self {
         self.interface_driver = Some(interface_driver);
         self
     }
 
-    pub(crate) fn connected_switch_ids(mut self, connected_switches: Vec<SwitchId>) -> Self {
+    /// sets the icon id
+    pub fn icon_id(mut self, icon_id: IconId) -> Self {
+        self.icon = Some(IconRef { id: icon_id });
+        self
+    }
+
+    /// sets the private host id
+    pub fn private_host_id(mut self, private_host_id: PrivateHostId) -> Self {
+        self.private_host = Some(PrivateHostRef { id: private_host_id });
+        self
+    }
+
+    /// sets the connected switch ids
+    pub fn connected_switch_ids(mut self, connected_switches: Vec<SwitchId>) -> Self {
         self.connected_switches = Some(connected_switches.into_iter().map(|id| ConnectedSwitch::Switch(SwitchRef { id, scope: None, })).collect());
         self
     }
 
     /* commented out because it's not used
-    pub(crate) fn connect_shared_switch(mut self) -> Self {
+    pub fn connect_shared_switch(mut self) -> Self {
         self.connected_switches = Some(vec![ConnectedSwitch::Shared]);
         self
     }
     */
 
-    pub(crate) fn wait_disk_migration(mut self, wait_disk_migration: bool) -> Self {
+    /// sets whether the creation waits for the disks to be migrated
+    pub fn wait_disk_migration(mut self, wait_disk_migration: bool) -> Self {
         self.wait_disk_migration = Some(wait_disk_migration);
         self
     }
 
-    pub(crate) fn build(self) -> ServerInfo {
+    /// builds the server info
+    pub fn build(self) -> ServerInfo {
         ServerInfo {
             name: self.name,
             server_plan: self.server_plan,
@@ -569,6 +1396,8 @@ impl ServerInfoBuilder {
             host_name: self.host_name,
             tags: self.tags,
             interface_driver: self.interface_driver,
+            icon: self.icon,
+            private_host: self.private_host,
             connected_switches: self.connected_switches,
             wait_disk_migration: self.wait_disk_migration,
         }
@@ -576,10 +1405,148 @@ impl ServerInfoBuilder {
 }
 
 
+/// the password is changed for each request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VncProxy {
+    #[serde(rename = "Host")]
+    host: String,
+
+    #[serde(rename = "IOServerHost", skip_serializing_if = "Option::is_none", default)]
+    io_server_host: Option<String>,
+
+    // a number in a string
+    #[serde(rename = "Port")]
+    port: String,
+
+    #[serde(rename = "Password")]
+    password: String,
+
+    // contents of a .vnc file for vnc viewers
+    #[serde(rename = "VNCFile", skip_serializing_if = "Option::is_none", default)]
+    vnc_file: Option<String>,
+}
+
+impl VncProxy {
+    /// the vnc proxy from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Server, e.to_string()))
+    }
+
+    /// the io server host is reachable from the internet, the host is not always
+    pub fn host(&self) -> &str {
+        self.io_server_host.as_deref().filter(|host| !host.is_empty()).unwrap_or(&self.host)
+    }
+
+    /// the port of the vnc proxy
+    pub fn port(&self) -> &str {
+        &self.port
+    }
+
+    /// the password of the vnc proxy
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// the vnc file of the vnc proxy
+    pub fn vnc_file(&self) -> Option<&str> {
+        self.vnc_file.as_deref()
+    }
+}
+
+/// a key combination sent to the server console
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShortcutKey {
+    /// ctrl + alt + delete
+    #[serde(rename = "ctrl-alt-delete")]
+    CtrlAltDelete,
+
+    /// the windows key
+    #[serde(rename = "windows")]
+    Windows,
+}
+
+
+// PrivateHost
+
+/// the id of a private host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrivateHostId(pub ResourceId);
+
+impl fmt::Display for PrivateHostId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<String> for PrivateHostId {
+    fn from(s: String) -> Self {
+        Self(s.into())
+    }
+}
+
+/// a private host referred to by id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrivateHostRef {
+    #[serde(rename = "ID")]
+    id: PrivateHostId,
+}
+
+/// private hosts are contracted in the control panel, so only read here
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrivateHost {
+    #[serde(rename = "ID")]
+    id: PrivateHostId,
+
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none", default)]
+    name: Option<String>,
+
+    #[serde(rename = "AssignedCPU", skip_serializing_if = "Option::is_none", default)]
+    assigned_cpu: Option<u32>,
+
+    #[serde(rename = "AssignedMemoryMB", skip_serializing_if = "Option::is_none", default)]
+    assigned_memory_mb: Option<u64>,
+}
+
+impl PrivateHost {
+    /// fetches the private host by id
+    pub async fn get(private_host_id: impl Borrow<PrivateHostId>) -> Result<Self, Error> {
+        let private_host_id = private_host_id.borrow();
+        let resource_value = ResourceKind::PrivateHost.get(private_host_id.to_string()).await?;
+        Self::from_value(resource_value)
+    }
+
+    /// the private host from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::PrivateHost, e.to_string()))
+    }
+
+    /// the id of the private host
+    pub fn id(&self) -> &PrivateHostId {
+        &self.id
+    }
+
+    /// the name of the private host
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// the assigned cpu of the private host
+    pub fn assigned_cpu(&self) -> u32 {
+        self.assigned_cpu.unwrap_or_default()
+    }
+
+    /// the memory in MB assigned to the private host
+    pub fn assigned_memory_mb(&self) -> u64 {
+        self.assigned_memory_mb.unwrap_or_default()
+    }
+}
+
+
 // ServerPlan
 
+/// the id of a server plan
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ServerPlanId(pub ResourceId);
+pub struct ServerPlanId(pub ResourceId);
 
 impl fmt::Display for ServerPlanId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -593,40 +1560,151 @@ impl From<String> for ServerPlanId {
     }
 }
 
+/// a server plan referred to by id
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ServerPlanRef {
+pub struct ServerPlanRef {
     #[serde(rename = "ID")]
     id: ServerPlanId,
+
+    // only in responses
+    #[serde(rename = "CPU", skip_serializing, default)]
+    cpu: Option<u32>,
+
+    #[serde(rename = "MemoryMB", skip_serializing, default)]
+    memory_mb: Option<u64>,
+
+    #[serde(rename = "Generation", skip_serializing, default)]
+    generation: Option<u32>,
+
+    #[serde(rename = "Commitment", skip_serializing, default)]
+    commitment: Option<ServerPlanCommitment>,
+}
+
+/// dedicatedcpu plans don't share the cores with the other servers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ServerPlanCommitment {
+    /// the cores are shared with the other servers
+    #[default]
+    #[serde(rename = "standard")]
+    Standard,
+
+    /// the cores are dedicated to the server
+    #[serde(rename = "dedicatedcpu")]
+    DedicatedCpu,
 }
 
-/* commented out because it's not used
+impl fmt::Display for ServerPlanCommitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Standard => write!(f, "standard"),
+            Self::DedicatedCpu => write!(f, "dedicatedcpu"),
+        }
+    }
+}
+
+/// a server plan, the cores and the memory of a server
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ServerPlan {
+pub struct ServerPlan {
     #[serde(rename = "ID")]
     id: ServerPlanId,
+
+    #[serde(rename = "Name", default)]
+    name: Option<String>,
+
+    #[serde(rename = "CPU")]
+    cpu: u32,
+
+    #[serde(rename = "MemoryMB")]
+    memory_mb: u64,
+
+    #[serde(rename = "Generation", default)]
+    generation: Option<u32>,
+
+    #[serde(rename = "Commitment", default)]
+    commitment: ServerPlanCommitment,
+
+    #[serde(rename = "Availability", default)]
+    availability: Option<String>,
+
+    // the key of the price list
+    #[serde(rename = "ServiceClass", default)]
+    service_class: Option<String>,
 }
 
 impl ServerPlan {
-    pub(crate) fn from_value(value: Value) -> Result<ServerPlan, Error> {
+    /// the plans still sold, of all the generations and the commitments
+    pub async fn search_available() -> Result<Vec<ServerPlan>, Error> {
+        let resource_values = ResourceKind::ServerPlan.search_all(&SearchQuery::new()).await?;
+        let plans = resource_values.into_iter().map(Self::from_value).collect::<Result<Vec<_>, _>>()?;
+        Ok(plans.into_iter().filter(|plan| plan.availability.as_deref().is_none_or(|availability| availability == "available")).collect())
+    }
+
+    /// the latest generation if not given
+    pub async fn find(cpu: u32, memory_mb: u64, generation: Option<u32>, commitment: ServerPlanCommitment) -> Result<Option<ServerPlan>, Error> {
+        let plans = Self::search_available().await?;
+        Ok(Self::choose(plans, cpu, memory_mb, generation, commitment))
+    }
+
+    fn choose(plans: Vec<ServerPlan>, cpu: u32, memory_mb: u64, generation: Option<u32>, commitment: ServerPlanCommitment) -> Option<ServerPlan> {
+        plans.into_iter()
+            .filter(|plan| plan.cpu == cpu && plan.memory_mb == memory_mb && plan.commitment == commitment)
+            .filter(|plan| generation.is_none() || plan.generation == generation)
+            .max_by_key(|plan| plan.generation)
+    }
+
+    /// the server plan from the json of the api
+    pub fn from_value(value: Value) -> Result<ServerPlan, Error> {
         serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::ServerPlan, e.to_string()))
     }
 
-    pub(crate) fn kind() -> ResourceKind {
+    /// the kind of the server plans
+    pub fn kind() -> ResourceKind {
         ResourceKind::ServerPlan
     }
 
-    pub(crate) fn id(&self) -> &ServerPlanId {
+    /// the id of the server plan
+    pub fn id(&self) -> &ServerPlanId {
         &self.id
     }
+
+    /// the name of the server plan
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// the cpu of the server plan
+    pub fn cpu(&self) -> u32 {
+        self.cpu
+    }
+
+    /// the memory in MB of the server plan
+    pub fn memory_mb(&self) -> u64 {
+        self.memory_mb
+    }
+
+    /// the generation of the server plan
+    pub fn generation(&self) -> Option<u32> {
+        self.generation
+    }
+
+    /// the service class of the server plan
+    pub fn service_class(&self) -> Option<&str> {
+        self.service_class.as_deref()
+    }
+
+    /// the commitment of the server plan
+    pub fn commitment(&self) -> ServerPlanCommitment {
+        self.commitment
+    }
 }
-*/
 
 
 
 // Switch
 
+/// the id of a switch
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct SwitchId(pub ResourceId);
+pub struct SwitchId(pub ResourceId);
 
 impl fmt::Display for SwitchId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -640,8 +1718,17 @@ impl From<String> for SwitchId {
     }
 }
 
+impl FromStr for SwitchId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
+/// a switch referred to by id
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct SwitchRef {
+pub struct SwitchRef {
     #[serde(rename = "ID")]
     id: SwitchId,
 
@@ -649,40 +1736,63 @@ pub(crate) struct SwitchRef {
     scope: Option<SwitchScope>,
 }
 
+/// a switch of the user
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct Switch {
+pub struct Switch {
     #[serde(rename = "ID")]
     id: SwitchId,
 
+    // e.g. 2024-01-01T00:00:00+09:00
+    #[serde(rename = "CreatedAt", skip_serializing_if = "Option::is_none")]
+    created_at: Option<String>,
+
     #[serde(flatten)]
     info: SwitchInfo,
 }
 
 impl Switch {
-    pub(crate) async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
-        let resource_value = ResourceKind::Switch.search_by_name(name).await?;
+    /// fetches the switch by id
+    pub async fn get(switch_id: impl Borrow<SwitchId>) -> Result<Self, Error> {
+        let switch_id = switch_id.borrow();
+        let resource_value = ResourceKind::Switch.get(switch_id.to_string()).await?;
+        Self::from_value(resource_value)
+    }
+
+    /// fetches the switch found by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::Switch.search_by_name(name, ResourceScope::User).await?;
         resource_value.map(|resource_value| Self::from_value(resource_value)).transpose()
     }
 
-    pub(crate) async fn create(info: SwitchInfo) -> Result<Switch, Error> {
+    /// searches the switches whose names contain the name
+    pub async fn search_by_name(name: impl AsRef<str>) -> Result<Vec<Self>, Error> {
+        let resource_values = ResourceKind::Switch.search_all_by_name(name, ResourceScope::User).await?;
+        resource_values.into_iter().map(Self::from_value).collect()
+    }
+
+    /// creates the switch
+    pub async fn create(info: SwitchInfo) -> Result<Switch, Error> {
         let info_value = info.to_value()?;
         let res_value = ResourceKind::Switch.create(info_value).await?;
         Switch::from_value(res_value)
     }
 
-    pub(crate) async fn delete(switch_id: impl Borrow<SwitchId>) -> Result<(), Error> {
+    /// deletes the switch
+    pub async fn delete(switch_id: impl Borrow<SwitchId>) -> Result<(), Error> {
         let switch_id = switch_id.borrow();
         ResourceKind::Switch.delete(switch_id.to_string()).await
     } 
 
-    pub(crate) async fn wait_delete(switch_id: impl Borrow<SwitchId>) -> Result<(), Error> {
+    /// waits until the switch is deleted
+    pub async fn wait_delete(switch_id: impl Borrow<SwitchId>) -> Result<(), Error> {
         let switch_id = switch_id.borrow();
         ResourceKind::Switch.wait_delete(switch_id.to_string()).await
     }
 
-    pub(crate) async fn connected_servers(switch_id: impl Borrow<SwitchId>) -> Result<Vec<Server>, Error> {
+    /// the servers connected to the switch
+    pub async fn connected_servers(switch_id: impl Borrow<SwitchId>) -> Result<Vec<Server>, Error> {
         let switch_id = switch_id.borrow();
-        let resource_values = search(format!("switch/{}/server", switch_id), "Servers", None, None, None, 50).await?;
+        let resource_values = search(format!("switch/{}/server", switch_id), "Servers", &SearchQuery::new()).await?;
         let mut servers = Vec::new();
         for resource_value in resource_values {
             let server = Server::from_value(resource_value)?;
@@ -691,9 +1801,10 @@ impl Switch {
         Ok(servers)
     }
 
-    pub(crate) async fn connected_appliances(switch_id: impl Borrow<SwitchId>) -> Result<Vec<Appliance>, Error> {
+    /// the appliances connected to the switch
+    pub async fn connected_appliances(switch_id: impl Borrow<SwitchId>) -> Result<Vec<Appliance>, Error> {
         let switch_id = switch_id.borrow();
-        let resource_values = search(format!("switch/{}/appliance", switch_id), "Appliances", None, None, None, 50).await?;
+        let resource_values = search(format!("switch/{}/appliance", switch_id), "Appliances", &SearchQuery::new()).await?;
         let mut appliances = Vec::new();
         for resource_value in resource_values {
             let appliance = Appliance::from_value(resource_value)?;
@@ -702,44 +1813,77 @@ impl Switch {
         Ok(appliances)
     }
 
-    pub(crate) fn from_value(value: Value) -> Result<Self, Error> {
+    /// the switch from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
         serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Switch, e.to_string()))
     }
 
     /* commented out because it's not used
-    pub(crate) fn kind() -> ResourceKind {
+    pub fn kind() -> ResourceKind {
         ResourceKind::Switch
     }
     */
 
-    pub(crate) fn id(&self) -> &SwitchId {
+    /// the id of the switch
+    pub fn id(&self) -> &SwitchId {
         &self.id
     }
+
+    /// the name of the switch
+    pub fn name(&self) -> Option<&str> {
+        self.info.name.as_deref()
+    }
+
+    /// the description of the switch
+    pub fn description(&self) -> Option<&str> {
+        self.info.description.as_deref()
+    }
+
+    /// the tags of the switch
+    pub fn tags(&self) -> &[String] {
+        self.info.tags.as_deref().unwrap_or_default()
+    }
+
+    /// when the switch was created, as the api returns it
+    pub fn created_at(&self) -> Option<&str> {
+        self.created_at.as_deref()
+    }
 }
 
+/// the fields of a switch to create
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct SwitchInfo {
+pub struct SwitchInfo {
     #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
     name: Option<String>,
 
     #[serde(rename = "Description", skip_serializing_if = "Option::is_none")]
     description: Option<String>,
+
+    #[serde(rename = "Tags", skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+
+    #[serde(rename = "Icon", skip_serializing_if = "Option::is_none")]
+    icon: Option<IconRef>,
 }
 
 impl SwitchInfo {
-    pub(crate) fn builder() -> SwitchInfoBuilder {
+    /// a builder of the switch info
+    pub fn builder() -> SwitchInfoBuilder {
         SwitchInfoBuilder::new()
     }
 
-    pub(crate) fn to_value(&self) -> Result<Value, Error> {
+    /// the json of the switch info as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
         serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::Switch, e.to_string()))
     }
 }
 
+/// a builder of [`SwitchInfo`]
 #[derive(Debug)]
-pub(crate) struct SwitchInfoBuilder {
+pub struct SwitchInfoBuilder {
     name: Option<String>,
     description: Option<String>,
+    icon: Option<IconRef>,
 }
 
 impl SwitchInfoBuilder {
@@ -747,39 +1891,55 @@ impl SwitchInfoBuilder {
         Self {
             name: None,
             description: None,
+            icon: None,
         }
     }
 
-    pub(crate) fn name(mut self, name: impl Into<String>) -> Self {
+    /// sets the name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
         self.name = Some(name.into());
         self
     }
 
-    pub(crate) fn description(mut self, description: impl Into<String>) -> Self {
+    /// sets the description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
         self.description = Some(description.into());
         self
     }
 
-    pub(crate) fn build(self) -> SwitchInfo {
+    /// sets the icon id
+    pub fn icon_id(mut self, icon_id: IconId) -> Self {
+        self.icon = Some(IconRef { id: icon_id });
+        self
+    }
+
+    /// builds the switch info
+    pub fn build(self) -> SwitchInfo {
         SwitchInfo {
             name: self.name,
             description: self.description,
+            tags: None,
+            icon: self.icon,
         }
     }
 }
 
+/// whether a switch is the shared one or one of the user
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) enum SwitchScope {
+pub enum SwitchScope {
+    /// the shared switch of the internet
     #[serde(rename = "shared")]
     Shared,
+    /// a switch of the user
     #[serde(rename = "user")]
     User,
 }
 
 // Appliance
 
+/// the id of an appliance
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ApplianceId(pub ResourceId);
+pub struct ApplianceId(pub ResourceId);
 
 impl fmt::Display for ApplianceId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -793,11 +1953,27 @@ impl From<String> for ApplianceId {
     }
 }
 
+impl FromStr for ApplianceId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
+/// an appliance, e.g. a vpc router
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct Appliance {
+pub struct Appliance {
     #[serde(rename = "ID")]
     id: ApplianceId,
 
+    #[serde(rename = "Availability", skip_serializing_if = "Option::is_none")]
+    availability: Option<String>,
+
+    // e.g. 2024-01-01T00:00:00+09:00
+    #[serde(rename = "CreatedAt", skip_serializing_if = "Option::is_none")]
+    created_at: Option<String>,
+
     #[serde(rename = "Instance", skip_serializing_if = "Option::is_none")]
     instance: Option<Instance>,
 
@@ -806,104 +1982,232 @@ pub(crate) struct Appliance {
 }
 
 impl Appliance {
-    pub(crate) async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
-        let resource_value = ResourceKind::Appliance.search_by_name(name).await?;
+    /// fetches the appliance by id
+    pub async fn get(appliance_id: impl Borrow<ApplianceId>) -> Result<Self, Error> {
+        let appliance_id = appliance_id.borrow();
+        let resource_value = ResourceKind::Appliance.get(appliance_id.to_string()).await?;
+        Self::from_value(resource_value)
+    }
+
+    /// fetches the appliance found by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::Appliance.search_by_name(name, ResourceScope::User).await?;
         resource_value.map(|resource_value| Self::from_value(resource_value)).transpose()
     }
 
-    pub(crate) async fn create(info: ApplianceInfo) -> Result<Appliance, Error> {
+    /// searches the appliances whose names contain the name
+    pub async fn search_by_name(name: impl AsRef<str>) -> Result<Vec<Self>, Error> {
+        let resource_values = ResourceKind::Appliance.search_all_by_name(name, ResourceScope::User).await?;
+        resource_values.into_iter().map(Self::from_value).collect()
+    }
+
+    /// creates the appliance
+    pub async fn create(info: ApplianceInfo) -> Result<Appliance, Error> {
         let info_value = info.to_value()?;
         let res_value = ResourceKind::Appliance.create(info_value).await?;
         Appliance::from_value(res_value)
     }
 
-    pub(crate) async fn update(appliance_id: impl Borrow<ApplianceId>, info: ApplianceInfo) -> Result<(), Error> {
+    /// updates the appliance to the info
+    pub async fn update(appliance_id: impl Borrow<ApplianceId>, info: ApplianceInfo) -> Result<(), Error> {
         let appliance_id = appliance_id.borrow();
         let info_value = info.to_value()?;
         ResourceKind::Appliance.update(appliance_id.to_string(), info_value).await
     }
 
-    pub(crate) async fn delete(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
+    /// deletes the appliance
+    pub async fn delete(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
         let appliance_id = appliance_id.borrow();
         ResourceKind::Appliance.delete(appliance_id.to_string()).await
     } 
 
-    pub(crate) async fn wait_delete(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
+    /// waits until the appliance is deleted
+    pub async fn wait_delete(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
         let appliance_id = appliance_id.borrow();
         ResourceKind::Appliance.wait_delete(appliance_id.to_string()).await
     }
 
-    pub(crate) async fn connect_to_switch(appliance_id: impl Borrow<ApplianceId>, switch_id: impl Borrow<SwitchId>) -> Result<(), Error> {
+    /// connects the second interface of the appliance to the switch
+    pub async fn connect_to_switch(appliance_id: impl Borrow<ApplianceId>, switch_id: impl Borrow<SwitchId>) -> Result<(), Error> {
         let appliance_id = appliance_id.borrow();
         let switch_id = switch_id.borrow();
         update(format!("appliance/{}/interface/1/to/switch/{}", appliance_id, switch_id), None).await
     }
 
-    pub(crate) async fn is_connected_to_switch(appliance_id: impl Borrow<ApplianceId>, switch_id: impl Borrow<SwitchId>) -> Result<bool, Error> {
+    /// whether the appliance is connected to the switch
+    pub async fn is_connected_to_switch(appliance_id: impl Borrow<ApplianceId>, switch_id: impl Borrow<SwitchId>) -> Result<bool, Error> {
         let appliance_id = appliance_id.borrow();
         let switch_id = switch_id.borrow();
         let appliances = Switch::connected_appliances(switch_id).await?;
         Ok(appliances.iter().any(|appliance| appliance.id() == appliance_id))
     }
     
-    pub(crate) async fn apply_config(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
+    /// the sims registered to the mobile gateway
+    pub async fn mobile_gateway_sims(appliance_id: impl Borrow<ApplianceId>) -> Result<Vec<MobileGatewaySim>, Error> {
+        let appliance_id = appliance_id.borrow();
+        let mut value = request_api(Method::GET, format!("appliance/{}/mobilegateway/sims", appliance_id), &None, &None).await?;
+        let sims = value["sim"].take();
+        if sims.is_null() {
+            return Ok(Vec::new());
+        }
+        serde_json::from_value(sims).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Appliance, e.to_string()))
+    }
+
+    /// registers the sim to the mobile gateway
+    pub async fn add_mobile_gateway_sim(appliance_id: impl Borrow<ApplianceId>, sim_id: impl Borrow<CommonServiceItemId>) -> Result<(), Error> {
+        let appliance_id = appliance_id.borrow();
+        let sim_id = sim_id.borrow();
+        let body = json!({ "sim": { "resource_id": sim_id.to_string() } });
+        request_api(Method::POST, format!("appliance/{}/mobilegateway/sims", appliance_id), &None, &Some(body)).await?;
+        Ok(())
+    }
+
+    /// removes the sim from the mobile gateway
+    pub async fn remove_mobile_gateway_sim(appliance_id: impl Borrow<ApplianceId>, sim_id: impl Borrow<CommonServiceItemId>) -> Result<(), Error> {
+        let appliance_id = appliance_id.borrow();
+        let sim_id = sim_id.borrow();
+        delete(format!("appliance/{}/mobilegateway/sims/{}", appliance_id, sim_id), None).await
+    }
+
+    /// applies the settings of the appliance, e.g. after an update of a vpc router
+    pub async fn apply_config(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
         let appliance_id = appliance_id.borrow();
         update(format!("appliance/{}/config", appliance_id), None).await
     }
 
-    pub(crate) async fn wait_available(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
+    /// waits until the appliance is available
+    pub async fn wait_available(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
         let appliance_id = appliance_id.borrow();
         ResourceKind::Appliance.wait_available(appliance_id.to_string()).await
     }
 
-    pub(crate) async fn instance_status(appliance_id: impl Borrow<ApplianceId>) ->Result<InstanceStatus, Error> {
+    /// the power status of the appliance
+    pub async fn power_status(appliance_id: impl Borrow<ApplianceId>) -> Result<InstanceStatus, Error> {
         let appliance_id = appliance_id.borrow();
-        ResourceKind::Appliance.instance_status(appliance_id.to_string()).await
+        ResourceKind::Appliance.power_status(appliance_id.to_string()).await
     }
 
-    pub(crate) async fn is_up(appliance_id: impl Borrow<ApplianceId>) -> Result<bool, Error> {
+    /// whether the appliance is up
+    pub async fn is_up(appliance_id: impl Borrow<ApplianceId>) -> Result<bool, Error> {
+        Ok(Self::power_status(appliance_id).await? == InstanceStatus::Up)
+    }
+
+    /// true if booted here
+    pub async fn ensure_up(appliance_id: impl Borrow<ApplianceId>) -> Result<bool, Error> {
         let appliance_id = appliance_id.borrow();
-        match ResourceKind::Appliance.instance_status(appliance_id.to_string()).await? {
-            InstanceStatus::Up => Ok(true),
-            _ => Ok(false),
-        }
+        ResourceKind::Appliance.ensure_up(appliance_id.to_string()).await
+    }
+
+    /// the graceful shutdown like down, true if shut down here
+    pub async fn ensure_down(appliance_id: impl Borrow<ApplianceId>) -> Result<bool, Error> {
+        let appliance_id = appliance_id.borrow();
+        ResourceKind::Appliance.ensure_down(appliance_id.to_string(), false).await
     }
 
-    pub(crate) async fn up(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
+    /// boots the appliance without waiting
+    pub async fn up(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
         let appliance_id = appliance_id.borrow();
         ResourceKind::Appliance.up_resource(appliance_id.to_string()).await
     }
 
-    pub(crate) async fn wait_up(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
+    /// waits until the appliance is up
+    pub async fn wait_up(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
         let appliance_id = appliance_id.borrow();
         ResourceKind::Appliance.wait_up(appliance_id.to_string()).await
     }
 
-    pub(crate) async fn down(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
+    /// shuts the appliance down without waiting
+    pub async fn down(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
         let appliance_id = appliance_id.borrow();
         ResourceKind::Appliance.down_resource(appliance_id.to_string()).await
     }
 
-    pub(crate) async fn wait_down(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
+    /// waits until the appliance is down
+    pub async fn wait_down(appliance_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
         let appliance_id = appliance_id.borrow();
         ResourceKind::Appliance.wait_down(appliance_id.to_string()).await
     }
 
-    pub(crate) fn from_value(value: Value) -> Result<Self, Error> {
+    /// the appliance from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
         serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Appliance, e.to_string()))
     }
 
     /* commented out because it's not used
-    pub(crate) fn kind() -> ResourceKind {
+    pub fn kind() -> ResourceKind {
         ResourceKind::Appliance
     }
     */
 
-    pub(crate) fn id(&self) -> &ApplianceId {
+    /// the id of the appliance
+    pub fn id(&self) -> &ApplianceId {
         &self.id
     }
 
-    pub(crate) fn public_shared_ip(&self) -> Result<Ipv4Addr, Error> {
+    /// the name of the appliance
+    pub fn name(&self) -> Option<&str> {
+        self.info.name.as_deref()
+    }
+
+    /// the description of the appliance
+    pub fn description(&self) -> Option<&str> {
+        self.info.description.as_deref()
+    }
+
+    /// the tags of the appliance
+    pub fn tags(&self) -> &[String] {
+        self.info.tags.as_deref().unwrap_or_default()
+    }
+
+    /// when the appliance was created, as the api returns it
+    pub fn created_at(&self) -> Option<&str> {
+        self.created_at.as_deref()
+    }
+
+    /// the availability of the appliance
+    pub fn availability(&self) -> Option<Availability> {
+        self.availability.as_deref().and_then(|availability| availability.parse().ok())
+    }
+
+    /// the instance status of the appliance
+    pub fn instance_status(&self) -> Option<InstanceStatus> {
+        self.instance.as_ref()?.status
+    }
+
+    /// the vpc router plan id of the appliance
+    pub fn vpc_router_plan_id(&self) -> Option<&VpcRouterPlanId> {
+        let Some(ApplianceClassInfo::VpcRouter(vpc_router_info)) = &self.info.class_info else {
+            return None;
+        };
+        vpc_router_info.plan.as_ref().map(|plan| &plan.id)
+    }
+
+    /// by the slot, none for an unconnected one, the first one is the public side of a vpc router
+    pub fn interfaces(&self) -> Vec<Option<&ApplianceInterface>> {
+        self.info.interfaces.iter().flatten().map(|interface| interface.as_ref()).collect()
+    }
+
+    /// the class of the appliance, none if the api didn't return it
+    pub fn class(&self) -> Option<&ApplianceClass> {
+        self.info.class.as_ref()
+    }
+
+    /// whether the appliance is a vpc router
+    pub fn is_vpc_router(&self) -> bool {
+        self.info.class == Some(ApplianceClass::VpcRouter)
+    }
+
+    /// Settings.Router.Firewall.Enabled of a vpc router, none for the others or if the settings are not fetched
+    pub fn is_firewall_enabled(&self) -> Option<bool> {
+        let Some(ApplianceClassInfo::VpcRouter(vpc_router_info)) = &self.info.class_info else {
+            return None;
+        };
+        let enabled = vpc_router_info.settings.as_ref()?["Router"]["Firewall"]["Enabled"].as_str()?;
+        Some(enabled == "True")
+    }
+
+    /// the public shared ip of the appliance
+    pub fn public_shared_ip(&self) -> Result<Ipv4Addr, Error> {
         let Some(interfaces) = self.info.interfaces.as_ref() else {
             return Err(Error::ApplianceDoesntHaveInterfaceInfo);
         };
@@ -936,17 +2240,24 @@ impl Appliance {
     }
 }
 
+/// the fields of an appliance to create or update
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ApplianceInfo {
+pub struct ApplianceInfo {
     #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
     name: Option<String>,
 
     #[serde(rename = "Description", skip_serializing_if = "Option::is_none")]
     description: Option<String>,
 
+    #[serde(rename = "Tags", skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+
     #[serde(rename = "Class", skip_serializing_if = "Option::is_none")]
     class: Option<ApplianceClass>,
 
+    #[serde(rename = "Icon", skip_serializing_if = "Option::is_none")]
+    icon: Option<IconRef>,
+
     #[serde(rename = "Interfaces", skip_serializing_if = "Option::is_none")]
     interfaces: Option<Vec<Option<ApplianceInterface>>>,
 
@@ -955,20 +2266,24 @@ pub(crate) struct ApplianceInfo {
 }
 
 impl ApplianceInfo {
-    pub(crate) fn builder() -> ApplianceInfoBuilder {
+    /// a builder of the appliance info
+    pub fn builder() -> ApplianceInfoBuilder {
         ApplianceInfoBuilder::new()
     }
 
-    pub(crate) fn to_value(&self) -> Result<Value, Error> {
+    /// the json of the appliance info as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
         serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::Appliance, e.to_string()))
     }
 }
 
+/// a builder of [`ApplianceInfo`]
 #[derive(Debug)]
-pub(crate) struct ApplianceInfoBuilder {
+pub struct ApplianceInfoBuilder {
     name: Option<String>,
     description: Option<String>,
     class: Option<ApplianceClass>,
+    icon: Option<IconRef>,
     class_info: Option<ApplianceClassInfo>,
 }
 
@@ -978,44 +2293,82 @@ impl ApplianceInfoBuilder {
             name: None,
             description: None,
             class: None,
+            icon: None,
             class_info: None,
         }
     }
 
-    pub(crate) fn name(mut self, name: impl Into<String>) -> Self {
+    /// sets the name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
         self.name = Some(name.into());
         self
     }
 
-    pub(crate) fn description(mut self, description: impl Into<String>) -> Self {
+    /// sets the description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
         self.description = Some(description.into());
         self
     }
 
-    pub(crate) fn vpc_router(mut self, vpc_router_info: VpcRouterInfo) -> Self {
+    /// sets the vpc router
+    pub fn vpc_router(mut self, vpc_router_info: VpcRouterInfo) -> Self {
         self.class = Some(ApplianceClass::VpcRouter);
         self.class_info= Some(ApplianceClassInfo::VpcRouter(vpc_router_info));
         self
     }
 
-    pub(crate) fn vpc_router_info(mut self, vpc_router_info: VpcRouterInfo) -> Self {
+    /// sets the vpc router info
+    pub fn vpc_router_info(mut self, vpc_router_info: VpcRouterInfo) -> Self {
         self.class_info= Some(ApplianceClassInfo::VpcRouter(vpc_router_info));
         self
     }
 
-    pub(crate) fn build(self) -> ApplianceInfo {
+    /// sets the nfs
+    pub fn nfs(mut self, nfs_info: NfsInfo) -> Self {
+        self.class = Some(ApplianceClass::Nfs);
+        self.class_info = Some(ApplianceClassInfo::Nfs(nfs_info));
+        self
+    }
+
+    /// sets the mobile gateway
+    pub fn mobile_gateway(mut self, mobile_gateway_info: MobileGatewayInfo) -> Self {
+        self.class = Some(ApplianceClass::MobileGateway);
+        self.class_info = Some(ApplianceClassInfo::MobileGateway(mobile_gateway_info));
+        self
+    }
+
+    /// sets the mobile gateway info
+    pub fn mobile_gateway_info(mut self, mobile_gateway_info: MobileGatewayInfo) -> Self {
+        self.class_info = Some(ApplianceClassInfo::MobileGateway(mobile_gateway_info));
+        self
+    }
+
+    /// sets the icon id
+    pub fn icon_id(mut self, icon_id: IconId) -> Self {
+        self.icon = Some(IconRef { id: icon_id });
+        self
+    }
+
+    /// builds the appliance info
+    pub fn build(self) -> ApplianceInfo {
         ApplianceInfo {
             name: self.name,
             description: self.description,
+            tags: None,
             interfaces: None,
             class: self.class,
+            icon: self.icon,
             class_info: self.class_info,
         }
     }
 }
 
+/// an interface of an appliance
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ApplianceInterface {
+pub struct ApplianceInterface {
+    #[serde(rename = "MACAddress", skip_serializing_if = "Option::is_none", default)]
+    mac_address: Option<String>,
+
     #[serde(rename = "IPAddress", skip_serializing_if = "Option::is_none")]
     ip_address: Option<Ipv4Addr>,
 
@@ -1026,22 +2379,70 @@ pub(crate) struct ApplianceInterface {
     switch: Option<SwitchRef>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) enum ApplianceClass {
-    #[serde(rename = "vpcrouter")]
-    VpcRouter,
-}
+impl ApplianceInterface {
+    /// the mac address of the appliance interface
+    pub fn mac_address(&self) -> Option<&str> {
+        self.mac_address.as_deref()
+    }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(untagged)]
-pub(crate) enum ApplianceClassInfo {
+    /// the ip address of the appliance interface
+    pub fn ip_address(&self) -> Option<Ipv4Addr> {
+        self.ip_address
+    }
 
+    /// the user ip address of the appliance interface
+    pub fn user_ip_address(&self) -> Option<Ipv4Addr> {
+        self.user_ip_address
+    }
+
+    /// the switch id of the appliance interface
+    pub fn switch_id(&self) -> Option<&SwitchId> {
+        self.switch.as_ref().map(|switch| &switch.id)
+    }
+
+    /// whether the appliance interface is connected to the shared switch
+    pub fn is_shared_switch(&self) -> bool {
+        self.switch.as_ref().and_then(|switch| switch.scope.as_ref()) == Some(&SwitchScope::Shared)
+    }
+}
+
+/// the classes of the appliances
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApplianceClass {
+    /// a vpc router
+    #[serde(rename = "vpcrouter")]
+    VpcRouter,
+
+    /// a nfs server
+    #[serde(rename = "nfs")]
+    Nfs,
+
+    /// a mobile gateway
+    #[serde(rename = "mobilegateway")]
+    MobileGateway,
+}
+
+/// the fields of each class of the appliances
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ApplianceClassInfo {
+
+    /// the fields of a vpc router
     #[serde(rename = "vpcrouter")]
     VpcRouter(VpcRouterInfo),
+
+    /// the fields of a nfs server
+    #[serde(rename = "nfs")]
+    Nfs(NfsInfo),
+
+    /// the fields of a mobile gateway
+    #[serde(rename = "mobilegateway")]
+    MobileGateway(MobileGatewayInfo),
 }
 
+/// the fields of a vpc router
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct VpcRouterInfo {
+pub struct VpcRouterInfo {
     #[serde(rename = "Plan", skip_serializing_if = "Option::is_none")]
     plan: Option<VpcRouterPlanRef>,
 
@@ -1053,19 +2454,21 @@ pub(crate) struct VpcRouterInfo {
 }
 
 impl VpcRouterInfo {
-    pub(crate) fn builder() -> VpcRouterInfoBuilder {
+    /// a builder of the vpc router info
+    pub fn builder() -> VpcRouterInfoBuilder {
         VpcRouterInfoBuilder::new()
     }
 
     /* commented out because it's not used
-    pub(crate) fn to_value(&self) -> Result<Value, Error> {
+    pub fn to_value(&self) -> Result<Value, Error> {
         serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::Appliance, e.to_string()))
     }
     */
 }
 
+/// a builder of [`VpcRouterInfo`]
 #[derive(Debug)]
-pub(crate) struct VpcRouterInfoBuilder {
+pub struct VpcRouterInfoBuilder {
     plan: Option<VpcRouterPlanRef>,
     remark: Option<Value>,
     settings: Option<Value>,
@@ -1080,22 +2483,26 @@ impl VpcRouterInfoBuilder {
         }
     }
 
-    pub(crate) fn plan_id(mut self, plan_id: VpcRouterPlanId) -> Self {
+    /// sets the plan id
+    pub fn plan_id(mut self, plan_id: VpcRouterPlanId) -> Self {
         self.plan = Some(VpcRouterPlanRef { id: plan_id });
         self
     }
 
-    pub(crate) fn remark(mut self, remark: Value) -> Self {
+    /// sets the remark
+    pub fn remark(mut self, remark: Value) -> Self {
         self.remark = Some(remark);
         self
     }
 
-    pub(crate) fn settings(mut self, settings: Value) -> Self {
+    /// sets the settings
+    pub fn settings(mut self, settings: Value) -> Self {
         self.settings = Some(settings);
         self
     }
 
-    pub(crate) fn build(self) -> VpcRouterInfo {
+    /// builds the vpc router info
+    pub fn build(self) -> VpcRouterInfo {
         VpcRouterInfo {
             plan: self.plan,
             remark: self.remark,
@@ -1104,26 +2511,275 @@ impl VpcRouterInfoBuilder {
     }
 }
 
+/// the id of a vpc router plan
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct VpcRouterPlanId(pub ResourceId);
+pub struct VpcRouterPlanId(pub ResourceId);
+
+impl fmt::Display for VpcRouterPlanId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 
 impl VpcRouterPlanId {
-    pub(crate) fn new(id: u64) -> Self {
+    /// the plan id of the number
+    pub fn new(id: u64) -> Self {
         Self(id.into())
     }
 }
 
+/// a vpc router plan referred to by id
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct VpcRouterPlanRef {
+pub struct VpcRouterPlanRef {
     #[serde(rename = "ID")]
     id: VpcRouterPlanId,
 }
 
+/// the fields of a nfs server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NfsInfo {
+    #[serde(rename = "Plan", skip_serializing_if = "Option::is_none")]
+    plan: Option<NfsPlanRef>,
+
+    #[serde(rename = "Remark", skip_serializing_if = "Option::is_none")]
+    remark: Option<NfsRemark>,
+}
+
+impl NfsInfo {
+    /// a builder of the nfs info
+    pub fn builder() -> NfsInfoBuilder {
+        NfsInfoBuilder::new()
+    }
+}
+
+/// the network and the address of a nfs server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NfsRemark {
+    #[serde(rename = "Network")]
+    network: NfsRemarkNetwork,
+
+    #[serde(rename = "Servers")]
+    servers: Vec<NfsRemarkServer>,
+
+    #[serde(rename = "Switch")]
+    switch: SwitchRef,
+
+    #[serde(rename = "Plan")]
+    plan: NfsPlanRef,
+}
+
+/// the network of a nfs server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NfsRemarkNetwork {
+    #[serde(rename = "NetworkMaskLen")]
+    network_mask_len: u8,
+
+    #[serde(rename = "DefaultRoute")]
+    default_route: Ipv4Addr,
+}
+
+/// the address of a nfs server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NfsRemarkServer {
+    #[serde(rename = "IPAddress")]
+    ip_address: Ipv4Addr,
+}
+
+/// a builder of [`NfsInfo`]
+#[derive(Debug)]
+pub struct NfsInfoBuilder {
+    plan_id: Option<NfsPlanId>,
+    switch_id: Option<SwitchId>,
+    ip_address: Option<Ipv4Addr>,
+    network_mask_len: u8,
+    default_route: Option<Ipv4Addr>,
+}
+
+impl NfsInfoBuilder {
+    fn new() -> Self {
+        Self {
+            plan_id: None,
+            switch_id: None,
+            ip_address: None,
+            network_mask_len: 24,
+            default_route: None,
+        }
+    }
+
+    /// the plan determines the storage class and the size
+    pub fn plan_id(mut self, plan_id: NfsPlanId) -> Self {
+        self.plan_id = Some(plan_id);
+        self
+    }
+
+    /// sets the switch id
+    pub fn switch_id(mut self, switch_id: impl Borrow<SwitchId>) -> Self {
+        self.switch_id = Some(switch_id.borrow().clone());
+        self
+    }
+
+    /// sets the ip address
+    pub fn ip_address(mut self, ip_address: Ipv4Addr) -> Self {
+        self.ip_address = Some(ip_address);
+        self
+    }
+
+    /// sets the network mask len
+    pub fn network_mask_len(mut self, network_mask_len: u8) -> Self {
+        self.network_mask_len = network_mask_len;
+        self
+    }
+
+    /// sets the default route
+    pub fn default_route(mut self, default_route: Ipv4Addr) -> Self {
+        self.default_route = Some(default_route);
+        self
+    }
+
+    /// plan, switch, ip address and default route are required for the remark
+    pub fn build(self) -> NfsInfo {
+        let plan = self.plan_id.map(|id| NfsPlanRef { id });
+        let remark = match (&plan, self.switch_id, self.ip_address, self.default_route) {
+            (Some(plan), Some(switch_id), Some(ip_address), Some(default_route)) => Some(NfsRemark {
+                network: NfsRemarkNetwork { network_mask_len: self.network_mask_len, default_route },
+                servers: vec![NfsRemarkServer { ip_address }],
+                switch: SwitchRef { id: switch_id, scope: None },
+                plan: plan.clone(),
+            }),
+            _ => None,
+        };
+        NfsInfo { plan, remark }
+    }
+}
+
+/// the id of a nfs plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NfsPlanId(pub ResourceId);
+
+impl fmt::Display for NfsPlanId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// a nfs plan referred to by id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NfsPlanRef {
+    #[serde(rename = "ID")]
+    id: NfsPlanId,
+}
+
+/// the fields of a mobile gateway
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MobileGatewayInfo {
+    #[serde(rename = "Plan", skip_serializing_if = "Option::is_none")]
+    plan: Option<MobileGatewayPlanRef>,
+
+    #[serde(rename = "Remark", skip_serializing_if = "Option::is_none")]
+    remark: Option<Value>,
+
+    #[serde(rename = "Settings", skip_serializing_if = "Option::is_none")]
+    settings: Option<Value>,
+}
+
+impl MobileGatewayInfo {
+    /// a builder of the mobile gateway info
+    pub fn builder() -> MobileGatewayInfoBuilder {
+        MobileGatewayInfoBuilder::new()
+    }
+}
+
+/// a builder of [`MobileGatewayInfo`]
+#[derive(Debug)]
+pub struct MobileGatewayInfoBuilder {
+    plan: Option<MobileGatewayPlanRef>,
+    remark: Option<Value>,
+    settings: Option<Value>,
+}
+
+impl MobileGatewayInfoBuilder {
+    fn new() -> Self {
+        Self {
+            plan: None,
+            remark: None,
+            settings: None,
+        }
+    }
+
+    /// sets the plan id
+    pub fn plan_id(mut self, plan_id: MobileGatewayPlanId) -> Self {
+        self.plan = Some(MobileGatewayPlanRef { id: plan_id });
+        self
+    }
+
+    /// sets the remark
+    pub fn remark(mut self, remark: Value) -> Self {
+        self.remark = Some(remark);
+        self
+    }
+
+    /// sets the settings
+    pub fn settings(mut self, settings: Value) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
+    /// builds the mobile gateway info
+    pub fn build(self) -> MobileGatewayInfo {
+        MobileGatewayInfo {
+            plan: self.plan,
+            remark: self.remark,
+            settings: self.settings,
+        }
+    }
+}
+
+/// the id of a mobile gateway plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MobileGatewayPlanId(pub ResourceId);
+
+impl MobileGatewayPlanId {
+    /// the plan id of the number
+    pub fn new(id: u64) -> Self {
+        Self(id.into())
+    }
+}
+
+/// a mobile gateway plan referred to by id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MobileGatewayPlanRef {
+    #[serde(rename = "ID")]
+    id: MobileGatewayPlanId,
+}
+
+/// a sim registered to a mobile gateway, the fields are snake case in this api
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MobileGatewaySim {
+    #[serde(rename = "resource_id")]
+    id: CommonServiceItemId,
+
+    #[serde(rename = "ip", default)]
+    ip: Option<String>,
+}
+
+impl MobileGatewaySim {
+    /// the id of the mobile gateway sim
+    pub fn id(&self) -> &CommonServiceItemId {
+        &self.id
+    }
+
+    /// empty if not assigned
+    pub fn ip(&self) -> Option<&str> {
+        self.ip.as_deref().filter(|ip| !ip.is_empty())
+    }
+}
+
 
 // Disk
 
+/// the id of a disk
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct DiskId(pub ResourceId);
+pub struct DiskId(pub ResourceId);
 
 impl fmt::Display for DiskId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -1137,22 +2793,53 @@ impl From<String> for DiskId {
     }
 }
 
+impl FromStr for DiskId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
+/// a disk referred to by id
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct Disk {
+pub struct DiskRef {
     #[serde(rename = "ID")]
     id: DiskId,
+}
+
+/// a disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Disk {
+    #[serde(rename = "ID")]
+    id: DiskId,
+
+    #[serde(rename = "Availability", skip_serializing_if = "Option::is_none")]
+    availability: Option<String>,
+
+    // e.g. 2024-01-01T00:00:00+09:00
+    #[serde(rename = "CreatedAt", skip_serializing_if = "Option::is_none")]
+    created_at: Option<String>,
 
     #[serde(flatten)]
     info: DiskInfo,
 }
 
 impl Disk {
-    pub(crate) async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
-        let resource_value = ResourceKind::Disk.search_by_name(name).await?;
+    /// fetches the disk found by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::Disk.search_by_name(name, ResourceScope::User).await?;
         resource_value.map(|resource_value| Self::from_value(resource_value)).transpose()
     }
 
-    pub(crate) async fn create(info: DiskInfo, config: DiskConfig) -> Result<Disk, Error> {
+    /// searches the disks whose names contain the name
+    pub async fn search_by_name(name: impl AsRef<str>) -> Result<Vec<Self>, Error> {
+        let resource_values = ResourceKind::Disk.search_all_by_name(name, ResourceScope::User).await?;
+        resource_values.into_iter().map(Self::from_value).collect()
+    }
+
+    /// creates the disk
+    pub async fn create(info: DiskInfo, config: DiskConfig) -> Result<Disk, Error> {
         let info_value = info.to_value()?;
         let config_value = config.to_value()?;
 
@@ -1161,24 +2848,33 @@ impl Disk {
         Disk::from_value(res_value)
     }
 
-    pub(crate) async fn delete(disk_id: impl Borrow<DiskId>) -> Result<(), Error> {
+    /// deletes the disk
+    pub async fn delete(disk_id: impl Borrow<DiskId>) -> Result<(), Error> {
         let disk_id = disk_id.borrow();
         ResourceKind::Disk.delete(disk_id.to_string()).await
     } 
 
-    pub(crate) async fn wait_delete(disk_id: impl Borrow<DiskId>) -> Result<(), Error> {
+    /// waits until the disk is deleted
+    pub async fn wait_delete(disk_id: impl Borrow<DiskId>) -> Result<(), Error> {
         let disk_id = disk_id.borrow();
         ResourceKind::Disk.wait_delete(disk_id.to_string()).await
     }
 
-    pub(crate) async fn wait_available(disk_id: impl Borrow<DiskId>) -> Result<(), Error> {
+    /// waits until the disk is available
+    pub async fn wait_available(disk_id: impl Borrow<DiskId>) -> Result<(), Error> {
         let disk_id = disk_id.borrow();
         ResourceKind::Disk.wait_available(disk_id.to_string()).await
     }
 
+    /// waits until the disk is available, handing each poll to on_progress
+    pub async fn wait_available_with_progress(disk_id: impl Borrow<DiskId>, on_progress: impl FnMut(&WaitProgress)) -> Result<(), Error> {
+        let disk_id = disk_id.borrow();
+        ResourceKind::Disk.wait_available_with_progress(disk_id.to_string(), on_progress).await
+    }
+
     /*
-    pub(crate) async fn search_all() -> Result<Vec<Disk>, Error> {
-        let resource_values = ResourceKind::Disk.search_all().await?;
+    pub async fn search_all() -> Result<Vec<Disk>, Error> {
+        let resource_values = ResourceKind::Disk.search_all(&SearchQuery::new()).await?;
         let mut disks = Vec::new();
         for resource_value in resource_values {
             let disk = Disk::from_value(resource_value)?;
@@ -1188,29 +2884,90 @@ impl Disk {
     }
     */
 
-    pub(crate) fn from_value(value: Value) -> Result<Self, Error> {
+    /// the disk from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
         serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Disk, e.to_string()))
     }
 
     /*
-    pub(crate) fn kind() -> ResourceKind {
+    pub fn kind() -> ResourceKind {
         ResourceKind::Disk
     }
     */
 
-    pub(crate) fn id(&self) -> &DiskId {
+    /// the id of the disk
+    pub fn id(&self) -> &DiskId {
         &self.id
     }
+
+    /// the name of the disk
+    pub fn name(&self) -> Option<&str> {
+        self.info.name.as_deref()
+    }
+
+    /// the server id of the disk
+    pub fn server_id(&self) -> Option<&ServerId> {
+        self.info.server.as_ref().map(|server| &server.id)
+    }
+
+    /// the plan id of the disk
+    pub fn plan_id(&self) -> Option<&DiskPlanId> {
+        self.info.plan.as_ref().map(|plan| &plan.id)
+    }
+
+    /// the plan name of the disk
+    pub fn plan_name(&self) -> Option<&str> {
+        self.info.plan.as_ref()?.name.as_deref()
+    }
+
+    /// the size in MB of the disk
+    pub fn size_mb(&self) -> Option<u64> {
+        self.info.size_mb
+    }
+
+    /// the description of the disk
+    pub fn description(&self) -> Option<&str> {
+        self.info.description.as_deref()
+    }
+
+    /// the tags of the disk
+    pub fn tags(&self) -> &[String] {
+        self.info.tags.as_deref().unwrap_or_default()
+    }
+
+    /// the source archive id of the disk
+    pub fn source_archive_id(&self) -> Option<&ArchiveId> {
+        self.info.source_archive.as_ref().map(|archive| &archive.id)
+    }
+
+    /// the connection of the disk
+    pub fn connection(&self) -> Option<&DiskConnection> {
+        self.info.connection.as_ref()
+    }
+
+    /// when the disk was created, as the api returns it
+    pub fn created_at(&self) -> Option<&str> {
+        self.created_at.as_deref()
+    }
+
+    /// the availability of the disk
+    pub fn availability(&self) -> Option<Availability> {
+        self.availability.as_deref().and_then(|availability| availability.parse().ok())
+    }
 }
 
+/// the fields of a disk to create
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct DiskInfo {
+pub struct DiskInfo {
     #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
     name: Option<String>,
 
     #[serde(rename = "Description", skip_serializing_if = "Option::is_none")]
     description: Option<String>,
 
+    #[serde(rename = "Tags", skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+
     #[serde(rename = "Plan", skip_serializing_if = "Option::is_none")]
     plan: Option<DiskPlanRef>,
 
@@ -1228,17 +2985,20 @@ pub(crate) struct DiskInfo {
 }
 
 impl DiskInfo {
-    pub(crate) fn builder() -> DiskInfoBuilder {
+    /// a builder of the disk info
+    pub fn builder() -> DiskInfoBuilder {
         DiskInfoBuilder::new() 
     }
 
-    pub(crate) fn to_value(&self) -> Result<Value, Error> {
+    /// the json of the disk info as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
         serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::Disk, e.to_string()))
     }
 }
 
+/// a builder of [`DiskInfo`]
 #[derive(Debug)]
-pub(crate) struct DiskInfoBuilder {
+pub struct DiskInfoBuilder {
     name: Option<String>,
     description: Option<String>,
     plan: Option<DiskPlanRef>,
@@ -1261,45 +3021,54 @@ impl DiskInfoBuilder {
         }
     }
 
-    pub(crate) fn name(mut self, name: impl Into<String>) -> Self {
+    /// sets the name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
         self.name = Some(name.into());
         self
     }
 
-    pub(crate) fn description(mut self, description: impl Into<String>) -> Self {
+    /// sets the description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
         self.description = Some(description.into());
         self
     }
 
-    pub(crate) fn plan_id(mut self, plan_id: DiskPlanId) -> Self {
-        self.plan = Some(DiskPlanRef { id: plan_id });
+    /// sets the plan id
+    pub fn plan_id(mut self, plan_id: DiskPlanId) -> Self {
+        self.plan = Some(DiskPlanRef { id: plan_id, name: None });
         self
     }
 
-    pub(crate) fn source_archive_id(mut self, archive_id: ArchiveId) -> Self {
+    /// sets the source archive id
+    pub fn source_archive_id(mut self, archive_id: ArchiveId) -> Self {
         self.source_archive = Some(ArchiveRef { id: archive_id });
         self
     }
 
-    pub(crate) fn size_mb(mut self, size_mb: u64) -> Self {
+    /// sets the size in MB
+    pub fn size_mb(mut self, size_mb: u64) -> Self {
         self.size_mb = Some(size_mb);
         self
     }
     
-    pub(crate) fn connection(mut self, connection: DiskConnection) -> Self {
+    /// sets the connection
+    pub fn connection(mut self, connection: DiskConnection) -> Self {
         self.connection = Some(connection);
         self
     }
 
-    pub(crate) fn server_id(mut self, server_id: ServerId) -> Self {
+    /// sets the server id
+    pub fn server_id(mut self, server_id: ServerId) -> Self {
         self.server = Some(ServerRef { id: server_id });
         self
     }
 
-    pub(crate) fn build(self) -> DiskInfo {
+    /// builds the disk info
+    pub fn build(self) -> DiskInfo {
         DiskInfo {
             name: self.name,
             description: self.description,
+            tags: None,
             plan: self.plan,
             source_archive: self.source_archive,
             size_mb: self.size_mb,
@@ -1309,24 +3078,40 @@ impl DiskInfoBuilder {
     }
 }
 
+/// how a disk is connected to the server
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) enum DiskConnection {
+pub enum DiskConnection {
+    /// the virtio driver
     #[serde(rename = "virtio")]
     Virtio,
 
+    /// the ide emulation
     #[serde(rename = "ide")]
     Ide,
 }
 
+impl fmt::Display for DiskConnection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Virtio => write!(f, "virtio"),
+            Self::Ide => write!(f, "ide"),
+        }
+    }
+}
+
 
+/// the disk edit, the settings written to the disk on creation
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct DiskConfig {
+pub struct DiskConfig {
     #[serde(rename = "HostName", skip_serializing_if = "Option::is_none")]
     host_name: Option<String>,
 
     #[serde(rename = "SSHKeys", skip_serializing_if = "Option::is_none")]
     ssh_keys: Option<Vec<SshPublicKeyRef>>,
 
+    #[serde(rename = "Password", skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+
     #[serde(rename = "ChangePartitionUUID", skip_serializing_if = "Option::is_none")]
     change_partition_uuid: Option<bool>,
 
@@ -1347,19 +3132,23 @@ pub(crate) struct DiskConfig {
 }
 
 impl DiskConfig {
-    pub(crate) fn builder() -> DiskConfigBuilder {
+    /// a builder of the disk config
+    pub fn builder() -> DiskConfigBuilder {
         DiskConfigBuilder::new() 
     }
 
-    pub(crate) fn to_value(&self) -> Result<Value, Error> {
+    /// the json of the disk config as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
         serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::Disk, e.to_string()))
     }
 }
 
+/// a builder of [`DiskConfig`]
 #[derive(Debug)]
-pub(crate) struct DiskConfigBuilder {
+pub struct DiskConfigBuilder {
     host_name: Option<String>,
     ssh_keys: Option<Vec<SshPublicKeyRef>>,
+    password: Option<String>,
     change_partition_uuid: Option<bool>,
     disable_pw_auth: Option<bool>,
     user_ip_address: Option<Ipv4Addr>,
@@ -1373,6 +3162,7 @@ impl DiskConfigBuilder {
         Self {
             host_name: None,
             ssh_keys: None,
+            password: None,
             change_partition_uuid: None,
             disable_pw_auth: None,
             user_ip_address: None,
@@ -1382,42 +3172,56 @@ impl DiskConfigBuilder {
         }
     }
 
-    pub(crate) fn host_name(mut self, host_name: impl Into<String>) -> Self {
+    /// sets the host name
+    pub fn host_name(mut self, host_name: impl Into<String>) -> Self {
         self.host_name = Some(host_name.into());
         self
     }
 
-    pub(crate) fn ssh_key_ids(mut self, ssh_key_ids: Vec<SshPublicKeyId>) -> Self {
+    /// sets the ssh key ids
+    pub fn ssh_key_ids(mut self, ssh_key_ids: Vec<SshPublicKeyId>) -> Self {
         self.ssh_keys = Some(ssh_key_ids.into_iter().map(|id| SshPublicKeyRef { id }).collect());
         self
     }
 
-    pub(crate) fn change_partition_uuid(mut self, change_partition_uuid: bool) -> Self {
+    /// sets the password
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// sets whether to change the partition uuid
+    pub fn change_partition_uuid(mut self, change_partition_uuid: bool) -> Self {
         self.change_partition_uuid = Some(change_partition_uuid);
         self
     }
 
-    pub(crate) fn disable_pw_auth(mut self, disable_pw_auth: bool) -> Self {
+    /// sets whether to disable the password authentication
+    pub fn disable_pw_auth(mut self, disable_pw_auth: bool) -> Self {
         self.disable_pw_auth = Some(disable_pw_auth);
         self
     }
 
-    pub(crate) fn user_ip_address(mut self, user_ip_address: Ipv4Addr) -> Self {
+    /// sets the user ip address
+    pub fn user_ip_address(mut self, user_ip_address: Ipv4Addr) -> Self {
         self.user_ip_address = Some(user_ip_address);
         self
     }
 
-    pub(crate) fn user_subnet(mut self, user_subnet: Ipv4Net) -> Self {
+    /// sets the user subnet
+    pub fn user_subnet(mut self, user_subnet: Ipv4Net) -> Self {
         self.user_subnet = Some(user_subnet);
         self
     }
 
-    pub(crate) fn enable_dhcp(mut self, enable_dhcp: bool) -> Self {
+    /// sets whether to enable dhcp
+    pub fn enable_dhcp(mut self, enable_dhcp: bool) -> Self {
         self.enable_dhcp = Some(enable_dhcp);
         self
     }
 
-    pub(crate) fn setup_shell_note(mut self, id: NoteId, variables: Value) -> Self {
+    /// sets the setup shell note
+    pub fn setup_shell_note(mut self, id: NoteId, variables: Value) -> Self {
         if self.notes.is_none() {
             self.notes = Some(Vec::new());
         }
@@ -1425,10 +3229,20 @@ impl DiskConfigBuilder {
         self
     }
 
-    pub(crate) fn build(self) -> DiskConfig {
+    /// run in the order, after the ones already added
+    pub fn note_id_and_variables_pairs(mut self, pairs: Vec<(NoteId, Value)>) -> Self {
+        for (id, variables) in pairs {
+            self = self.setup_shell_note(id, variables);
+        }
+        self
+    }
+
+    /// builds the disk config
+    pub fn build(self) -> DiskConfig {
         DiskConfig {
             host_name: self.host_name,
             ssh_keys: self.ssh_keys,
+            password: self.password,
             change_partition_uuid: self.change_partition_uuid,
             disable_pw_auth: self.disable_pw_auth,
             user_ip_address: self.user_ip_address,
@@ -1441,8 +3255,9 @@ impl DiskConfigBuilder {
 
 // DiskPlan
 
+/// the id of a disk plan
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct DiskPlanId(pub ResourceId);
+pub struct DiskPlanId(pub ResourceId);
 
 impl fmt::Display for DiskPlanId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -1456,39 +3271,44 @@ impl From<String> for DiskPlanId {
     }
 }
 
+/// a disk plan referred to by id
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct DiskPlanRef {
+pub struct DiskPlanRef {
     #[serde(rename = "ID")]
     id: DiskPlanId,
+
+    // only in responses
+    #[serde(rename = "Name", skip_serializing, default)]
+    name: Option<String>,
 }
 
 /* comment out unused
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct DiskPlan {
+pub struct DiskPlan {
     #[serde(rename = "ID")]
     id: DiskPlanId,
 }
 
 impl DiskPlan {
-    pub(crate) async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
-        let resource_value = ResourceKind::DiskPlan.search_by_name(name).await?;
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::DiskPlan.search_by_name(name, ResourceScope::User).await?;
         resource_value.map(|resource_value| Self::from_value(resource_value)).transpose()
     }
 
-    pub(crate) async fn wait_available(disk_plan_id: impl Borrow<DiskPlanId>) -> Result<(), Error> {
+    pub async fn wait_available(disk_plan_id: impl Borrow<DiskPlanId>) -> Result<(), Error> {
         let disk_plan_id = disk_plan_id.borrow();
         ResourceKind::DiskPlan.wait_available(disk_plan_id.to_string()).await
     }
 
-    pub(crate) fn from_value(value: Value) -> Result<Self, Error> {
+    pub fn from_value(value: Value) -> Result<Self, Error> {
         serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::DiskPlan, e.to_string()))
     }
 
-    pub(crate) fn kind() -> ResourceKind {
+    pub fn kind() -> ResourceKind {
         ResourceKind::DiskPlan
     }
 
-    pub(crate) fn id(&self) -> &DiskPlanId {
+    pub fn id(&self) -> &DiskPlanId {
         &self.id
     }
 }
@@ -1497,8 +3317,9 @@ impl DiskPlan {
 
 // SshPublicKey
 
+/// the id of a ssh public key
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct SshPublicKeyId(pub ResourceId);
+pub struct SshPublicKeyId(pub ResourceId);
 
 impl fmt::Display for SshPublicKeyId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -1512,14 +3333,16 @@ impl From<String> for SshPublicKeyId {
     }
 }
 
+/// a ssh public key referred to by id
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct SshPublicKeyRef {
+pub struct SshPublicKeyRef {
     #[serde(rename = "ID")]
     id: SshPublicKeyId,
 }
 
+/// a ssh public key registered to the account
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct SshPublicKey {
+pub struct SshPublicKey {
     #[serde(rename = "ID")]
     id: SshPublicKeyId,
 
@@ -1528,39 +3351,68 @@ pub(crate) struct SshPublicKey {
 }
 
 impl SshPublicKey {
-    pub(crate) fn public_key(&self) -> &str {
+    /// the public key of the ssh public key
+    pub fn public_key(&self) -> &str {
         &self.info.public_key.as_ref().expect("must be set")
     }
 
-    pub(crate) async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
-        let resource_value = ResourceKind::SshPublicKey.search_by_name(name).await?;
+    /// fetches the ssh public key found by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::SshPublicKey.search_by_name(name, ResourceScope::User).await?;
         resource_value.map(|resource_value| Self::from_value(resource_value)).transpose()
     }
 
-    pub(crate) async fn create(info: SshPublicKeyInfo) -> Result<SshPublicKey, Error> {
+    /// searches the ssh public keys whose names contain the name
+    pub async fn search_by_name(name: impl AsRef<str>) -> Result<Vec<Self>, Error> {
+        let resource_values = ResourceKind::SshPublicKey.search_all_by_name(name, ResourceScope::User).await?;
+        resource_values.into_iter().map(Self::from_value).collect()
+    }
+
+    /// creates the ssh public key
+    pub async fn create(info: SshPublicKeyInfo) -> Result<SshPublicKey, Error> {
         let req_value = info.to_value()?;
         let res_value = ResourceKind::SshPublicKey.create(req_value).await?;
         SshPublicKey::from_value(res_value)
     }
 
-    pub(crate) fn from_value(value: Value) -> Result<Self, Error> {
+    /// deletes the ssh public key
+    pub async fn delete(ssh_public_key_id: impl Borrow<SshPublicKeyId>) -> Result<(), Error> {
+        let ssh_public_key_id = ssh_public_key_id.borrow();
+        ResourceKind::SshPublicKey.delete(ssh_public_key_id.to_string()).await
+    }
+
+    /// waits until the ssh public key is deleted
+    pub async fn wait_delete(ssh_public_key_id: impl Borrow<SshPublicKeyId>) -> Result<(), Error> {
+        let ssh_public_key_id = ssh_public_key_id.borrow();
+        ResourceKind::SshPublicKey.wait_delete(ssh_public_key_id.to_string()).await
+    }
+
+    /// the ssh public key from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
         serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::SshPublicKey, e.to_string()))
     }
 
     /* commented out because it's not used
-    pub(crate) fn kind() -> ResourceKind {
+    pub fn kind() -> ResourceKind {
         ResourceKind::SshPublicKey
     }
     */
 
-    pub(crate) fn id(&self) -> &SshPublicKeyId {
+    /// the id of the ssh public key
+    pub fn id(&self) -> &SshPublicKeyId {
         &self.id
     }
+
+    /// the name of the ssh public key
+    pub fn name(&self) -> Option<&str> {
+        self.info.name.as_deref()
+    }
 }
 
 
+/// the fields of a ssh public key to create
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct SshPublicKeyInfo {
+pub struct SshPublicKeyInfo {
     #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
     name: Option<String>,
 
@@ -1572,17 +3424,20 @@ pub(crate) struct SshPublicKeyInfo {
 }
 
 impl SshPublicKeyInfo {
-    pub(crate) fn builder() -> SshPublicKeyInfoBuilder {
+    /// a builder of the ssh public key info
+    pub fn builder() -> SshPublicKeyInfoBuilder {
         SshPublicKeyInfoBuilder::new()
     }
 
-    pub(crate) fn to_value(&self) -> Result<Value, Error> {
+    /// the json of the ssh public key info as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
         serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::SshPublicKey, e.to_string()))
     }
 }
 
+/// a builder of [`SshPublicKeyInfo`]
 #[derive(Debug)]
-pub(crate) struct SshPublicKeyInfoBuilder {
+pub struct SshPublicKeyInfoBuilder {
     name: Option<String>,
     description: Option<String>,
     public_key: Option<String>,
@@ -1597,22 +3452,26 @@ impl SshPublicKeyInfoBuilder {
         }
     }
 
-    pub(crate) fn name(mut self, name: impl Into<String>) -> Self {
+    /// sets the name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
         self.name = Some(name.into());
         self
     }
 
-    pub(crate) fn description(mut self, description: impl Into<String>) -> Self {
+    /// sets the description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
         self.description = Some(description.into());
         self
     }
 
-    pub(crate) fn public_key(mut self, public_key: impl Into<String>) -> Self {
+    /// sets the public key
+    pub fn public_key(mut self, public_key: impl Into<String>) -> Self {
         self.public_key = Some(public_key.into());
         self
     }
 
-    pub(crate) fn build(self) -> SshPublicKeyInfo {
+    /// builds the ssh public key info
+    pub fn build(self) -> SshPublicKeyInfo {
         SshPublicKeyInfo {
             name: self.name,
             description: self.description,
@@ -1623,8 +3482,9 @@ impl SshPublicKeyInfoBuilder {
 
 // Note
 
+/// the id of a note
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct NoteId(pub ResourceId);
+pub struct NoteId(pub ResourceId);
 
 impl fmt::Display for NoteId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -1638,8 +3498,9 @@ impl From<String> for NoteId {
     }
 }
 
+/// a note referred to by id
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct NoteRef {
+pub struct NoteRef {
     #[serde(rename = "ID")]
     id: NoteId,
 
@@ -1647,67 +3508,168 @@ pub(crate) struct NoteRef {
     variables: Value,
 }
 
+/// the variables the official startup scripts of sakura cloud take, not set ones are left to the defaults of the scripts
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OfficialNoteVariables {
+    /// install usacloud
+    #[serde(rename = "usacloud", default, skip_serializing_if = "Option::is_none")]
+    pub usacloud: Option<bool>,
+
+    /// update the packages at the first boot
+    #[serde(rename = "updatepackage", default, skip_serializing_if = "Option::is_none")]
+    pub updatepackage: Option<bool>,
+}
+
+impl OfficialNoteVariables {
+    /// the fields of other are overwritten
+    pub fn merge(&self, other: &Self) -> Self {
+        Self {
+            usacloud: other.usacloud.or(self.usacloud),
+            updatepackage: other.updatepackage.or(self.updatepackage),
+        }
+    }
+}
+
+/// a note, e.g. a startup script
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct Note {
+pub struct Note {
     #[serde(rename = "ID")]
     id: NoteId,
 
+    #[serde(rename = "Availability", skip_serializing_if = "Option::is_none")]
+    availability: Option<String>,
+
     #[serde(flatten)]
     info: NoteInfo,
 }
 
 impl Note {
-    pub(crate) fn from_value(value: Value) -> Result<Self, Error> {
+    /// the note from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
         serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Note, e.to_string()))
     }
 
-    pub(crate) async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
-        let resource_value = ResourceKind::Note.search_by_name(name).await?;
+    /// fetches the note found by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::Note.search_by_name(name, ResourceScope::User).await?;
         resource_value.map(|resource_value| Self::from_value(resource_value)).transpose()
     }
 
-    pub(crate) async fn get(id: impl Borrow<NoteId>) -> Result<Self, Error> {
+    /// searches the notes whose names contain the name
+    pub async fn search_by_name(name: impl AsRef<str>) -> Result<Vec<Self>, Error> {
+        let resource_values = ResourceKind::Note.search_all_by_name(name, ResourceScope::User).await?;
+        resource_values.into_iter().map(Self::from_value).collect()
+    }
+
+    /// the content is not in the search result, get each note for it
+    pub async fn search_all(scope: ResourceScope) -> Result<Vec<Self>, Error> {
+        let resource_values = ResourceKind::Note.search_all(&SearchQuery::new()).await?;
+        resource_values.into_iter().filter(|resource_value| scope.matches(resource_value)).map(Self::from_value).collect()
+    }
+
+    /// the startup scripts published by sakura cloud, e.g. the one taking OfficialNoteVariables
+    pub async fn official_startup_script(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::Note.search_by_name(name, ResourceScope::Shared).await?;
+        resource_value.map(Self::from_value).transpose()
+    }
+
+    /// fetches the note by id
+    pub async fn get(id: impl Borrow<NoteId>) -> Result<Self, Error> {
         let id = id.borrow();
         let resource_value = ResourceKind::Note.get(id.to_string()).await?;
         Note::from_value(resource_value)
     }
 
-    pub(crate) async fn create(info: NoteInfo) -> Result<Note, Error> {
+    /// creates the note
+    pub async fn create(info: NoteInfo) -> Result<Note, Error> {
         let info_value = info.to_value()?;
         let res_value = ResourceKind::Note.create(info_value).await?;
         Note::from_value(res_value)
     }
 
-    pub(crate) async fn update(note_id: impl Borrow<NoteId>, info: NoteInfo) -> Result<(), Error> {
+    /// updates the note to the info
+    pub async fn update(note_id: impl Borrow<NoteId>, info: NoteInfo) -> Result<(), Error> {
         let note_id = note_id.borrow();
         let info_value = info.to_value()?;
         ResourceKind::Note.update(note_id.to_string(), info_value).await
     }
 
-    pub(crate) async fn wait_available(note_id: impl Borrow<NoteId>) -> Result<(), Error> {
+    /// true if updated
+    pub async fn update_content_if_needed(note_id: impl Borrow<NoteId>, content: impl AsRef<str>) -> Result<bool, Error> {
+        let note_id = note_id.borrow();
+        let content = content.as_ref();
+        let note = Note::get(note_id).await?;
+        if note.content() == content {
+            return Ok(false);
+        }
+
+        let info = NoteInfo::builder()
+            .content(content)
+            .build();
+        Note::update(note_id, info).await?;
+        Ok(true)
+    }
+
+    /// deletes the note
+    pub async fn delete(note_id: impl Borrow<NoteId>) -> Result<(), Error> {
+        let note_id = note_id.borrow();
+        ResourceKind::Note.delete(note_id.to_string()).await
+    }
+
+    /// waits until the note is deleted
+    pub async fn wait_delete(note_id: impl Borrow<NoteId>) -> Result<(), Error> {
+        let note_id = note_id.borrow();
+        ResourceKind::Note.wait_delete(note_id.to_string()).await
+    }
+
+    /// waits until the note is available
+    pub async fn wait_available(note_id: impl Borrow<NoteId>) -> Result<(), Error> {
         let note_id = note_id.borrow();
         ResourceKind::Note.wait_available(note_id.to_string()).await
     }
 
     /* commented out because it's not used
-    pub(crate) fn kind() -> ResourceKind {
+    pub fn kind() -> ResourceKind {
         ResourceKind::Note
     }
     */
 
-    pub(crate) fn id(&self) -> &NoteId {
+    /// the id of the note
+    pub fn id(&self) -> &NoteId {
         &self.id
     }
 
-    pub(crate) fn content(&self) -> &str {
+    /// the name of the note
+    pub fn name(&self) -> Option<&str> {
+        self.info.name.as_deref()
+    }
+
+    /// the content of the note
+    pub fn content(&self) -> &str {
         self.info.content.as_deref().expect("responsibility of the caller to ensure content is set")
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct NoteInfo {
-    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
-    name: Option<String>,
+    /// the class of the note
+    pub fn class(&self) -> Option<NoteClass> {
+        self.info.class
+    }
+
+    /// the availability of the note
+    pub fn availability(&self) -> Option<Availability> {
+        self.availability.as_deref().and_then(|availability| availability.parse().ok())
+    }
+
+    /// whether the note is available
+    pub fn is_available(&self) -> bool {
+        self.availability() == Some(Availability::Available)
+    }
+}
+
+/// the fields of a note to create or update
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteInfo {
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
 
     #[serde(rename = "Class", skip_serializing_if = "Option::is_none")]
     class: Option<NoteClass>,
@@ -1720,17 +3682,20 @@ pub(crate) struct NoteInfo {
 }
 
 impl NoteInfo {
-    pub(crate) fn builder() -> NoteInfoBuilder {
+    /// a builder of the note info
+    pub fn builder() -> NoteInfoBuilder {
         NoteInfoBuilder::new()
     }
 
-    pub(crate) fn to_value(&self) -> Result<Value, Error> {
+    /// the json of the note info as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
         serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::Note, e.to_string()))
     }
 }
 
+/// a builder of [`NoteInfo`]
 #[derive(Debug)]
-pub(crate) struct NoteInfoBuilder {
+pub struct NoteInfoBuilder {
     name: Option<String>,
     class: Option<NoteClass>,
     description: Option<String>,
@@ -1747,27 +3712,32 @@ impl NoteInfoBuilder {
         }
     }
 
-    pub(crate) fn name(mut self, name: impl Into<String>) -> Self {
+    /// sets the name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
         self.name = Some(name.into());
         self
     }
 
-    pub(crate) fn class(mut self, class: NoteClass) -> Self {
+    /// sets the class
+    pub fn class(mut self, class: NoteClass) -> Self {
         self.class = Some(class);
         self
     }
 
-    pub(crate) fn description(mut self, description: impl Into<String>) -> Self {
+    /// sets the description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
         self.description = Some(description.into());
         self
     }
 
-    pub(crate) fn content(mut self, content: impl Into<String>) -> Self {
+    /// sets the content
+    pub fn content(mut self, content: impl Into<String>) -> Self {
         self.content = Some(content.into());
         self
     }
 
-    pub(crate) fn build(self) -> NoteInfo {
+    /// builds the note info
+    pub fn build(self) -> NoteInfo {
         NoteInfo {
             name: self.name,
             class: self.class,
@@ -1777,21 +3747,38 @@ impl NoteInfoBuilder {
     }
 }
 
+/// the classes of the notes
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) enum NoteClass {
+pub enum NoteClass {
+    /// a shell script
     #[serde(rename = "shell")]
     Shell,
+
+    /// cloud-init, some of the official notes are
+    #[serde(rename = "yaml_cloud_config")]
+    YamlCloudConfig,
+}
+
+impl fmt::Display for NoteClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shell => write!(f, "shell"),
+            Self::YamlCloudConfig => write!(f, "yaml_cloud_config"),
+        }
+    }
 }
 
 
+/// an ip address in the json of the api
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct IpAddressRef {
+pub struct IpAddressRef {
     #[serde(rename = "IPAddress")]
     ip_address: Ipv4Addr,
 }
 
+/// a network by the default route and the mask length
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct Ipv4Net {
+pub struct Ipv4Net {
     #[serde(rename = "DefaultRoute")]
     default_route: Ipv4Addr,
 
@@ -1800,7 +3787,8 @@ pub(crate) struct Ipv4Net {
 }
 
 impl Ipv4Net {
-    pub(crate) fn new(default_route: Ipv4Addr, network_mask_len: u8) -> Self {
+    /// the network of the default route and the mask length
+    pub fn new(default_route: Ipv4Addr, network_mask_len: u8) -> Self {
         Self {
             default_route: default_route,
             network_mask_len: network_mask_len,
@@ -1808,133 +3796,2179 @@ impl Ipv4Net {
     }
 }
 
+/// a network as one string, e.g. "192.168.0.0/24"
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct SingleLineIpv4Net(pub(crate) String);
+pub struct SingleLineIpv4Net(pub String);
 
-// InterfaceDriver
+// CommonServiceItem
+
+/// the id of a common service item
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) enum InterfaceDriver {
-    #[serde(rename = "virtio")]
-    Virtio,
+pub struct CommonServiceItemId(pub ResourceId);
 
-    #[serde(rename = "e1000")]
-    E1000,
+impl fmt::Display for CommonServiceItemId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
-impl Default for InterfaceDriver {
-    fn default() -> Self {
-        Self::Virtio
+impl From<String> for CommonServiceItemId {
+    fn from(s: String) -> Self {
+        Self(s.into())
+    }
+}
+
+/// the provider of a common service item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommonServiceItemProvider {
+    #[serde(rename = "Class")]
+    class: CommonServiceItemClass,
+}
+
+/// the classes of the common service items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommonServiceItemClass {
+    /// a simple monitor
+    #[serde(rename = "simplemon")]
+    SimpleMonitor,
+
+    /// an auto backup
+    #[serde(rename = "autobackup")]
+    AutoBackup,
+
+    /// a sim
+    #[serde(rename = "sim")]
+    Sim,
+
+    /// an enhanced load balancer
+    #[serde(rename = "proxylb")]
+    ProxyLb,
+
+    /// a dns zone
+    #[serde(rename = "dns")]
+    Dns,
+}
+
+/// "True" / "False" strings used in settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SettingBool {
+    /// "True"
+    True,
+    /// "False"
+    False,
+}
+
+impl From<bool> for SettingBool {
+    fn from(b: bool) -> Self {
+        if b { Self::True } else { Self::False }
+    }
+}
+
+// SimpleMonitor
+
+/// a simple monitor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimpleMonitor {
+    #[serde(rename = "ID")]
+    id: CommonServiceItemId,
+
+    #[serde(flatten)]
+    info: SimpleMonitorInfo,
+}
+
+impl SimpleMonitor {
+    /// fetches the simple monitor having the tag, none if there is none
+    pub async fn get_by_tag(tag: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::CommonServiceItem.search_one_by_tags(vec![tag.as_ref()], ResourceScope::User).await?;
+        resource_value.map(Self::from_value).transpose()
+    }
+
+    /// creates the simple monitor
+    pub async fn create(info: SimpleMonitorInfo) -> Result<SimpleMonitor, Error> {
+        let info_value = info.to_value()?;
+        let res_value = ResourceKind::CommonServiceItem.create(info_value).await?;
+        SimpleMonitor::from_value(res_value)
+    }
+
+    /// updates the simple monitor to the info
+    pub async fn update(simple_monitor_id: impl Borrow<CommonServiceItemId>, info: SimpleMonitorInfo) -> Result<(), Error> {
+        let simple_monitor_id = simple_monitor_id.borrow();
+        let info_value = info.to_value()?;
+        ResourceKind::CommonServiceItem.update(simple_monitor_id.to_string(), info_value).await
+    }
+
+    /// deletes the simple monitor
+    pub async fn delete(simple_monitor_id: impl Borrow<CommonServiceItemId>) -> Result<(), Error> {
+        let simple_monitor_id = simple_monitor_id.borrow();
+        ResourceKind::CommonServiceItem.delete(simple_monitor_id.to_string()).await
+    }
+
+    /// waits until the simple monitor is deleted
+    pub async fn wait_delete(simple_monitor_id: impl Borrow<CommonServiceItemId>) -> Result<(), Error> {
+        let simple_monitor_id = simple_monitor_id.borrow();
+        ResourceKind::CommonServiceItem.wait_delete(simple_monitor_id.to_string()).await
+    }
+
+    /// the simple monitor from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::CommonServiceItem, e.to_string()))
+    }
+
+    /// the id of the simple monitor
+    pub fn id(&self) -> &CommonServiceItemId {
+        &self.id
     }
 }
 
+/// the fields of a simple monitor to create or update
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct Instance {
+pub struct SimpleMonitorInfo {
+    // simple monitor's name must be the target
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    #[serde(rename = "Description", skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    #[serde(rename = "Tags", skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+
+    #[serde(rename = "Provider", skip_serializing_if = "Option::is_none")]
+    provider: Option<CommonServiceItemProvider>,
+
     #[serde(rename = "Status", skip_serializing_if = "Option::is_none")]
-    status: Option<InstanceStatus>,
+    status: Option<SimpleMonitorStatus>,
+
+    #[serde(rename = "Settings", skip_serializing_if = "Option::is_none")]
+    settings: Option<SimpleMonitorSettings>,
 }
 
-impl Instance {
-    pub(crate) fn from_value(value: Value) -> Result<Self, Error> {
-        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Instance, e.to_string()))
+impl SimpleMonitorInfo {
+    /// a builder of the simple monitor info
+    pub fn builder() -> SimpleMonitorInfoBuilder {
+        SimpleMonitorInfoBuilder::new()
+    }
+
+    /// the json of the simple monitor info as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
+        serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::CommonServiceItem, e.to_string()))
+    }
+}
+
+/// the target of a simple monitor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimpleMonitorStatus {
+    #[serde(rename = "Target")]
+    target: String,
+}
+
+/// the settings of a simple monitor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimpleMonitorSettings {
+    #[serde(rename = "SimpleMonitor")]
+    simple_monitor: SimpleMonitorSetting,
+}
+
+/// the check and the notifications of a simple monitor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimpleMonitorSetting {
+    #[serde(rename = "DelayLoop", skip_serializing_if = "Option::is_none")]
+    delay_loop: Option<u64>,
+
+    #[serde(rename = "HealthCheck")]
+    health_check: SimpleMonitorHealthCheck,
+
+    #[serde(rename = "Enabled")]
+    enabled: SettingBool,
+
+    #[serde(rename = "NotifyEmail", skip_serializing_if = "Option::is_none")]
+    notify_email: Option<SimpleMonitorNotifyEmail>,
+
+    #[serde(rename = "NotifySlack", skip_serializing_if = "Option::is_none")]
+    notify_slack: Option<SimpleMonitorNotifySlack>,
+}
+
+/// how a simple monitor checks the target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimpleMonitorHealthCheck {
+    #[serde(rename = "Protocol")]
+    protocol: SimpleMonitorProtocol,
+
+    // port and status are strings in the api
+    #[serde(rename = "Port", skip_serializing_if = "Option::is_none")]
+    port: Option<String>,
+
+    #[serde(rename = "Path", skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+
+    #[serde(rename = "Status", skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+}
+
+impl SimpleMonitorHealthCheck {
+    /// a ping check
+    pub fn ping() -> Self {
+        Self { protocol: SimpleMonitorProtocol::Ping, port: None, path: None, status: None }
+    }
+
+    /// a tcp connect check of the port
+    pub fn tcp(port: u16) -> Self {
+        Self { protocol: SimpleMonitorProtocol::Tcp, port: Some(port.to_string()), path: None, status: None }
+    }
+
+    /// a http check of the port and the path, expecting the status code
+    pub fn http(port: u16, path: impl Into<String>, status: u16) -> Self {
+        Self { protocol: SimpleMonitorProtocol::Http, port: Some(port.to_string()), path: Some(path.into()), status: Some(status.to_string()) }
     }
 }
 
+/// the protocols of the simple monitor checks
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) enum InstanceStatus {
-    #[serde(rename = "cleaning")]
-    Cleaning,
+pub enum SimpleMonitorProtocol {
+    /// icmp ping
+    #[serde(rename = "ping")]
+    Ping,
+
+    /// tcp connect
+    #[serde(rename = "tcp")]
+    Tcp,
+
+    /// http get
+    #[serde(rename = "http")]
+    Http,
+}
 
-    #[serde(rename = "up")]
-    Up,
+/// the email notification of a simple monitor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimpleMonitorNotifyEmail {
+    #[serde(rename = "Enabled")]
+    enabled: SettingBool,
+}
 
-    #[serde(rename = "down")]
-    Down,
+/// the slack notification of a simple monitor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimpleMonitorNotifySlack {
+    #[serde(rename = "Enabled")]
+    enabled: SettingBool,
+
+    #[serde(rename = "IncomingWebhooksURL", skip_serializing_if = "Option::is_none")]
+    incoming_webhooks_url: Option<String>,
 }
 
-impl fmt::Display for InstanceStatus {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::Cleaning => write!(f, "cleaning"),
-            Self::Up => write!(f, "up"),
-            Self::Down => write!(f, "down"),
+/// a builder of [`SimpleMonitorInfo`]
+#[derive(Debug)]
+pub struct SimpleMonitorInfoBuilder {
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+    target: Option<String>,
+    delay_loop: Option<u64>,
+    health_check: SimpleMonitorHealthCheck,
+    notify_email: bool,
+    slack_webhook_url: Option<String>,
+}
+
+impl SimpleMonitorInfoBuilder {
+    fn new() -> Self {
+        Self {
+            description: None,
+            tags: None,
+            target: None,
+            delay_loop: None,
+            health_check: SimpleMonitorHealthCheck::ping(),
+            notify_email: false,
+            slack_webhook_url: None,
+        }
+    }
+
+    /// sets the description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// sets the tags
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    /// sets the target
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// sets the delay loop
+    pub fn delay_loop(mut self, delay_loop: u64) -> Self {
+        self.delay_loop = Some(delay_loop);
+        self
+    }
+
+    /// sets the health check
+    pub fn health_check(mut self, health_check: SimpleMonitorHealthCheck) -> Self {
+        self.health_check = health_check;
+        self
+    }
+
+    /// sets the notify email
+    pub fn notify_email(mut self, notify_email: bool) -> Self {
+        self.notify_email = notify_email;
+        self
+    }
+
+    /// sets the slack webhook url
+    pub fn slack_webhook_url(mut self, slack_webhook_url: impl Into<String>) -> Self {
+        self.slack_webhook_url = Some(slack_webhook_url.into());
+        self
+    }
+
+    /// builds the simple monitor info
+    pub fn build(self) -> SimpleMonitorInfo {
+        SimpleMonitorInfo {
+            name: self.target.clone(),
+            description: self.description,
+            tags: self.tags,
+            provider: Some(CommonServiceItemProvider { class: CommonServiceItemClass::SimpleMonitor }),
+            status: self.target.map(|target| SimpleMonitorStatus { target }),
+            settings: Some(SimpleMonitorSettings {
+                simple_monitor: SimpleMonitorSetting {
+                    delay_loop: self.delay_loop,
+                    health_check: self.health_check,
+                    enabled: SettingBool::True,
+                    notify_email: Some(SimpleMonitorNotifyEmail { enabled: self.notify_email.into() }),
+                    notify_slack: Some(SimpleMonitorNotifySlack {
+                        enabled: self.slack_webhook_url.is_some().into(),
+                        incoming_webhooks_url: self.slack_webhook_url,
+                    }),
+                },
+            }),
         }
     }
 }
 
+// AutoBackup
 
+/// an auto backup of a disk
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ApiKeyId(pub ResourceId);
+pub struct AutoBackup {
+    #[serde(rename = "ID")]
+    id: CommonServiceItemId,
+
+    #[serde(flatten)]
+    info: AutoBackupInfo,
+}
+
+impl AutoBackup {
+    /// fetches the auto backup found by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::CommonServiceItem.search_by_name(name, ResourceScope::User).await?;
+        resource_value.map(Self::from_value).transpose()
+    }
+
+    /// creates the auto backup
+    pub async fn create(info: AutoBackupInfo) -> Result<AutoBackup, Error> {
+        let info_value = info.to_value()?;
+        let res_value = ResourceKind::CommonServiceItem.create(info_value).await?;
+        AutoBackup::from_value(res_value)
+    }
+
+    /// updates the auto backup to the info
+    pub async fn update(auto_backup_id: impl Borrow<CommonServiceItemId>, info: AutoBackupInfo) -> Result<(), Error> {
+        let auto_backup_id = auto_backup_id.borrow();
+        let info_value = info.to_value()?;
+        ResourceKind::CommonServiceItem.update(auto_backup_id.to_string(), info_value).await
+    }
 
+    /// deletes the auto backup
+    pub async fn delete(auto_backup_id: impl Borrow<CommonServiceItemId>) -> Result<(), Error> {
+        let auto_backup_id = auto_backup_id.borrow();
+        ResourceKind::CommonServiceItem.delete(auto_backup_id.to_string()).await
+    }
+
+    /// waits until the auto backup is deleted
+    pub async fn wait_delete(auto_backup_id: impl Borrow<CommonServiceItemId>) -> Result<(), Error> {
+        let auto_backup_id = auto_backup_id.borrow();
+        ResourceKind::CommonServiceItem.wait_delete(auto_backup_id.to_string()).await
+    }
+
+    /// the auto backup from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::CommonServiceItem, e.to_string()))
+    }
+
+    /// the id of the auto backup
+    pub fn id(&self) -> &CommonServiceItemId {
+        &self.id
+    }
+
+    /// the disk id of the auto backup
+    pub fn disk_id(&self) -> Option<&DiskId> {
+        self.info.status.as_ref().map(|status| &status.disk_id)
+    }
+}
+
+/// the fields of an auto backup to create or update
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ApiKeyRef {
-    #[serde(rename = "ID")]
-    id: ApiKeyId,
+pub struct AutoBackupInfo {
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    #[serde(rename = "Description", skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    #[serde(rename = "Tags", skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+
+    #[serde(rename = "Provider", skip_serializing_if = "Option::is_none")]
+    provider: Option<CommonServiceItemProvider>,
+
+    #[serde(rename = "Status", skip_serializing_if = "Option::is_none")]
+    status: Option<AutoBackupStatus>,
+
+    #[serde(rename = "Settings", skip_serializing_if = "Option::is_none")]
+    settings: Option<AutoBackupSettings>,
+}
+
+impl AutoBackupInfo {
+    /// a builder of the auto backup info
+    pub fn builder() -> AutoBackupInfoBuilder {
+        AutoBackupInfoBuilder::new()
+    }
+
+    /// the json of the auto backup info as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
+        serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::CommonServiceItem, e.to_string()))
+    }
+}
+
+/// the disk of an auto backup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoBackupStatus {
+    #[serde(rename = "DiskId")]
+    disk_id: DiskId,
+
+    #[serde(rename = "ZoneName", skip_serializing_if = "Option::is_none")]
+    zone_name: Option<String>,
+}
+
+/// the settings of an auto backup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoBackupSettings {
+    #[serde(rename = "Autobackup")]
+    auto_backup: AutoBackupSetting,
+}
+
+/// the backup days of an auto backup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoBackupSetting {
+    // only "weekdays" is supported by the api
+    #[serde(rename = "BackupSpanType")]
+    backup_span_type: String,
+
+    #[serde(rename = "BackupSpanWeekdays")]
+    backup_span_weekdays: Vec<Weekday>,
+
+    #[serde(rename = "MaximumNumberOfArchives")]
+    maximum_number_of_archives: u8,
+}
+
+/// the days of the week of the auto backups
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weekday {
+    /// sunday
+    #[serde(rename = "sun")]
+    Sun,
+
+    /// monday
+    #[serde(rename = "mon")]
+    Mon,
+
+    /// tuesday
+    #[serde(rename = "tue")]
+    Tue,
+
+    /// wednesday
+    #[serde(rename = "wed")]
+    Wed,
+
+    /// thursday
+    #[serde(rename = "thu")]
+    Thu,
+
+    /// friday
+    #[serde(rename = "fri")]
+    Fri,
+
+    /// saturday
+    #[serde(rename = "sat")]
+    Sat,
+}
+
+/// a builder of [`AutoBackupInfo`]
+#[derive(Debug)]
+pub struct AutoBackupInfoBuilder {
+    name: Option<String>,
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+    disk_id: Option<DiskId>,
+    weekdays: Vec<Weekday>,
+    generations: u8,
 }
 
+impl AutoBackupInfoBuilder {
+    fn new() -> Self {
+        Self {
+            name: None,
+            description: None,
+            tags: None,
+            disk_id: None,
+            weekdays: vec![Weekday::Sun],
+            generations: 1,
+        }
+    }
+
+    /// sets the name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// sets the description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// sets the tags
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    /// sets the disk id
+    pub fn disk_id(mut self, disk_id: impl Borrow<DiskId>) -> Self {
+        self.disk_id = Some(disk_id.borrow().clone());
+        self
+    }
+
+    /// sets the weekdays
+    pub fn weekdays(mut self, weekdays: Vec<Weekday>) -> Self {
+        self.weekdays = weekdays;
+        self
+    }
+
+    /// the api accepts 1 to 10
+    pub fn generations(mut self, generations: u8) -> Self {
+        self.generations = generations;
+        self
+    }
+
+    /// builds the auto backup info
+    pub fn build(self) -> AutoBackupInfo {
+        AutoBackupInfo {
+            name: self.name,
+            description: self.description,
+            tags: self.tags,
+            provider: Some(CommonServiceItemProvider { class: CommonServiceItemClass::AutoBackup }),
+            status: self.disk_id.map(|disk_id| AutoBackupStatus { disk_id, zone_name: None }),
+            settings: Some(AutoBackupSettings {
+                auto_backup: AutoBackupSetting {
+                    backup_span_type: "weekdays".to_string(),
+                    backup_span_weekdays: self.weekdays,
+                    maximum_number_of_archives: self.generations,
+                },
+            }),
+        }
+    }
+}
+
+// Sim
+
+/// a sim of a mobile gateway
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sim {
+    #[serde(rename = "ID")]
+    id: CommonServiceItemId,
+
+    #[serde(flatten)]
+    info: SimInfo,
+}
+
+impl Sim {
+    /// all the sims of the account
+    pub async fn search_all() -> Result<Vec<Self>, Error> {
+        let query = SearchQuery::new().filter("Provider.Class", "sim");
+        let resource_values = ResourceKind::CommonServiceItem.search_all(&query).await?;
+        resource_values.into_iter().map(Self::from_value).collect()
+    }
+
+    /// fetches the sim found by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::CommonServiceItem.search_by_name(name, ResourceScope::User).await?;
+        resource_value.map(Self::from_value).transpose()
+    }
+
+    /// creates the sim
+    pub async fn create(info: SimInfo) -> Result<Sim, Error> {
+        let info_value = info.to_value()?;
+        let res_value = ResourceKind::CommonServiceItem.create(info_value).await?;
+        Sim::from_value(res_value)
+    }
+
+    /// activates the sim
+    pub async fn activate(sim_id: impl Borrow<CommonServiceItemId>) -> Result<(), Error> {
+        let sim_id = sim_id.borrow();
+        update(format!("commonserviceitem/{}/sim/activate", sim_id), None).await
+    }
+
+    /// assigns the ip address to the sim
+    pub async fn assign_ip(sim_id: impl Borrow<CommonServiceItemId>, ip: Ipv4Addr) -> Result<(), Error> {
+        let sim_id = sim_id.borrow();
+        update(format!("commonserviceitem/{}/sim/ip", sim_id), Some(json!({ "sim": { "ip": ip.to_string() } }))).await
+    }
+
+    /// clears the ip address of the sim
+    pub async fn clear_ip(sim_id: impl Borrow<CommonServiceItemId>) -> Result<(), Error> {
+        let sim_id = sim_id.borrow();
+        delete(format!("commonserviceitem/{}/sim/ip", sim_id), None).await
+    }
+
+    /// the sim from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::CommonServiceItem, e.to_string()))
+    }
+
+    /// the id of the sim
+    pub fn id(&self) -> &CommonServiceItemId {
+        &self.id
+    }
+
+    /// the name of the sim
+    pub fn name(&self) -> Option<&str> {
+        self.info.name.as_deref()
+    }
+
+    /// the iccid of the sim
+    pub fn iccid(&self) -> Option<&str> {
+        self.info.status.as_ref().map(|status| status.iccid.as_str())
+    }
+}
+
+/// the fields of a sim to register
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimInfo {
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    #[serde(rename = "Description", skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    #[serde(rename = "Tags", skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+
+    #[serde(rename = "Provider", skip_serializing_if = "Option::is_none")]
+    provider: Option<CommonServiceItemProvider>,
+
+    #[serde(rename = "Status", skip_serializing_if = "Option::is_none")]
+    status: Option<SimStatus>,
+
+    // passcode is write only, so it's not in the response
+    #[serde(rename = "Remark", skip_serializing_if = "Option::is_none", default)]
+    remark: Option<SimRemark>,
+}
+
+impl SimInfo {
+    /// a builder of the sim info
+    pub fn builder() -> SimInfoBuilder {
+        SimInfoBuilder::new()
+    }
+
+    /// the json of the sim info as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
+        serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::CommonServiceItem, e.to_string()))
+    }
+}
+
+/// the iccid of a sim
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimStatus {
+    #[serde(rename = "ICCID")]
+    iccid: String,
+}
+
+/// the passcode of a sim
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimRemark {
+    #[serde(rename = "PassCode", skip_serializing_if = "Option::is_none", default)]
+    passcode: Option<String>,
+}
+
+/// a builder of [`SimInfo`]
+#[derive(Debug)]
+pub struct SimInfoBuilder {
+    name: Option<String>,
+    description: Option<String>,
+    iccid: Option<String>,
+    passcode: Option<String>,
+}
+
+impl SimInfoBuilder {
+    fn new() -> Self {
+        Self {
+            name: None,
+            description: None,
+            iccid: None,
+            passcode: None,
+        }
+    }
+
+    /// sets the name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// sets the description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// sets the iccid
+    pub fn iccid(mut self, iccid: impl Into<String>) -> Self {
+        self.iccid = Some(iccid.into());
+        self
+    }
+
+    /// sets the passcode
+    pub fn passcode(mut self, passcode: impl Into<String>) -> Self {
+        self.passcode = Some(passcode.into());
+        self
+    }
+
+    /// builds the sim info
+    pub fn build(self) -> SimInfo {
+        SimInfo {
+            name: self.name,
+            description: self.description,
+            tags: None,
+            provider: Some(CommonServiceItemProvider { class: CommonServiceItemClass::Sim }),
+            status: self.iccid.map(|iccid| SimStatus { iccid }),
+            remark: self.passcode.map(|passcode| SimRemark { passcode: Some(passcode) }),
+        }
+    }
+}
+
+// ProxyLB
+
+/// an enhanced load balancer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyLb {
+    #[serde(rename = "ID")]
+    id: CommonServiceItemId,
+
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    #[serde(rename = "Status", skip_serializing_if = "Option::is_none")]
+    status: Option<ProxyLbStatus>,
+}
+
+impl ProxyLb {
+    /// all the enhanced load balancers of the account
+    pub async fn search_all() -> Result<Vec<Self>, Error> {
+        let query = SearchQuery::new().filter("Provider.Class", "proxylb");
+        let resource_values = ResourceKind::CommonServiceItem.search_all(&query).await?;
+        resource_values.into_iter().map(Self::from_value).collect()
+    }
+
+    /// fetches the enhanced load balancer found by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::CommonServiceItem.search_by_name(name, ResourceScope::User).await?;
+        resource_value.map(Self::from_value).transpose()
+    }
+
+    /// the certificates of the enhanced load balancer
+    pub async fn certificates(proxy_lb_id: impl Borrow<CommonServiceItemId>) -> Result<ProxyLbCertificates, Error> {
+        let proxy_lb_id = proxy_lb_id.borrow();
+        let value = fetch(format!("commonserviceitem/{}/proxylb/sslcertificate", proxy_lb_id), "ProxyLB").await?;
+        ProxyLbCertificates::from_value(value)
+    }
+
+    /// replaces all the certificates including the additional ones
+    pub async fn set_certificates(proxy_lb_id: impl Borrow<CommonServiceItemId>, certificates: ProxyLbCertificates) -> Result<(), Error> {
+        let proxy_lb_id = proxy_lb_id.borrow();
+        let certificates_value = serde_json::to_value(&certificates).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::CommonServiceItem, e.to_string()))?;
+        update(format!("commonserviceitem/{}/proxylb/sslcertificate", proxy_lb_id), Some(json!({ "ProxyLB": certificates_value }))).await
+    }
+
+    /// the enhanced load balancer from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::CommonServiceItem, e.to_string()))
+    }
+
+    /// the id of the enhanced load balancer
+    pub fn id(&self) -> &CommonServiceItemId {
+        &self.id
+    }
+
+    /// the name of the enhanced load balancer
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// the fqdn of the enhanced load balancer
+    pub fn fqdn(&self) -> Option<&str> {
+        self.status.as_ref().and_then(|status| status.fqdn.as_deref())
+    }
+}
+
+/// the fqdn of an enhanced load balancer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyLbStatus {
+    #[serde(rename = "FQDN", skip_serializing_if = "Option::is_none", default)]
+    fqdn: Option<String>,
+}
+
+/// the certificates of an enhanced load balancer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyLbCertificates {
+    #[serde(rename = "PrimaryCert", skip_serializing_if = "Option::is_none", default)]
+    primary_cert: Option<ProxyLbCertificate>,
+
+    #[serde(rename = "AdditionalCerts", default)]
+    additional_certs: Vec<ProxyLbCertificate>,
+}
+
+impl ProxyLbCertificates {
+    /// the certificates of the primary one and the additional ones
+    pub fn new(primary_cert: ProxyLbCertificate, additional_certs: Vec<ProxyLbCertificate>) -> Self {
+        Self { primary_cert: Some(primary_cert), additional_certs }
+    }
+
+    /// the certificates from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::CommonServiceItem, e.to_string()))
+    }
+
+    /// the primary certificate
+    pub fn primary_cert(&self) -> Option<&ProxyLbCertificate> {
+        // the api returns an empty object if not set
+        self.primary_cert.as_ref().filter(|cert| !cert.server_certificate.is_empty())
+    }
+
+    /// the additional certificates
+    pub fn additional_certs(&self) -> &[ProxyLbCertificate] {
+        &self.additional_certs
+    }
+}
+
+/// a certificate of an enhanced load balancer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyLbCertificate {
+    #[serde(rename = "ServerCertificate", default)]
+    server_certificate: String,
+
+    #[serde(rename = "IntermediateCertificate", default)]
+    intermediate_certificate: String,
+
+    // the api doesn't return the private key, it's write only
+    #[serde(rename = "PrivateKey", default)]
+    private_key: String,
+
+    #[serde(rename = "CertificateCommonName", skip_serializing, default)]
+    common_name: Option<String>,
+
+    #[serde(rename = "CertificateEndDate", skip_serializing, default)]
+    end_date: Option<String>,
+}
+
+impl ProxyLbCertificate {
+    /// a certificate of the pem strings, the common name and the end date are filled by the api
+    pub fn new(server_certificate: impl Into<String>, intermediate_certificate: impl Into<String>, private_key: impl Into<String>) -> Self {
+        Self {
+            server_certificate: server_certificate.into(),
+            intermediate_certificate: intermediate_certificate.into(),
+            private_key: private_key.into(),
+            common_name: None,
+            end_date: None,
+        }
+    }
+
+    /// the common name of the certificate, filled by the api
+    pub fn common_name(&self) -> Option<&str> {
+        self.common_name.as_deref()
+    }
+
+    /// the end date of the certificate, filled by the api
+    pub fn end_date(&self) -> Option<&str> {
+        self.end_date.as_deref()
+    }
+}
+
+// Dns
+
+/// a dns zone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dns {
+    #[serde(rename = "ID")]
+    id: CommonServiceItemId,
+
+    // the dns zone, e.g. example.com
+    #[serde(rename = "Name")]
+    name: String,
+
+    #[serde(rename = "Settings")]
+    settings: DnsSettings,
+}
+
+impl Dns {
+    /// the name search is partial and shared with the other common service items
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let name = name.as_ref();
+        let resource_values = ResourceKind::CommonServiceItem.search_all_by_name(name, ResourceScope::User).await?;
+        let mut resource_values: Vec<_> = resource_values.into_iter()
+            .filter(|resource_value| resource_value["Provider"]["Class"] == "dns" && resource_value["Name"] == name)
+            .collect();
+        if resource_values.len() > 1 {
+            return Err(Error::TooManyResources(ResourceKind::CommonServiceItem.prural_name().to_string(), resource_ids(&resource_values)));
+        }
+        resource_values.pop().map(Self::from_value).transpose()
+    }
+
+    /// the record sets are replaced as a whole, so the others are sent back as they are
+    pub async fn set_a_record(&self, name: impl Into<String>, ip: Ipv4Addr, ttl: Option<u32>) -> Result<(), Error> {
+        let mut settings = self.settings.clone();
+        settings.set_a_record(name.into(), ip, ttl);
+        let settings_value = serde_json::to_value(&settings).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::CommonServiceItem, e.to_string()))?;
+        ResourceKind::CommonServiceItem.update(self.id.to_string(), json!({ "Settings": settings_value })).await
+    }
+
+    /// the dns zone from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::CommonServiceItem, e.to_string()))
+    }
+
+    /// the id of the dns zone
+    pub fn id(&self) -> &CommonServiceItemId {
+        &self.id
+    }
+
+    /// the rdata of the a record of the name, relative to the zone
+    pub fn a_record(&self, name: impl AsRef<str>) -> Option<&str> {
+        let name = name.as_ref();
+        self.settings.dns.resource_record_sets.iter()
+            .find(|record| record.name == name && record.record_type == "A")
+            .map(|record| record.rdata.as_str())
+    }
+}
+
+/// the settings of a dns zone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsSettings {
+    #[serde(rename = "DNS")]
+    dns: DnsSetting,
+}
+
+impl DnsSettings {
+    // one a record per name, like a failover target
+    fn set_a_record(&mut self, name: String, ip: Ipv4Addr, ttl: Option<u32>) {
+        let records = &mut self.dns.resource_record_sets;
+        records.retain(|record| !(record.name == name && record.record_type == "A"));
+        records.push(DnsRecord { name, record_type: "A".to_string(), rdata: ip.to_string(), ttl });
+    }
+}
+
+/// the records of a dns zone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsSetting {
+    #[serde(rename = "ResourceRecordSets", default)]
+    resource_record_sets: Vec<DnsRecord>,
+}
+
+/// a record of a dns zone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsRecord {
+    // relative to the zone, @ for the apex
+    #[serde(rename = "Name")]
+    name: String,
+
+    #[serde(rename = "Type")]
+    record_type: String,
+
+    #[serde(rename = "RData")]
+    rdata: String,
+
+    #[serde(rename = "TTL", skip_serializing_if = "Option::is_none")]
+    ttl: Option<u32>,
+}
+
+// Cdrom
+
+/// the id of an iso image
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CdromId(pub ResourceId);
+
+impl fmt::Display for CdromId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<String> for CdromId {
+    fn from(s: String) -> Self {
+        Self(s.into())
+    }
+}
+
+/// an iso image referred to by id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CdromRef {
+    #[serde(rename = "ID")]
+    id: CdromId,
+}
+
+/// an iso image
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cdrom {
+    #[serde(rename = "ID")]
+    id: CdromId,
+
+    #[serde(rename = "Availability", skip_serializing_if = "Option::is_none")]
+    availability: Option<String>,
+
+    #[serde(flatten)]
+    info: CdromInfo,
+}
+
+impl Cdrom {
+    /// fetches the iso image found by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::Cdrom.search_by_name(name, ResourceScope::User).await?;
+        resource_value.map(Self::from_value).transpose()
+    }
+
+    /// the ftp server is opened on creation, upload the iso image and close it to make the cdrom available
+    pub async fn create(info: CdromInfo) -> Result<(Cdrom, FtpServer), Error> {
+        let kind = ResourceKind::Cdrom;
+        let info_value = info.to_value()?;
+        let mut res_value = request_api_for_resource(Method::POST, kind.path(), None, Some(json!({ kind.single_name(): info_value }))).await?;
+        let cdrom = Cdrom::from_value(res_value[kind.single_name()].take())?;
+        let ftp_server = FtpServer::from_value(res_value["FTPServer"].take())?;
+        Ok((cdrom, ftp_server))
+    }
+
+    /// opens the ftp server to upload the iso image
+    pub async fn open_ftp(cdrom_id: impl Borrow<CdromId>) -> Result<FtpServer, Error> {
+        let cdrom_id = cdrom_id.borrow();
+        let path = format!("{}/{}/ftp", ResourceKind::Cdrom.path(), cdrom_id);
+        let res_value = request_api_for_resource(Method::PUT, path, Some("FTPServer"), Some(json!({ "ChangePassword": false }))).await?;
+        FtpServer::from_value(res_value)
+    }
+
+    /// closes the ftp server of the iso image
+    pub async fn close_ftp(cdrom_id: impl Borrow<CdromId>) -> Result<(), Error> {
+        let cdrom_id = cdrom_id.borrow();
+        delete(format!("{}/{}/ftp", ResourceKind::Cdrom.path(), cdrom_id), None).await
+    }
+
+    /// waits until the iso image is available
+    pub async fn wait_available(cdrom_id: impl Borrow<CdromId>) -> Result<(), Error> {
+        let cdrom_id = cdrom_id.borrow();
+        ResourceKind::Cdrom.wait_available(cdrom_id.to_string()).await
+    }
+
+    /// the iso image from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Cdrom, e.to_string()))
+    }
+
+    /// the id of the iso image
+    pub fn id(&self) -> &CdromId {
+        &self.id
+    }
+
+    /// the name of the iso image
+    pub fn name(&self) -> Option<&str> {
+        self.info.name.as_deref()
+    }
+
+    /// the availability of the iso image
+    pub fn availability(&self) -> Option<Availability> {
+        self.availability.as_deref().and_then(|availability| availability.parse().ok())
+    }
+
+    /// whether the iso image is available
+    pub fn is_available(&self) -> bool {
+        self.availability() == Some(Availability::Available)
+    }
+}
+
+/// the fields of an iso image to create
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CdromInfo {
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    #[serde(rename = "Description", skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    #[serde(rename = "Tags", skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+
+    // 5120 or 10240
+    #[serde(rename = "SizeMB", skip_serializing_if = "Option::is_none")]
+    size_mb: Option<u64>,
+}
+
+impl CdromInfo {
+    /// a builder of the iso image info
+    pub fn builder() -> CdromInfoBuilder {
+        CdromInfoBuilder::new()
+    }
+
+    /// the json of the iso image info as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
+        serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::Cdrom, e.to_string()))
+    }
+}
+
+/// a builder of [`CdromInfo`]
+#[derive(Debug)]
+pub struct CdromInfoBuilder {
+    name: Option<String>,
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+    size_mb: Option<u64>,
+}
+
+impl CdromInfoBuilder {
+    fn new() -> Self {
+        Self {
+            name: None,
+            description: None,
+            tags: None,
+            size_mb: None,
+        }
+    }
+
+    /// sets the name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// sets the description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// sets the size in MB
+    pub fn size_mb(mut self, size_mb: u64) -> Self {
+        self.size_mb = Some(size_mb);
+        self
+    }
+
+    /// builds the iso image info
+    pub fn build(self) -> CdromInfo {
+        CdromInfo {
+            name: self.name,
+            description: self.description,
+            tags: self.tags,
+            size_mb: self.size_mb,
+        }
+    }
+}
+
+/// ftps server to upload an iso image or to transfer an archive image, only explicit ftps is accepted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FtpServer {
+    #[serde(rename = "HostName")]
+    host_name: String,
+
+    #[serde(rename = "IPAddress")]
+    ip_address: Ipv4Addr,
+
+    #[serde(rename = "User")]
+    user: String,
+
+    #[serde(rename = "Password")]
+    password: String,
+}
+
+impl FtpServer {
+    /// the ftp server from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Cdrom, e.to_string()))
+    }
+
+    /// the host name of the ftp server
+    pub fn host_name(&self) -> &str {
+        &self.host_name
+    }
+
+    /// the ip address of the ftp server
+    pub fn ip_address(&self) -> Ipv4Addr {
+        self.ip_address
+    }
+
+    /// the user of the ftp server
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// the password of the ftp server
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+}
+
+// Icon
+
+/// the id of an icon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IconId(pub ResourceId);
+
+impl fmt::Display for IconId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<String> for IconId {
+    fn from(s: String) -> Self {
+        Self(s.into())
+    }
+}
+
+/// an icon referred to by id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IconRef {
+    #[serde(rename = "ID")]
+    id: IconId,
+}
+
+/// an icon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Icon {
+    #[serde(rename = "ID")]
+    id: IconId,
+
+    #[serde(rename = "URL", skip_serializing_if = "Option::is_none", default)]
+    url: Option<String>,
+
+    #[serde(flatten)]
+    info: IconInfo,
+}
+
+impl Icon {
+    /// fetches the icon found by the name, none if there is none, see [`ResourceChooser`] when several are found
+    pub async fn get_by_name(name: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let resource_value = ResourceKind::Icon.search_by_name(name, ResourceScope::User).await?;
+        resource_value.map(Self::from_value).transpose()
+    }
+
+    /// creates the icon
+    pub async fn create(info: IconInfo) -> Result<Icon, Error> {
+        let info_value = info.to_value()?;
+        let res_value = ResourceKind::Icon.create(info_value).await?;
+        Icon::from_value(res_value)
+    }
+
+    /// deletes the icon
+    pub async fn delete(icon_id: impl Borrow<IconId>) -> Result<(), Error> {
+        let icon_id = icon_id.borrow();
+        ResourceKind::Icon.delete(icon_id.to_string()).await
+    }
+
+    /// waits until the icon is deleted
+    pub async fn wait_delete(icon_id: impl Borrow<IconId>) -> Result<(), Error> {
+        let icon_id = icon_id.borrow();
+        ResourceKind::Icon.wait_delete(icon_id.to_string()).await
+    }
+
+    /// the icon from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Icon, e.to_string()))
+    }
+
+    /// the id of the icon
+    pub fn id(&self) -> &IconId {
+        &self.id
+    }
+
+    /// the url of the icon
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+}
+
+/// the fields of an icon to create
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IconInfo {
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    #[serde(rename = "Tags", skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+
+    // base64 encoded png, write only
+    #[serde(rename = "Image", skip_serializing_if = "Option::is_none", default)]
+    image: Option<String>,
+}
+
+impl IconInfo {
+    /// a builder of the icon info
+    pub fn builder() -> IconInfoBuilder {
+        IconInfoBuilder::new()
+    }
+
+    /// the json of the icon info as the api takes it
+    pub fn to_value(&self) -> Result<Value, Error> {
+        serde_json::to_value(self).map_err(|e| Error::ResourceSerializationFailed(ResourceKind::Icon, e.to_string()))
+    }
+}
+
+/// a builder of [`IconInfo`]
+#[derive(Debug)]
+pub struct IconInfoBuilder {
+    name: Option<String>,
+    tags: Option<Vec<String>>,
+    image: Option<String>,
+}
+
+impl IconInfoBuilder {
+    fn new() -> Self {
+        Self {
+            name: None,
+            tags: None,
+            image: None,
+        }
+    }
+
+    /// sets the name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// sets the png
+    pub fn png(mut self, png: impl AsRef<[u8]>) -> Self {
+        self.image = Some(BASE64_STANDARD.encode(png));
+        self
+    }
+
+    /// builds the icon info
+    pub fn build(self) -> IconInfo {
+        IconInfo {
+            name: self.name,
+            tags: self.tags,
+            image: self.image,
+        }
+    }
+}
+
+// Interface
+
+/// the id of an interface
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterfaceId(pub ResourceId);
+
+impl fmt::Display for InterfaceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<String> for InterfaceId {
+    fn from(s: String) -> Self {
+        Self(s.into())
+    }
+}
+
+impl FromStr for InterfaceId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
+/// an interface of a server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interface {
+    #[serde(rename = "ID")]
+    id: InterfaceId,
+
+    #[serde(rename = "MACAddress", skip_serializing_if = "Option::is_none", default)]
+    mac_address: Option<String>,
+
+    // assigned by the api only on the shared switch
+    #[serde(rename = "IPAddress", skip_serializing_if = "Option::is_none", default)]
+    ip_address: Option<Ipv4Addr>,
+
+    #[serde(rename = "UserIPAddress", skip_serializing_if = "Option::is_none", default)]
+    user_ip_address: Option<Ipv4Addr>,
+
+    #[serde(rename = "Switch", skip_serializing_if = "Option::is_none", default)]
+    switch: Option<SwitchRef>,
+
+    #[serde(rename = "PacketFilter", skip_serializing_if = "Option::is_none", default)]
+    packet_filter: Option<PacketFilterRef>,
+}
+
+// the server must be down to add or delete interfaces
+impl Interface {
+    /// fetches the interface by id
+    pub async fn get(interface_id: impl Borrow<InterfaceId>) -> Result<Self, Error> {
+        let interface_id = interface_id.borrow();
+        let resource_value = ResourceKind::Interface.get(interface_id.to_string()).await?;
+        Self::from_value(resource_value)
+    }
+
+    /// creates the interface
+    pub async fn create(server_id: impl Borrow<ServerId>) -> Result<Self, Error> {
+        let server_id = server_id.borrow();
+        let server_ref = ServerRef { id: server_id.clone() };
+        let res_value = ResourceKind::Interface.create(json!({ "Server": server_ref })).await?;
+        Self::from_value(res_value)
+    }
+
+    /// deletes the interface
+    pub async fn delete(interface_id: impl Borrow<InterfaceId>) -> Result<(), Error> {
+        let interface_id = interface_id.borrow();
+        ResourceKind::Interface.delete(interface_id.to_string()).await
+    }
+
+    /// connects the interface to the switch
+    pub async fn connect_to_switch(interface_id: impl Borrow<InterfaceId>, switch_id: impl Borrow<SwitchId>) -> Result<(), Error> {
+        let interface_id = interface_id.borrow();
+        let switch_id = switch_id.borrow();
+        update(format!("{}/{}/to/switch/{}", ResourceKind::Interface.path(), interface_id, switch_id), None).await
+    }
+
+    /// disconnects the interface from its switch
+    pub async fn disconnect_from_switch(interface_id: impl Borrow<InterfaceId>) -> Result<(), Error> {
+        let interface_id = interface_id.borrow();
+        delete(format!("{}/{}/to/switch", ResourceKind::Interface.path(), interface_id), None).await
+    }
+
+    /// attaches the packet filter to the interface
+    pub async fn attach_packet_filter(interface_id: impl Borrow<InterfaceId>, packet_filter_id: impl Borrow<PacketFilterId>) -> Result<(), Error> {
+        let interface_id = interface_id.borrow();
+        let packet_filter_id = packet_filter_id.borrow();
+        update(format!("{}/{}/to/packetfilter/{}", ResourceKind::Interface.path(), interface_id, packet_filter_id), None).await
+    }
+
+    /// detaches the packet filter from the interface
+    pub async fn detach_packet_filter(interface_id: impl Borrow<InterfaceId>) -> Result<(), Error> {
+        let interface_id = interface_id.borrow();
+        delete(format!("{}/{}/to/packetfilter", ResourceKind::Interface.path(), interface_id), None).await
+    }
+
+    /// the interface from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Interface, e.to_string()))
+    }
+
+    /// the id of the interface
+    pub fn id(&self) -> &InterfaceId {
+        &self.id
+    }
+
+    /// the mac address of the interface
+    pub fn mac_address(&self) -> Option<&str> {
+        self.mac_address.as_deref()
+    }
+
+    /// the ip address of the interface
+    pub fn ip_address(&self) -> Option<Ipv4Addr> {
+        self.ip_address
+    }
+
+    /// the user ip address of the interface
+    pub fn user_ip_address(&self) -> Option<Ipv4Addr> {
+        self.user_ip_address
+    }
+
+    /// the switch id of the interface
+    pub fn switch_id(&self) -> Option<&SwitchId> {
+        self.switch.as_ref().map(|switch| &switch.id)
+    }
+
+    /// whether the interface is connected to the shared switch
+    pub fn is_shared_switch(&self) -> bool {
+        self.switch.as_ref().and_then(|switch| switch.scope.as_ref()) == Some(&SwitchScope::Shared)
+    }
+
+    /// the packet filter id of the interface
+    pub fn packet_filter_id(&self) -> Option<&PacketFilterId> {
+        self.packet_filter.as_ref().map(|packet_filter| &packet_filter.id)
+    }
+}
+
+// PacketFilter, only referenced from interfaces
+
+/// the id of a packet filter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PacketFilterId(pub ResourceId);
+
+impl fmt::Display for PacketFilterId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<String> for PacketFilterId {
+    fn from(s: String) -> Self {
+        Self(s.into())
+    }
+}
+
+impl FromStr for PacketFilterId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
+/// a packet filter referred to by id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PacketFilterRef {
+    #[serde(rename = "ID")]
+    id: PacketFilterId,
+}
+
+/// the models of the server nics
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InterfaceDriver {
+    /// virtio-net
+    #[serde(rename = "virtio")]
+    Virtio,
+
+    /// the emulated intel e1000
+    #[serde(rename = "e1000")]
+    E1000,
+}
+
+impl Default for InterfaceDriver {
+    fn default() -> Self {
+        Self::Virtio
+    }
+}
+
+/// the power status of a server or an appliance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Instance {
+    #[serde(rename = "Status", skip_serializing_if = "Option::is_none")]
+    status: Option<InstanceStatus>,
+
+    #[serde(rename = "CDROM", skip_serializing_if = "Option::is_none", default)]
+    cdrom: Option<CdromRef>,
+}
+
+impl Instance {
+    /// the power status from the json of the api
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Instance, e.to_string()))
+    }
+}
+
+/// the power statuses of the servers and the appliances
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstanceStatus {
+    /// shutting down, it must be waited before the next boot
+    #[serde(rename = "cleaning")]
+    Cleaning,
+
+    /// up
+    #[serde(rename = "up")]
+    Up,
+
+    /// down
+    #[serde(rename = "down")]
+    Down,
+}
+
+impl fmt::Display for InstanceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cleaning => write!(f, "cleaning"),
+            Self::Up => write!(f, "up"),
+            Self::Down => write!(f, "down"),
+        }
+    }
+}
+
+impl FromStr for InstanceStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cleaning" => Ok(Self::Cleaning),
+            "up" => Ok(Self::Up),
+            "down" => Ok(Self::Down),
+            _ => Err(()),
+        }
+    }
+}
+
+/// of the disks, the archives and the other resources without the power
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Availability {
+    /// available
+    #[serde(rename = "available")]
+    Available,
+
+    /// being copied
+    #[serde(rename = "migrating")]
+    Migrating,
+
+    /// waiting for the upload of the image
+    #[serde(rename = "uploading")]
+    Uploading,
+
+    /// failed to be created
+    #[serde(rename = "failed")]
+    Failed,
+
+    /// copied from another zone
+    #[serde(rename = "transferring")]
+    Transferring,
+
+    /// no longer provided
+    #[serde(rename = "discontinued")]
+    Discontinued,
+}
+
+impl fmt::Display for Availability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Available => write!(f, "available"),
+            Self::Migrating => write!(f, "migrating"),
+            Self::Uploading => write!(f, "uploading"),
+            Self::Failed => write!(f, "failed"),
+            Self::Transferring => write!(f, "transferring"),
+            Self::Discontinued => write!(f, "discontinued"),
+        }
+    }
+}
+
+impl FromStr for Availability {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "available" => Ok(Self::Available),
+            "migrating" => Ok(Self::Migrating),
+            "uploading" => Ok(Self::Uploading),
+            "failed" => Ok(Self::Failed),
+            "transferring" => Ok(Self::Transferring),
+            "discontinued" => Ok(Self::Discontinued),
+            _ => Err(()),
+        }
+    }
+}
+
+/// what a wait is waiting on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceStatus {
+    /// the power status of a server or an appliance
+    Instance(InstanceStatus),
+    /// the availability of the other resources
+    Availability(Availability),
+}
+
+impl fmt::Display for ResourceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Instance(status) => status.fmt(f),
+            Self::Availability(availability) => availability.fmt(f),
+        }
+    }
+}
+
+impl From<InstanceStatus> for ResourceStatus {
+    fn from(status: InstanceStatus) -> Self {
+        Self::Instance(status)
+    }
+}
+
+impl From<Availability> for ResourceStatus {
+    fn from(availability: Availability) -> Self {
+        Self::Availability(availability)
+    }
+}
+
+
+/// the id of an api key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyId(pub ResourceId);
+
+/// an api key referred to by id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyRef {
+    #[serde(rename = "ID")]
+    id: ApiKeyId,
+}
+
+
+/// name searches match shared resources too (e.g. public archives and icons), so the single resource searches filter them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ResourceScope {
+    /// the resources of the user
+    User,
+    /// the resources shared by sakura cloud, e.g. the public archives
+    Shared,
+    /// both
+    Any,
+}
+
+impl ResourceScope {
+    /// the scope of the search filter, none for any
+    pub fn as_str(&self) -> Option<&'static str> {
+        match self {
+            Self::User => Some("user"),
+            Self::Shared => Some("shared"),
+            Self::Any => None,
+        }
+    }
+
+    // scope less resources are regarded as user's
+    fn matches_scope(&self, scope: Option<&str>) -> bool {
+        match self.as_str() {
+            Some(expected) => scope.unwrap_or("user") == expected,
+            None => true,
+        }
+    }
+
+    /// whether the resource json is in the scope, the ones without the scope are regarded as the user's
+    pub fn matches(&self, resource_value: &Value) -> bool {
+        self.matches_scope(resource_value["Scope"].as_str())
+    }
+}
+
+/// the query of the search api, all pages are fetched with the page size
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    filter: serde_json::Map<String, Value>,
+    sort: Vec<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    page_size: Option<u64>,
+}
+
+impl SearchQuery {
+    const DEFAULT_PAGE_SIZE: u64 = 50;
+
+    /// an empty query, all the resources of the kind
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// partial match, the words separated by spaces are all matched
+    pub fn name(self, name: impl AsRef<str>) -> Self {
+        self.filter("Name", json!([ name.as_ref() ]))
+    }
+
+    /// added to the tags already given, all of them must be matched
+    pub fn tags<T: AsRef<str>>(mut self, tags: impl IntoIterator<Item = T>) -> Self {
+        let tags = tags.into_iter().map(|tag| Value::from(tag.as_ref())).collect::<Vec<_>>();
+        match self.filter.get_mut("Tags").and_then(Value::as_array_mut) {
+            Some(current_tags) => current_tags.extend(tags),
+            None => { self.filter.insert("Tags".to_string(), Value::from(tags)); },
+        }
+        self
+    }
+
+    /// only for the kinds having the scope, see `ResourceScope` for the others
+    pub fn scope(mut self, scope: ResourceScope) -> Self {
+        match scope.as_str() {
+            Some(scope) => self.filter("Scope", scope),
+            None => {
+                self.filter.remove("Scope");
+                self
+            },
+        }
+    }
+
+    /// a raw filter, e.g. ("Provider.Class", "sim")
+    pub fn filter(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.filter.insert(key.into(), value.into());
+        self
+    }
+
+    /// ascending, or descending with the "-" prefix, e.g. "-CreatedAt"
+    pub fn sort(mut self, field: impl Into<String>) -> Self {
+        self.sort.push(field.into());
+        self
+    }
+
+    /// only these fields in the response, e.g. "ID", "Name"
+    pub fn include(mut self, field: impl Into<String>) -> Self {
+        self.include.push(field.into());
+        self
+    }
+
+    /// leaves the field out of the response
+    pub fn exclude(mut self, field: impl Into<String>) -> Self {
+        self.exclude.push(field.into());
+        self
+    }
+
+    /// the count of a page, 50 if not given
+    pub fn page_size(mut self, page_size: u64) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    fn to_value(&self, index_from: u64) -> Value {
+        let mut query = json!({ "From": index_from, "Count": self.page_size.unwrap_or(Self::DEFAULT_PAGE_SIZE) });
+        if !self.filter.is_empty() {
+            query["Filter"] = Value::Object(self.filter.clone());
+        }
+        if !self.sort.is_empty() {
+            query["Sort"] = json!(self.sort);
+        }
+        if !self.include.is_empty() {
+            query["Include"] = json!(self.include);
+        }
+        if !self.exclude.is_empty() {
+            query["Exclude"] = json!(self.exclude);
+        }
+        query
+    }
+}
+
+// Utils
+
+// not every kind has the scope, so it's checked on the results instead of the filter
+async fn search_single_resource(path: impl AsRef<str>, query: &SearchQuery, resource_name: impl AsRef<str>, name: Option<&str>, scope: ResourceScope) -> Result<Option<Value>, Error> {
+    let path = path.as_ref();
+    let resource_name = resource_name.as_ref();
+    let mut resource_values = search(path, resource_name, query).await?;
+    resource_values.retain(|resource_value| scope.matches(resource_value));
+
+    if resource_values.len() > 1 {
+        disambiguate(resource_name, name, resource_values).map(Some)
+    }
+    else if resource_values.len() < 1 {
+        Ok(None)
+    } else {
+        let resource_value = resource_values[0].take();
+        Ok(Some(resource_value))
+    }
+}
+
+/// tagged on the resources created by this tool, so they are told from the others of the same name
+pub const MANAGED_TAG: &str = "managed-by-sacloud-random-tools";
+
+// the request body of the kinds which have the tags
+fn tag_managed(resource_value: &mut Value) {
+    let Some(resource_object) = resource_value.as_object_mut() else {
+        return;
+    };
+    let tags = resource_object.entry("Tags").or_insert_with(|| json!([]));
+    if let Some(tags) = tags.as_array_mut() {
+        if !tags.iter().any(|tag| tag == MANAGED_TAG) {
+            tags.push(json!(MANAGED_TAG));
+        }
+    }
+}
+
+// the ssh keys and the interfaces have no tags
+fn is_taggable(resource_name: &str) -> bool {
+    [ResourceKind::Server, ResourceKind::Disk, ResourceKind::Switch, ResourceKind::Appliance, ResourceKind::Archive, ResourceKind::Note, ResourceKind::CommonServiceItem, ResourceKind::Cdrom, ResourceKind::Icon]
+        .iter().any(|kind| kind.single_name() == resource_name)
+}
+
+fn is_managed(resource_value: &Value) -> bool {
+    resource_value["Tags"].as_array().is_some_and(|tags| tags.iter().any(|tag| tag == MANAGED_TAG))
+}
+
+fn resource_ids(resource_values: &[Value]) -> Vec<String> {
+    resource_values.iter().map(|resource_value| match &resource_value["ID"] {
+        Value::String(id) => id.clone(),
+        id => id.to_string(),
+    }).collect()
+}
+
+tokio::task_local! {
+    static RESOURCE_CHOOSER: ResourceChooser;
+}
+
+type Choose = dyn Fn(&str, &[Value]) -> Option<usize> + Send + Sync;
+
+/// asked for the index of the candidate to use, when the name and the managed tag are not enough to tell one
+#[derive(Clone)]
+pub struct ResourceChooser(Arc<Choose>);
+
+impl ResourceChooser {
+    /// a chooser of the candidates, the index of the chosen one or none to fail
+    pub fn new(chooser: impl Fn(&str, &[Value]) -> Option<usize> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(chooser))
+    }
+
+    /// the lookups in the future use this chooser, except in the tasks spawned from it
+    pub async fn scope<F: Future>(self, f: F) -> F::Output {
+        RESOURCE_CHOOSER.scope(self, f).await
+    }
+
+    /// the chooser of the running scope, e.g. to carry it into another runtime
+    pub fn current() -> Option<Self> {
+        RESOURCE_CHOOSER.try_with(|chooser| chooser.clone()).ok()
+    }
+}
+
+impl fmt::Debug for ResourceChooser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ResourceChooser")
+    }
+}
+
+// the name search is partial, so the exact name is preferred, then the managed tag, then the chooser
+fn disambiguate(resource_name: &str, name: Option<&str>, mut candidates: Vec<Value>) -> Result<Value, Error> {
+    if let Some(name) = name {
+        if candidates.iter().any(|candidate| candidate["Name"] == name) {
+            candidates.retain(|candidate| candidate["Name"] == name);
+        }
+    }
+    if candidates.iter().any(is_managed) {
+        candidates.retain(is_managed);
+    }
+    if candidates.len() == 1 {
+        return Ok(candidates.remove(0));
+    }
+    if let Some(i) = RESOURCE_CHOOSER.try_with(|chooser| (chooser.0)(resource_name, &candidates)).ok().flatten().filter(|i| *i < candidates.len()) {
+        return Ok(candidates.swap_remove(i));
+    }
+    Err(Error::TooManyResources(resource_name.to_string(), resource_ids(&candidates)))
+}
+
+/// what the predicate of wait_for makes of each fetch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WaitCheck {
+    /// the resource is done
+    Done,
+    /// the resource is still working in the status
+    Working(ResourceStatus),
+    /// the resource failed
+    Failed,
+    /// the raw status the predicate doesn't know
+    Unknown(String),
+    /// the resource has no status
+    NoStatus,
+}
+
+/// refetches the resource as T until the predicate is done, the progress is reported on stderr,
+/// e.g. `wait_for(ResourceKind::Disk, id, |disk: &Disk| ..., Some(Duration::from_secs(600)))`
+pub async fn wait_for<T: DeserializeOwned>(kind: ResourceKind, resource_id: impl AsRef<str>, predicate: impl FnMut(&T) -> WaitCheck, timeout: Option<Duration>) -> Result<T, Error> {
+    let path = format!("{}/{}", kind.path(), resource_id.as_ref());
+    let mut reporter = ProgressReporter::new(&path);
+    wait_resource(&path, kind, predicate, timeout, |progress| reporter.update(progress)).await
+}
+
+async fn wait_resource<T: DeserializeOwned>(path: &str, kind: ResourceKind, mut predicate: impl FnMut(&T) -> WaitCheck, timeout: Option<Duration>, mut on_progress: impl FnMut(&WaitProgress)) -> Result<T, Error> {
+    let started = Instant::now();
+    loop {
+        let resource = fetch(path, kind.single_name()).await?;
+        let typed = serde_json::from_value::<T>(resource.clone()).map_err(|e| Error::ResourceDeserializationFailed(kind, e.to_string()))?;
+        match predicate(&typed) {
+            WaitCheck::Done => return Ok(typed),
+            WaitCheck::Failed => return Err(Error::ResourceApiWaitStatusFailed(path.to_string(), resource)),
+            WaitCheck::Unknown(status) => return Err(Error::ResourceApiWaitStatusUnknown(status, path.to_string(), resource)),
+            WaitCheck::NoStatus => return Err(Error::ResourceApiWaitStatusNotFound(path.to_string(), resource)),
+            WaitCheck::Working(status) => on_progress(&WaitProgress::new(status, &resource, started.elapsed())),
+        }
+        if let Some(timeout) = timeout.filter(|timeout| started.elapsed() >= *timeout) {
+            return Err(Error::ResourceApiWaitTimedOut(path.to_string(), timeout.as_secs(), resource));
+        }
+        sleep(ApiEndpoint::current()?.poll_interval).await;
+    }
+}
+
+// the fields the waits of every kind look at
+#[derive(Debug, Deserialize)]
+struct WaitStatuses {
+    #[serde(rename = "Instance", default)]
+    instance: Option<Value>,
+
+    #[serde(rename = "Availability", default)]
+    availability: Option<String>,
+}
+
+impl WaitStatuses {
+    fn instance_status(&self) -> Option<&str> {
+        self.instance.as_ref().and_then(|instance| instance["Status"].as_str())
+    }
+}
+
+fn check_up(statuses: &WaitStatuses) -> WaitCheck {
+    check_status(statuses.instance_status(), &[InstanceStatus::Cleaning], &[InstanceStatus::Up], &[InstanceStatus::Down])
+}
+
+fn check_down(statuses: &WaitStatuses) -> WaitCheck {
+    check_status(statuses.instance_status(), &[InstanceStatus::Up, InstanceStatus::Cleaning], &[InstanceStatus::Down], &[])
+}
+
+fn check_available(statuses: &WaitStatuses) -> WaitCheck {
+    check_status(statuses.availability.as_deref(),
+        &[Availability::Uploading, Availability::Migrating, Availability::Transferring],
+        &[Availability::Available],
+        &[Availability::Failed, Availability::Discontinued])
+}
+
+// the statuses out of the enum, or in none of the sets, are unknown and stop the wait
+fn check_status<S>(raw_status: Option<&str>, working_statuses: &[S], success_statuses: &[S], failed_statuses: &[S]) -> WaitCheck
+where
+    S: FromStr + Copy + PartialEq + Into<ResourceStatus>,
+{
+    let Some(raw_status) = raw_status else {
+        return WaitCheck::NoStatus;
+    };
+    match raw_status.parse::<S>() {
+        Ok(status) if failed_statuses.contains(&status) => WaitCheck::Failed,
+        Ok(status) if success_statuses.contains(&status) => WaitCheck::Done,
+        Ok(status) if working_statuses.contains(&status) => WaitCheck::Working(status.into()),
+        _ => WaitCheck::Unknown(raw_status.to_string()),
+    }
+}
+
+/// a snapshot of a resource still being worked on, disks and archives also report how much has been copied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaitProgress {
+    /// the status of the resource
+    pub status: ResourceStatus,
+    /// the copied size in MB
+    pub migrated_mb: Option<u64>,
+    /// the whole size in MB
+    pub size_mb: Option<u64>,
+    /// the time since the wait started
+    pub elapsed: Duration,
+}
+
+impl WaitProgress {
+    fn new(status: ResourceStatus, resource: &Value, elapsed: Duration) -> Self {
+        WaitProgress {
+            status,
+            migrated_mb: resource["MigratedMB"].as_u64(),
+            size_mb: resource["SizeMB"].as_u64().filter(|size_mb| *size_mb > 0),
+            elapsed,
+        }
+    }
+
+    /// the copied percentage, none unless the sizes are reported
+    pub fn percent(&self) -> Option<u64> {
+        let (migrated_mb, size_mb) = (self.migrated_mb?, self.size_mb?);
+        Some((migrated_mb * 100 / size_mb).min(100))
+    }
 
-// Utils
+    /// extrapolated from the copy rate so far
+    pub fn eta(&self) -> Option<Duration> {
+        let (migrated_mb, size_mb) = (self.migrated_mb.filter(|migrated_mb| *migrated_mb > 0)?, self.size_mb?);
+        let remaining_mb = size_mb.saturating_sub(migrated_mb);
+        Some(self.elapsed.mul_f64(remaining_mb as f64 / migrated_mb as f64))
+    }
+}
 
-async fn search_single_resource(path: impl AsRef<str>, filter: Value, resource_name: impl AsRef<str>) -> Result<Option<Value>, Error> {
-    let path = path.as_ref();
-    let resource_name = resource_name.as_ref();
-    let mut resource_values = search(path, resource_name, Some(filter), None, None, 50).await?;
+// Reports the progress of a long wait, redrawn in place on a terminal and logged periodically otherwise
+struct ProgressReporter {
+    path: String,
+    last_logged: Option<Instant>,
+    is_terminal: bool,
+    drawn: bool,
+}
 
-    if resource_values.len() > 1 {
-        Err(Error::TooManyResources(resource_name.to_string(), resource_values.len()))
+impl ProgressReporter {
+    // Quick waits stay silent
+    const QUIET_PERIOD: Duration = Duration::from_secs(5);
+    const LOG_INTERVAL: Duration = Duration::from_secs(30);
+
+    fn new(path: impl AsRef<str>) -> Self {
+        ProgressReporter {
+            path: path.as_ref().to_string(),
+            last_logged: None,
+            is_terminal: io::stderr().is_terminal(),
+            drawn: false,
+        }
     }
-    else if resource_values.len() < 1 {
-        Ok(None)
-    } else {
-        let resource_value = resource_values[0].take();
-        Ok(Some(resource_value))
+
+    fn update(&mut self, progress: &WaitProgress) {
+        if progress.elapsed < Self::QUIET_PERIOD {
+            return;
+        }
+        let line = progress_line(&self.path, progress);
+        if self.is_terminal {
+            eprint!("\r\x1b[2K{}", line);
+            let _ = io::stderr().flush();
+            self.drawn = true;
+        } else if self.last_logged.is_none_or(|last| last.elapsed() >= Self::LOG_INTERVAL) {
+            log::info!("{}", line);
+            self.last_logged = Some(Instant::now());
+        }
     }
 }
 
-async fn wait_resource_up(path: impl AsRef<str>, resource_name: impl AsRef<str>) -> Result<(), Error> {
-    wait_resource_status(path, resource_name,
-        |res| res["Instance"]["Status"].as_str().map(|s| s.to_string()),
-        ["cleaning"].into_iter().collect(),
-        ["up"].into_iter().collect(),
-        ["down"].into_iter().collect()).await
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        if self.drawn {
+            eprintln!();
+        }
+    }
 }
 
-async fn wait_resource_down(path: impl AsRef<str>, resource_name: impl AsRef<str>) -> Result<(), Error> {
-    wait_resource_status(path, resource_name,
-        |res| res["Instance"]["Status"].as_str().map(|s| s.to_string()),
-        ["up", "cleaning"].into_iter().collect(),
-        ["down"].into_iter().collect(),
-        [].into_iter().collect()).await
+// e.g. "[WAIT] disk/123 migrating 1024/20480 MB (5%), elapsed 1m20s, eta 25m20s"
+fn progress_line(path: &str, progress: &WaitProgress) -> String {
+    let mut line = format!("[WAIT] {} {}", path, progress.status);
+    if let (Some(migrated_mb), Some(size_mb), Some(percent)) = (progress.migrated_mb, progress.size_mb, progress.percent()) {
+        line.push_str(&format!(" {}/{} MB ({}%)", migrated_mb, size_mb, percent));
+    }
+    line.push_str(&format!(", elapsed {}", format_duration(progress.elapsed)));
+    if let Some(eta) = progress.eta() {
+        line.push_str(&format!(", eta {}", format_duration(eta)));
+    }
+    line
 }
 
-async fn wait_resource_available(path: impl AsRef<str>, resource_name: impl AsRef<str>) -> Result<(), Error> {
-    wait_resource_status(path, resource_name,
-        |res| res["Availability"].as_str().map(|s| s.to_string()),
-        ["uploading", "migrating"].into_iter().collect(),
-        ["available"].into_iter().collect(),
-        ["failed"].into_iter().collect()).await
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h{:02}m{:02}s", secs / 3600, secs % 3600 / 60, secs % 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
 }
 
-async fn wait_resource_status(path: impl AsRef<str>, resource_name: impl AsRef<str>, status_accessor_fn: impl Fn(&Value) -> Option<String>, working_value_set: HashSet<&str>, success_value_set: HashSet<&str>, failed_value_set: HashSet<&str>) -> Result<(), Error> {
-    let path = path.as_ref();
-    let resource_name = resource_name.as_ref();
-    loop {
-        let resource = fetch(path, resource_name).await?;
-        let Some(status) = status_accessor_fn(&resource) else {
-            return Err(Error::ResourceApiWaitStatusNotFound(path.to_string(), resource.clone()));
-        };
-        let status: &str = &status;
-        if failed_value_set.contains(status) {
-            return Err(Error::ResourceApiWaitStatusFailed(path.to_string(), resource.clone()));
-        }
-        if success_value_set.contains(status) {
-            break;
-        }
-        if !working_value_set.contains(status) {
-            return Err(Error::ResourceApiWaitStatusUnknown(status.to_string(), path.to_string(), resource.clone()));
-        }
-        sleep(Duration::from_secs(2)).await;
-    }
-    Ok(())
+/// the unix time of the time in the api responses, e.g. 2024-01-01T00:00:00+09:00
+pub fn unix_time(api_time: &str) -> Option<u64> {
+    let (date, time) = api_time.split_once('T')?;
+    let mut date_parts = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date_parts.next()??, date_parts.next()??, date_parts.next()??);
+    let (time, offset_secs) = match time.find(['+', '-']) {
+        Some(i) => {
+            let (time, offset) = time.split_at(i);
+            let (hours, minutes) = offset[1..].split_once(':')?;
+            let offset_secs = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+            (time, if offset.starts_with('-') { -offset_secs } else { offset_secs })
+        },
+        None => (time.strip_suffix('Z')?, 0),
+    };
+    let mut time_parts = time.splitn(3, ':').map(|part| part.parse::<i64>().ok());
+    let (hour, minute, second) = (time_parts.next()??, time_parts.next()??, time_parts.next()??);
+
+    // days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    u64::try_from(days * 86400 + hour * 3600 + minute * 60 + second - offset_secs).ok()
 }
 
 async fn fetch_until_not_found(path: impl AsRef<str>) -> Result<(), Error> {
@@ -1945,13 +5979,16 @@ async fn fetch_until_not_found(path: impl AsRef<str>) -> Result<(), Error> {
             Err(Error::ApiNotFound(..)) => return Ok(()),
             Err(e) => return Err(e),
         }
-        sleep(Duration::from_secs(2)).await;
+        sleep(ApiEndpoint::current()?.poll_interval).await;
     }
 }
 
-async fn create(path: impl AsRef<str>, body: Value, resource_name: impl AsRef<str>) -> Result<Value, Error> {
+async fn create(path: impl AsRef<str>, mut body: Value, resource_name: impl AsRef<str>) -> Result<Value, Error> {
     let path = path.as_ref();
     let resource_name = resource_name.as_ref();
+    if is_taggable(resource_name) {
+        tag_managed(&mut body[resource_name]);
+    }
     let resource = request_api_for_resource(Method::POST, path, Some(resource_name), Some(body.clone())).await?;
     Ok(resource)
 }
@@ -1971,31 +6008,15 @@ async fn delete(path: impl AsRef<str>, body: Option<Value>) -> Result<(), Error>
     Ok(())
 }
 
-async fn search(path: impl AsRef<str>, resource_name: impl AsRef<str>, filter: Option<Value>, sort: Option<Value>, other: Option<Value>, page_count: u64) -> Result<Vec<Value>, Error> {
+async fn search(path: impl AsRef<str>, resource_name: impl AsRef<str>, search_query: &SearchQuery) -> Result<Vec<Value>, Error> {
     let path = path.as_ref();
     let resource_name = resource_name.as_ref();
     let mut result_resources = Vec::new();
     let mut index_from = 0;
-    let query = if let Some(other) = other {
-        other
-    } else {
-        json!({})
-    };
     loop {
-        let mut query = query.clone();
-        query["From"] = Value::from(index_from);
-        query["Count"] = Value::from(page_count);
-        if let Some(filter) = filter.clone() {
-            query["Filter"] = filter;
-        }
-        if let Some(sort) = sort.clone() {
-            query["Sort"] = sort;
-        }
-
-        let query = Some(query);
-        let value = request_api(Method::GET, path, &query, &None).await?;
+        let query = search_query.to_value(index_from);
+        let value = request_api(Method::GET, path, &Some(query.clone()), &None).await?;
 
-        let query = query.expect("must be Some");
         let Some(total) = value["Total"].as_u64() else {
             return Err(Error::SearchApiInvalidTotalCount(path.to_string(), query.clone()));
         };
@@ -2063,17 +6084,27 @@ async fn request_api_for_resource(method: Method, path: impl AsRef<str>, resourc
     }
 }
 
-async fn request_api(method: Method, path: impl AsRef<str>, query: &Option<Value>, body: &Option<Value>) -> Result<Value, Error> {
+/// calls the cloud api of the current endpoint, and returns the response json
+pub async fn request_api(method: Method, path: impl AsRef<str>, query: &Option<Value>, body: &Option<Value>) -> Result<Value, Error> {
+    let endpoint = ApiEndpoint::current()?;
+    request_api_with_base_url(&endpoint, &endpoint.base_url, method, path, query, body).await
+}
+
+async fn request_system_api(method: Method, path: impl AsRef<str>, query: &Option<Value>, body: &Option<Value>) -> Result<Value, Error> {
+    let endpoint = ApiEndpoint::current()?;
+    request_api_with_base_url(&endpoint, &endpoint.system_base_url, method, path, query, body).await
+}
+
+async fn request_api_with_base_url(endpoint: &ApiEndpoint, base_url: &Url, method: Method, path: impl AsRef<str>, query: &Option<Value>, body: &Option<Value>) -> Result<Value, Error> {
     let path = path.as_ref();
-    log::trace!("START API REQUEST: method={:?}, path={}, query={}, body={}", method, path, serde_json::to_string_pretty(&query).unwrap_or_default(), serde_json::to_string_pretty(&body).unwrap_or_default());
+    log::trace!("START API REQUEST: method={:?}, base_url={}, path={}, query={}, body={}", method, base_url, path, serde_json::to_string_pretty(&query).unwrap_or_default(), serde_json::to_string_pretty(&body.as_ref().map(redact)).unwrap_or_default());
 
-    let mut url = API_BASE_URL.join(path).expect("must be valid url");
+    let mut url = base_url.join(path).expect("must be valid url");
     if let Some(query) = query {
         url.set_query(Some(&query.to_string()));
     }
-    let client = reqwest::Client::new();
-    let mut req = client.request(method, url)
-        .basic_auth(&*ACCESS_TOKEN, Some(&*SECRET_TOKEN));
+    let mut req = endpoint.client.request(method, url)
+        .basic_auth(&endpoint.access_token, Some(&endpoint.secret_token));
     if let Some(body) = body {
         req = req.json(&body)
     };
@@ -2107,7 +6138,11 @@ async fn request_api(method: Method, path: impl AsRef<str>, query: &Option<Value
             ()
         },
         status_code => {
-            log::trace!("ERROR API REQUEST: response={}", res.text().await.unwrap_or_default());
+            let text = res.text().await.unwrap_or_default();
+            match serde_json::from_str::<Value>(&text) {
+                Ok(value) => log::trace!("ERROR API REQUEST: response={}", redact(&value)),
+                Err(_) => log::trace!("ERROR API REQUEST: response={}", text),
+            }
             match status_code {
                 StatusCode::BAD_REQUEST => {
                     // 400 Bad Request	リクエストパラメータが不正等。 例：許可されないフィールドに対し、負の値、過去の日付、異なる型の値等が指定されている
@@ -2169,14 +6204,88 @@ async fn request_api(method: Method, path: impl AsRef<str>, query: &Option<Value
     }
 
     let value = res.json().await.map_err(|e| Error::InvalidResponseJson(e.to_string(), path.to_string(), body.clone()))?;
-    log::trace!("END API REQUEST: value={}", serde_json::to_string_pretty(&value).unwrap_or_default());
+    log::trace!("END API REQUEST: value={}", serde_json::to_string_pretty(&redact(&value)).unwrap_or_default());
     Ok(value)
 }
 
+// the fields whose values are masked in the logs, matched in lowercase without `_`, e.g. Password and private_key
+const REDACTED_FIELD_PATTERNS: &[&str] = &["password", "secret", "privatekey", "publickey", "presharedkey", "passphrase", "token", "webhookurl"];
+
+// matched by the patterns but not secret, e.g. the flag to change the password
+const NOT_REDACTED_FIELDS: &[&str] = &["ChangePassword", "DisablePWAuth"];
+
+/// the string replacing the secrets in the logs and the errors
+pub const REDACTED: &str = "[REDACTED]";
+
+fn is_redacted_field(key: &str) -> bool {
+    if NOT_REDACTED_FIELDS.contains(&key) {
+        return false;
+    }
+    let normalized = key.to_lowercase().replace('_', "");
+    REDACTED_FIELD_PATTERNS.iter().any(|pattern| normalized.contains(pattern))
+}
+
+/// a copy to log or to share, booleans and nulls are kept to see whether it's set
+pub fn redact(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.iter().map(|(key, value)| {
+            let value = match value {
+                Value::Bool(_) | Value::Null => value.clone(),
+                _ if is_redacted_field(key) => Value::from(REDACTED),
+                _ => redact(value),
+            };
+            (key.clone(), value)
+        }).collect()),
+        Value::Array(values) => Value::Array(values.iter().map(redact).collect()),
+        _ => value.clone(),
+    }
+}
+
+/// the strings masked by `redact`, to mask them in a text made from the value, e.g. a rendered template
+pub fn redacted_strings(value: &Value) -> Vec<String> {
+    let mut strings = Vec::new();
+    collect_redacted_strings(value, false, &mut strings);
+    strings
+}
+
+fn collect_redacted_strings(value: &Value, redacted: bool, strings: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => map.iter().for_each(|(key, value)| collect_redacted_strings(value, redacted || is_redacted_field(key), strings)),
+        Value::Array(values) => values.iter().for_each(|value| collect_redacted_strings(value, redacted, strings)),
+        Value::String(s) if redacted && !s.is_empty() => strings.push(s.clone()),
+        _ => (),
+    }
+}
+
+/// replaces the strings in the text with [`REDACTED`]
+pub fn redact_text(text: &str, strings: &[String]) -> String {
+    strings.iter().fold(text.to_string(), |text, s| text.replace(s.as_str(), REDACTED))
+}
+
 // test
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::{net::TcpListener, io::{AsyncReadExt, AsyncWriteExt}};
+
+    #[test]
+    fn redaction() {
+        let body = json!({
+            "Disk": { "Name": "dev-server", "SizeMB": 20480 },
+            "Config": { "Password": "p@ss", "ChangePassword": true, "SSHKeys": [{ "ID": "1" }] },
+            "SSHKey": { "PublicKey": "ssh-ed25519 AAAA", "private_key": { "inner": "k" } },
+        });
+        assert_eq!(redact(&body), json!({
+            "Disk": { "Name": "dev-server", "SizeMB": 20480 },
+            "Config": { "Password": REDACTED, "ChangePassword": true, "SSHKeys": [{ "ID": "1" }] },
+            "SSHKey": { "PublicKey": REDACTED, "private_key": REDACTED },
+        }));
+
+        let strings = redacted_strings(&body);
+        assert_eq!(strings, vec!["p@ss".to_string(), "ssh-ed25519 AAAA".to_string(), "k".to_string()]);
+        assert_eq!(redact_text("echo p@ss", &strings), "echo [REDACTED]");
+    }
 
     #[test]
     fn server_json() {
@@ -2354,6 +6463,407 @@ mod tests {
         assert_eq!(ssh_public_key.info, info);
     }
 
+    #[test]
+    fn simple_monitor_json() {
+        let info = SimpleMonitorInfo::builder()
+            .description("DESCRIPTION")
+            .tags(vec!["TAG".to_string()])
+            .target("203.0.113.1")
+            .delay_loop(60)
+            .health_check(SimpleMonitorHealthCheck::tcp(10022))
+            .notify_email(true)
+            .build();
+
+        assert_eq!(serde_json::to_value(json!({ "CommonServiceItem": &info })).unwrap(), json!({
+            "CommonServiceItem": {
+                "Name": "203.0.113.1",
+                "Description": "DESCRIPTION",
+                "Tags": ["TAG"],
+                "Provider": { "Class": "simplemon" },
+                "Status": { "Target": "203.0.113.1" },
+                "Settings": {
+                    "SimpleMonitor": {
+                        "DelayLoop": 60,
+                        "HealthCheck": { "Protocol": "tcp", "Port": "10022" },
+                        "Enabled": "True",
+                        "NotifyEmail": { "Enabled": "True" },
+                        "NotifySlack": { "Enabled": "False" },
+                    },
+                },
+            },
+        }));
+
+        let simple_monitor = SimpleMonitor::from_value(json!({
+            "ID": "SIMPLE_MONITOR_ID",
+            "Name": "203.0.113.1",
+            "Description": "DESCRIPTION",
+            "Tags": ["TAG"],
+            "Provider": { "Class": "simplemon" },
+            "Status": { "Target": "203.0.113.1" },
+            "Settings": {
+                "SimpleMonitor": {
+                    "DelayLoop": 60,
+                    "HealthCheck": { "Protocol": "tcp", "Port": "10022" },
+                    "Enabled": "True",
+                    "NotifyEmail": { "Enabled": "True" },
+                    "NotifySlack": { "Enabled": "False" },
+                },
+            },
+            "UnknowField": "UNKNOWN",
+        })).unwrap();
+
+        assert_eq!(simple_monitor.id(), &CommonServiceItemId("SIMPLE_MONITOR_ID".into()));
+        assert_eq!(simple_monitor.info, info);
+    }
+
+    #[test]
+    fn auto_backup_json() {
+        let info = AutoBackupInfo::builder()
+            .name("NAME")
+            .tags(vec!["TAG".to_string()])
+            .disk_id(DiskId("DISK_ID".into()))
+            .weekdays(vec![Weekday::Mon, Weekday::Thu])
+            .generations(3)
+            .build();
+
+        assert_eq!(serde_json::to_value(json!({ "CommonServiceItem": &info })).unwrap(), json!({
+            "CommonServiceItem": {
+                "Name": "NAME",
+                "Tags": ["TAG"],
+                "Provider": { "Class": "autobackup" },
+                "Status": { "DiskId": "DISK_ID" },
+                "Settings": {
+                    "Autobackup": {
+                        "BackupSpanType": "weekdays",
+                        "BackupSpanWeekdays": ["mon", "thu"],
+                        "MaximumNumberOfArchives": 3,
+                    },
+                },
+            },
+        }));
+
+        let auto_backup = AutoBackup::from_value(json!({
+            "ID": "AUTO_BACKUP_ID",
+            "Name": "NAME",
+            "Tags": ["TAG"],
+            "Provider": { "Class": "autobackup" },
+            "Status": { "DiskId": "DISK_ID" },
+            "Settings": {
+                "Autobackup": {
+                    "BackupSpanType": "weekdays",
+                    "BackupSpanWeekdays": ["mon", "thu"],
+                    "MaximumNumberOfArchives": 3,
+                },
+            },
+            "UnknowField": "UNKNOWN",
+        })).unwrap();
+
+        assert_eq!(auto_backup.id(), &CommonServiceItemId("AUTO_BACKUP_ID".into()));
+        assert_eq!(auto_backup.disk_id(), Some(&DiskId("DISK_ID".into())));
+        assert_eq!(auto_backup.info, info);
+    }
+
+    #[test]
+    fn cdrom_json() {
+        let info = CdromInfo::builder()
+            .name("NAME")
+            .size_mb(5120)
+            .build();
+
+        assert_eq!(serde_json::to_value(json!({ "CDROM": &info })).unwrap(), json!({
+            "CDROM": {
+                "Name": "NAME",
+                "SizeMB": 5120,
+            },
+        }));
+
+        let cdrom = Cdrom::from_value(json!({
+            "ID": "CDROM_ID",
+            "Name": "NAME",
+            "Availability": "uploading",
+            "SizeMB": 5120,
+            "UnknowField": "UNKNOWN",
+        })).unwrap();
+
+        assert_eq!(cdrom.id(), &CdromId("CDROM_ID".into()));
+        assert_eq!(cdrom.name(), Some("NAME"));
+        assert!(!cdrom.is_available());
+
+        let ftp_server = FtpServer::from_value(json!({
+            "HostName": "sac-is1b-ssl.sakura.ad.jp",
+            "IPAddress": "192.0.2.1",
+            "User": "USER",
+            "Password": "PASSWORD",
+        })).unwrap();
+
+        assert_eq!(ftp_server.host_name(), "sac-is1b-ssl.sakura.ad.jp");
+        assert_eq!(ftp_server.ip_address(), Ipv4Addr::new(192, 0, 2, 1));
+
+        let server = Server::from_value(json!({
+            "ID": "SERVER_ID",
+            "Instance": { "Status": "up", "CDROM": { "ID": "CDROM_ID" } },
+        })).unwrap();
+
+        assert_eq!(server.inserted_cdrom_id(), Some(&CdromId("CDROM_ID".into())));
+    }
+
+    #[test]
+    fn vnc_proxy_json() {
+        let vnc_proxy = VncProxy::from_value(json!({
+            "Status": "OK",
+            "Host": "localhost",
+            "IOServerHost": "sac-is1a-vnc.sakura.ad.jp",
+            "Port": "51234",
+            "Password": "PASSWORD",
+            "VNCFile": "[connection]\nhost=sac-is1a-vnc.sakura.ad.jp\nport=51234\n",
+            "is_ok": true,
+        })).unwrap();
+
+        assert_eq!(vnc_proxy.host(), "sac-is1a-vnc.sakura.ad.jp");
+        assert_eq!(vnc_proxy.port(), "51234");
+        assert_eq!(vnc_proxy.password(), "PASSWORD");
+        assert!(vnc_proxy.vnc_file().is_some());
+
+        assert_eq!(serde_json::to_value(ShortcutKey::CtrlAltDelete).unwrap(), json!("ctrl-alt-delete"));
+    }
+
+    #[test]
+    fn server_plan_json() {
+        let server = Server::from_value(json!({
+            "ID": "SERVER_ID",
+            "ServerPlan": { "ID": "100002004", "CPU": 2, "MemoryMB": 4096, "Generation": 100 },
+        })).unwrap();
+
+        assert_eq!(server.plan_size(), Some((2, 4096)));
+        assert_eq!(server.plan_generation(), Some(100));
+        assert_eq!(server.plan_commitment(), None);
+
+        let plans = [
+            json!({ "ID": "100002004", "CPU": 2, "MemoryMB": 4096, "Generation": 100, "Commitment": "standard", "Availability": "available" }),
+            json!({ "ID": "200002004", "CPU": 2, "MemoryMB": 4096, "Generation": 200, "Commitment": "standard", "Availability": "available" }),
+            json!({ "ID": "200002004d", "CPU": 2, "MemoryMB": 4096, "Generation": 200, "Commitment": "dedicatedcpu", "Availability": "available" }),
+            json!({ "ID": "200001001", "CPU": 1, "MemoryMB": 1024, "Generation": 200, "Commitment": "standard", "Availability": "available" }),
+        ].into_iter().map(|plan| ServerPlan::from_value(plan).unwrap()).collect::<Vec<_>>();
+        let id = |plan: Option<ServerPlan>| plan.map(|plan| plan.id().to_string());
+        assert_eq!(id(ServerPlan::choose(plans.clone(), 2, 4096, None, ServerPlanCommitment::Standard)).as_deref(), Some("200002004"));
+        assert_eq!(id(ServerPlan::choose(plans.clone(), 2, 4096, Some(100), ServerPlanCommitment::Standard)).as_deref(), Some("100002004"));
+        assert_eq!(id(ServerPlan::choose(plans.clone(), 2, 4096, None, ServerPlanCommitment::DedicatedCpu)).as_deref(), Some("200002004d"));
+        assert_eq!(id(ServerPlan::choose(plans, 1, 1024, Some(100), ServerPlanCommitment::Standard)), None);
+
+        let info = ServerInfo::builder()
+            .server_plan(ServerPlanId("100002004".into()))
+            .build();
+
+        assert_eq!(info.to_value().unwrap(), json!({
+            "ServerPlan": { "ID": "100002004" },
+        }));
+    }
+
+    #[test]
+    fn icon_json() {
+        let info = IconInfo::builder()
+            .name("NAME")
+            .png(b"PNG")
+            .build();
+
+        assert_eq!(serde_json::to_value(json!({ "Icon": &info })).unwrap(), json!({
+            "Icon": {
+                "Name": "NAME",
+                "Image": "UE5H",
+            },
+        }));
+
+        let icon = Icon::from_value(json!({
+            "ID": "ICON_ID",
+            "Name": "NAME",
+            "URL": "https://secure.sakura.ad.jp/cloud/zone/is1a/api/cloud/1.1/icon/ICON_ID.png",
+            "Scope": "user",
+        })).unwrap();
+
+        assert_eq!(icon.id(), &IconId("ICON_ID".into()));
+        assert_eq!(icon.url(), Some("https://secure.sakura.ad.jp/cloud/zone/is1a/api/cloud/1.1/icon/ICON_ID.png"));
+
+        let info = SwitchInfo::builder()
+            .name("NAME")
+            .icon_id(IconId("ICON_ID".into()))
+            .build();
+
+        assert_eq!(info.to_value().unwrap(), json!({
+            "Name": "NAME",
+            "Icon": { "ID": "ICON_ID" },
+        }));
+    }
+
+    #[test]
+    fn private_host_json() {
+        let private_host = PrivateHost::from_value(json!({
+            "ID": "PRIVATE_HOST_ID",
+            "Name": "NAME",
+            "AssignedCPU": 4,
+            "AssignedMemoryMB": 8192,
+            "Host": { "Name": "HOST" },
+        })).unwrap();
+
+        assert_eq!(private_host.id(), &PrivateHostId("PRIVATE_HOST_ID".into()));
+        assert_eq!(private_host.name(), Some("NAME"));
+        assert_eq!((private_host.assigned_cpu(), private_host.assigned_memory_mb()), (4, 8192));
+
+        let info = ServerInfo::builder()
+            .name("NAME")
+            .private_host_id(PrivateHostId("PRIVATE_HOST_ID".into()))
+            .build();
+
+        assert_eq!(info.to_value().unwrap(), json!({
+            "Name": "NAME",
+            "PrivateHost": { "ID": "PRIVATE_HOST_ID" },
+        }));
+    }
+
+    #[test]
+    fn interface_json() {
+        let interface = Interface::from_value(json!({
+            "ID": "INTERFACE_ID",
+            "MACAddress": "9C:A3:BA:00:00:01",
+            "IPAddress": null,
+            "UserIPAddress": "192.168.3.2",
+            "Switch": { "ID": "SWITCH_ID", "Name": "NAME", "Scope": "user" },
+            "PacketFilter": null,
+            "Server": { "ID": "SERVER_ID" },
+        })).unwrap();
+
+        assert_eq!(interface.id(), &InterfaceId("INTERFACE_ID".into()));
+        assert_eq!(interface.mac_address(), Some("9C:A3:BA:00:00:01"));
+        assert_eq!(interface.user_ip_address(), Some(Ipv4Addr::new(192, 168, 3, 2)));
+        assert_eq!(interface.switch_id(), Some(&SwitchId("SWITCH_ID".into())));
+        assert!(!interface.is_shared_switch());
+        assert_eq!(interface.packet_filter_id(), None);
+    }
+
+    #[test]
+    fn resource_id_from_str() {
+        assert_eq!("113000000000".parse::<ResourceId>().unwrap(), ResourceId::String("113000000000".to_string()));
+        assert_eq!("113000000000".parse::<ServerId>().unwrap(), ServerId("113000000000".into()));
+        assert_eq!("113000000000".parse::<ServerId>().unwrap().to_string(), "113000000000");
+        assert!(matches!("".parse::<DiskId>(), Err(Error::InvalidResourceId(id)) if id.is_empty()));
+        assert!(matches!("my-server".parse::<ServerId>(), Err(Error::InvalidResourceId(id)) if id == "my-server"));
+        assert!(matches!(" 113000000000".parse::<SwitchId>(), Err(Error::InvalidResourceId(_))));
+        assert!(matches!("-1".parse::<ApplianceId>(), Err(Error::InvalidResourceId(_))));
+    }
+
+    #[test]
+    fn interfaces_in_resource_json() {
+        let switch_id = SwitchId("SWITCH_ID".into());
+        let server = Server::from_value(json!({
+            "ID": "SERVER_ID",
+            "Interfaces": [
+                {
+                    "ID": "INTERFACE_ID_0",
+                    "MACAddress": "9C:A3:BA:00:00:01",
+                    "IPAddress": null,
+                    "UserIPAddress": "192.168.2.4",
+                    "Switch": { "ID": "SWITCH_ID", "Scope": "user" },
+                },
+                {
+                    "ID": "INTERFACE_ID_1",
+                    "IPAddress": "133.242.0.10",
+                    "Switch": { "ID": "SHARED_ID", "Scope": "shared" },
+                },
+            ],
+        })).unwrap();
+        assert_eq!(server.interface_ids(), vec![&InterfaceId("INTERFACE_ID_0".into()), &InterfaceId("INTERFACE_ID_1".into())]);
+        assert_eq!(server.interfaces()[0].mac_address(), Some("9C:A3:BA:00:00:01"));
+        assert_eq!(server.interfaces()[1].ip_address(), Some(Ipv4Addr::new(133, 242, 0, 10)));
+        assert!(server.interfaces()[1].is_shared_switch());
+        assert_eq!(server.user_ip_address_on(&switch_id), Some(Ipv4Addr::new(192, 168, 2, 4)));
+        assert_eq!(server.user_ip_address_on(SwitchId("OTHER_ID".into())), None);
+
+        let vpc_router = Appliance::from_value(json!({
+            "ID": "APPLIANCE_ID",
+            "Class": "vpcrouter",
+            "Interfaces": [
+                { "MACAddress": "9C:A3:BA:00:00:02", "IPAddress": "133.242.0.11", "Switch": { "ID": "SHARED_ID", "Scope": "shared" } },
+                { "Switch": { "ID": "SWITCH_ID", "Scope": "user" } },
+                null,
+            ],
+        })).unwrap();
+        let interfaces = vpc_router.interfaces();
+        assert_eq!(interfaces.len(), 3);
+        assert_eq!(interfaces[0].and_then(|interface| interface.mac_address()), Some("9C:A3:BA:00:00:02"));
+        assert_eq!(interfaces[1].and_then(|interface| interface.switch_id()), Some(&switch_id));
+        assert!(interfaces[2].is_none());
+        assert_eq!(vpc_router.public_shared_ip().unwrap(), Ipv4Addr::new(133, 242, 0, 11));
+    }
+
+    #[test]
+    fn sim_json() {
+        let info = SimInfo::builder()
+            .name("NAME")
+            .iccid("ICCID")
+            .passcode("PASSCODE")
+            .build();
+
+        assert_eq!(serde_json::to_value(json!({ "CommonServiceItem": &info })).unwrap(), json!({
+            "CommonServiceItem": {
+                "Name": "NAME",
+                "Provider": { "Class": "sim" },
+                "Status": { "ICCID": "ICCID" },
+                "Remark": { "PassCode": "PASSCODE" },
+            },
+        }));
+
+        let sim = Sim::from_value(json!({
+            "ID": "SIM_ID",
+            "Name": "NAME",
+            "Provider": { "Class": "sim" },
+            "Status": { "ICCID": "ICCID" },
+            "UnknowField": "UNKNOWN",
+        })).unwrap();
+
+        assert_eq!(sim.id(), &CommonServiceItemId("SIM_ID".into()));
+        assert_eq!(sim.name(), Some("NAME"));
+        assert_eq!(sim.iccid(), Some("ICCID"));
+
+        let sims: Vec<MobileGatewaySim> = serde_json::from_value(json!([
+            { "resource_id": "SIM_ID", "iccid": "ICCID", "ip": "" },
+        ])).unwrap();
+        assert_eq!(sims[0].id(), &CommonServiceItemId("SIM_ID".into()));
+        assert_eq!(sims[0].ip(), None);
+    }
+
+    #[test]
+    fn proxy_lb_certificates_json() {
+        let certificates = ProxyLbCertificates::new(ProxyLbCertificate::new("CERT", "INTERMEDIATE", "KEY"), Vec::new());
+
+        assert_eq!(serde_json::to_value(json!({ "ProxyLB": &certificates })).unwrap(), json!({
+            "ProxyLB": {
+                "PrimaryCert": {
+                    "ServerCertificate": "CERT",
+                    "IntermediateCertificate": "INTERMEDIATE",
+                    "PrivateKey": "KEY",
+                },
+                "AdditionalCerts": [],
+            },
+        }));
+
+        let certificates = ProxyLbCertificates::from_value(json!({
+            "PrimaryCert": {
+                "ServerCertificate": "CERT",
+                "IntermediateCertificate": "INTERMEDIATE",
+                "CertificateCommonName": "example.com",
+                "CertificateEndDate": "2025-01-01T00:00:00+09:00",
+            },
+            "AdditionalCerts": [],
+            "UnknowField": "UNKNOWN",
+        })).unwrap();
+
+        let primary_cert = certificates.primary_cert().unwrap();
+        assert_eq!(primary_cert.common_name(), Some("example.com"));
+        assert_eq!(primary_cert.end_date(), Some("2025-01-01T00:00:00+09:00"));
+
+        let certificates = ProxyLbCertificates::from_value(json!({ "PrimaryCert": {}, "AdditionalCerts": [] })).unwrap();
+        assert_eq!(certificates.primary_cert(), None);
+    }
+
     #[test]
     fn switch_json() {
         let id = SwitchId("SWITCH_ID".into());
@@ -2382,5 +6892,396 @@ mod tests {
         assert_eq!(switch.id(), &id);
         assert_eq!(switch.info, info);
     }
+
+    #[test]
+    fn response_only_fields_json() {
+        let server = Server::from_value(json!({
+            "ID": "SERVER_ID",
+            "Name": "NAME",
+            "Tags": ["color-blue"],
+            "CreatedAt": "2024-01-01T00:00:00+09:00",
+            "Availability": "available",
+            "Instance": { "Status": "up" },
+        })).unwrap();
+        assert_eq!(server.tags(), &["color-blue".to_string()]);
+        assert_eq!(server.created_at(), Some("2024-01-01T00:00:00+09:00"));
+        assert_eq!(server.availability(), Some(Availability::Available));
+        assert_eq!(server.instance_status(), Some(InstanceStatus::Up));
+
+        let disk = Disk::from_value(json!({
+            "ID": "DISK_ID",
+            "Plan": { "ID": 4, "Name": "SSDプラン" },
+            "Connection": "virtio",
+            "Tags": [],
+            "Availability": "migrating",
+        })).unwrap();
+        assert_eq!(disk.plan_name(), Some("SSDプラン"));
+        assert_eq!(disk.connection().map(|connection| connection.to_string()), Some("virtio".to_string()));
+        assert_eq!(disk.availability(), Some(Availability::Migrating));
+        // the plan name is not sent back
+        assert_eq!(disk.info.to_value().unwrap()["Plan"], json!({ "ID": 4 }));
+
+        let vpc_router = Appliance::from_value(json!({
+            "ID": "APPLIANCE_ID",
+            "Class": "vpcrouter",
+            "Plan": { "ID": 1 },
+            "Tags": ["TAG"],
+        })).unwrap();
+        assert_eq!(vpc_router.vpc_router_plan_id().map(|id| id.to_string()), Some("1".to_string()));
+        assert_eq!(vpc_router.tags(), &["TAG".to_string()]);
+        assert_eq!(vpc_router.instance_status(), None);
+    }
+
+    #[test]
+    fn nfs_json() {
+        let info = ApplianceInfo::builder()
+            .name("NAME")
+            .description("DESCRIPTION")
+            .nfs(
+                NfsInfo::builder()
+                    .plan_id(NfsPlanId("NFS_PLAN_ID".into()))
+                    .switch_id(SwitchId("SWITCH_ID".into()))
+                    .ip_address(Ipv4Addr::new(192, 168, 2, 3))
+                    .default_route(Ipv4Addr::new(192, 168, 2, 1))
+                    .build()
+            )
+            .build();
+
+        assert_eq!(serde_json::to_value(json!({ "Appliance": &info })).unwrap(), json!({
+            "Appliance": {
+                "Name": "NAME",
+                "Description": "DESCRIPTION",
+                "Class": "nfs",
+                "Plan": { "ID": "NFS_PLAN_ID" },
+                "Remark": {
+                    "Network": { "NetworkMaskLen": 24, "DefaultRoute": "192.168.2.1" },
+                    "Servers": [ { "IPAddress": "192.168.2.3" } ],
+                    "Switch": { "ID": "SWITCH_ID" },
+                    "Plan": { "ID": "NFS_PLAN_ID" },
+                },
+            },
+        }));
+    }
+
+    // answers the queued responses in order, and records "METHOD /path?query" of the requests
+    async fn mock_endpoint(responses: Vec<(u16, Value)>) -> (ApiEndpoint, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                // the request bodies are not checked, so the head is enough
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request_line = String::from_utf8_lossy(&request).lines().next().unwrap_or_default().to_string();
+                recorded.lock().unwrap().push(request_line.rsplit_once(' ').map(|(line, _)| line.to_string()).unwrap_or_default());
+
+                let body = body.to_string();
+                let response = format!("HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+                stream.write_all(response.as_bytes()).await.unwrap();
+                let _ = stream.shutdown().await;
+            }
+        });
+        let endpoint = ApiEndpoint::new(base_url.clone(), base_url, "TOKEN", "SECRET").poll_interval(Duration::from_millis(1));
+        (endpoint, requests)
+    }
+
+    fn variant_name(e: &Error) -> String {
+        serde_json::to_value(e).unwrap().as_object().and_then(|e| e.keys().next().cloned()).unwrap_or_default()
+    }
+
+    #[test]
+    fn search_query_json() {
+        let query = SearchQuery::new()
+            .name("NAME")
+            .tags(["a"])
+            .tags(["b"])
+            .scope(ResourceScope::User)
+            .sort("-CreatedAt")
+            .include("ID")
+            .include("Name")
+            .page_size(10);
+        assert_eq!(query.to_value(20), json!({
+            "From": 20,
+            "Count": 10,
+            "Filter": { "Name": [ "NAME" ], "Tags": [ "a", "b" ], "Scope": "user" },
+            "Sort": [ "-CreatedAt" ],
+            "Include": [ "ID", "Name" ],
+        }));
+        assert_eq!(SearchQuery::new().to_value(0), json!({ "From": 0, "Count": 50 }));
+    }
+
+    #[tokio::test]
+    async fn search_pagination() {
+        let (endpoint, requests) = mock_endpoint(vec![
+            (200, json!({ "Total": 3, "From": 0, "Count": 2, "Servers": [ { "ID": "1" }, { "ID": "2" } ] })),
+            (200, json!({ "Total": 3, "From": 2, "Count": 1, "Servers": [ { "ID": "3" } ] })),
+        ]).await;
+        let servers = endpoint.scope(search("server", "Servers", &SearchQuery::new().page_size(2))).await.unwrap();
+        assert_eq!(servers, vec![json!({ "ID": "1" }), json!({ "ID": "2" }), json!({ "ID": "3" })]);
+        let requests = requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("GET /server?"));
+        assert!(requests[1].contains("%22From%22:2"), "{}", requests[1]);
+
+        let (endpoint, _) = mock_endpoint(vec![
+            (200, json!({ "Total": 3, "From": 1, "Count": 2, "Servers": [] })),
+        ]).await;
+        let result = endpoint.scope(search("server", "Servers", &SearchQuery::new().page_size(2))).await;
+        assert!(matches!(result, Err(Error::SearchApiInvalidIndexFrom(Some(1), ..))));
+    }
+
+    #[tokio::test]
+    async fn same_named_resources() {
+        // the partial match of the name
+        let response = json!({ "Total": 2, "From": 0, "Count": 2, "SSHKeys": [ { "ID": "1", "Name": "dev-pub-key" }, { "ID": "2", "Name": "dev-pub-key-1" } ] });
+        let (endpoint, _) = mock_endpoint(vec![(200, response)]).await;
+        let key = endpoint.scope(ResourceKind::SshPublicKey.search_by_name("dev-pub-key", ResourceScope::User)).await.unwrap();
+        assert_eq!(key.unwrap()["ID"], "1");
+
+        let response = json!({ "Total": 3, "From": 0, "Count": 3, "Servers": [
+            { "ID": "1", "Name": "dev-server", "Tags": [] },
+            { "ID": "2", "Name": "dev-server", "Tags": [MANAGED_TAG] },
+            { "ID": "3", "Name": "dev-server" },
+        ] });
+        let (endpoint, _) = mock_endpoint(vec![(200, response)]).await;
+        let server = endpoint.scope(ResourceKind::Server.search_by_name("dev-server", ResourceScope::User)).await.unwrap();
+        assert_eq!(server.unwrap()["ID"], "2");
+
+        let response = json!({ "Total": 2, "From": 0, "Count": 2, "Servers": [ { "ID": "1", "Name": "dev-server" }, { "ID": "3", "Name": "dev-server" } ] });
+        let (endpoint, _) = mock_endpoint(vec![(200, response)]).await;
+        let result = endpoint.scope(ResourceKind::Server.search_by_name("dev-server", ResourceScope::User)).await;
+        assert!(matches!(result, Err(Error::TooManyResources(_, ids)) if ids == ["1", "3"]));
+
+        let mut body = json!({ "Name": "dev-server", "Tags": ["web"] });
+        tag_managed(&mut body);
+        tag_managed(&mut body);
+        assert_eq!(body["Tags"], json!(["web", MANAGED_TAG]));
+    }
+
+    #[tokio::test]
+    async fn official_startup_script() {
+        let response = json!({ "Total": 2, "From": 0, "Count": 2, "Notes": [
+            { "ID": "1", "Name": "update packages", "Scope": "user" },
+            { "ID": "2", "Name": "update packages", "Scope": "shared", "Availability": "available" },
+        ] });
+        let (endpoint, _) = mock_endpoint(vec![(200, response)]).await;
+        let note = endpoint.scope(Note::official_startup_script("update packages")).await.unwrap().unwrap();
+        assert_eq!(note.id(), &NoteId("2".into()));
+        assert!(note.is_available());
+
+        let note = Note::from_value(json!({ "ID": "3", "Availability": "migrating" })).unwrap();
+        assert_eq!(note.availability(), Some(Availability::Migrating));
+        assert!(!note.is_available());
+    }
+
+    #[tokio::test]
+    async fn scope_aware_search() {
+        let response = json!({ "Total": 3, "From": 0, "Count": 3, "Icons": [ { "ID": "1", "Scope": "user" }, { "ID": "2", "Scope": "shared" }, { "ID": "3", "Scope": "shared" } ] });
+
+        let (endpoint, _) = mock_endpoint(vec![(200, response.clone())]).await;
+        let icon = endpoint.scope(ResourceKind::Icon.search_by_name("NAME", ResourceScope::User)).await.unwrap();
+        assert_eq!(icon, Some(json!({ "ID": "1", "Scope": "user" })));
+
+        let (endpoint, _) = mock_endpoint(vec![(200, response.clone())]).await;
+        let result = endpoint.scope(ResourceKind::Icon.search_by_name("NAME", ResourceScope::Shared)).await;
+        assert!(matches!(result, Err(Error::TooManyResources(_, ids)) if ids == ["2", "3"]));
+
+        let (endpoint, _) = mock_endpoint(vec![(200, response)]).await;
+        let icons = endpoint.scope(ResourceKind::Icon.search_all_by_name("NAME", ResourceScope::Any)).await.unwrap();
+        assert_eq!(icons.len(), 3);
+
+        // scope less kinds are regarded as user's
+        assert!(ResourceScope::User.matches(&json!({ "ID": "1" })));
+        assert!(!ResourceScope::Shared.matches(&json!({ "ID": "1" })));
+    }
+
+    #[tokio::test]
+    async fn dns_a_record() {
+        let response = json!({ "Total": 2, "From": 0, "Count": 2, "CommonServiceItems": [
+            { "ID": "1", "Name": "dev.example.com", "Provider": { "Class": "simplemon" }, "Settings": { "SimpleMonitor": {} } },
+            { "ID": "2", "Name": "example.com", "Provider": { "Class": "dns" }, "Settings": { "DNS": { "ResourceRecordSets": [
+                { "Name": "dev", "Type": "A", "RData": "192.0.2.1", "TTL": 60 },
+                { "Name": "dev", "Type": "TXT", "RData": "keep" },
+            ] } } },
+        ] });
+        let (endpoint, requests) = mock_endpoint(vec![(200, response), (200, json!({ "Success": true }))]).await;
+        let dns = endpoint.clone().scope(Dns::get_by_name("example.com")).await.unwrap().unwrap();
+        assert_eq!(dns.id().to_string(), "2");
+        assert_eq!(dns.a_record("dev"), Some("192.0.2.1"));
+        endpoint.scope(dns.set_a_record("dev", Ipv4Addr::new(198, 51, 100, 1), Some(60))).await.unwrap();
+        assert_eq!(requests.lock().unwrap()[1], "PUT /commonserviceitem/2");
+
+        let mut settings = dns.settings.clone();
+        settings.set_a_record("dev".to_string(), Ipv4Addr::new(198, 51, 100, 1), None);
+        assert_eq!(settings.dns.resource_record_sets.len(), 2);
+        assert_eq!(settings.dns.resource_record_sets[0].rdata, "keep");
+        assert_eq!(settings.dns.resource_record_sets[1].rdata, "198.51.100.1");
+    }
+
+    #[tokio::test]
+    async fn wait_loops() {
+        let (endpoint, requests) = mock_endpoint(vec![
+            (200, json!({ "Server": { "Availability": "migrating" } })),
+            (200, json!({ "Server": { "Availability": "available" } })),
+        ]).await;
+        endpoint.scope(ResourceKind::Server.wait_available("1")).await.unwrap();
+        assert_eq!(*requests.lock().unwrap(), vec!["GET /server/1", "GET /server/1"]);
+
+        let (endpoint, _) = mock_endpoint(vec![
+            (200, json!({ "Disk": { "Availability": "migrating", "MigratedMB": 0, "SizeMB": 20480 } })),
+            (200, json!({ "Disk": { "Availability": "migrating", "MigratedMB": 10240, "SizeMB": 20480 } })),
+            (200, json!({ "Disk": { "Availability": "available", "MigratedMB": 20480, "SizeMB": 20480 } })),
+        ]).await;
+        let mut percents = Vec::new();
+        endpoint.scope(Disk::wait_available_with_progress(DiskId::from("1".to_string()), |progress| percents.push((progress.status, progress.percent())))).await.unwrap();
+        let migrating = ResourceStatus::Availability(Availability::Migrating);
+        assert_eq!(percents, vec![(migrating, Some(0)), (migrating, Some(50))]);
+
+        let (endpoint, _) = mock_endpoint(vec![
+            (200, json!({ "Archive": { "Availability": "transferring" } })),
+            (200, json!({ "Archive": { "Availability": "available" } })),
+        ]).await;
+        endpoint.scope(ResourceKind::Archive.wait_available("1")).await.unwrap();
+
+        let (endpoint, _) = mock_endpoint(vec![(200, json!({ "Archive": { "Availability": "discontinued" } }))]).await;
+        let result = endpoint.scope(ResourceKind::Archive.wait_available("1")).await;
+        assert!(matches!(result, Err(Error::ResourceApiWaitStatusFailed(..))));
+
+        let (endpoint, _) = mock_endpoint(vec![(200, json!({ "Server": { "Availability": "failed" } }))]).await;
+        let result = endpoint.scope(ResourceKind::Server.wait_available("1")).await;
+        assert!(matches!(result, Err(Error::ResourceApiWaitStatusFailed(..))));
+
+        let (endpoint, _) = mock_endpoint(vec![(200, json!({ "Server": { "Availability": "unknown" } }))]).await;
+        let result = endpoint.scope(ResourceKind::Server.wait_available("1")).await;
+        assert!(matches!(result, Err(Error::ResourceApiWaitStatusUnknown(status, ..)) if status == "unknown"));
+
+        // a caller's predicate on the typed resource
+        let (endpoint, requests) = mock_endpoint(vec![
+            (200, json!({ "Server": { "ID": "1", "Name": "dev-server", "Availability": "available", "Tags": [] } })),
+            (200, json!({ "Server": { "ID": "1", "Name": "dev-server", "Availability": "available", "Tags": ["ready"] } })),
+        ]).await;
+        let summary = endpoint.scope(wait_for(ResourceKind::Server, "1", |summary: &ResourceSummary| {
+            if summary.tags().iter().any(|tag| tag == "ready") { WaitCheck::Done } else { WaitCheck::Working(Availability::Available.into()) }
+        }, None)).await.unwrap();
+        assert_eq!(summary.tags(), ["ready"]);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        let (endpoint, _) = mock_endpoint(vec![(200, json!({ "Server": { "Instance": { "Status": "cleaning" } } }))]).await;
+        let result = endpoint.scope(wait_for(ResourceKind::Server, "1", check_up, Some(Duration::ZERO))).await;
+        assert!(matches!(result, Err(Error::ResourceApiWaitTimedOut(_, 0, _))));
+
+        let (endpoint, requests) = mock_endpoint(vec![(200, json!({})), (404, json!({}))]).await;
+        endpoint.scope(fetch_until_not_found("server/1")).await.unwrap();
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn ensure_power() {
+        let (endpoint, requests) = mock_endpoint(vec![(200, json!({ "Instance": { "Status": "up" } }))]).await;
+        assert!(!endpoint.scope(Server::ensure_up(ServerId("1".into()))).await.unwrap());
+        assert_eq!(*requests.lock().unwrap(), vec!["GET /server/1/power"]);
+
+        // being booted by someone else in between
+        let (endpoint, requests) = mock_endpoint(vec![
+            (200, json!({ "Instance": { "Status": "down" } })),
+            (409, json!({ "is_fatal": true })),
+            (200, json!({ "Instance": { "Status": "down" } })),
+            (200, json!({ "Server": { "Instance": { "Status": "up" } } })),
+        ]).await;
+        assert!(endpoint.scope(Server::ensure_up(ServerId("1".into()))).await.unwrap());
+        assert_eq!(*requests.lock().unwrap(), vec!["GET /server/1/power", "PUT /server/1/power", "GET /server/1/power", "GET /server/1"]);
+
+        let (endpoint, requests) = mock_endpoint(vec![
+            (200, json!({ "Instance": { "Status": "up" } })),
+            (200, json!({ "Success": true })),
+            (200, json!({ "Appliance": { "Instance": { "Status": "down" } } })),
+        ]).await;
+        assert!(endpoint.scope(Appliance::ensure_down(ApplianceId("1".into()))).await.unwrap());
+        assert_eq!(*requests.lock().unwrap(), vec!["GET /appliance/1/power", "DELETE /appliance/1/power", "GET /appliance/1"]);
+
+        let (endpoint, _) = mock_endpoint(vec![(200, json!({ "Instance": { "Status": "down" } })), (409, json!({ "is_fatal": true })), (500, json!({}))]).await;
+        let result = endpoint.scope(Server::ensure_up(ServerId("1".into()))).await;
+        assert!(matches!(result, Err(Error::ApiInternalServerError(..))));
+
+
+        // the plain operations are idempotent too
+        let (endpoint, requests) = mock_endpoint(vec![(409, json!({ "is_fatal": true })), (200, json!({ "Instance": { "Status": "up" } }))]).await;
+        endpoint.scope(Server::up(ServerId("1".into()))).await.unwrap();
+        assert_eq!(*requests.lock().unwrap(), vec!["PUT /server/1/power", "GET /server/1/power"]);
+
+        let (endpoint, _) = mock_endpoint(vec![(409, json!({ "is_fatal": true })), (200, json!({ "Instance": { "Status": "cleaning" } }))]).await;
+        endpoint.scope(Server::shutdown(ServerId("1".into()))).await.unwrap();
+
+        let (endpoint, _) = mock_endpoint(vec![(409, json!({ "is_fatal": true })), (200, json!({ "Instance": { "Status": "up" } }))]).await;
+        let result = endpoint.scope(Appliance::down(ApplianceId("1".into()))).await;
+        assert!(matches!(result, Err(Error::ApiConflict(..))));
+    }
+
+    #[test]
+    fn api_unix_time() {
+        assert_eq!(unix_time("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(unix_time("2024-01-01T09:00:00+09:00"), Some(1704067200));
+        assert_eq!(unix_time("2024-03-01T00:00:00-01:30"), Some(1709256600));
+        assert_eq!(unix_time("2024-01-01"), None);
+        assert_eq!(unix_time("2024-01-01T00:00"), None);
+    }
+
+    #[tokio::test]
+    async fn error_mapping() {
+        for (status, expected) in [(400, "ApiBadRequest"), (401, "ApiUnauthorized"), (404, "ApiNotFound"), (409, "ApiConflict"), (503, "ApiServiceUnavailable"), (418, "ApiUnknownStatusCode")] {
+            let (endpoint, _) = mock_endpoint(vec![(status, json!({ "is_fatal": true }))]).await;
+            let e = endpoint.scope(request_api(Method::GET, "server", &None, &None)).await.unwrap_err();
+            assert_eq!(variant_name(&e), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn success_quirks() {
+        let cases = [
+            (json!({ "is_ok": true, "Success": true, "Server": {} }), None),
+            (json!({ "Success": "Accepted", "Server": {} }), None),
+            (json!({ "is_ok": false, "Server": {} }), Some("ResourceApiInvalidStatusFalse")),
+            (json!({ "Success": "Rejected", "Server": {} }), Some("ResourceApiInvalidStatusFalse")),
+            (json!({ "Success": false, "Server": {} }), Some("ResourceApiInvalidStatusFalse")),
+            (json!({ "is_ok": "true", "Server": {} }), Some("ResourceApiInvalidStatusDataType")),
+            (json!({ "Success": 1, "Server": {} }), Some("ResourceApiInvalidStatusDataType")),
+            (json!({ "is_ok": true, "Server": "1" }), Some("ResourceApiInvalidResourceObject")),
+        ];
+        for (response, expected) in cases {
+            let (endpoint, _) = mock_endpoint(vec![(200, response.clone())]).await;
+            let result = endpoint.scope(request_api_for_resource(Method::GET, "server/1", Some("Server"), None)).await;
+            match expected {
+                None => assert_eq!(result.unwrap(), json!({}), "{}", response),
+                Some(expected) => assert_eq!(variant_name(&result.unwrap_err()), expected, "{}", response),
+            }
+        }
+    }
+
+    #[test]
+    fn wait_progress_line() {
+        let disk = json!({ "Availability": "migrating", "MigratedMB": 1024, "SizeMB": 20480 });
+        let progress = WaitProgress::new(Availability::Migrating.into(), &disk, Duration::from_secs(80));
+        assert_eq!(progress.percent(), Some(5));
+        assert_eq!(progress.eta(), Some(Duration::from_secs(1520)));
+        assert_eq!(progress_line("disk/1", &progress), "[WAIT] disk/1 migrating 1024/20480 MB (5%), elapsed 1m20s, eta 25m20s");
+
+        let disk = json!({ "Availability": "migrating", "MigratedMB": 0, "SizeMB": 20480 });
+        assert_eq!(progress_line("disk/1", &WaitProgress::new(Availability::Migrating.into(), &disk, Duration::from_secs(7))), "[WAIT] disk/1 migrating 0/20480 MB (0%), elapsed 7s");
+
+        let server = json!({ "Instance": { "Status": "cleaning" } });
+        let progress = WaitProgress::new(InstanceStatus::Cleaning.into(), &server, Duration::from_secs(3725));
+        assert_eq!((progress.percent(), progress.eta()), (None, None));
+        assert_eq!(progress_line("server/1", &progress), "[WAIT] server/1 cleaning, elapsed 1h02m05s");
+    }
 }
 
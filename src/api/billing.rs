@@ -0,0 +1,172 @@
+//! The bills of the account in the system api, and the public price list to estimate the cost with.
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use reqwest::Method;
+
+use super::{
+    Error,
+    ResourceKind, ResourceId,
+    SearchQuery,
+    request_api, request_system_api, search,
+};
+
+/// a monthly bill of the account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bill {
+    /// the id of the bill, not of a resource
+    #[serde(rename = "BillID")]
+    pub id: ResourceId,
+
+    /// in JPY
+    #[serde(rename = "Amount", default)]
+    pub amount: Option<f64>,
+
+    /// the date of the bill as the api returns it, which the latest bill is found by
+    #[serde(rename = "Date", default)]
+    pub date: Option<String>,
+
+    /// whether it's paid, none if the api doesn't tell
+    #[serde(rename = "Paid", default)]
+    pub paid: Option<bool>,
+}
+
+/// a line of a bill, one per resource and service class
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BillDetail {
+    /// the description of the line as the api returns it
+    #[serde(rename = "Description", default)]
+    pub description: Option<String>,
+
+    /// e.g. cloud/plan/fixed/1core-1gb
+    #[serde(rename = "ServiceClassPath", default)]
+    pub service_class_path: Option<String>,
+
+    /// in JPY
+    #[serde(rename = "Amount", default)]
+    pub amount: Option<f64>,
+
+    /// none for the zone less services
+    #[serde(rename = "Zone", default)]
+    pub zone: Option<String>,
+
+    /// the usage formatted by the api
+    #[serde(rename = "FormattedUsage", default)]
+    pub usage: Option<String>,
+}
+
+/// the csv of a bill with its file name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BillDetailCsv {
+    /// the file name the api suggests
+    #[serde(rename = "Filename", default)]
+    pub filename: Option<String>,
+
+    /// the csv itself
+    #[serde(rename = "Body")]
+    pub body: String,
+}
+
+/// the price of a service class, one per zone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceClassPrice {
+    /// e.g. cloud/plan/fixed/1core-1gb, what `ResourceSummary::service_class` returns
+    #[serde(rename = "ServiceClassPath")]
+    pub service_class_path: String,
+
+    /// e.g. 1Core-1GB
+    #[serde(rename = "DisplayName", default)]
+    pub display_name: Option<String>,
+
+    /// none if the api has no price for it
+    #[serde(rename = "Price", default)]
+    pub price: Option<Price>,
+}
+
+/// the prices of a service class in a zone, in JPY
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Price {
+    /// the price per hour
+    #[serde(rename = "Hourly", default)]
+    pub hourly: Option<f64>,
+
+    /// the price per day
+    #[serde(rename = "Daily", default)]
+    pub daily: Option<f64>,
+
+    /// the price per month, which the cost estimations use
+    #[serde(rename = "Monthly", default)]
+    pub monthly: Option<f64>,
+
+    /// none for the entry common to all the zones, see `find_price`
+    #[serde(rename = "Zone", default)]
+    pub zone: Option<String>,
+}
+
+/// the account of the tokens, which the bills are filed under
+pub async fn account_id() -> Result<ResourceId, Error> {
+    let value = request_api(Method::GET, "auth-status", &None, &None).await?;
+    serde_json::from_value(value["Account"]["ID"].clone()).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Bill, e.to_string()))
+}
+
+/// the bills of the account, in the order the api returns them
+pub async fn bills(account_id: &ResourceId) -> Result<Vec<Bill>, Error> {
+    let mut value = request_system_api(Method::GET, format!("bill/by-contract/{}/", account_id), &None, &None).await?;
+    from_array(value[ResourceKind::Bill.prural_name()].take())
+}
+
+/// the lines of a bill
+pub async fn bill_details(bill_id: &ResourceId) -> Result<Vec<BillDetail>, Error> {
+    let mut value = request_system_api(Method::GET, format!("bill/id/{}/", bill_id), &None, &None).await?;
+    from_array(value["BillDetails"].take())
+}
+
+/// the csv of a bill, e.g. for `cost --bill-csv`
+pub async fn bill_detail_csv(bill_id: &ResourceId) -> Result<BillDetailCsv, Error> {
+    let value = request_system_api(Method::GET, format!("bill/id/{}/csv", bill_id), &None, &None).await?;
+    serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Bill, e.to_string()))
+}
+
+/// the whole public price list, in all the zones
+pub async fn service_class_prices() -> Result<Vec<ServiceClassPrice>, Error> {
+    let kind = ResourceKind::ServiceClassPrice;
+    let values = search(kind.path(), kind.prural_name(), &SearchQuery::new().page_size(500)).await?;
+    from_array(Value::from(values))
+}
+
+/// price list has an entry per zone, zone less entry is regarded as common
+pub fn find_price<'a>(prices: &'a [ServiceClassPrice], service_class_path: &str, zone: &str) -> Option<&'a Price> {
+    let candidates = prices.iter()
+        .filter(|p| p.service_class_path == service_class_path)
+        .filter_map(|p| p.price.as_ref())
+        .collect::<Vec<_>>();
+    candidates.iter().find(|p| p.zone.as_deref() == Some(zone))
+        .or_else(|| candidates.iter().find(|p| p.zone.is_none()))
+        .copied()
+}
+
+fn from_array<T: serde::de::DeserializeOwned>(value: Value) -> Result<Vec<T>, Error> {
+    if value.is_null() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_value(value).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Bill, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn price_json() {
+        let prices: Vec<ServiceClassPrice> = from_array(json!([
+            { "ServiceClassPath": "cloud/plan/fixed/1core-1gb", "Price": { "Hourly": 1.0, "Monthly": 100.0, "Zone": "is1a" } },
+            { "ServiceClassPath": "cloud/plan/fixed/1core-1gb", "Price": { "Hourly": 2.0, "Monthly": 200.0, "Zone": "tk1a" } },
+            { "ServiceClassPath": "cloud/disk/ssd/20g", "Price": { "Monthly": 50.0 } },
+        ])).unwrap();
+
+        assert_eq!(find_price(&prices, "cloud/plan/fixed/1core-1gb", "tk1a").and_then(|p| p.monthly), Some(200.0));
+        assert_eq!(find_price(&prices, "cloud/disk/ssd/20g", "tk1a").and_then(|p| p.monthly), Some(50.0));
+        assert_eq!(find_price(&prices, "cloud/plan/fixed/2core-2gb", "tk1a"), None);
+    }
+}
@@ -0,0 +1,142 @@
+//! The monitor api of the servers, the disks and the interfaces, as samples in the time order.
+
+use std::{borrow::Borrow, collections::BTreeMap};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use reqwest::Method;
+
+use super::{
+    Error,
+    ResourceKind,
+    ServerId, DiskId, InterfaceId,
+    request_api,
+};
+
+// the monitor api returns { "Data": { "<timestamp>": { ... }, ... } }, timestamps are ISO 8601 with the same offset
+// so the lexical order of the keys is the time order
+
+/// a sample value of the server monitor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CpuTimeValue {
+    /// the CPU-TIME of the api, none when the sample has no value
+    #[serde(rename = "CPU-TIME", default)]
+    pub cpu_time: Option<f64>,
+}
+
+/// a sample value of the disk monitor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiskValue {
+    /// the Read of the api, none when the sample has no value
+    #[serde(rename = "Read", default)]
+    pub read: Option<f64>,
+
+    /// the Write of the api, none when the sample has no value
+    #[serde(rename = "Write", default)]
+    pub write: Option<f64>,
+}
+
+/// a sample value of the interface monitor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterfaceValue {
+    /// the Receive of the api, none when the sample has no value
+    #[serde(rename = "Receive", default)]
+    pub receive: Option<f64>,
+
+    /// the Send of the api, none when the sample has no value
+    #[serde(rename = "Send", default)]
+    pub send: Option<f64>,
+}
+
+/// a value of the monitor at a time
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Sample<T> {
+    /// the timestamp key of the api, ISO 8601
+    pub time: String,
+    /// the value at the time
+    pub value: T,
+}
+
+/// the start and the end of the samples, the api default when both are none
+#[derive(Debug, Clone, Default)]
+pub struct MonitorSpan {
+    start: Option<String>,
+    end: Option<String>,
+}
+
+impl MonitorSpan {
+    /// ISO 8601 strings, e.g. 2024-01-01T00:00:00+09:00
+    pub fn new(start: Option<String>, end: Option<String>) -> Self {
+        Self { start, end }
+    }
+
+    fn to_query(&self) -> Option<Value> {
+        if self.start.is_none() && self.end.is_none() {
+            return None;
+        }
+        let mut query = json!({});
+        if let Some(start) = &self.start {
+            query["Start"] = Value::from(start.clone());
+        }
+        if let Some(end) = &self.end {
+            query["End"] = Value::from(end.clone());
+        }
+        Some(query)
+    }
+}
+
+/// fetches the cpu time samples of the server
+pub async fn server_cpu(server_id: impl Borrow<ServerId>, span: &MonitorSpan) -> Result<Vec<Sample<CpuTimeValue>>, Error> {
+    let server_id = server_id.borrow();
+    fetch_samples(format!("server/{}/monitor", server_id), span).await
+}
+
+/// fetches the read and write samples of the disk
+pub async fn disk(disk_id: impl Borrow<DiskId>, span: &MonitorSpan) -> Result<Vec<Sample<DiskValue>>, Error> {
+    let disk_id = disk_id.borrow();
+    fetch_samples(format!("disk/{}/monitor", disk_id), span).await
+}
+
+/// fetches the receive and send samples of the interface
+pub async fn interface(interface_id: impl Borrow<InterfaceId>, span: &MonitorSpan) -> Result<Vec<Sample<InterfaceValue>>, Error> {
+    let interface_id = interface_id.borrow();
+    fetch_samples(format!("interface/{}/monitor", interface_id), span).await
+}
+
+async fn fetch_samples<T: DeserializeOwned>(path: impl AsRef<str>, span: &MonitorSpan) -> Result<Vec<Sample<T>>, Error> {
+    let mut value = request_api(Method::GET, path, &span.to_query(), &None).await?;
+    let data = value[ResourceKind::Monitor.single_name()].take();
+    samples_from_value(data)
+}
+
+fn samples_from_value<T: DeserializeOwned>(data: Value) -> Result<Vec<Sample<T>>, Error> {
+    if data.is_null() {
+        return Ok(Vec::new());
+    }
+    let data: BTreeMap<String, T> = serde_json::from_value(data).map_err(|e| Error::ResourceDeserializationFailed(ResourceKind::Monitor, e.to_string()))?;
+    Ok(data.into_iter().map(|(time, value)| Sample { time, value }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monitor_json() {
+        let samples: Vec<Sample<CpuTimeValue>> = samples_from_value(json!({
+            "2024-01-01T00:10:00+09:00": { "CPU-TIME": 0.5 },
+            "2024-01-01T00:05:00+09:00": { "CPU-TIME": 0.25 },
+            "2024-01-01T00:15:00+09:00": { "CPU-TIME": null },
+        })).unwrap();
+        assert_eq!(samples.iter().map(|s| s.time.as_str()).collect::<Vec<_>>(), vec![
+            "2024-01-01T00:05:00+09:00",
+            "2024-01-01T00:10:00+09:00",
+            "2024-01-01T00:15:00+09:00",
+        ]);
+        assert_eq!(samples.iter().map(|s| s.value.cpu_time).collect::<Vec<_>>(), vec![Some(0.25), Some(0.5), None]);
+
+        let samples: Vec<Sample<InterfaceValue>> = samples_from_value(json!({
+            "2024-01-01T00:05:00+09:00": { "Receive": 10.0, "Send": 20.0 },
+        })).unwrap();
+        assert_eq!(samples[0].value, InterfaceValue { receive: Some(10.0), send: Some(20.0) });
+    }
+}
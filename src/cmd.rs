@@ -1,128 +1,602 @@
-use std::{path::PathBuf, io, time::Duration, thread};
-use clap::{Parser, Subcommand};
-use tokio::{fs, time::sleep, runtime::Runtime, signal};
-use serde::Serialize;
+//! The subcommands of the binary, each of them is runnable on its own with `run`.
+
+use std::{path::PathBuf, str::FromStr, io::{self, IsTerminal}, os::unix::fs::PermissionsExt, env, future::Future, time::{Duration, SystemTime, UNIX_EPOCH}, thread, net::Ipv4Addr, sync::{Arc, Mutex}};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use tokio::{fs, io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader}, time::{sleep, timeout, Instant}, runtime::Runtime, signal, process::Command};
+use serde::{Serialize, Deserialize};
+use serde_json::{json, to_string_pretty, Value};
 use dirs::home_dir;
+use futures::StreamExt;
+use crossterm::event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Row, Sparkline, Table},
+    DefaultTerminal, Frame,
+};
 
 use crate::{
     api::{
         self,
-        Server, ServerId,
+        monitor::{self, MonitorSpan},
+        billing,
+        ApiEndpoint, ResourceChooser, ResourceKind, ResourceId, ResourceSummary, SearchQuery,
+        Server, ServerId, ServerPlan, ServerPlanCommitment,
         Switch, SwitchId,
-        SshPublicKeyId,
-        Appliance, ApplianceId,
-        Archive,
-        Disk,
-        Note,
+        SshPublicKey, SshPublicKeyId,
+        Appliance, ApplianceId, ApplianceClass,
+        Archive, ArchiveId, ArchiveInfo,
+        Disk, DiskId,
+        Note, NoteInfo, NoteClass, ResourceScope,
+        SimpleMonitor,
+        Dns,
+        AutoBackup,
+        Sim, SimInfo,
+        ProxyLb, ProxyLbCertificates, ProxyLbCertificate,
+        Cdrom, CdromInfo,
+        Icon,
+        PrivateHost,
+        Interface, InterfaceId, PacketFilterId,
+        ShortcutKey,
         InstanceStatus,
     },
     service_env::{
         self,
         CONFIG,
         PRIMARY_SERVER_FORWARDED_PORT,
+        REPLACEMENT_SERVER_FORWARDED_PORT,
+        PRIMARY_SERVER_DISK_SIZE_MB,
+        ForwardingDirection,
+        Feature,
+        ForwardingPortConfig,
+        HealthCheckConfig,
+        StandbyDnsConfig,
+        EquipmentKind,
         PrimaryVpcRouter,
         PrimarySwitch,
         PrimaryServer,
+        ServerColor,
         PrimaryServerDisk,
         PrimaryServerSshPublicKey,
+        ExtraServerSshPublicKey,
         PrimaryServerSetupShellNote,
+        PrimarySimpleMonitor,
+        PrimaryServerDiskAutoBackup,
+        PrimaryNfs,
+        PrimaryMobileGateway,
+        PrimaryIcon,
+        MigrationArchive,
+        GoldenArchive,
+        Manifest, ManifestServer,
     },
     service_script::{
         self,
-        ServiceScript,
+        ServiceScript, SetupState, SetupStatus,
     },
     ssh::{
         self,
-        Session,
+        Session, SshAuth, SshOptions, SshJump, SyncOptions, Glob,
+    },
+    object_storage::{
+        self,
+        Bucket,
+    },
+    ftps,
+    notifier::{
+        self,
+        NotifyEvent,
+    },
+    prometheus::{
+        self,
+        Gauges,
     },
 };
 
-#[derive(Debug, Serialize)]
-pub(crate) enum Error {
+/// the errors of the commands
+#[derive(Debug, Serialize, thiserror::Error)]
+pub enum Error {
+    /// the server and the switch it should be connected to
+    #[error("the server {0} is not connected to the switch {1}")]
     PrimaryServerNotConnectedToSwitch(ServerId, SwitchId),
+    /// the switch and the vpc router it should be connected to
+    #[error("the switch {0} is not connected to the vpc router {1}")]
     PrimarySwitchNotConnectedToVpcRouter(SwitchId, ApplianceId),
+    /// the key resource, the registered key and the given key
+    #[error("the ssh public key {0} is already registered with another key")]
     PrimarySshPublicKeyAlreadyRegisteredButMismatch(SshPublicKeyId, String, String),
+    /// no public key is given or found for the new disk
+    #[error("an ssh public key is needed to create the disk")]
     PrimarySshPublicKeyNotGivenForNewServerDisk,
+    /// the ssh public key couldn't be read, with the path and the io error
+    #[error("couldn't read the ssh public key {0}: {1}")]
     PrimarySshPublicKeyGivenButCouldntRead(PathBuf, String),
+    /// the ssh key couldn't be generated, with the path and the ssh-keygen error
+    #[error("couldn't generate the ssh key {0}: {1}")]
+    SshKeyCouldntGenerate(PathBuf, String),
+    /// the vpc router of the prefix doesn't exist
+    #[error("the vpc router doesn't exist, run update first")]
     PrimaryVpcRouterNotExists,
-    ServiceScriptError(service_script::Error),
-    ApiError(api::Error),
-    ServiceEnvError(service_env::Error),
-    SshError(ssh::Error),
+    /// the switch of the prefix doesn't exist
+    #[error("the switch doesn't exist, run update first")]
+    PrimarySwitchNotExists,
+    /// the server of the prefix doesn't exist
+    #[error("the server doesn't exist, run update first")]
+    PrimaryServerNotExists,
+    /// the disk of the server of the prefix doesn't exist
+    #[error("the server disk doesn't exist, run update first")]
+    PrimaryServerDiskNotExists,
+    /// the ssh public key of the prefix doesn't exist
+    #[error("the ssh public key doesn't exist, run update first")]
+    PrimarySshPublicKeyNotExists,
+    /// the new key and the login error, the old key is still authorized
+    #[error("couldn't log in with the new key {0}, the old key is left authorized: {1}")]
+    NewSshKeyLoginFailed(PathBuf, String),
+    /// the new key is the registered one
+    #[error("the new key {0} is the same as the registered one")]
+    NewSshKeyNotChanged(PathBuf),
+    /// the csv couldn't be written, with the path and the io error
+    #[error("couldn't write the bill csv {0}: {1}")]
+    BillCsvCouldntWrite(PathBuf, String),
+    /// the name of the sim not found
+    #[error("the sim {0} doesn't exist")]
+    SimNotExists(String),
+    /// the name of the note not found
+    #[error("the note {0} doesn't exist")]
+    NoteNotExists(String),
+    /// the script couldn't be read, with the path and the io error
+    #[error("couldn't read the script {0}: {1}")]
+    NoteScriptCouldntRead(PathBuf, String),
+    /// the name of the proxy lb not found
+    #[error("the proxy lb {0} doesn't exist")]
+    ProxyLbNotExists(String),
+    /// the proxy lb and the number of its additional certificates
+    #[error("the proxy lb {0} has {1} additional certificates")]
+    ProxyLbHasAdditionalCerts(String, usize),
+    /// the certificate couldn't be read, with the path and the io error
+    #[error("couldn't read the certificate {0}: {1}")]
+    CertCouldntRead(PathBuf, String),
+    /// the command needs object_storage in the config
+    #[error("object_storage is not in the config")]
+    ObjectStorageNotConfigured,
+    /// the backup has no destination
+    #[error("neither --local-dir nor --to-bucket is given")]
+    BackupLocalDirNotGiven,
+    /// the backup couldn't be written, with the path and the io error
+    #[error("couldn't write the backup {0}: {1}")]
+    BackupCouldntWriteLocalFile(PathBuf, String),
+    /// the iso has no name to find or upload
+    #[error("neither --name nor --iso is given")]
+    IsoNameNotGiven,
+    /// the iso couldn't be read, with the path and the io error
+    #[error("couldn't read the iso {0}: {1}")]
+    IsoCouldntRead(PathBuf, String),
+    /// the iso path and its size beyond the cdrom sizes
+    #[error("the iso {0} is too large, {1} bytes")]
+    IsoTooLarge(PathBuf, u64),
+    /// the name of the iso image not found
+    #[error("the iso image {0} doesn't exist")]
+    CdromNotExists(String),
+    /// the image couldn't be read, with the path and the io error
+    #[error("couldn't read the image {0}: {1}")]
+    ArchiveImageCouldntRead(PathBuf, String),
+    /// the image path and its size beyond the archive sizes
+    #[error("the image {0} is too large, {1} bytes")]
+    ArchiveImageTooLarge(PathBuf, u64),
+    /// the name of the archive already used
+    #[error("the archive {0} already exists")]
+    ArchiveAlreadyExists(String),
+    /// the name of the archive not found
+    #[error("the archive {0} doesn't exist")]
+    ArchiveNotExists(String),
+    /// the name of the iso image not yet available
+    #[error("the iso image {0} is not available")]
+    CdromNotAvailable(String),
+    /// the server has no iso image to eject
+    #[error("no iso image is inserted")]
+    CdromNotInserted,
+    /// the name of the switch not found
+    #[error("the switch {0} doesn't exist")]
+    NicSwitchNotExists(String),
+    /// the nic is not of the server of the prefix
+    #[error("the nic {0} is not of the server")]
+    NicNotOfPrimaryServer(InterfaceId),
+    /// the first nic connects the server to the switch of the prefix
+    #[error("the first nic {0} is not removable")]
+    NicFirstNotRemovable(InterfaceId),
+    /// the api returned no vnc file
+    #[error("the api didn't provide the vnc file")]
+    ConsoleVncFileNotProvided,
+    /// the vnc file couldn't be written, with the path and the io error
+    #[error("couldn't write the vnc file {0}: {1}")]
+    ConsoleVncFileCouldntWrite(PathBuf, String),
+    /// the error launching the vnc viewer
+    #[error("couldn't launch the vnc viewer: {0}")]
+    ConsoleViewerCouldntLaunch(String),
+    /// the command needs the wireguard of the config
+    #[error("features.wireguard is off or server.wireguard is not in the config")]
+    WireguardNotConfigured,
+    /// the wireguard private key couldn't be read, with the path and the io error
+    #[error("couldn't read the wireguard private key {0}: {1}")]
+    WireguardKeyCouldntRead(PathBuf, String),
+    /// the wireguard config couldn't be written, with the path and the io error
+    #[error("couldn't write the wireguard config {0}: {1}")]
+    WireguardConfCouldntWrite(PathBuf, String),
+    /// the qrencode error
+    #[error("couldn't render the qr code by qrencode: {0}")]
+    WireguardQrCouldntRender(String),
+    /// the icon of the config has neither id nor png
+    #[error("icon needs either id or png in the config")]
+    IconNeitherIdNorPngGiven,
+    /// the png of the icon couldn't be read, with the path and the io error
+    #[error("couldn't read the icon {0}: {1}")]
+    IconPngCouldntRead(PathBuf, String),
+    /// the known_hosts couldn't be updated, with the path and the io error
+    #[error("couldn't update the known hosts {0}: {1}")]
+    KnownHostsCouldntUpdate(PathBuf, String),
+    /// the control dir couldn't be created, with the path and the io error
+    #[error("couldn't create the ssh control dir {0}: {1}")]
+    ControlDirCouldntCreate(PathBuf, String),
+    /// the url and why the health check failed
+    #[error("the health check of {0} didn't respond 200: {1}")]
+    HealthCheckFailed(String, String),
+    /// the update progress couldn't be read, with the path and the io error
+    #[error("couldn't read the update progress {0}: {1}")]
+    UpdateProgressCouldntRead(PathBuf, String),
+    /// the update progress couldn't be written, with the path and the io error
+    #[error("couldn't write the update progress {0}: {1}")]
+    UpdateProgressCouldntWrite(PathBuf, String),
+    /// the zone migrated to is the current one
+    #[error("already in {0}")]
+    MigrationToSameZone(String),
+    /// the snapshot archive is gone while migrating
+    #[error("the snapshot archive disappeared")]
+    MigrationArchiveNotExists,
+    /// the archive whose size the api didn't tell
+    #[error("the size of the snapshot {0} is unknown")]
+    MigrationArchiveSizeUnknown(ArchiveId),
+    /// the disk image of the migration couldn't be written, with the path and the io error
+    #[error("couldn't write the disk image {0}: {1}")]
+    MigrationImageCouldntWrite(PathBuf, String),
+    /// the command needs standby in the config
+    #[error("no standby in config")]
+    StandbyNotConfigured,
+    /// standby.zone is the current zone
+    #[error("standby.zone {0} is the current zone")]
+    StandbyInCurrentZone(String),
+    /// the standby zone whose server is up
+    #[error("the standby server in {0} is up, shut it down if it's not the active one")]
+    StandbyServerIsUp(String),
+    /// the standby zone without the standby
+    #[error("no standby in {0}, run `update` first")]
+    StandbyNotExists(String),
+    /// the name of the dns zone not found
+    #[error("dns zone {0} not exists")]
+    DnsZoneNotExists(String),
+    /// the appliance given to adopt as the vpc router
+    #[error("appliance {0} is not a vpc router")]
+    AdoptNotVpcRouter(ApplianceId),
+    /// the server given to adopt without a disk
+    #[error("server {0} has no disk")]
+    AdoptServerHasNoDisk(ServerId),
+    /// the name and the resource using it
+    #[error("{0} is already used by {1}")]
+    AdoptNameTaken(String, String),
+    /// the server whose plan is unknown
+    #[error("the plan of the server {0} is unknown")]
+    ManifestServerPlanUnknown(ServerId),
+    /// the disk whose plan or size is unknown
+    #[error("the plan or the size of the disk {0} is unknown")]
+    ManifestDiskPlanUnknown(DiskId),
+    /// the manifest couldn't be written, with the path and the io error
+    #[error("couldn't write the manifest {0}: {1}")]
+    ManifestCouldntWrite(PathBuf, String),
+    /// what the manifest can't reproduce
+    #[error("the manifest can't be reproduced:\n  {}", .0.join("\n  "))]
+    ManifestHasProblems(Vec<String>),
+    /// the filter not in name=substr or tag=tag
+    #[error("invalid filter: {0}")]
+    InvalidListFilter(String),
+    /// the interval not a positive number of s, m, h or d
+    #[error("invalid interval: {0}, e.g. 30s, 10m or 1h")]
+    InvalidInterval(String),
+    /// the forward spec not in the format of the option
+    #[error("invalid forward spec: {0}")]
+    InvalidForwardSpec(String),
+    /// what the config has wrong
+    #[error("the config has problems:\n  {}", .0.join("\n  "))]
+    ConfigHasProblems(Vec<String>),
+    /// the method not of the http methods
+    #[error("invalid method: {0}")]
+    ApiCallInvalidMethod(String),
+    /// the query not in k=v
+    #[error("invalid query: {0}")]
+    ApiCallInvalidQuery(String),
+    /// the request body couldn't be read, with the path and the io error
+    #[error("couldn't read the body {0}: {1}")]
+    ApiCallBodyCouldntRead(PathBuf, String),
+    /// the request body isn't json, with the path and the json error
+    #[error("invalid body json {0}: {1}")]
+    ApiCallBodyInvalidJson(PathBuf, String),
+    /// the io error of the terminal
+    #[error("the dashboard terminal failed: {0}")]
+    DashboardTerminalFailed(String),
+    /// an error of the setup scripts
+    #[error("setup script failed")]
+    ServiceScriptError(#[from] service_script::Error),
+    /// an error of the api
+    #[error("api call failed")]
+    ApiError(#[from] api::Error),
+    /// an error of the environment
+    #[error("service env failed")]
+    ServiceEnvError(#[from] service_env::Error),
+    /// an error of ssh
+    #[error("ssh failed")]
+    SshError(#[from] ssh::Error),
+    /// an error of the object storage
+    #[error("object storage failed")]
+    ObjectStorageError(#[from] object_storage::Error),
+    /// an error of the metrics listener
+    #[error("metrics listener failed")]
+    PrometheusError(#[from] prometheus::Error),
+    /// an error of ftps
+    #[error("ftps failed")]
+    FtpsError(#[from] ftps::Error),
+    /// the prefixes whose clean failed
+    #[error("clean of {} failed", .0.join(", "))]
+    CleanFailed(Vec<String>),
+    /// the prefix typed for the confirmation didn't match
+    #[error("the prefix didn't match, aborted")]
+    PrefixNotMatched,
+    /// the confirmation needs a terminal
+    #[error("stdin is not a terminal, pass --yes or --force to confirm")]
+    NotInteractive,
 }
 
-impl From<api::Error> for Error {
-    fn from(e: api::Error) -> Self {
-        Error::ApiError(e)
+/// 2 is used by clap for usage errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// the other errors, exit code 1
+    Other,
+    /// the api rejected the tokens, exit code 3
+    Auth,
+    /// a resource is not found, exit code 4
+    NotFound,
+    /// the wait timed out, exit code 5
+    Timeout,
+    /// ssh failed, exit code 6
+    Ssh,
+    /// the confirmation was refused, exit code 7
+    Aborted,
+}
+
+impl ErrorCategory {
+    /// the exit code of the category
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Other => 1,
+            Self::Auth => 3,
+            Self::NotFound => 4,
+            Self::Timeout => 5,
+            Self::Ssh => 6,
+            Self::Aborted => 7,
+        }
     }
 }
 
-impl From<service_env::Error> for Error {
-    fn from(e: service_env::Error) -> Self {
-        Error::ServiceEnvError(e)
+impl Error {
+    /// the category of the error for the exit code
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::PrimaryVpcRouterNotExists
+            | Self::PrimarySwitchNotExists
+            | Self::PrimaryServerNotExists
+            | Self::PrimaryServerDiskNotExists
+            | Self::PrimarySshPublicKeyNotExists
+            | Self::SimNotExists(_)
+            | Self::NoteNotExists(_)
+            | Self::ProxyLbNotExists(_)
+            | Self::CdromNotExists(_)
+            | Self::ArchiveNotExists(_)
+            | Self::NicSwitchNotExists(_)
+            | Self::ServiceEnvError(service_env::Error::ServerPlanNotFound(..) | service_env::Error::NoteNotFound(_)) => ErrorCategory::NotFound,
+            Self::PrefixNotMatched | Self::NotInteractive => ErrorCategory::Aborted,
+            Self::ApiError(e) => api_error_category(e),
+            Self::ServiceEnvError(service_env::Error::ApiError(e)) => api_error_category(e),
+            Self::ServiceScriptError(service_script::Error::TimeoutToStart | service_script::Error::TimeoutToFinish) => ErrorCategory::Timeout,
+            Self::ServiceScriptError(service_script::Error::SshError(e)) | Self::SshError(e) => ssh_error_category(e),
+            _ => ErrorCategory::Other,
+        }
     }
 }
 
-impl From<service_script::Error> for Error {
-    fn from(e: service_script::Error) -> Self {
-        Error::ServiceScriptError(e)
+fn api_error_category(e: &api::Error) -> ErrorCategory {
+    match e {
+        api::Error::ApiUnauthorized(..) | api::Error::ApiForbidden(..) => ErrorCategory::Auth,
+        api::Error::ResourceNotFound(_) | api::Error::ApiNotFound(..) => ErrorCategory::NotFound,
+        api::Error::ApiRequestTimeout(..) => ErrorCategory::Timeout,
+        _ => ErrorCategory::Other,
     }
 }
 
-impl From<ssh::Error> for Error {
-    fn from(e: ssh::Error) -> Self {
-        Error::SshError(e)
+fn ssh_error_category(e: &ssh::Error) -> ErrorCategory {
+    match e {
+        ssh::Error::SshAgentNotRunning | ssh::Error::SshAddFailed(..) | ssh::Error::SshKeygenFailed(..) | ssh::Error::HostKeyMismatch(..) => ErrorCategory::Auth,
+        _ => ErrorCategory::Ssh,
     }
 }
 
+/// the options of the binary given to every command, instead of switching the process
+#[derive(Debug, Clone, Default, Args)]
+pub struct GlobalArgs {
+    /// confirm the destructive commands without asking, they are refused if stdin is not a terminal otherwise
+    #[arg(long, global = true)]
+    pub yes: bool,
+
+    /// open a new ssh connection instead of reusing the one left by the previous command
+    #[arg(long, global = true)]
+    pub no_reuse: bool,
+
+    /// the resource to use when the same named ones are found, asked on the terminal otherwise
+    #[arg(long = "id", global = true, value_parser = parse_id::<ResourceId>)]
+    pub ids: Vec<ResourceId>,
+}
+
+/// the subcommands of the binary
+// the variants are left undocumented, a doc on a variant would override the help of its command struct
+#[allow(missing_docs)]
 #[derive(Debug, Subcommand)]
-pub(crate) enum Cmd {
+pub enum Cmd {
     SyncRemoteDir(SyncRemoteDirCmd),
     PortForwarding(PortForwardingCmd),
     Update(UpdateCmd),
+    Provision(ProvisionCmd),
+    Deploy(DeployCmd),
     Clean(CleanCmd),
+    Doctor(DoctorCmd),
+    List(ListCmd),
+    ApiCall(ApiCallCmd),
+    Metrics(MetricsCmd),
+    Cost(CostCmd),
+    Sim(SimCmd),
+    Cert(CertCmd),
+    Backup(BackupCmd),
+    MountIso(MountIsoCmd),
+    Nic(NicCmd),
+    ResizeServer(ResizeServerCmd),
+    Console(ConsoleCmd),
+    Config(ConfigCmd),
+    Logs(LogsCmd),
+    Reboot(RebootCmd),
+    Migrate(MigrateCmd),
+    Failover(FailoverCmd),
+    Export(ExportCmd),
+    Adopt(AdoptCmd),
+    ExportManifest(ExportManifestCmd),
+    ApplyManifest(ApplyManifestCmd),
+    ServeMetrics(ServeMetricsCmd),
+    Watch(WatchCmd),
+    ShowPassword(ShowPasswordCmd),
+    RotateKey(RotateKeyCmd),
+    ServerPlans(ServerPlansCmd),
+    Note(NoteCmd),
+    Reap(ReapCmd),
+    Dashboard(DashboardCmd),
+    PruneArchives(PruneArchivesCmd),
+    WireguardConfig(WireguardConfigCmd),
+    Status(StatusCmd),
+    Bake(BakeCmd),
+    UploadArchive(UploadArchiveCmd),
+    DownloadArchive(DownloadArchiveCmd),
+    Describe(DescribeCmd),
 }
 
 impl Cmd {
-    pub(crate) async fn run(&self) -> Result<(), Error> {
+    /// runs the command, with the resources chosen by --id
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        resource_chooser(global).scope(self.run_with(global)).await
+    }
+
+    async fn run_with(&self, global: &GlobalArgs) -> Result<(), Error> {
         match self {
-            Cmd::SyncRemoteDir(cmd) => cmd.run().await,
-            Cmd::PortForwarding(cmd) => cmd.run().await,
-            Cmd::Update(cmd) => cmd.run().await,
-            Cmd::Clean(cmd) => cmd.run().await,
+            Cmd::SyncRemoteDir(cmd) => cmd.run(global).await,
+            Cmd::PortForwarding(cmd) => cmd.run(global).await,
+            Cmd::Update(cmd) => cmd.run(global).await,
+            Cmd::Provision(cmd) => cmd.run(global).await,
+            Cmd::Deploy(cmd) => cmd.run(global).await,
+            Cmd::Clean(cmd) => cmd.run(global).await,
+            Cmd::Doctor(cmd) => cmd.run(global).await,
+            Cmd::List(cmd) => cmd.run().await,
+            Cmd::ApiCall(cmd) => cmd.run().await,
+            Cmd::Metrics(cmd) => cmd.run().await,
+            Cmd::Cost(cmd) => cmd.run().await,
+            Cmd::Sim(cmd) => cmd.run().await,
+            Cmd::Cert(cmd) => cmd.run().await,
+            Cmd::Backup(cmd) => cmd.run(global).await,
+            Cmd::MountIso(cmd) => cmd.run().await,
+            Cmd::Nic(cmd) => cmd.run(global).await,
+            Cmd::ResizeServer(cmd) => cmd.run(global).await,
+            Cmd::Console(cmd) => cmd.run().await,
+            Cmd::Config(cmd) => cmd.run().await,
+            Cmd::Logs(cmd) => cmd.run(global).await,
+            Cmd::Reboot(cmd) => cmd.run(global).await,
+            Cmd::Migrate(cmd) => cmd.run(global).await,
+            Cmd::Failover(cmd) => cmd.run(global).await,
+            Cmd::Export(cmd) => cmd.run().await,
+            Cmd::Adopt(cmd) => cmd.run(global).await,
+            Cmd::ExportManifest(cmd) => cmd.run().await,
+            Cmd::ApplyManifest(cmd) => cmd.run(global).await,
+            Cmd::ServeMetrics(cmd) => cmd.run().await,
+            Cmd::Watch(cmd) => cmd.run(global).await,
+            Cmd::ShowPassword(cmd) => cmd.run(global).await,
+            Cmd::RotateKey(cmd) => cmd.run(global).await,
+            Cmd::ServerPlans(cmd) => cmd.run().await,
+            Cmd::Note(cmd) => cmd.run().await,
+            Cmd::Reap(cmd) => cmd.run(global).await,
+            Cmd::Dashboard(cmd) => cmd.run(global).await,
+            Cmd::PruneArchives(cmd) => cmd.run(global).await,
+            Cmd::WireguardConfig(cmd) => cmd.run().await,
+            Cmd::Status(cmd) => cmd.run(global).await,
+            Cmd::Bake(cmd) => cmd.run(global).await,
+            Cmd::UploadArchive(cmd) => cmd.run().await,
+            Cmd::DownloadArchive(cmd) => cmd.run().await,
+            Cmd::Describe(cmd) => cmd.run().await,
         }
     }
 }
 
+/// downloads the files of the remote dir on the server into the local dir over sftp
 #[derive(Debug, Parser)]
-pub(crate) struct SyncRemoteDirCmd {
+pub struct SyncRemoteDirCmd {
     #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
     prefix: String,
 
-    #[arg(long)]
-    privkey: Option<PathBuf>,
+    #[command(flatten)]
+    ssh: SshArgs,
 
     #[arg(long)]
     local_dir: PathBuf,
 
     #[arg(long)]
     remote_dir: PathBuf,
+
+    // number of files downloaded at once
+    #[arg(long, default_value_t = 4)]
+    parallelism: usize,
+
+    // only compare the sizes of the downloaded files, skipping sha256sum on the server
+    #[arg(long)]
+    no_verify_sha256: bool,
+
+    // glob patterns of the file names to download, e.g. --include '*.tar.gz', all files if not given
+    #[arg(long)]
+    include: Vec<String>,
+
+    // glob patterns of the file names to skip, temporary files like *.tmp and *.swp are always skipped
+    #[arg(long)]
+    exclude: Vec<String>,
 }
 
 impl SyncRemoteDirCmd {
-    pub(crate) async fn run(&self) -> Result<(), Error> {
+    /// runs `sync-remote-dir`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
         let prefix = self.prefix.as_str();
         let local_dir = self.local_dir.as_path();
         let remote_dir = self.remote_dir.as_path();
-        let ssh_public_key_path = self.privkey.clone().unwrap_or(default_privkey_path());
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
 
         let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
             return Err(Error::PrimaryVpcRouterNotExists);
         };
         let public_shared_ip = vpc_router.public_shared_ip()?;
-        let session = Session::connect(public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, "ubuntu", ssh_public_key_path).await?;
+        let session = Session::connect(public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, &ssh_options).await?;
 
-        let result = session.sync_remote_dir(remote_dir, local_dir).await;
+        let result = session.sync_remote_dir(remote_dir, local_dir, &SyncOptions {
+            parallelism: self.parallelism,
+            verify_sha256: !self.no_verify_sha256,
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+        }).await;
         let _ = session.close().await;
         match result {
             Ok(_) => Ok(()),
@@ -131,269 +605,409 @@ impl SyncRemoteDirCmd {
     }
 }
 
+/// forwards the ports of the config and the options to the server, reconnecting until ctrl-c
 #[derive(Debug, Parser)]
-pub(crate) struct PortForwardingCmd {
+pub struct PortForwardingCmd {
     #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
     prefix: String,
 
+    #[command(flatten)]
+    ssh: SshArgs,
+
+    // local_port:remote_port, added to the forwarding ports in config
     #[arg(long)]
-    privkey: Option<PathBuf>,
+    forward: Vec<String>,
+
+    // bind_address:local_port:remote_host:remote_port, e.g. to reach another host in the private segment
+    #[arg(long)]
+    forward_addr: Vec<String>,
+
+    // forward only the ports given by --forward and --forward-addr
+    #[arg(long)]
+    no_config_forwards: bool,
 }
 
 impl PortForwardingCmd {
-    pub(crate) async fn run(&self) -> Result<(), Error> {
+    /// runs `port-forwarding`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
         let prefix = self.prefix.as_str();
-        let ssh_public_key_path = self.privkey.clone().unwrap_or(default_privkey_path());
+        let forwarding_ports = self.forwarding_ports()?;
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
 
         let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
             return Err(Error::PrimaryVpcRouterNotExists);
         };
         let public_shared_ip = vpc_router.public_shared_ip()?;
-        let session = Session::connect(public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, "ubuntu", ssh_public_key_path).await?;
-
-        for forwarding_port in &CONFIG.forwarding_ports {
-            log::info!("[START] port forwarding: {} -> {}", forwarding_port.remote_port, forwarding_port.local_port);
-            match session.forward_remote_port(forwarding_port.remote_port, forwarding_port.local_port).await {
-                Ok(_) => (),
-                Err(e) => {
-                    let _ = session.close().await;
-                    return Err(Error::SshError(e));
-                },
-            }
-            log::info!("[DONE] port forwarding: ok");
+        let mut session = Session::connect(public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, &ssh_options).await?;
+        if let Err(e) = forward_ports(&session, &forwarding_ports).await {
+            let _ = session.close().await;
+            return Err(e);
         }
 
+        let mut backoff = RECONNECT_MIN_BACKOFF;
         loop {
-            // I don't know the proer way to keep the session alive
             tokio::select! {
                 _ = signal::ctrl_c() => {
                     break;
                 },
                 _ = sleep(Duration::from_secs(5)) => {},
             }
+
+            let alive = matches!(timeout(Duration::from_secs(10), session.check()).await, Ok(Ok(_)));
+            if alive {
+                continue;
+            }
+            log::warn!("[DISCONNECTED] ssh session is not alive, reconnecting...");
+            let _ = session.close().await;
+
+            // the connect itself retries for a while, so the backoff is between these retries
+            session = loop {
+                log::info!("[START] reconnecting...");
+                let result = match Session::connect(public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, &ssh_options).await {
+                    Ok(session) => match forward_ports(&session, &forwarding_ports).await {
+                        Ok(_) => Ok(session),
+                        Err(e) => {
+                            let _ = session.close().await;
+                            Err(e)
+                        },
+                    },
+                    Err(e @ ssh::Error::HostKeyMismatch(..)) => return Err(e.into()),
+                    Err(e) => Err(e.into()),
+                };
+                match result {
+                    Ok(session) => {
+                        log::info!("[DONE] reconnected, ok");
+                        backoff = RECONNECT_MIN_BACKOFF;
+                        break session;
+                    },
+                    Err(e) => {
+                        log::warn!("[RETRY] reconnect failed, retrying in {} seconds...: {}", backoff.as_secs(), e);
+                        tokio::select! {
+                            _ = signal::ctrl_c() => return Ok(()),
+                            _ = sleep(backoff) => {},
+                        }
+                        backoff = (backoff * 2).min(RECONNECT_MAX_BACKOFF);
+                    },
+                }
+            };
         }
         let _ = session.close().await;
         Ok(())
     }
+
+    fn forwarding_ports(&self) -> Result<Vec<ForwardingPortConfig>, Error> {
+        let mut forwarding_ports = if self.no_config_forwards {
+            Vec::new()
+        } else {
+            CONFIG.forwarding_ports.clone()
+        };
+        for spec in &self.forward {
+            let Some((local_port, remote_port)) = spec.split_once(':') else {
+                return Err(Error::InvalidForwardSpec(spec.clone()));
+            };
+            forwarding_ports.push(forwarding_port(spec, ForwardingPortConfig::default_host(), local_port, ForwardingPortConfig::default_host(), remote_port)?);
+        }
+        for spec in &self.forward_addr {
+            let [bind_address, local_port, remote_host, remote_port] = spec.splitn(4, ':').collect::<Vec<_>>()[..] else {
+                return Err(Error::InvalidForwardSpec(spec.clone()));
+            };
+            forwarding_ports.push(forwarding_port(spec, bind_address.to_string(), local_port, remote_host.to_string(), remote_port)?);
+        }
+        Ok(forwarding_ports)
+    }
+}
+
+fn forwarding_port(spec: &str, bind_address: String, local_port: &str, remote_host: String, remote_port: &str) -> Result<ForwardingPortConfig, Error> {
+    let (Ok(local_port), Ok(remote_port)) = (local_port.parse(), remote_port.parse()) else {
+        return Err(Error::InvalidForwardSpec(spec.to_string()));
+    };
+    Ok(ForwardingPortConfig {
+        remote_port,
+        local_port,
+        direction: ForwardingDirection::Local,
+        remote_host,
+        bind_address,
+    })
+}
+
+const RECONNECT_MIN_BACKOFF: Duration = Duration::from_secs(5);
+const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+// forwards can't be stopped, so they are requested again for each session
+async fn forward_ports(session: &Session, forwarding_ports: &[ForwardingPortConfig]) -> Result<(), Error> {
+    for forwarding_port in forwarding_ports {
+        match forwarding_port.direction {
+            ForwardingDirection::Local => {
+                log::info!("[START] port forwarding: {}:{} -> {}:{}", forwarding_port.remote_host, forwarding_port.remote_port, forwarding_port.bind_address, forwarding_port.local_port);
+                session.forward_remote_port(&forwarding_port.remote_host, forwarding_port.remote_port, &forwarding_port.bind_address, forwarding_port.local_port).await?;
+            },
+            ForwardingDirection::Remote => {
+                log::info!("[START] remote port forwarding: {} -> {}", forwarding_port.local_port, forwarding_port.remote_port);
+                session.forward_local_port(forwarding_port.local_port, forwarding_port.remote_port).await?;
+            },
+        }
+        log::info!("[DONE] port forwarding: ok");
+    }
+    if let Some(socks_proxy_port) = CONFIG.socks_proxy_port {
+        log::info!("[START] socks proxy: {}", socks_proxy_port);
+        session.forward_dynamic(socks_proxy_port).await?;
+        log::info!("[DONE] socks proxy: ok");
+    }
+    Ok(())
 }
 
+/// provision and deploy: creates the missing resources of the prefix and runs the setup scripts on the server
 #[derive(Debug, Parser)]
-pub(crate) struct UpdateCmd {
+pub struct UpdateCmd {
     #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
     prefix: String,
 
     #[arg(long)]
     pubkey: Option<PathBuf>,
 
+    #[command(flatten)]
+    ssh: SshArgs,
+
+    // register a simple monitor for the forwarded port, see `simple_monitor` in config
     #[arg(long)]
-    privkey: Option<PathBuf>,
-}
+    simple_monitor: bool,
 
-impl UpdateCmd {
-    pub(crate) async fn run(&self) -> Result<(), Error> {
-        let prefix = self.prefix.as_str();
-        let ssh_public_key_path = self.pubkey.clone().unwrap_or(default_pubkey_path());
-        let ssh_private_key_path = self.privkey.clone().unwrap_or(default_privkey_path());
+    #[command(flatten)]
+    scripts: ScriptsArgs,
 
-        let ssh_public_key = match fs::read_to_string(&ssh_public_key_path).await {
-            Ok(ssh_public_key) => Some(ssh_public_key),
-            Err(e) => return Err(Error::PrimarySshPublicKeyGivenButCouldntRead(ssh_public_key_path, e.to_string())),
-        };
+    // leave the standby in `standby.zone` as it is, it's refreshed by default since the server has to be shut down for it
+    #[arg(long)]
+    skip_standby: bool,
 
-        // VPC Router
-        let vpc_router = if let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? {
-            log::info!("[CHECKED] vpc router existence check: already exists, id: {}, ok", vpc_router.id());
-            Appliance::wait_available(vpc_router.id()).await?;
-            log::info!("[CHECKED] vpc router availability check: ok");
-            vpc_router
-        } else {
-            log::info!("[START] vpc router existence check: not exists, creating...");
-            let vpc_router = PrimaryVpcRouter::create(prefix).await?;
-            log::info!("[DONE] vpc router created, id: {}, ok", vpc_router.id());
+    // an ed25519 key pair in ~/.sacloud-random-tools/keys if ~/.ssh has no key, used by the later commands too
+    #[arg(long)]
+    generate_key: bool,
 
-            log::info!("[START] vpc router wait available...");
-            Appliance::wait_available(vpc_router.id()).await?;
-            log::info!("[CHECKED] vpc router available, ok");
-            vpc_router
-        };
+    // e.g. 4h or 2d, `reap` cleans the environment after it
+    #[arg(long)]
+    ttl: Option<String>,
 
-        // Switch
-        let switch = if let Some(switch) = PrimarySwitch::try_get(prefix).await? {
-            log::info!("[CHECKED] switch existence check: already exists, id: {}, ok", switch.id());
-            let is_connected = Appliance::is_connected_to_switch(vpc_router.id(), switch.id()).await?;
-            if !is_connected {
-                return Err(Error::PrimarySwitchNotConnectedToVpcRouter(switch.id().clone(), vpc_router.id().clone()))
-            }
-            log::info!("[CHECKED] switch connection check: connected to vpc router, ok");
-            switch
-        } else {
-            log::info!("[START] switch existence check: not exists, creating...");
-            let switch = PrimarySwitch::create(prefix).await?;
-            log::info!("[DONE] switch created, id: {}, ok", switch.id());
-            log::info!("[START] switch connection check: connecting to vpc router...");
-            Appliance::connect_to_switch(vpc_router.id(), switch.id()).await?;
-            log::info!("[DONE] switch connected to vpc router, ok");
-            switch
-        };
+    // skip the phases the last failed `update` finished, the config must be the same as that run
+    #[arg(long)]
+    resume: bool,
 
-        if Appliance::is_up(vpc_router.id()).await? {
-            log::info!("[CHECKED] vpc router up check: ok");
-            Appliance::wait_available(vpc_router.id()).await?;
-            log::info!("[CHECKED] vpc router availability check: ok");
-        } else {
-            log::info!("[START] vpc router booting...");
-            Appliance::up(vpc_router.id()).await?;
-            Appliance::wait_up(vpc_router.id()).await?;
-            log::info!("[DONE] vpc router booted, ok");
+    // set up a new server and disk alongside the current ones, and switch the vpc router to them after the health check
+    #[arg(long, conflicts_with = "resume")]
+    replace_server: bool,
+}
 
-            log::info!("[START] vpc router wait available...");
-            Appliance::wait_available(vpc_router.id()).await?;
-            log::info!("[DONE] vpc router available, ok");
-        }
+impl UpdateCmd {
+    /// runs `update`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        notified("update", &self.prefix, self.update(global)).await
+    }
 
-        // セットアップスクリプトのために一旦 Firewall は外す
-        log::info!("[START] vpc router config update without firewall for setup script...");
-        PrimaryVpcRouter::update_config(vpc_router.id(), false).await?;
-        Appliance::apply_config(vpc_router.id()).await?;
-        log::info!("[DONE] vpc router config updated without firewall, ok");
-
-        // Guard で戻す
-        struct FirewallGuard(ApplianceId);
-        impl Drop for FirewallGuard {
-            fn drop(&mut self) {
-                log::info!("[IMPORTANT] ensure vpc router config with firewall...");
-                let vpc_router_id = self.0.clone();
-                let handler = thread::spawn(move || {
-                    Runtime::new().expect("[FATAL_ERROR] failed to new runtime").block_on(async move {
-                        PrimaryVpcRouter::update_config(&vpc_router_id, true).await
-                            .expect("[FATAL_ERROR] failed to update vpc router config with firewall");
-                        Appliance::apply_config(&vpc_router_id).await
-                            .expect("[FATAL_ERROR] failed to apply vpc router config with firewall");
-                        Appliance::wait_available(&vpc_router_id).await
-                            .expect("[FATAL_ERROR] failed to wait vpc router available");
-                        log::info!("[IMPORTANT] firewall ensured");
-                    })
-                });
-                handler.join().expect("[FATAL_ERROR] failed to join handler");
+    async fn update(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        ensure_ssh_key(&self.pubkey, self.generate_key).await?;
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
+        let scripts_dir = self.scripts.scripts_dir();
+        validate_config()?;
+        let ttl = self.ttl.as_deref().map(parse_interval).transpose()?;
+        check_cost(prefix, &api::zone()?, PRIMARY_SERVER_DISK_SIZE_MB, global).await?;
+        let standby = CONFIG.standby.as_ref().filter(|_| !self.skip_standby);
+        if let Some(standby) = standby {
+            if standby.zone == api::zone()? {
+                return Err(Error::StandbyInCurrentZone(standby.zone.clone()));
             }
+            confirm_prefix(&format!("The server will be shut down for the snapshot, and the standby in {} will be refreshed.", standby.zone), prefix, global)?;
         }
-        let _firewall_guard = FirewallGuard(vpc_router.id().clone());
 
-        Appliance::wait_available(vpc_router.id()).await?;
-        log::info!("[CHECKED] vpc router availability check: ok");
+        // before the provision, which would create another server while the old one is retired
+        let half_switched_color = try_get_half_switched_color(prefix).await?;
+        if let Some(color) = half_switched_color {
+            confirm_prefix(&format!("The last switch to the {} server stopped halfway, and it will be finished.", color.name()), prefix, global)?;
+            finish_switch(prefix, color, &ssh_options, true).await?;
+        }
 
-        // Server
-        let server = if let Some(server) = PrimaryServer::try_get(prefix).await? {
-            log::info!("[CHECKED] server existence check: already exists, id: {}, ok", server.id());
-            let is_connected = Server::is_connected_to_switch(server.id(), switch.id()).await?;
-            if !is_connected {
-                return Err(Error::PrimaryServerNotConnectedToSwitch(server.id().clone(), switch.id().clone()))
+        // without the server, it's created as usual, and the finished switch is not replaced again
+        let replaces_server = self.replace_server && half_switched_color.is_none() && PrimaryServer::try_get(prefix).await?.is_some();
+        if replaces_server {
+            confirm_prefix("A new server will be set up alongside, and the current one will be deleted after the switch.", prefix, global)?;
+        } else if self.replace_server {
+            log::info!("[NOTE] no server to replace, creating it");
+        }
+
+        let mut progress = if self.resume {
+            let progress = UpdateProgress::load(prefix).await?;
+            if progress.phases.is_empty() {
+                log::info!("[NOTE] nothing to resume for {}, updating from the start", prefix);
             }
-            log::info!("[CHECKED] server connection check: connected to switch, ok");
-            server
+            progress
         } else {
-            log::info!("[START] server existence check: not exists, creating...");
-            let server = PrimaryServer::create(prefix, switch.id()).await?;
-            log::info!("[DONE] server created, id: {}, ok", server.id());
-            server
+            UpdateProgress::clear(prefix).await?;
+            UpdateProgress::default()
         };
 
-        // Disk
-        if let Some(disk) = PrimaryServerDisk::try_get(prefix).await? {
-            log::info!("[CHECKED] disk existence check: already exists, id: {}, ok", disk.id());
-            Disk::wait_available(disk.id()).await?;
-            log::info!("[CHECKED] disk availability check: ok");
+        let golden_archive_id = latest_golden_archive(prefix).await?;
+        let firewall_guard = if progress.is_done(UpdatePhase::Provisioned) {
+            log::info!("[SKIPPED] provision: done by the last update");
+            None
         } else {
-            // Setup Startup Script
-            let note = if let Some(note) = PrimaryServerSetupShellNote::try_get(prefix).await? {
-                log::info!("[CHECKED] note existence check: already exists, id: {}, ok", note.id());
-                log::info!("[START] note content updating if needed...");
-                PrimaryServerSetupShellNote::update_content_if_needed(note.id()).await?;
-                Note::wait_available(note.id()).await?;
-                log::info!("[DONE] note content updated, ok");
-                note
-            } else {
-                log::info!("[START] note existence check: not exists, creating...");
-                let note = PrimaryServerSetupShellNote::create(prefix).await?;
-                Note::wait_available(note.id()).await?;
-                log::info!("[DONE] note created, id: {}, ok", note.id());
-                note
-            };
+            let firewall_guard = provision(prefix, &self.pubkey, self.simple_monitor, golden_archive_id.as_ref(), PRIMARY_SERVER_DISK_SIZE_MB).await?;
+            progress.done(prefix, UpdatePhase::Provisioned).await?;
+            Some(firewall_guard)
+        };
+        // before the deploy, so a failed one expires too
+        let expires_at = ttl.map(|ttl| SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default() + ttl.as_secs());
+        if let Some(expires_at) = expires_at {
+            service_env::set_expires_at(prefix, expires_at).await?;
+            log::info!("[DONE] expiry tagged, reaped after {}, ok", format_unix_time(expires_at));
+        }
+        if replaces_server {
+            replace_server(prefix, &self.pubkey, &ssh_options, scripts_dir, firewall_guard, golden_archive_id.as_ref()).await?;
+            // the new server isn't tagged yet
+            if let Some(expires_at) = expires_at {
+                service_env::set_expires_at(prefix, expires_at).await?;
+            }
+        } else {
+            deploy(prefix, &ssh_options, scripts_dir, firewall_guard, Some(&mut progress)).await?;
+        }
 
-            // Setup SSH Public Key
-            let ssh_public_key = if let Some(current_ssh_public_key) = PrimaryServerSshPublicKey::try_get(prefix).await? {
-                log::info!("[CHECKED] ssh public key existence check: already exists, id: {}, ok", current_ssh_public_key.id());
-                if let Some(ssh_public_key) = ssh_public_key {
-                    if current_ssh_public_key.public_key() != ssh_public_key {
-                        // 同名の古い公開鍵を消していいのかわからないのでエラーにする
-                        return Err(Error::PrimarySshPublicKeyAlreadyRegisteredButMismatch(
-                                current_ssh_public_key.id().clone(),
-                                current_ssh_public_key.public_key().to_string(),
-                                ssh_public_key.to_string(),
-                        ));
-                    }
-                }
-                log::info!("[CHECKED] ssh public key mismatch check: ok");
-                current_ssh_public_key
+        if let Some(standby) = standby {
+            if progress.is_done(UpdatePhase::StandbyRefreshed) {
+                log::info!("[SKIPPED] standby refresh: done by the last update");
             } else {
-                log::info!("[CHECKED] ssh public key existence check: not exists");
-                let Some(ssh_public_key) = ssh_public_key else {
-                    return Err(Error::PrimarySshPublicKeyNotGivenForNewServerDisk);
-                };
-                log::info!("[START] ssh public key existence check: not exists, creating...");
-                let ssh_public_key = PrimaryServerSshPublicKey::create(prefix, ssh_public_key).await?;
-                log::info!("[DONE] ssh public key created, id: {}, ok", ssh_public_key.id());
-                ssh_public_key
+                refresh_standby(prefix, &self.pubkey, &standby.zone, global).await?;
+                progress.done(prefix, UpdatePhase::StandbyRefreshed).await?;
+            }
+        }
+        if let Some(dns) = CONFIG.standby.as_ref().and_then(|standby| standby.dns.as_ref()) {
+            let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
+                return Err(Error::PrimaryVpcRouterNotExists);
             };
+            point_dns_record(dns, vpc_router.public_shared_ip()?).await?;
+        }
+        UpdateProgress::clear(prefix).await?;
+        Ok(())
+    }
+}
 
-            log::info!("[START] search latest public ubuntu archive...");
-            let archive = Archive::latest_public_ubuntu().await?;
-            log::info!("[DONE] search latest public ubuntu archive, id: {}, ok", archive.id());
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum UpdatePhase {
+    #[serde(rename = "provisioned")]
+    Provisioned,
 
-            log::info!("[START] disk existence check: not exists, creating...");
-            let disk = PrimaryServerDisk::create_for_server(prefix, server.id(), archive.id(), note.id(), ssh_public_key.id()).await?;
-            log::info!("[DONE] disk created, id: {}, ok", disk.id());
+    // the scripts are put and the server is restarted, only waiting for them is left
+    #[serde(rename = "setup_started")]
+    SetupStarted,
 
-            log::info!("[START] disk wait available...");
-            Disk::wait_available(disk.id()).await?;
-            log::info!("[DONE] disk available, ok");
-        };
+    #[serde(rename = "setup_finished")]
+    SetupFinished,
+
+    #[serde(rename = "standby_refreshed")]
+    StandbyRefreshed,
+}
 
-        Server::wait_available(server.id()).await?;
-        log::info!("[CHECKED] server availability check: ok");
+// the phases of `update` done so far, removed when it finishes, read by `update --resume`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct UpdateProgress {
+    phases: Vec<UpdatePhase>,
+}
 
-        if !Server::is_up(server.id()).await? {
-            log::info!("[START] server booting...");
-            Server::up(server.id()).await?;
-            Server::wait_up(server.id()).await?;
-            log::info!("[DONE] server booted, ok");
+impl UpdateProgress {
+    async fn load(prefix: &str) -> Result<Self, Error> {
+        let path = update_progress_path(prefix);
+        match fs::read(&path).await {
+            Ok(json) => serde_json::from_slice(&json).map_err(|e| Error::UpdateProgressCouldntRead(path.clone(), e.to_string())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(Error::UpdateProgressCouldntRead(path.clone(), e.to_string())),
         }
+    }
 
-        log::info!("[START] prepare setup script for server...");
-        let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
-            return Err(Error::PrimaryVpcRouterNotExists);
-        };
-        let public_shared_ip = vpc_router.public_shared_ip()?;
-        ServiceScript::prepare_for_server(public_shared_ip, "ubuntu", &ssh_private_key_path).await?;
-        log::info!("[DONE] setup script prepared, ok");
+    fn is_done(&self, phase: UpdatePhase) -> bool {
+        self.phases.contains(&phase)
+    }
+
+    async fn done(&mut self, prefix: &str, phase: UpdatePhase) -> Result<(), Error> {
+        self.phases.push(phase);
+        let path = update_progress_path(prefix);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await.map_err(|e| Error::UpdateProgressCouldntWrite(path.clone(), e.to_string()))?;
+        }
+        let json = serde_json::to_vec(self).expect("must be serializable");
+        fs::write(&path, json).await.map_err(|e| Error::UpdateProgressCouldntWrite(path.clone(), e.to_string()))
+    }
+
+    async fn clear(prefix: &str) -> Result<(), Error> {
+        let path = update_progress_path(prefix);
+        match fs::remove_file(&path).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(Error::UpdateProgressCouldntWrite(path.clone(), e.to_string())),
+        }
+    }
+}
 
-        log::info!("[START] restart server for running setup script...");
-        Server::down(server.id()).await?;
-        Server::wait_down(server.id()).await?;
-        Server::up(server.id()).await?;
-        Server::wait_up(server.id()).await?;
-        log::info!("[DONE] server restarted for running setup script, ok");
+fn update_progress_path(prefix: &str) -> PathBuf {
+    home_dir().expect("home dir is prerequisite").join(".sacloud-random-tools/update").join(format!("{}.json", prefix))
+}
+
+// the standby is the same environment built from the archive of the primary disk, and kept shut down
+async fn refresh_standby(prefix: &str, pubkey: &Option<PathBuf>, zone: &str, global: &GlobalArgs) -> Result<(), Error> {
+    log::info!("[START] standby refreshing in {}...", zone);
+    let archive_id = transfer_primary_disk(prefix, zone, global).await?;
+
+    ApiEndpoint::for_zone(zone)?.scope(async {
+        // the server and the disk are recreated from the archive, the rest is kept
+        if let Some(server) = PrimaryServer::try_get(prefix).await? {
+            // an up standby may be the active one after `failover`
+            if Server::is_up(server.id()).await? {
+                return Err(Error::StandbyServerIsUp(zone.to_string()));
+            }
+            log::info!("[START] standby server delete...");
+            Server::delete(server.id()).await?;
+            Server::wait_delete(server.id()).await?;
+            log::info!("[DONE] standby server delete: ok");
+        }
+        if let Some(disk) = PrimaryServerDisk::try_get(prefix).await? {
+            log::info!("[START] standby disk delete...");
+            Disk::delete(disk.id()).await?;
+            Disk::wait_delete(disk.id()).await?;
+            log::info!("[DONE] standby disk delete: ok");
+        }
 
-        log::info!("[START] wait for server setup script finished...");
-        ServiceScript::wait_for_done(public_shared_ip, "ubuntu", &ssh_private_key_path).await?;
-        log::info!("[DONE] server setup script finished, ok");
+        // the disk is already set up, so no deploy
+        let _firewall_guard = provision(prefix, pubkey, false, Some(&archive_id), PRIMARY_SERVER_DISK_SIZE_MB).await?;
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerNotExists);
+        };
+        shut_down_server_gracefully(server.id()).await?;
 
+        log::info!("[START] transferred archive deleting...");
+        Archive::delete(&archive_id).await?;
+        Archive::wait_delete(&archive_id).await?;
+        log::info!("[DONE] transferred archive deleted, ok");
         Ok(())
+    }).await?;
+    log::info!("[DONE] standby refreshed in {}, ok", zone);
+    Ok(())
+}
+
+// dns is a global resource, so any zone is fine
+async fn point_dns_record(dns_config: &StandbyDnsConfig, ip: Ipv4Addr) -> Result<(), Error> {
+    let Some(dns) = Dns::get_by_name(&dns_config.zone).await? else {
+        return Err(Error::DnsZoneNotExists(dns_config.zone.clone()));
+    };
+    let ip_string = ip.to_string();
+    if dns.a_record(&dns_config.name) == Some(ip_string.as_str()) {
+        log::info!("[CHECKED] dns record check: {}.{} already points {}, ok", dns_config.name, dns_config.zone, ip);
+        return Ok(());
     }
+    log::info!("[START] dns record pointing {}.{} to {}...", dns_config.name, dns_config.zone, ip);
+    dns.set_a_record(&dns_config.name, ip, Some(dns_config.ttl)).await?;
+    log::info!("[DONE] dns record updated, ok");
+    Ok(())
 }
 
+/// boots the standby and points the dns record and the simple monitor to it, the primary zone may be unreachable
 #[derive(Debug, Parser)]
-pub(crate) struct CleanCmd {
+pub struct FailoverCmd {
     #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
     prefix: String,
 
@@ -401,178 +1015,4749 @@ pub(crate) struct CleanCmd {
     force: bool,
 }
 
-impl CleanCmd {
-    pub(crate) async fn run(&self) -> Result<(), Error> {
+impl FailoverCmd {
+    /// runs `failover`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
         let prefix = self.prefix.as_str();
-
-        // confirm server down
-        if !self.force {
-            println!("Realy down? If ok, input the prefix again:");
-            let mut input = String::new();
-            io::stdin().read_line(&mut input).unwrap();
-            if input.trim() != prefix {
-                log::error!("prefix not matched");
-                return Ok(());
-            }
-        }
-
-        log::info!("[START] instance status check...");
-        let vpc_router = PrimaryVpcRouter::try_get(prefix).await?;
-        let switch = PrimarySwitch::try_get(prefix).await?;
-        let server = PrimaryServer::try_get(prefix).await?;
-        let disk = PrimaryServerDisk::try_get(prefix).await?;
-
-        if let Some(vpc_router) = &vpc_router {
-            loop {
-                match Appliance::instance_status(vpc_router.id()).await {
-                    Err(api::Error::ResourceUnknownInstanceStatus) => {
-                        log::info!("[WAIT] vpc router instance status check: unknown, retrying...");
-                        sleep(Duration::from_secs(5)).await;
-                    },
-                    Err(e) => return Err(e.into()),
-                    Ok(InstanceStatus::Up | InstanceStatus::Down) => {
-                        break;
-                    },
-                    Ok(status) => {
-                        log::info!("[WAIT] vpc router instance status check: {}, retrying...", status);
-                        sleep(Duration::from_secs(5)).await;
-                    },
-                }
-            }
+        let Some(standby) = &CONFIG.standby else {
+            return Err(Error::StandbyNotConfigured);
+        };
+        let zone = api::zone()?;
+        if standby.zone == zone {
+            return Err(Error::StandbyInCurrentZone(standby.zone.clone()));
         }
-
-        if let Some(server) = &server {
-            loop {
-                match Server::instance_status(server.id()).await {
-                    Err(api::Error::ResourceUnknownInstanceStatus) => {
-                        log::info!("[WAIT] server instance status check: unknown, retrying...");
-                        sleep(Duration::from_secs(5)).await;
-                    },
-                    Err(e) => return Err(e.into()),
-                    Ok(InstanceStatus::Up | InstanceStatus::Down) => {
-                        break;
-                    },
-                    Ok(status) => {
-                        log::info!("[WAIT] server instance status check: {}, retrying...", status);
-                        sleep(Duration::from_secs(5)).await;
-                    },
-                }
-            }
+        if !self.force {
+            confirm_prefix(&format!("The standby in {} will be the active one.", standby.zone), prefix, global)?;
         }
-        log::info!("[CHECKED] instance status check: ok");
 
-        if let Some(vpc_router) = vpc_router {
-            if Appliance::is_up(vpc_router.id()).await? {
-                log::info!("[START] vpc router down...");
-                Appliance::down(vpc_router.id()).await?;
-                Appliance::wait_down(vpc_router.id()).await?;
-                log::info!("[DONE] vpc router down: ok");
+        let public_shared_ip = ApiEndpoint::for_zone(&standby.zone)?.scope(async {
+            let Some(server) = PrimaryServer::try_get(prefix).await? else {
+                return Err(Error::StandbyNotExists(standby.zone.clone()));
+            };
+            let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
+                return Err(Error::StandbyNotExists(standby.zone.clone()));
+            };
+            if !Appliance::is_up(vpc_router.id()).await? {
+                log::info!("[START] vpc router booting...");
+                Appliance::ensure_up(vpc_router.id()).await?;
+                log::info!("[DONE] vpc router booted, ok");
             }
-            log::info!("[START] vpc router delete...");
-            Appliance::delete(vpc_router.id()).await?;
-            Appliance::wait_delete(vpc_router.id()).await?;
-            log::info!("[DONE] vpc router delete: ok");
-        }
-
-        if let Some(server) = server {
             if Server::is_up(server.id()).await? {
-                log::info!("[START] server down...");
-                Server::down(server.id()).await?;
-                Server::wait_down(server.id()).await?;
-                log::info!("[DONE] server down: ok");
+                log::info!("[CHECKED] standby server up check: already up, ok");
+            } else {
+                boot_server_again(server.id()).await?;
             }
-            log::info!("[START] server delete...");
-            Server::delete(server.id()).await?;
-            Server::wait_delete(server.id()).await?;
-            log::info!("[DONE] server delete: ok");
-        }
+            Ok(vpc_router.public_shared_ip()?)
+        }).await?;
 
-        if let Some(disk) = disk {
-            log::info!("[START] disk delete...");
-            Disk::delete(disk.id()).await?;
-            Disk::wait_delete(disk.id()).await?;
-            log::info!("[DONE] disk delete: ok");
+        if let Some(dns) = &standby.dns {
+            point_dns_record(dns, public_shared_ip).await?;
+        } else {
+            log::info!("[NOTE] no standby.dns in config, the standby is at {}", public_shared_ip);
         }
-
-        if let Some(switch) = switch {
-            log::info!("[START] switch delete...");
-            Switch::delete(switch.id()).await?;
-            Switch::wait_delete(switch.id()).await?;
-            log::info!("[DONE] switch delete: ok");
+        if let Some(simple_monitor) = PrimarySimpleMonitor::try_get(prefix).await? {
+            log::info!("[START] simple monitor retargeting...");
+            PrimarySimpleMonitor::update(simple_monitor.id(), prefix, public_shared_ip).await?;
+            log::info!("[DONE] simple monitor retargeted, ok");
         }
 
-        log::info!("[NOTE] ssh public key is not deleted for safety");
-        log::info!("[DONE] all checks passed, ok");
-
+        log::info!("[IMPORTANT] set SACLOUD_ZONE={} and standby.zone to {} in config for the later commands", standby.zone, zone);
+        log::info!("[NOTE] the server in {} is left as is, shut it down before the next `update` refreshes it as the standby", zone);
         Ok(())
     }
 }
 
-fn default_pubkey_path() -> PathBuf {
-    home_dir().expect("home dir is prerequisite").join(".ssh/id_rsa.pub")
-}
+/// a live view of the prefix in the terminal, the resources, the monitor graphs, the tunnel and the setup, until q
+#[derive(Debug, Parser)]
+pub struct DashboardCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
 
-fn default_privkey_path() -> PathBuf {
-    home_dir().expect("home dir is prerequisite").join(".ssh/id_rsa")
+    // e.g. 5s or 1m
+    #[arg(long, default_value = "5s", value_parser = parse_interval)]
+    refresh: Duration,
+
+    // the monitor api has 5 minutes resolution, so it's fetched less often than the status
+    #[arg(long, default_value = "1m", value_parser = parse_interval)]
+    monitor_interval: Duration,
+
+    // number of the latest monitor samples in the graphs
+    #[arg(long, default_value_t = 24)]
+    count: usize,
+
+    // keep the forwarding ports in config open like `port-forwarding`, the tunnel is shown either way
+    #[arg(long)]
+    port_forwarding: bool,
+
+    #[command(flatten)]
+    ssh: SshArgs,
 }
 
-/* TODO remove old code
-pub(crate) async fn show_all_resources() -> Result<(), Error> {
-    let resource_pairs = vec![
-        ("privatehost", "PrivateHosts"),
-        ("server", "Servers"),
-        ("disk", "Disks"),
-        ("switch", "Switches"),
-        ("archive", "Archives"),
-        ("cdrom", "CDROMs"),
-        ("bridge", "Bridges"),
-        ("internet", "Internet"),
-        // ("ipaddress", "IPAddress"),
-        // ("ipv6addr", "IPv6Addrs"),
-        // ("ipv6net", "IPv6Nets"),
-        // ("subnet", "Subnets"),
-        // ("interface", "Interfaces"),
-        ("packetfilter", "PacketFilters"),
-        ("appliance", "Appliances"),
-        ("commonserviceitem", "CommonServiceItems"),
-        ("icon", "Icons"),
-        ("note", "Notes"),
-        ("sshkey", "SSHKeys"),
-    ];
-    for (path, resource_name) in resource_pairs {
-        let resources = api::request_search_api(path, resource_name, None, None, None, 50).await?;
-
-        // filter shared resources
-        let resources = resources.iter().filter(|v| { v["Scope"].as_str().map(|s| s == "user").unwrap_or(true) }).collect::<Vec<_>>();
-
-        if 0 < resources.len() {
-            println!("{}", resource_name);
-            for resource in resources {
-                match api::get_resource_id(&resource) {
-                    Ok(resource_id) => {
-                        if let Some(resource_name) = resource["Name"].as_str() {
-                            println!("{}: {}", resource_id, resource_name);
-                        } else {
-                            println!("{}: {}", resource_id, to_string_pretty(&resource).expect("must be valid json"));
-                        }
-                    }
-                    Err(api::Error::ResourceApiNoResourceId(_)) => {
-                        if let Some(resource_name) = resource["Name"].as_str() {
-                            println!("{}", resource_name);
-                        } else {
-                            println!("{}", to_string_pretty(&resource).expect("must be valid json"));
-                        }
+impl DashboardCmd {
+    /// runs `dashboard`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
+
+        // restored even if the dashboard fails, or the shell is left in the raw mode
+        let mut terminal = ratatui::try_init().map_err(|e| Error::DashboardTerminalFailed(e.to_string()))?;
+        let result = self.dashboard(prefix, &ssh_options, &mut terminal).await;
+        ratatui::restore();
+        result
+    }
+
+    async fn dashboard(&self, prefix: &str, ssh_options: &SshOptions, terminal: &mut DefaultTerminal) -> Result<(), Error> {
+        let mut view = DashboardView {
+            title: format!("{} in {}, refreshed every {} seconds", prefix, api::zone()?, self.refresh.as_secs()),
+            resources: Ok(Vec::new()),
+            graphs: Ok(Vec::new()),
+            tunnel: "not connected yet".to_string(),
+            setup: "setup: unknown, not connected".to_string(),
+        };
+        let mut events = EventStream::new();
+
+        // the session reads the setup status, and forwards the ports with --port-forwarding
+        let mut session: Option<Session> = None;
+        let mut next_monitor = Instant::now();
+        loop {
+            view.resources = dashboard_resources(prefix).await.map_err(|e| e.to_string());
+
+            if Instant::now() >= next_monitor {
+                view.graphs = match self.monitor_graphs(prefix).await {
+                    Ok(Some(graphs)) => Ok(graphs),
+                    Ok(None) => Err("no server".to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                next_monitor = Instant::now() + self.monitor_interval;
+            }
+
+            // a failed connection is retried on the next refresh
+            let alive = match &session {
+                Some(session) => matches!(timeout(Duration::from_secs(10), session.check()).await, Ok(Ok(_))),
+                None => false,
+            };
+            if !alive {
+                if let Some(session) = session.take() {
+                    let _ = session.close().await;
+                }
+                let connected = if self.port_forwarding {
+                    connect_forwarding(prefix, ssh_options).await
+                } else {
+                    connect_primary_server(prefix, ssh_options).await
+                };
+                match connected {
+                    Ok(connected) => {
+                        view.tunnel = if self.port_forwarding {
+                            format!("connected, {} ports forwarded", CONFIG.forwarding_ports.len())
+                        } else {
+                            "connected".to_string()
+                        };
+                        session = Some(connected);
+                    },
+                    Err(e) => view.tunnel = format!("disconnected: {}", e),
+                }
+            }
+            view.setup = match &session {
+                Some(session) => match SetupStatus::read(session).await {
+                    Ok(status) => format_setup_status(&status),
+                    Err(e) => format!("setup: unknown: {}", e),
+                },
+                None => "setup: unknown, not connected".to_string(),
+            };
+
+            terminal.draw(|frame| view.render(frame)).map_err(|e| Error::DashboardTerminalFailed(e.to_string()))?;
+
+            // the keys are read while waiting for the next refresh, the raw mode takes ctrl-c as a key too
+            let next_refresh = sleep(self.refresh);
+            tokio::pin!(next_refresh);
+            let quit = loop {
+                tokio::select! {
+                    _ = &mut next_refresh => break false,
+                    event = events.next() => match event {
+                        Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => match key.code {
+                            KeyCode::Char('q') | KeyCode::Esc => break true,
+                            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break true,
+                            KeyCode::Char('r') => break false,
+                            _ => {},
+                        },
+                        Some(Ok(Event::Resize(..))) => {
+                            terminal.draw(|frame| view.render(frame)).map_err(|e| Error::DashboardTerminalFailed(e.to_string()))?;
+                        },
+                        Some(Ok(_)) => {},
+                        Some(Err(e)) => return Err(Error::DashboardTerminalFailed(e.to_string())),
+                        None => break true,
+                    },
+                }
+            };
+            if quit {
+                break;
+            }
+        }
+
+        if let Some(session) = session {
+            let _ = session.close().await;
+        }
+        Ok(())
+    }
+
+    // none without the server
+    async fn monitor_graphs(&self, prefix: &str) -> Result<Option<Vec<DashboardGraph>>, Error> {
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            return Ok(None);
+        };
+        let span = MonitorSpan::default();
+        let mut graphs = Vec::new();
+
+        let samples = monitor::server_cpu(server.id(), &span).await?;
+        let samples = last_samples(&samples, self.count);
+        graphs.push(("cpu-time".to_string(), samples.iter().map(|s| s.value.cpu_time).collect()));
+
+        for interface_id in server.interface_ids() {
+            let samples = monitor::interface(interface_id, &span).await?;
+            let samples = last_samples(&samples, self.count);
+            graphs.push((format!("nic {} receive", interface_id), samples.iter().map(|s| s.value.receive).collect()));
+            graphs.push((format!("nic {} send", interface_id), samples.iter().map(|s| s.value.send).collect()));
+        }
+        Ok(Some(graphs))
+    }
+}
+
+// the label, and the id, the instance status and the availability, none for a missing one
+type DashboardResource = (&'static str, Option<[String; 3]>);
+// the label and the monitor samples
+type DashboardGraph = (String, Vec<Option<f64>>);
+
+// what a refresh of `dashboard` fetched, the failures are shown in place of each part
+struct DashboardView {
+    title: String,
+    resources: Result<Vec<DashboardResource>, String>,
+    graphs: Result<Vec<DashboardGraph>, String>,
+    tunnel: String,
+    setup: String,
+}
+
+impl DashboardView {
+    fn render(&self, frame: &mut Frame) {
+        let graph_count = self.graphs.as_ref().map(Vec::len).unwrap_or(1) as u16;
+        let [title_area, resources_area, graphs_area, status_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(7),
+            Constraint::Length(graph_count + 2),
+            Constraint::Length(4),
+        ]).areas(frame.area());
+
+        frame.render_widget(Line::from(vec![
+            Span::styled(self.title.as_str(), Style::new().bold()),
+            Span::raw(", q to quit, r to refresh"),
+        ]), title_area);
+
+        let resources_block = Block::bordered().title("resources");
+        match &self.resources {
+            Ok(resources) => {
+                let rows = resources.iter().map(|(label, resource)| match resource {
+                    Some([id, status, availability]) => Row::new([label.to_string(), id.clone(), status.clone(), availability.clone()]),
+                    None => Row::new([label.to_string(), "-".to_string(), "not exists".to_string(), String::new()]).red(),
+                });
+                let table = Table::new(rows, [Constraint::Length(12), Constraint::Length(14), Constraint::Length(12), Constraint::Min(12)])
+                    .header(Row::new(["", "id", "status", "availability"]).bold())
+                    .block(resources_block);
+                frame.render_widget(table, resources_area);
+            },
+            Err(e) => frame.render_widget(Paragraph::new(format!("failed: {}", e)).red().block(resources_block), resources_area),
+        }
+
+        let graphs_block = Block::bordered().title("monitor");
+        match &self.graphs {
+            Ok(graphs) => {
+                let inner_area = graphs_block.inner(graphs_area);
+                frame.render_widget(graphs_block, graphs_area);
+                let rows = Layout::vertical(graphs.iter().map(|_| Constraint::Length(1))).split(inner_area);
+                for ((label, values), row) in graphs.iter().zip(rows.iter()) {
+                    let [label_area, sparkline_area, last_area] = Layout::horizontal([Constraint::Length(24), Constraint::Min(0), Constraint::Length(14)]).areas(*row);
+                    // relative to the max, the cpu time and the nic bytes are of different scales
+                    let max = values.iter().flatten().cloned().fold(0.0, f64::max);
+                    let data = values.iter().map(|value| match value {
+                        Some(v) if max > 0.0 => (v / max * 100.0).round() as u64,
+                        _ => 0,
+                    }).collect::<Vec<_>>();
+                    let last = values.iter().rev().find_map(|v| *v);
+                    frame.render_widget(Paragraph::new(label.as_str()), label_area);
+                    frame.render_widget(Sparkline::default().data(&data).max(100).green(), sparkline_area);
+                    frame.render_widget(Paragraph::new(last.map(|v| format!("{:.3}", v)).unwrap_or("-".to_string())).right_aligned(), last_area);
+                }
+            },
+            Err(e) => frame.render_widget(Paragraph::new(e.as_str()).red().block(graphs_block), graphs_area),
+        }
+
+        let status = Paragraph::new(vec![
+            Line::from(format!("tunnel: {}", self.tunnel)),
+            Line::from(self.setup.as_str()),
+        ]).block(Block::bordered().title("server"));
+        frame.render_widget(status, status_area);
+    }
+}
+
+// the id, the instance status and the availability of each resource, none for the missing ones
+async fn dashboard_resources(prefix: &str) -> Result<Vec<DashboardResource>, Error> {
+    let mut resources = Vec::new();
+    resources.push(("vpc router", ResourceKind::Appliance, PrimaryVpcRouter::try_get(prefix).await?.map(|vpc_router| vpc_router.id().to_string())));
+    resources.push(("switch", ResourceKind::Switch, PrimarySwitch::try_get(prefix).await?.map(|switch| switch.id().to_string())));
+    resources.push(("server", ResourceKind::Server, PrimaryServer::try_get(prefix).await?.map(|server| server.id().to_string())));
+    resources.push(("disk", ResourceKind::Disk, PrimaryServerDisk::try_get(prefix).await?.map(|disk| disk.id().to_string())));
+
+    let mut rows = Vec::new();
+    for (label, kind, id) in resources {
+        let Some(id) = id else {
+            rows.push((label, None));
+            continue;
+        };
+        let summary = ResourceSummary::from_value(kind, kind.get(&id).await?)?;
+        let status = summary.instance_status().unwrap_or("-").to_string();
+        let availability = summary.availability().unwrap_or("-").to_string();
+        rows.push((label, Some([id, status, availability])));
+    }
+    Ok(rows)
+}
+
+fn format_setup_status(status: &SetupStatus) -> String {
+    match (status.state(), status.code()) {
+        (SetupState::Finished, Some(0)) => format!("setup: finished in {} seconds", status.duration().as_secs()),
+        (SetupState::Finished, code) => format!("setup: failed at {} with code {}", status.step(), code.map(|code| code.to_string()).unwrap_or("-".to_string())),
+        (state, _) => format!("setup: {} {}", state.as_str(), status.step()),
+    }
+}
+
+// unattended-upgrades touches the stamp on every run, and logs to the dpkg log only when it installs something
+const UNATTENDED_UPGRADES_STAMP_PATH: &str = "/var/lib/apt/periodic/unattended-upgrades-stamp";
+const UNATTENDED_UPGRADES_DPKG_LOG_PATH: &str = "/var/log/unattended-upgrades/unattended-upgrades-dpkg.log";
+
+/// a one-shot summary of the prefix, the resources and what the setup script left on the server
+#[derive(Debug, Parser)]
+pub struct StatusCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[command(flatten)]
+    ssh: SshArgs,
+}
+
+impl StatusCmd {
+    /// runs `status`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let mut lines = vec![format!("{} in {}", prefix, api::zone()?), String::new()];
+        lines.extend(dashboard_resources(prefix).await?.into_iter().map(|(label, resource)| match resource {
+            Some([id, status, availability]) => format!("{:<12}{}\t{}\t{}", label, id, status, availability),
+            None => format!("{:<12}-\tnot exists", label),
+        }));
+        lines.push(String::new());
+
+        if CONFIG.features.is_enabled(Feature::MonitoringAgent) {
+            lines.push(match (CONFIG.monitoring_agent(), PrimaryVpcRouter::try_get(prefix).await?) {
+                (Some(monitoring_agent), Some(vpc_router)) => format!("monitoring agent: http://{}:{}/metrics, scraped from {}", vpc_router.public_shared_ip()?, monitoring_agent.forwarded_port, monitoring_agent.scrape_sources.join(", ")),
+                (Some(_), None) => "monitoring agent: unknown, no vpc router".to_string(),
+                (None, _) => "monitoring agent: not exposed, monitoring_agent.scrape_sources is empty".to_string(),
+            });
+        }
+
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            lines.push("setup: unknown, no server".to_string());
+            println!("{}", lines.join("\n"));
+            return Ok(());
+        };
+        lines.push(format!("server ip: {} ({})", service_env::active_server_ip(prefix).await?, server.color().name()));
+
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
+        let session = connect_primary_server(prefix, &ssh_options).await?;
+        lines.push(match SetupStatus::read(&session).await {
+            Ok(status) => format_setup_status(&status),
+            Err(e) => format!("setup: unknown: {}", e),
+        });
+        if CONFIG.features.is_enabled(Feature::UnattendedUpgrades) {
+            let last_run = session.remote_modified_at(UNATTENDED_UPGRADES_STAMP_PATH).await?;
+            let last_patch = session.remote_modified_at(UNATTENDED_UPGRADES_DPKG_LOG_PATH).await?;
+            lines.push(format!("unattended-upgrades: last run {}, last patch {}", format_modified_at(last_run), format_modified_at(last_patch)));
+        }
+        session.close().await?;
+
+        println!("{}", lines.join("\n"));
+        Ok(())
+    }
+}
+
+fn format_modified_at(modified_at: Option<SystemTime>) -> String {
+    match modified_at {
+        Some(modified_at) => format_unix_time(modified_at.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()),
+        None => "never".to_string(),
+    }
+}
+
+/// keeps the environment converged: the crashed server and vpc router are booted, the firewall is enabled again,
+/// and the drift is reported, nothing is created or deleted
+#[derive(Debug, Parser)]
+pub struct WatchCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    // e.g. 30s, 10m or 1h
+    #[arg(long, default_value = "10m", value_parser = parse_interval)]
+    interval: Duration,
+
+    // keep the forwarding ports in config open like `port-forwarding`
+    #[arg(long)]
+    port_forwarding: bool,
+
+    #[command(flatten)]
+    ssh: SshArgs,
+}
+
+impl WatchCmd {
+    /// runs `watch`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
+        log_event("watch_started", json!({ "prefix": prefix, "interval_secs": self.interval.as_secs() }));
+
+        let mut session: Option<Session> = None;
+        let mut next_reconcile = Instant::now();
+        loop {
+            if Instant::now() >= next_reconcile {
+                if let Err(e) = reconcile(prefix).await {
+                    log_event("reconcile_failed", json!({ "prefix": prefix, "error": e.to_string() }));
+                }
+                next_reconcile = Instant::now() + self.interval;
+            }
+
+            // a failed connection is retried on the next tick
+            if self.port_forwarding {
+                let alive = match &session {
+                    Some(session) => matches!(timeout(Duration::from_secs(10), session.check()).await, Ok(Ok(_))),
+                    None => false,
+                };
+                if !alive {
+                    if let Some(session) = session.take() {
+                        log_event("tunnel_disconnected", json!({ "prefix": prefix }));
+                        let _ = session.close().await;
+                    }
+                    match connect_forwarding(prefix, &ssh_options).await {
+                        Ok(connected) => {
+                            log_event("tunnel_connected", json!({ "prefix": prefix, "ports": CONFIG.forwarding_ports.len() }));
+                            session = Some(connected);
+                        },
+                        Err(e) => log_event("tunnel_failed", json!({ "prefix": prefix, "error": e.to_string() })),
+                    }
+                }
+            }
+
+            tokio::select! {
+                _ = signal::ctrl_c() => break,
+                _ = sleep(Duration::from_secs(5)) => {},
+            }
+        }
+
+        if let Some(session) = session {
+            let _ = session.close().await;
+        }
+        log_event("watch_stopped", json!({ "prefix": prefix }));
+        Ok(())
+    }
+}
+
+async fn connect_primary_server(prefix: &str, ssh_options: &SshOptions) -> Result<Session, Error> {
+    let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
+        return Err(Error::PrimaryVpcRouterNotExists);
+    };
+    Ok(Session::connect(vpc_router.public_shared_ip()?, PRIMARY_SERVER_FORWARDED_PORT, ssh_options).await?)
+}
+
+async fn connect_forwarding(prefix: &str, ssh_options: &SshOptions) -> Result<Session, Error> {
+    let session = connect_primary_server(prefix, ssh_options).await?;
+    if let Err(e) = forward_ports(&session, &CONFIG.forwarding_ports).await {
+        let _ = session.close().await;
+        return Err(e);
+    }
+    Ok(session)
+}
+
+// the longest setup `watch` waits for before it turns the firewall back on
+const SETUP_FIREWALL_OFF_MAX: Duration = Duration::from_secs(3 * 60 * 60);
+
+// one pass of `watch`, the same checks as `provision` without creating anything
+async fn reconcile(prefix: &str) -> Result<(), Error> {
+    let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
+        log_event("drift", json!({ "prefix": prefix, "resource": "vpc_router", "problem": "not exists" }));
+        return Ok(());
+    };
+    if Appliance::ensure_up(vpc_router.id()).await? {
+        log_event("booted", json!({ "prefix": prefix, "resource": "vpc_router", "id": vpc_router.id() }));
+    }
+
+    // `update` disables it while the setup scripts run, the tag is only trusted for a while in case `update` was killed
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    let is_setup_running = vpc_router.setup_since().is_some_and(|setup_since| now < setup_since + SETUP_FIREWALL_OFF_MAX.as_secs());
+    if is_setup_running {
+        log_event("firewall_skipped", json!({ "prefix": prefix, "id": vpc_router.id(), "setup_since": vpc_router.setup_since() }));
+    } else if Appliance::get(vpc_router.id()).await?.is_firewall_enabled() == Some(false) {
+        PrimaryVpcRouter::update_config(prefix, vpc_router.id(), true).await?;
+        Appliance::apply_config(vpc_router.id()).await?;
+        Appliance::wait_available(vpc_router.id()).await?;
+        log_event("firewall_enabled", json!({ "prefix": prefix, "id": vpc_router.id() }));
+    }
+
+    let Some(switch) = PrimarySwitch::try_get(prefix).await? else {
+        log_event("drift", json!({ "prefix": prefix, "resource": "switch", "problem": "not exists" }));
+        return Ok(());
+    };
+    if !Appliance::is_connected_to_switch(vpc_router.id(), switch.id()).await? {
+        log_event("drift", json!({ "prefix": prefix, "resource": "switch", "id": switch.id(), "problem": "not connected to the vpc router" }));
+    }
+
+    let Some(server) = PrimaryServer::try_get(prefix).await? else {
+        log_event("drift", json!({ "prefix": prefix, "resource": "server", "problem": "not exists" }));
+        return Ok(());
+    };
+    if !Server::is_connected_to_switch(server.id(), switch.id()).await? {
+        log_event("drift", json!({ "prefix": prefix, "resource": "server", "id": server.id(), "problem": "not connected to the switch" }));
+    }
+    if PrimaryServerDisk::try_get(prefix).await?.is_none() {
+        log_event("drift", json!({ "prefix": prefix, "resource": "disk", "problem": "not exists" }));
+        return Ok(());
+    }
+    if Server::ensure_up(server.id()).await? {
+        log_event("booted", json!({ "prefix": prefix, "resource": "server", "id": server.id() }));
+    }
+
+    log_event("reconciled", json!({ "prefix": prefix }));
+    Ok(())
+}
+
+// one json per line, for log collectors
+fn log_event(event: &str, mut fields: Value) {
+    fields["event"] = Value::from(event);
+    if event == "drift" || event.ends_with("_failed") || event.ends_with("_disconnected") {
+        log::warn!("[EVENT] {}", fields);
+    } else {
+        log::info!("[EVENT] {}", fields);
+    }
+}
+
+// e.g. 30s, 10m, 1h or 90 in seconds
+fn parse_interval(s: &str) -> Result<Duration, Error> {
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let number = number.parse::<u64>().map_err(|_| Error::InvalidInterval(s.to_string()))?;
+    let secs = match unit {
+        "s" => number,
+        "m" => number * 60,
+        "h" => number * 60 * 60,
+        "d" => number * 60 * 60 * 24,
+        _ => return Err(Error::InvalidInterval(s.to_string())),
+    };
+    if secs == 0 {
+        return Err(Error::InvalidInterval(s.to_string()));
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// the ids are `From<String>` too, which clap would prefer to the validating FromStr
+pub fn parse_id<T: FromStr<Err = api::Error>>(s: &str) -> Result<T, api::Error> {
+    s.parse()
+}
+
+/// cleans the environments expired by `update --ttl` in the zone, without the confirmation to run from cron
+#[derive(Debug, Parser)]
+pub struct ReapCmd {
+    // only print the expired ones
+    #[arg(long)]
+    dry_run: bool,
+
+    // number of environments cleaned at once
+    #[arg(long, default_value_t = 4)]
+    parallelism: usize,
+}
+
+impl ReapCmd {
+    /// runs `reap`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        let mut expired_prefixes = Vec::new();
+        for (prefix, expires_at) in service_env::search_expires_at().await? {
+            if expires_at <= now {
+                log::info!("[CHECKED] {} expired at {}", prefix, format_unix_time(expires_at));
+                expired_prefixes.push(prefix);
+            } else {
+                log::info!("[CHECKED] {} expires at {}, ok", prefix, format_unix_time(expires_at));
+            }
+        }
+        if expired_prefixes.is_empty() || self.dry_run {
+            return Ok(());
+        }
+
+        // everything is deleted, the keys and the notes are of the ephemeral environment too
+        let clean = CleanCmd {
+            prefixes: expired_prefixes,
+            prefix_glob: None,
+            parallelism: self.parallelism,
+            force: true,
+            all: true,
+            include_keys: true,
+            include_notes: true,
+        };
+        clean.run(global).await
+    }
+}
+
+// e.g. 1760000000 (in 239 min)
+fn format_unix_time(time: u64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    if time > now {
+        format!("{} (in {} min)", time, (time - now) / 60)
+    } else {
+        format!("{} ({} min ago)", time, (now - time) / 60)
+    }
+}
+
+/// deletes the archives of the prefix beyond the retention, the newest ones are kept
+#[derive(Debug, Parser)]
+pub struct PruneArchivesCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    // number of the newest archives kept regardless of the age
+    #[arg(long, default_value_t = 5)]
+    keep: usize,
+
+    // the archives younger than it are kept too
+    #[arg(long)]
+    keep_days: Option<u64>,
+
+    // only print what would be deleted
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl PruneArchivesCmd {
+    /// runs `prune-archives`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        let archives = service_env::search_archives(prefix).await?;
+        let (kept, pruned) = service_env::partition_by_retention(archives, self.keep, self.keep_days, now);
+        for (action, archive) in kept.iter().map(|archive| ("keep", archive)).chain(pruned.iter().map(|archive| ("delete", archive))) {
+            println!("{}\t{}\t{}\t{}",
+                action,
+                archive.id(),
+                archive.name().unwrap_or_default(),
+                archive.created_at().unwrap_or("-"),
+            );
+        }
+        if pruned.is_empty() {
+            log::info!("[CHECKED] no archive beyond the retention, ok");
+            return Ok(());
+        }
+        if self.dry_run {
+            return Ok(());
+        }
+
+        confirm_prefix(&format!("{} archives will be deleted.", pruned.len()), prefix, global)?;
+        for archive in &pruned {
+            log::info!("[START] archive {} deleting...", archive.id());
+            Archive::delete(archive.id()).await?;
+            Archive::wait_delete(archive.id()).await?;
+            log::info!("[DONE] archive {} deleted, ok", archive.id());
+        }
+        Ok(())
+    }
+}
+
+/// wg0.conf of a new device behind the same peer as the server, printed or saved with --output
+#[derive(Debug, Parser)]
+pub struct WireguardConfigCmd {
+    // of the device, e.g. 10.0.0.3/24, not the server's
+    #[arg(long, required = true)]
+    address: Vec<String>,
+
+    // the output of wg genkey, generated by wg if not given and the public key is printed to register with the peer
+    #[arg(long)]
+    private_key_file: Option<PathBuf>,
+
+    // all traffic through the peer like the server if not given
+    #[arg(long, default_values_t = ["0.0.0.0/0".to_string(), "::/0".to_string()])]
+    allowed_ips: Vec<String>,
+
+    // written with 0600, stdout if not given
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    // also print the qr code for the mobile apps, by qrencode
+    #[arg(long)]
+    qr: bool,
+}
+
+impl WireguardConfigCmd {
+    /// runs `wireguard-config`
+    pub async fn run(&self) -> Result<(), Error> {
+        let Some(wireguard) = CONFIG.wireguard() else {
+            return Err(Error::WireguardNotConfigured);
+        };
+        let problems = wireguard.client_address_problems(&self.address);
+        if !problems.is_empty() {
+            return Err(Error::ConfigHasProblems(problems));
+        }
+
+        let (private_key, public_key) = match &self.private_key_file {
+            Some(path) => {
+                let private_key = fs::read_to_string(path).await.map_err(|e| Error::WireguardKeyCouldntRead(path.clone(), e.to_string()))?.trim().to_string();
+                let public_key = service_env::wireguard_public_key(&private_key)?;
+                (private_key, public_key)
+            },
+            None => service_env::generate_wireguard_key_pair()?,
+        };
+        log::info!("[NOTE] the public key of the device, register it with the peer {}: {}", wireguard.peer.endpoint, public_key);
+
+        let conf = wireguard.client_conf(&private_key, &self.address, &self.allowed_ips);
+        match &self.output {
+            Some(path) => {
+                fs::write(path, &conf).await.map_err(|e| Error::WireguardConfCouldntWrite(path.clone(), e.to_string()))?;
+                fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await.map_err(|e| Error::WireguardConfCouldntWrite(path.clone(), e.to_string()))?;
+                log::info!("[DONE] wireguard config saved to {}, ok", path.display());
+            },
+            None => print!("{}", conf),
+        }
+
+        if self.qr {
+            let mut child = Command::new("qrencode")
+                .args(["-t", "ansiutf8"])
+                .stdin(std::process::Stdio::piped())
+                .spawn()
+                .map_err(|e| Error::WireguardQrCouldntRender(e.to_string()))?;
+            let mut stdin = child.stdin.take().expect("stdin must be piped");
+            stdin.write_all(conf.as_bytes()).await.map_err(|e| Error::WireguardQrCouldntRender(e.to_string()))?;
+            drop(stdin);
+            let status = child.wait().await.map_err(|e| Error::WireguardQrCouldntRender(e.to_string()))?;
+            if !status.success() {
+                return Err(Error::WireguardQrCouldntRender(status.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// prints the generated disk password for the console login, after the confirmation
+#[derive(Debug, Parser)]
+pub struct ShowPasswordCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[arg(long)]
+    force: bool,
+}
+
+impl ShowPasswordCmd {
+    /// runs `show-password`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        if !self.force {
+            confirm_prefix("The disk password will be printed in the terminal.", prefix, global)?;
+        }
+        let password = service_env::disk_password(prefix)?;
+        println!("{}", password);
+        Ok(())
+    }
+}
+
+/// replaces the registered key: the new key is authorized on the server and verified by logging in with it,
+/// then the old key is removed from the server, and its resource is kept renamed unless --delete-old-key
+#[derive(Debug, Parser)]
+pub struct RotateKeyCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[arg(long)]
+    new_pubkey: PathBuf,
+
+    // the new pubkey without .pub if not given
+    #[arg(long)]
+    new_privkey: Option<PathBuf>,
+
+    // the current key to log in with
+    #[command(flatten)]
+    ssh: SshArgs,
+
+    #[arg(long)]
+    delete_old_key: bool,
+}
+
+impl RotateKeyCmd {
+    /// runs `rotate-key`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let new_public_key = fs::read_to_string(&self.new_pubkey).await
+            .map_err(|e| Error::PrimarySshPublicKeyGivenButCouldntRead(self.new_pubkey.clone(), e.to_string()))?;
+        let new_public_key = new_public_key.trim();
+        let new_privkey = self.new_privkey.clone().unwrap_or(self.new_pubkey.with_extension(""));
+
+        let Some(old_ssh_public_key) = PrimaryServerSshPublicKey::try_get(prefix).await? else {
+            return Err(Error::PrimarySshPublicKeyNotExists);
+        };
+        if old_ssh_public_key.public_key().trim() == new_public_key {
+            return Err(Error::NewSshKeyNotChanged(self.new_pubkey.clone()));
+        }
+
+        // left by an interrupted rotation, the key may be another one
+        if let Some(rotating) = PrimaryServerSshPublicKey::try_get_rotating(prefix).await? {
+            log::info!("[START] leftover rotating key delete: {}...", rotating.id());
+            SshPublicKey::delete(rotating.id()).await?;
+            SshPublicKey::wait_delete(rotating.id()).await?;
+            log::info!("[DONE] leftover rotating key delete, ok");
+        }
+        log::info!("[START] new ssh public key registering...");
+        let new_ssh_public_key = PrimaryServerSshPublicKey::create_rotating(prefix, new_public_key).await?;
+        log::info!("[DONE] new ssh public key registered, id: {}, ok", new_ssh_public_key.id());
+
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
+        let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
+            return Err(Error::PrimaryVpcRouterNotExists);
+        };
+        let public_shared_ip = vpc_router.public_shared_ip()?;
+
+        log::info!("[START] new key authorizing on the server...");
+        let session = Session::connect(public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, &ssh_options).await?;
+        let result = update_authorized_keys(&session, |content| ssh::add_authorized_key(content, new_public_key)).await;
+        let _ = session.close().await;
+        result?;
+        log::info!("[DONE] new key authorized, ok");
+
+        // not through the master connection, which is authenticated with the old key
+        log::info!("[START] login check with the new key...");
+        let new_ssh_options = SshOptions { auth: SshAuth::KeyFile(new_privkey.clone()), control_dir: None, ..ssh_options.clone() };
+        let session = Session::connect(public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, &new_ssh_options).await
+            .map_err(|e| Error::NewSshKeyLoginFailed(new_privkey.clone(), e.to_string()))?;
+        log::info!("[DONE] login check with the new key, ok");
+
+        log::info!("[START] old key removing from the server...");
+        let old_public_key = old_ssh_public_key.public_key().to_string();
+        let result = update_authorized_keys(&session, |content| ssh::remove_authorized_key(content, &old_public_key)).await;
+        let _ = session.close().await;
+        result?;
+        // the master connection authenticated with the old key must not be reused
+        if let Some(control_dir) = &ssh_options.control_dir {
+            ssh::forget_master_connection(control_dir).await?;
+        }
+        log::info!("[DONE] old key removed from the server, ok");
+
+        if self.delete_old_key {
+            log::info!("[START] old ssh public key delete...");
+            SshPublicKey::delete(old_ssh_public_key.id()).await?;
+            SshPublicKey::wait_delete(old_ssh_public_key.id()).await?;
+            log::info!("[DONE] old ssh public key delete, ok");
+        } else {
+            old_ssh_public_key.retire(prefix).await?;
+            log::info!("[NOTE] old ssh public key {} is kept renamed, delete it by hand or with --delete-old-key", old_ssh_public_key.id());
+        }
+        new_ssh_public_key.promote(prefix).await?;
+        log::info!("[IMPORTANT] log in with {} from now on, update `ssh.privkey` and `ssh.pubkey` in config if they are set", new_privkey.display());
+        log::info!("[DONE] key rotation, ok");
+        Ok(())
+    }
+}
+
+async fn update_authorized_keys(session: &Session, update: impl FnOnce(&str) -> String) -> Result<(), Error> {
+    let path = PathBuf::from(".ssh/authorized_keys");
+    let content = session.read_remote_file(&path).await?;
+    let content = update(&String::from_utf8_lossy(&content));
+    session.put_file(&path, content.as_bytes()).await?;
+    Ok(())
+}
+
+/// creates the missing resources of the prefix without running the setup scripts
+#[derive(Debug, Parser)]
+pub struct ProvisionCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[arg(long)]
+    pubkey: Option<PathBuf>,
+
+    // register a simple monitor for the forwarded port, see `simple_monitor` in config
+    #[arg(long)]
+    simple_monitor: bool,
+
+    // an ed25519 key pair in ~/.sacloud-random-tools/keys if ~/.ssh has no key, used by the later commands too
+    #[arg(long)]
+    generate_key: bool,
+}
+
+impl ProvisionCmd {
+    /// runs `provision`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        validate_config()?;
+        ensure_ssh_key(&self.pubkey, self.generate_key).await?;
+        check_cost(self.prefix.as_str(), &api::zone()?, PRIMARY_SERVER_DISK_SIZE_MB, global).await?;
+        provision(self.prefix.as_str(), &self.pubkey, self.simple_monitor, None, PRIMARY_SERVER_DISK_SIZE_MB).await?;
+        Ok(())
+    }
+}
+
+/// runs the setup scripts on the server of the prefix
+#[derive(Debug, Parser)]
+pub struct DeployCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[command(flatten)]
+    ssh: SshArgs,
+
+    #[command(flatten)]
+    scripts: ScriptsArgs,
+}
+
+impl DeployCmd {
+    /// runs `deploy`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
+        let scripts_dir = self.scripts.scripts_dir();
+        validate_config()?;
+        deploy(prefix, &ssh_options, scripts_dir, None, None).await
+    }
+}
+
+/// provision and deploy once, and archive the disk as {prefix}-golden-vN, which `update` copies new disks from
+#[derive(Debug, Parser)]
+pub struct BakeCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[arg(long)]
+    pubkey: Option<PathBuf>,
+
+    #[command(flatten)]
+    ssh: SshArgs,
+
+    #[command(flatten)]
+    scripts: ScriptsArgs,
+
+    // number of the newest golden archives kept, the older ones are deleted after baking
+    #[arg(long, default_value_t = 2)]
+    keep: usize,
+
+    #[arg(long)]
+    force: bool,
+}
+
+impl BakeCmd {
+    /// runs `bake`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        notified("bake", &self.prefix, self.bake(global)).await
+    }
+
+    async fn bake(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
+        let scripts_dir = self.scripts.scripts_dir();
+        validate_config()?;
+        check_cost(prefix, &api::zone()?, PRIMARY_SERVER_DISK_SIZE_MB, global).await?;
+
+        // a fresh prefix starts from the public ubuntu, not from the last golden archive,
+        // but an existing environment is set up again as is and its live disk is archived
+        let existing = PrimaryServer::try_get(prefix).await?.is_some();
+        if existing && !self.force {
+            confirm_prefix("The existing server will be set up again, shut down and its disk archived.", prefix, global)?;
+        }
+        let firewall_guard = provision(prefix, &self.pubkey, false, None, PRIMARY_SERVER_DISK_SIZE_MB).await?;
+        deploy(prefix, &ssh_options, scripts_dir, Some(firewall_guard), None).await?;
+
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerNotExists);
+        };
+        let Some(disk) = PrimaryServerDisk::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerDiskNotExists);
+        };
+        // the disk is archived while the server is down, so the archive is consistent
+        let was_up = shut_down_server_for(server.id(), "the golden archive", prefix, self.force || existing, global).await?;
+
+        log::info!("[START] golden archive creating...");
+        let golden_archive = GoldenArchive::create_from_disk(prefix, disk.id()).await?;
+        log::info!("[DONE] golden archive created, version: {}, id: {}, ok", golden_archive.version(), golden_archive.id());
+        log::info!("[START] golden archive wait available...");
+        let result = Archive::wait_available(golden_archive.id()).await;
+        if was_up {
+            boot_server_again(server.id()).await?;
+        }
+        result?;
+        log::info!("[DONE] golden archive available, ok");
+
+        // the new one is kept anyway
+        for stale in GoldenArchive::search(prefix).await?.iter().skip(self.keep.max(1)) {
+            log::info!("[START] golden archive v{} deleting, id: {}...", stale.version(), stale.id());
+            Archive::delete(stale.id()).await?;
+            Archive::wait_delete(stale.id()).await?;
+            log::info!("[DONE] golden archive deleted, ok");
+        }
+        log::info!("[DONE] bake: ok");
+        Ok(())
+    }
+}
+
+// the disks `update` creates are copied from it, so the setup scripts find most of the work done
+async fn latest_golden_archive(prefix: &str) -> Result<Option<ArchiveId>, Error> {
+    let golden_archive = GoldenArchive::try_get_latest(prefix).await?;
+    if let Some(golden_archive) = &golden_archive {
+        log::info!("[CHECKED] golden archive: v{}, id: {}, new disks are copied from it", golden_archive.version(), golden_archive.id());
+    }
+    Ok(golden_archive.map(|golden_archive| golden_archive.id().clone()))
+}
+
+/// rebuilds the environment in another zone from a snapshot of the primary server disk, the source zone is left as is
+#[derive(Debug, Parser)]
+pub struct MigrateCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    // e.g. is1b, the ids in config like private_host_id must exist in this zone
+    #[arg(long)]
+    to_zone: String,
+
+    #[arg(long)]
+    pubkey: Option<PathBuf>,
+
+    #[command(flatten)]
+    ssh: SshArgs,
+
+    // register a simple monitor for the forwarded port, see `simple_monitor` in config
+    #[arg(long)]
+    simple_monitor: bool,
+
+    #[command(flatten)]
+    scripts: ScriptsArgs,
+
+    #[arg(long)]
+    force: bool,
+}
+
+impl MigrateCmd {
+    /// runs `migrate`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let to_zone = self.to_zone.as_str();
+        let zone = api::zone()?;
+        if to_zone == zone {
+            return Err(Error::MigrationToSameZone(to_zone.to_string()));
+        }
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
+        let scripts_dir = self.scripts.scripts_dir();
+        validate_config()?;
+
+        if !self.force {
+            confirm_prefix(&format!("The server will be shut down for the snapshot, and the environment will be built in {}.", to_zone), prefix, global)?;
+        }
+        // before the snapshot, so an exceeded budget doesn't shut down the server
+        let target = ApiEndpoint::for_zone(to_zone)?;
+        target.clone().scope(check_cost(prefix, to_zone, PRIMARY_SERVER_DISK_SIZE_MB, global)).await?;
+
+        let archive_id = transfer_primary_disk(prefix, to_zone, global).await?;
+
+        log::info!("[START] environment building in {}...", to_zone);
+        target.scope(async {
+            let firewall_guard = provision(prefix, &self.pubkey, self.simple_monitor, Some(&archive_id), PRIMARY_SERVER_DISK_SIZE_MB).await?;
+            deploy(prefix, &ssh_options, scripts_dir, Some(firewall_guard), None).await?;
+
+            log::info!("[START] transferred archive deleting...");
+            Archive::delete(&archive_id).await?;
+            Archive::wait_delete(&archive_id).await?;
+            log::info!("[DONE] transferred archive deleted, ok");
+            Ok::<_, Error>(())
+        }).await?;
+        log::info!("[DONE] environment built in {}, ok", to_zone);
+
+        log::info!("[IMPORTANT] set SACLOUD_ZONE={} for the later commands", to_zone);
+        log::info!("[NOTE] the environment in {} is left, run `clean` with SACLOUD_ZONE={} after checking the new one", zone, zone);
+        Ok(())
+    }
+}
+
+// rerunning skips the steps already done, the transferred archive is kept until the disk is copied from it
+async fn transfer_primary_disk(prefix: &str, to_zone: &str, global: &GlobalArgs) -> Result<ArchiveId, Error> {
+    let target = ApiEndpoint::for_zone(to_zone)?;
+    let transferred = target.clone().scope(MigrationArchive::try_get(prefix)).await?;
+    match transferred {
+        Some(archive) if archive.is_available() => {
+            log::info!("[CHECKED] archive in {} existence check: already transferred, id: {}, ok", to_zone, archive.id());
+            Ok(archive.id().clone())
+        },
+        transferred => {
+            let snapshot = snapshot_primary_disk(prefix, global).await?;
+            let archive_id = target.scope(transfer_snapshot(prefix, &snapshot, transferred)).await?;
+
+            log::info!("[START] snapshot deleting...");
+            Archive::delete(snapshot.id()).await?;
+            Archive::wait_delete(snapshot.id()).await?;
+            log::info!("[DONE] snapshot deleted, ok");
+            Ok(archive_id)
+        },
+    }
+}
+
+// the disk is archived while the server is down, so the snapshot is consistent
+async fn snapshot_primary_disk(prefix: &str, global: &GlobalArgs) -> Result<MigrationArchive, Error> {
+    if let Some(snapshot) = MigrationArchive::try_get(prefix).await? {
+        log::info!("[CHECKED] snapshot existence check: already exists, id: {}, ok", snapshot.id());
+        Archive::wait_available(snapshot.id()).await?;
+        log::info!("[CHECKED] snapshot availability check: ok");
+    } else {
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerNotExists);
+        };
+        let Some(disk) = PrimaryServerDisk::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerDiskNotExists);
+        };
+        // already confirmed
+        let was_up = shut_down_server_for(server.id(), "the snapshot", prefix, true, global).await?;
+
+        log::info!("[START] snapshot existence check: not exists, creating...");
+        let snapshot = MigrationArchive::create_from_disk(prefix, disk.id()).await?;
+        log::info!("[DONE] snapshot created, id: {}, ok", snapshot.id());
+        log::info!("[START] snapshot wait available...");
+        Archive::wait_available(snapshot.id()).await?;
+        log::info!("[DONE] snapshot available, ok");
+
+        if was_up {
+            boot_server_again(server.id()).await?;
+        }
+    }
+    // refetch for the size
+    let Some(snapshot) = MigrationArchive::try_get(prefix).await? else {
+        return Err(Error::MigrationArchiveNotExists);
+    };
+    Ok(snapshot)
+}
+
+// runs in the target zone, the image goes through a local file
+async fn transfer_snapshot(prefix: &str, snapshot: &MigrationArchive, stale: Option<MigrationArchive>) -> Result<ArchiveId, Error> {
+    let Some(size_mb) = snapshot.size_mb() else {
+        return Err(Error::MigrationArchiveSizeUnknown(snapshot.id().clone()));
+    };
+    if let Some(stale) = stale {
+        log::info!("[START] stale archive in target zone deleting, id: {}...", stale.id());
+        Archive::delete(stale.id()).await?;
+        Archive::wait_delete(stale.id()).await?;
+        log::info!("[DONE] stale archive deleted, ok");
+    }
+
+    let image_path = migration_image_path(prefix);
+    if let Some(dir) = image_path.parent() {
+        fs::create_dir_all(dir).await.map_err(|e| Error::MigrationImageCouldntWrite(image_path.clone(), e.to_string()))?;
+    }
+
+    // the snapshot is in the source zone
+    let source = ApiEndpoint::from_env()?;
+    log::info!("[START] snapshot image downloading via ftps...: {}", image_path.display());
+    let ftp_server = source.clone().scope(Archive::open_ftp(snapshot.id())).await?;
+    let result = ftps::download_file(&ftp_server, &image_path).await;
+    // close anyway, the archive can't be used while the ftp server is open
+    source.scope(Archive::close_ftp(snapshot.id())).await?;
+    let size = result?;
+    log::info!("[DONE] snapshot image downloaded, {} bytes, ok", size);
+
+    log::info!("[START] archive creating in target zone...");
+    let (archive, ftp_server) = MigrationArchive::create_blank(prefix, size_mb).await?;
+    log::info!("[DONE] archive created, id: {}, ok", archive.id());
+
+    log::info!("[START] image uploading via ftps...");
+    let result = ftps::upload_file(&ftp_server, &image_path).await;
+    Archive::close_ftp(archive.id()).await?;
+    let size = result?;
+    log::info!("[DONE] image uploaded, {} bytes, ok", size);
+
+    log::info!("[START] archive wait available...");
+    Archive::wait_available(archive.id()).await?;
+    log::info!("[DONE] archive available, ok");
+
+    let _ = fs::remove_file(&image_path).await;
+    Ok(archive.id().clone())
+}
+
+// the disk image is as large as the disk, so it's kept out of the working dir
+fn migration_image_path(prefix: &str) -> PathBuf {
+    home_dir().expect("home dir is prerequisite").join(".sacloud-random-tools/migrate").join(format!("{}.img", prefix))
+}
+
+// fail before creating or uploading anything, see `config validate`
+fn validate_config() -> Result<(), Error> {
+    let problems = CONFIG.validate();
+    if !problems.is_empty() {
+        return Err(Error::ConfigHasProblems(problems));
+    }
+    log::info!("[CHECKED] config validation: ok");
+    Ok(())
+}
+
+// the existing resources are priced by their service classes, the missing ones by the ones `provision` creates them with
+async fn check_cost(prefix: &str, zone: &str, disk_size_mb: u64, global: &GlobalArgs) -> Result<(), Error> {
+    let budget = CONFIG.monthly_budget;
+    let prices = match billing::service_class_prices().await {
+        Ok(prices) => prices,
+        Err(e) if budget.is_none() => {
+            log::warn!("[SKIPPED] cost estimation, the price list couldn't be fetched: {}", e);
+            return Ok(());
+        },
+        Err(e) => return Err(e.into()),
+    };
+
+    let name_prefix = format!("{}-", prefix);
+    let mut total_hourly = 0.0;
+    let mut total_monthly = 0.0;
+    for kind in [ResourceKind::Server, ResourceKind::Disk, ResourceKind::Switch, ResourceKind::Appliance] {
+        let summaries = kind.search_summaries(&SearchQuery::new().name(prefix)).await?;
+        let mut service_classes = summaries.iter()
+            .filter(|summary| summary.name().map(|name| name.starts_with(&name_prefix)).unwrap_or(false))
+            .map(|summary| (summary.service_class().unwrap_or_default().to_string(), "existing"))
+            .collect::<Vec<_>>();
+        if service_classes.is_empty() {
+            let service_class = match kind {
+                ResourceKind::Server => PrimaryServer::plan().await?.service_class().unwrap_or_default().to_string(),
+                ResourceKind::Disk => format!("cloud/disk/ssd/{}g", disk_size_mb / 1024),
+                ResourceKind::Switch => "cloud/switch/default".to_string(),
+                _ => "cloud/vpcrouter/standard".to_string(),
+            };
+            service_classes.push((service_class, "new"));
+        }
+        for (service_class, state) in service_classes {
+            let Some(price) = billing::find_price(&prices, &service_class, zone) else {
+                log::warn!("[UNKNOWN] price not found: {} {} {}", state, kind.single_name(), service_class);
+                continue;
+            };
+            total_hourly += price.hourly.unwrap_or_default();
+            total_monthly += price.monthly.unwrap_or_default();
+            log::info!("[NOTE] {} {} {}: {} JPY/hour, {} JPY/month",
+                state,
+                kind.single_name(),
+                service_class,
+                price.hourly.map(|v| format!("{:.1}", v)).unwrap_or("-".to_string()),
+                price.monthly.map(|v| format!("{:.0}", v)).unwrap_or("-".to_string()),
+            );
+        }
+    }
+    log::info!("[CHECKED] estimated cost: {:.1} JPY/hour, {:.0} JPY/month", total_hourly, total_monthly);
+
+    if let Some(budget) = budget {
+        if total_monthly > budget {
+            confirm_prefix(&format!("The estimated cost {:.0} JPY/month exceeds the budget {:.0} JPY/month.", total_monthly, budget), prefix, global)?;
+        }
+    }
+    Ok(())
+}
+
+// resources only, the firewall stays open until the returned guard is dropped
+// the disk is copied from source_archive if given, otherwise from the latest public ubuntu, disk_size_mb is only for a new disk
+async fn provision(prefix: &str, pubkey: &Option<PathBuf>, simple_monitor: bool, source_archive_id: Option<&ArchiveId>, disk_size_mb: u64) -> Result<FirewallGuard, Error> {
+    let ssh_public_key = read_ssh_public_key(pubkey).await?;
+
+    // Icon
+    let icon_id = match &CONFIG.icon {
+        None => None,
+        Some(icon_config) => if let Some(icon_id) = &icon_config.id {
+            log::info!("[CHECKED] icon given by id: {}, ok", icon_id);
+            Some(icon_id.clone())
+        } else if let Some(icon) = PrimaryIcon::try_get(prefix).await? {
+            log::info!("[CHECKED] icon existence check: already exists, id: {}, ok", icon.id());
+            Some(icon.id().clone())
+        } else {
+            let Some(png_path) = &icon_config.png else {
+                return Err(Error::IconNeitherIdNorPngGiven);
+            };
+            let png = fs::read(png_path).await.map_err(|e| Error::IconPngCouldntRead(png_path.clone(), e.to_string()))?;
+            log::info!("[START] icon existence check: not exists, uploading...");
+            let icon = PrimaryIcon::create(prefix, png).await?;
+            log::info!("[DONE] icon uploaded, id: {}, url: {}, ok", icon.id(), icon.url().unwrap_or_default());
+            Some(icon.id().clone())
+        },
+    };
+
+    // VPC Router
+    let vpc_router = if let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? {
+        log::info!("[CHECKED] vpc router existence check: already exists, id: {}, ok", vpc_router.id());
+        Appliance::wait_available(vpc_router.id()).await?;
+        log::info!("[CHECKED] vpc router availability check: ok");
+        vpc_router
+    } else {
+        log::info!("[START] vpc router existence check: not exists, creating...");
+        let vpc_router = PrimaryVpcRouter::create(prefix, icon_id.as_ref()).await?;
+        log::info!("[DONE] vpc router created, id: {}, ok", vpc_router.id());
+
+        log::info!("[START] vpc router wait available...");
+        Appliance::wait_available(vpc_router.id()).await?;
+        log::info!("[CHECKED] vpc router available, ok");
+        vpc_router
+    };
+
+    // Switch
+    let switch = if let Some(switch) = PrimarySwitch::try_get(prefix).await? {
+        log::info!("[CHECKED] switch existence check: already exists, id: {}, ok", switch.id());
+        let is_connected = Appliance::is_connected_to_switch(vpc_router.id(), switch.id()).await?;
+        if !is_connected {
+            return Err(Error::PrimarySwitchNotConnectedToVpcRouter(switch.id().clone(), vpc_router.id().clone()))
+        }
+        log::info!("[CHECKED] switch connection check: connected to vpc router, ok");
+        switch
+    } else {
+        log::info!("[START] switch existence check: not exists, creating...");
+        let switch = PrimarySwitch::create(prefix, icon_id.as_ref()).await?;
+        log::info!("[DONE] switch created, id: {}, ok", switch.id());
+        log::info!("[START] switch connection check: connecting to vpc router...");
+        Appliance::connect_to_switch(vpc_router.id(), switch.id()).await?;
+        log::info!("[DONE] switch connected to vpc router, ok");
+        switch
+    };
+
+    // NFS
+    if let Some(nfs_config) = &CONFIG.nfs {
+        let nfs = if let Some(nfs) = PrimaryNfs::try_get(prefix).await? {
+            log::info!("[CHECKED] nfs existence check: already exists, id: {}, ok", nfs.id());
+            nfs
+        } else {
+            log::info!("[START] nfs existence check: not exists, creating...");
+            let nfs = PrimaryNfs::create(prefix, switch.id(), nfs_config).await?;
+            log::info!("[DONE] nfs created, id: {}, ok", nfs.id());
+            nfs
+        };
+
+        Appliance::wait_available(nfs.id()).await?;
+        log::info!("[CHECKED] nfs availability check: ok");
+
+        if !Appliance::is_up(nfs.id()).await? {
+            log::info!("[START] nfs booting...");
+            Appliance::ensure_up(nfs.id()).await?;
+            log::info!("[DONE] nfs booted, ok");
+        }
+    }
+
+    if Appliance::is_up(vpc_router.id()).await? {
+        log::info!("[CHECKED] vpc router up check: ok");
+        Appliance::wait_available(vpc_router.id()).await?;
+        log::info!("[CHECKED] vpc router availability check: ok");
+    } else {
+        log::info!("[START] vpc router booting...");
+        Appliance::ensure_up(vpc_router.id()).await?;
+        log::info!("[DONE] vpc router booted, ok");
+
+        log::info!("[START] vpc router wait available...");
+        Appliance::wait_available(vpc_router.id()).await?;
+        log::info!("[DONE] vpc router available, ok");
+    }
+
+    let firewall_guard = open_firewall_for_setup(prefix, vpc_router.id()).await?;
+
+    Appliance::wait_available(vpc_router.id()).await?;
+    log::info!("[CHECKED] vpc router availability check: ok");
+
+    // Server
+    let server = if let Some(server) = PrimaryServer::try_get(prefix).await? {
+        log::info!("[CHECKED] server existence check: already exists, id: {}, ok", server.id());
+        let is_connected = Server::is_connected_to_switch(server.id(), switch.id()).await?;
+        if !is_connected {
+            return Err(Error::PrimaryServerNotConnectedToSwitch(server.id().clone(), switch.id().clone()))
+        }
+        log::info!("[CHECKED] server connection check: connected to switch, ok");
+        server
+    } else {
+        log::info!("[START] server existence check: not exists, creating...");
+        if let Some(private_host_id) = &CONFIG.server.private_host_id {
+            let private_host = PrivateHost::get(private_host_id).await?;
+            log::info!("[CHECKED] private host: {}, id: {}, assigned {} cpus, {} MB memory, ok",
+                private_host.name().unwrap_or_default(), private_host.id(), private_host.assigned_cpu(), private_host.assigned_memory_mb());
+        }
+        let server = PrimaryServer::create(prefix, switch.id(), icon_id.as_ref()).await?;
+        log::info!("[DONE] server created, id: {}, ok", server.id());
+        server
+    };
+
+    // Disk
+    let disk = if let Some(disk) = PrimaryServerDisk::try_get(prefix).await? {
+        log::info!("[CHECKED] disk existence check: already exists, id: {}, ok", disk.id());
+        Disk::wait_available(disk.id()).await?;
+        log::info!("[CHECKED] disk availability check: ok");
+        disk
+    } else {
+        create_server_disk(prefix, &server, ssh_public_key, source_archive_id, disk_size_mb).await?
+    };
+
+    // Auto Backup
+    ensure_auto_backup(prefix, disk.id()).await?;
+
+    Server::wait_available(server.id()).await?;
+    log::info!("[CHECKED] server availability check: ok");
+
+    if !Server::is_up(server.id()).await? {
+        log::info!("[START] server booting...");
+        Server::ensure_up(server.id()).await?;
+        log::info!("[DONE] server booted, ok");
+    }
+
+    if simple_monitor {
+        // refetch, the created one may not have the ip yet
+        let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
+            return Err(Error::PrimaryVpcRouterNotExists);
+        };
+        let public_shared_ip = vpc_router.public_shared_ip()?;
+        if let Some(simple_monitor) = PrimarySimpleMonitor::try_get(prefix).await? {
+            log::info!("[CHECKED] simple monitor existence check: already exists, id: {}, ok", simple_monitor.id());
+            log::info!("[START] simple monitor updating...");
+            PrimarySimpleMonitor::update(simple_monitor.id(), prefix, public_shared_ip).await?;
+            log::info!("[DONE] simple monitor updated, ok");
+        } else {
+            log::info!("[START] simple monitor existence check: not exists, creating...");
+            let simple_monitor = PrimarySimpleMonitor::create(prefix, public_shared_ip).await?;
+            log::info!("[DONE] simple monitor created, id: {}, ok", simple_monitor.id());
+        }
+    }
+
+    Ok(firewall_guard)
+}
+
+async fn read_ssh_public_key(pubkey: &Option<PathBuf>) -> Result<Option<String>, Error> {
+    let ssh_public_key_path = pubkey.clone()
+        .or(CONFIG.ssh.as_ref().and_then(|ssh_config| ssh_config.pubkey.clone()))
+        .unwrap_or(default_pubkey_path());
+    match fs::read_to_string(&ssh_public_key_path).await {
+        Ok(ssh_public_key) => Ok(Some(ssh_public_key)),
+        Err(e) => Err(Error::PrimarySshPublicKeyGivenButCouldntRead(ssh_public_key_path, e.to_string())),
+    }
+}
+
+// with the note and the keys of the prefix, the disk is copied from source_archive if given, otherwise from the latest public ubuntu
+async fn create_server_disk(prefix: &str, server: &PrimaryServer, ssh_public_key: Option<String>, source_archive_id: Option<&ArchiveId>, disk_size_mb: u64) -> Result<PrimaryServerDisk, Error> {
+    // Setup Startup Script
+    let note = if let Some(note) = PrimaryServerSetupShellNote::try_get(prefix).await? {
+        log::info!("[CHECKED] note existence check: already exists, id: {}, ok", note.id());
+        log::info!("[START] note content updating if needed...");
+        PrimaryServerSetupShellNote::update_content_if_needed(note.id()).await?;
+        Note::wait_available(note.id()).await?;
+        log::info!("[DONE] note content updated, ok");
+        note
+    } else {
+        log::info!("[START] note existence check: not exists, creating...");
+        let note = PrimaryServerSetupShellNote::create(prefix).await?;
+        Note::wait_available(note.id()).await?;
+        log::info!("[DONE] note created, id: {}, ok", note.id());
+        note
+    };
+
+    // Setup SSH Public Key
+    let ssh_public_key = if let Some(current_ssh_public_key) = PrimaryServerSshPublicKey::try_get(prefix).await? {
+        log::info!("[CHECKED] ssh public key existence check: already exists, id: {}, ok", current_ssh_public_key.id());
+        if let Some(ssh_public_key) = ssh_public_key {
+            if current_ssh_public_key.public_key() != ssh_public_key {
+                // 同名の古い公開鍵を消していいのかわからないのでエラーにする
+                return Err(Error::PrimarySshPublicKeyAlreadyRegisteredButMismatch(
+                        current_ssh_public_key.id().clone(),
+                        current_ssh_public_key.public_key().to_string(),
+                        ssh_public_key.to_string(),
+                ));
+            }
+        }
+        log::info!("[CHECKED] ssh public key mismatch check: ok");
+        current_ssh_public_key
+    } else {
+        log::info!("[CHECKED] ssh public key existence check: not exists");
+        let Some(ssh_public_key) = ssh_public_key else {
+            return Err(Error::PrimarySshPublicKeyNotGivenForNewServerDisk);
+        };
+        log::info!("[START] ssh public key existence check: not exists, creating...");
+        let ssh_public_key = PrimaryServerSshPublicKey::create(prefix, ssh_public_key).await?;
+        log::info!("[DONE] ssh public key created, id: {}, ok", ssh_public_key.id());
+        ssh_public_key
+    };
+
+    // the extra keys of the config, a mismatch is an error like the primary one
+    let mut ssh_public_key_ids = vec![ssh_public_key.id().clone()];
+    for (i, extra_pubkey) in CONFIG.ssh.iter().flat_map(|ssh_config| &ssh_config.extra_pubkeys).enumerate() {
+        let number = i + 1;
+        let extra_public_key = read_extra_pubkey(extra_pubkey).await?;
+        let extra_ssh_public_key = if let Some(current) = ExtraServerSshPublicKey::try_get(prefix, number).await? {
+            if current.public_key().trim() != extra_public_key.trim() {
+                return Err(Error::PrimarySshPublicKeyAlreadyRegisteredButMismatch(current.id().clone(), current.public_key().to_string(), extra_public_key));
+            }
+            log::info!("[CHECKED] extra ssh public key {} existence check: already exists, id: {}, ok", number, current.id());
+            current
+        } else {
+            log::info!("[START] extra ssh public key {} existence check: not exists, creating...", number);
+            let created = ExtraServerSshPublicKey::create(prefix, number, &extra_public_key).await?;
+            log::info!("[DONE] extra ssh public key {} created, id: {}, ok", number, created.id());
+            created
+        };
+        ssh_public_key_ids.push(extra_ssh_public_key.id().clone());
+    }
+
+    let archive_id = match source_archive_id {
+        Some(archive_id) => archive_id.clone(),
+        None => {
+            log::info!("[START] search latest public ubuntu archive...");
+            let archive = Archive::latest_public_ubuntu().await?;
+            log::info!("[DONE] search latest public ubuntu archive, id: {}, ok", archive.id());
+            archive.id().clone()
+        },
+    };
+
+    // a disk from an archive of ours keeps the password of the original disk
+    let password = if CONFIG.server.generate_disk_password && source_archive_id.is_none() {
+        let password = service_env::generate_password();
+        service_env::store_disk_password(prefix, &password)?;
+        log::info!("[NOTE] disk password generated and stored in the keychain, see show-password");
+        Some(password)
+    } else {
+        None
+    };
+
+    let notes = service_env::disk_notes(prefix, note.id()).await?;
+    if notes.len() > 1 {
+        log::info!("[CHECKED] config notes availability check: {} notes, ok", notes.len() - 1);
+    }
+
+    log::info!("[START] disk existence check: not exists, creating...");
+    let disk = PrimaryServerDisk::create_for_server(prefix, server, &archive_id, notes, &ssh_public_key_ids, disk_size_mb, password.as_deref()).await?;
+    log::info!("[DONE] disk created, id: {}, ok", disk.id());
+    forget_host_key(prefix).await?;
+
+    log::info!("[START] disk wait available...");
+    Disk::wait_available(disk.id()).await?;
+    log::info!("[DONE] disk available, ok");
+    Ok(disk)
+}
+
+// the policy follows the disk, which is replaced by the recreation and by `update --replace-server`
+async fn ensure_auto_backup(prefix: &str, disk_id: &DiskId) -> Result<(), Error> {
+    let Some(auto_backup_config) = &CONFIG.auto_backup else {
+        return Ok(());
+    };
+    let auto_backup = match PrimaryServerDiskAutoBackup::try_get(prefix).await? {
+        // the target disk can't be changed, so recreate it for the new disk
+        Some(auto_backup) if auto_backup.disk_id() != Some(disk_id) => {
+            log::info!("[START] auto backup target check: targets another disk, deleting...");
+            AutoBackup::delete(auto_backup.id()).await?;
+            AutoBackup::wait_delete(auto_backup.id()).await?;
+            log::info!("[DONE] auto backup deleted, ok");
+            None
+        },
+        auto_backup => auto_backup,
+    };
+    if let Some(auto_backup) = auto_backup {
+        log::info!("[CHECKED] auto backup existence check: already exists, id: {}, ok", auto_backup.id());
+        log::info!("[START] auto backup updating...");
+        PrimaryServerDiskAutoBackup::update(auto_backup.id(), prefix, disk_id, auto_backup_config).await?;
+        log::info!("[DONE] auto backup updated, ok");
+    } else {
+        log::info!("[START] auto backup existence check: not exists, creating...");
+        let auto_backup = PrimaryServerDiskAutoBackup::create(prefix, disk_id, auto_backup_config).await?;
+        log::info!("[DONE] auto backup created, id: {}, ok", auto_backup.id());
+    }
+    Ok(())
+}
+
+// render, upload and run the setup scripts on the provisioned server
+// the progress is recorded by `update`, and the restart is skipped if it's already started
+async fn deploy(prefix: &str, ssh_options: &SshOptions, scripts_dir: Option<&PathBuf>, firewall_guard: Option<FirewallGuard>, progress: Option<&mut UpdateProgress>) -> Result<(), Error> {
+    let Some(server) = PrimaryServer::try_get(prefix).await? else {
+        return Err(Error::PrimaryServerNotExists);
+    };
+    let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
+        return Err(Error::PrimaryVpcRouterNotExists);
+    };
+    let public_shared_ip = vpc_router.public_shared_ip()?;
+
+    if !Server::is_up(server.id()).await? {
+        boot_server_again(server.id()).await?;
+    }
+
+    let _firewall_guard = match firewall_guard {
+        Some(firewall_guard) => firewall_guard,
+        None => {
+            let firewall_guard = open_firewall_for_setup(prefix, vpc_router.id()).await?;
+            Appliance::wait_available(vpc_router.id()).await?;
+            log::info!("[CHECKED] vpc router availability check: ok");
+            firewall_guard
+        },
+    };
+
+    match progress {
+        None => {
+            start_setup_scripts(server.id(), public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, ssh_options, scripts_dir).await?;
+            wait_setup_scripts(public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, ssh_options).await?;
+        },
+        Some(progress) if progress.is_done(UpdatePhase::SetupFinished) => {
+            log::info!("[SKIPPED] setup script: finished by the last update");
+        },
+        Some(progress) => {
+            if progress.is_done(UpdatePhase::SetupStarted) {
+                log::info!("[SKIPPED] setup script start: started by the last update");
+            } else {
+                start_setup_scripts(server.id(), public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, ssh_options, scripts_dir).await?;
+                progress.done(prefix, UpdatePhase::SetupStarted).await?;
+            }
+            wait_setup_scripts(public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, ssh_options).await?;
+            progress.done(prefix, UpdatePhase::SetupFinished).await?;
+        },
+    }
+
+    if let Some(health_check) = &CONFIG.health_check {
+        check_health(health_check, health_check.url(public_shared_ip), health_check.from_server, (public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT), ssh_options).await?;
+    }
+    Ok(())
+}
+
+// blue/green, the replacement is set up on REPLACEMENT_SERVER_FORWARDED_PORT while the current server keeps serving
+// the current one is deleted only after the vpc router is switched, so a failure before it leaves the service as it was
+async fn replace_server(prefix: &str, pubkey: &Option<PathBuf>, ssh_options: &SshOptions, scripts_dir: Option<&PathBuf>, firewall_guard: Option<FirewallGuard>, source_archive_id: Option<&ArchiveId>) -> Result<(), Error> {
+    let Some(server) = PrimaryServer::try_get(prefix).await? else {
+        return Err(Error::PrimaryServerNotExists);
+    };
+    if PrimaryServerDisk::try_get(prefix).await?.is_none() {
+        return Err(Error::PrimaryServerDiskNotExists);
+    }
+    let Some(switch) = PrimarySwitch::try_get(prefix).await? else {
+        return Err(Error::PrimarySwitchNotExists);
+    };
+    let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
+        return Err(Error::PrimaryVpcRouterNotExists);
+    };
+    let public_shared_ip = vpc_router.public_shared_ip()?;
+    let color = server.color().other();
+
+    let _firewall_guard = match firewall_guard {
+        Some(firewall_guard) => firewall_guard,
+        None => open_firewall_for_setup(prefix, vpc_router.id()).await?,
+    };
+
+    // left by a failed replacement, set up again from the start
+    delete_replacement(prefix, color).await?;
+
+    let icon_id = match &CONFIG.icon {
+        Some(icon_config) => match &icon_config.id {
+            Some(icon_id) => Some(icon_id.clone()),
+            None => PrimaryIcon::try_get(prefix).await?.map(|icon| icon.id().clone()),
+        },
+        None => None,
+    };
+    log::info!("[START] replacement server creating as {}...", color.name());
+    let replacement = PrimaryServer::create_replacement(prefix, color, switch.id(), icon_id.as_ref()).await?;
+    log::info!("[DONE] replacement server created, id: {}, ok", replacement.id());
+    create_server_disk(prefix, &replacement, read_ssh_public_key(pubkey).await?, source_archive_id, PRIMARY_SERVER_DISK_SIZE_MB).await?;
+    Server::wait_available(replacement.id()).await?;
+    boot_server_again(replacement.id()).await?;
+
+    log::info!("[START] vpc router forwarding port {} to the replacement...", REPLACEMENT_SERVER_FORWARDED_PORT);
+    PrimaryVpcRouter::update_config(prefix, vpc_router.id(), false).await?;
+    Appliance::apply_config(vpc_router.id()).await?;
+    Appliance::wait_available(vpc_router.id()).await?;
+    log::info!("[DONE] vpc router forwarding to the replacement, ok");
+
+    let replacement_ssh_options = SshOptions {
+        jump: ssh_options.jump.clone().map(|jump| SshJump { private_ip: color.ip(), ..jump }),
+        ..ssh_options.clone()
+    };
+    let replacement_address = (public_shared_ip, REPLACEMENT_SERVER_FORWARDED_PORT);
+    start_setup_scripts(replacement.id(), public_shared_ip, REPLACEMENT_SERVER_FORWARDED_PORT, &replacement_ssh_options, scripts_dir).await?;
+    wait_setup_scripts(public_shared_ip, REPLACEMENT_SERVER_FORWARDED_PORT, &replacement_ssh_options).await?;
+    // nothing but the replacement itself reaches it before the switch
+    if let Some(health_check) = &CONFIG.health_check {
+        check_health(health_check, health_check.url(Ipv4Addr::LOCALHOST), true, replacement_address, &replacement_ssh_options).await?;
+    }
+
+    finish_switch(prefix, color, ssh_options, false).await
+}
+
+// the color being switched to by a switch which stopped halfway, none if there's none
+// the disks are renamed before the servers, so the replacement is the server of the primary disk once it's promoted
+async fn try_get_half_switched_color(prefix: &str) -> Result<Option<ServerColor>, Error> {
+    let server = PrimaryServer::try_get(prefix).await?;
+    let disk = PrimaryServerDisk::try_get(prefix).await?;
+    for color in [ServerColor::Blue, ServerColor::Green] {
+        if server.as_ref().is_some_and(|server| server.color() == color) {
+            continue;
+        }
+        let Some(replacement) = PrimaryServer::try_get_replacement(prefix, color).await? else {
+            continue;
+        };
+        let is_half_switched = match &disk {
+            Some(disk) => disk.server_id() == Some(replacement.id()),
+            // the old disk is retired, but the replacement disk is not promoted yet
+            None => server.is_some() && PrimaryServerDisk::try_get_replacement(prefix, color).await?.is_some(),
+        };
+        if is_half_switched {
+            return Ok(Some(color));
+        }
+    }
+    Ok(None)
+}
+
+// each rename is to the name it ends with, so a half switch is finished by running it again
+async fn finish_switch(prefix: &str, color: ServerColor, ssh_options: &SshOptions, firewall_enabled: bool) -> Result<(), Error> {
+    let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
+        return Err(Error::PrimaryVpcRouterNotExists);
+    };
+
+    log::info!("[START] switching to the {} server...", color.name());
+    if let Some(replacement_disk) = PrimaryServerDisk::try_get_replacement(prefix, color).await? {
+        if let Some(disk) = PrimaryServerDisk::try_get(prefix).await? {
+            disk.retire(prefix, color.other()).await?;
+        }
+        replacement_disk.promote(prefix).await?;
+    }
+    if let Some(replacement) = PrimaryServer::try_get_replacement(prefix, color).await? {
+        if let Some(server) = PrimaryServer::try_get(prefix).await? {
+            server.retire(prefix).await?;
+        }
+        replacement.promote(prefix).await?;
+    }
+    let Some(server) = PrimaryServer::try_get(prefix).await? else {
+        return Err(Error::PrimaryServerNotExists);
+    };
+    let Some(disk) = PrimaryServerDisk::try_get(prefix).await? else {
+        return Err(Error::PrimaryServerDiskNotExists);
+    };
+    PrimaryVpcRouter::update_config(prefix, vpc_router.id(), firewall_enabled).await?;
+    Appliance::apply_config(vpc_router.id()).await?;
+    Appliance::wait_available(vpc_router.id()).await?;
+    // the forwarded port is the same, but the host is not
+    forget_host_key(prefix).await?;
+    if let Some(control_dir) = &ssh_options.control_dir {
+        ssh::forget_master_connection(control_dir).await?;
+    }
+    log::info!("[DONE] switched to the {} server, id: {}, ok", color.name(), server.id());
+
+    ensure_auto_backup(prefix, disk.id()).await?;
+    delete_replacement(prefix, color.other()).await?;
+
+    // the replacement port forwards to the old server until it's gone
+    log::info!("[START] vpc router forwarding port {} removal...", REPLACEMENT_SERVER_FORWARDED_PORT);
+    PrimaryVpcRouter::update_config(prefix, vpc_router.id(), firewall_enabled).await?;
+    Appliance::apply_config(vpc_router.id()).await?;
+    Appliance::wait_available(vpc_router.id()).await?;
+    log::info!("[DONE] vpc router forwarding port {} removed, ok", REPLACEMENT_SERVER_FORWARDED_PORT);
+    Ok(())
+}
+
+// the server and the disk named by the color, see ServerColor::resource_name
+async fn delete_replacement(prefix: &str, color: ServerColor) -> Result<(), Error> {
+    if let Some(server) = PrimaryServer::try_get_replacement(prefix, color).await? {
+        if Server::is_up(server.id()).await? {
+            shut_down_server_gracefully(server.id()).await?;
+        }
+        log::info!("[START] {} server delete...", color.name());
+        Server::delete(server.id()).await?;
+        Server::wait_delete(server.id()).await?;
+        log::info!("[DONE] {} server delete: ok", color.name());
+    }
+    if let Some(disk) = PrimaryServerDisk::try_get_replacement(prefix, color).await? {
+        log::info!("[START] {} disk delete...", color.name());
+        Disk::delete(disk.id()).await?;
+        Disk::wait_delete(disk.id()).await?;
+        log::info!("[DONE] {} disk delete: ok", color.name());
+    }
+    Ok(())
+}
+
+// retried until it responds 200, the app may take a while to come up after the setup scripts
+// the server is the one on the forwarded port, where curl runs if from_server
+async fn check_health(health_check: &HealthCheckConfig, url: String, from_server: bool, (public_shared_ip, port): (Ipv4Addr, u16), ssh_options: &SshOptions) -> Result<(), Error> {
+    log::info!("[START] health check of {}...", url);
+    let session = if from_server {
+        Some(Session::connect(public_shared_ip, port, ssh_options).await?)
+    } else {
+        None
+    };
+    let mut last_result = String::new();
+    for attempt in 1..=health_check.attempts {
+        let result = match &session {
+            Some(session) => health_status_from_server(session, &url).await,
+            None => match reqwest::Client::new().get(&url).timeout(Duration::from_secs(10)).send().await {
+                Ok(res) => Ok(res.status().as_u16()),
+                Err(e) => Err(e.to_string()),
+            },
+        };
+        match result {
+            Ok(200) => {
+                if let Some(session) = session {
+                    let _ = session.close().await;
+                }
+                log::info!("[DONE] health check responded 200, ok");
+                return Ok(());
+            },
+            Ok(status) => last_result = format!("status {}", status),
+            Err(e) => last_result = e,
+        }
+        if attempt < health_check.attempts {
+            log::warn!("[RETRY] health check {}, retrying in {} seconds...", last_result, health_check.interval_secs);
+            sleep(Duration::from_secs(health_check.interval_secs)).await;
+        }
+    }
+    if let Some(session) = session {
+        let _ = session.close().await;
+    }
+    Err(Error::HealthCheckFailed(url, last_result))
+}
+
+// the status code printed by curl, a failed connection is an error
+async fn health_status_from_server(session: &Session, url: &str) -> Result<u16, String> {
+    let mut output = session.stream_command(&["curl", "-sS", "-o", "/dev/null", "-w", "%{http_code}", "--max-time", "10", url]).await.map_err(|e| e.to_string())?;
+    let mut stdout = String::new();
+    output.stdout().map_err(|e| e.to_string())?.read_to_string(&mut stdout).await.map_err(|e| e.to_string())?;
+    output.wait().await.map_err(|e| e.to_string())?;
+    stdout.trim().parse::<u16>().map_err(|_| format!("unexpected curl output: {}", stdout))
+}
+
+/// deletes the resources of the prefixes
+#[derive(Debug, Parser)]
+pub struct CleanCmd {
+    // repeatable to clean several environments at once
+    #[arg(long = "prefix", env = "SACLOUD_SERVICE_PREFIX", required_unless_present = "prefix_glob")]
+    prefixes: Vec<String>,
+
+    // the environments created by this tool in the zone whose prefix matches, e.g. 'ci-*'
+    #[arg(long)]
+    prefix_glob: Option<String>,
+
+    // number of environments cleaned at once
+    #[arg(long, default_value_t = 4)]
+    parallelism: usize,
+
+    #[arg(long)]
+    force: bool,
+
+    // delete the ssh public key, the setup shell note and the uploaded icon too
+    #[arg(long)]
+    all: bool,
+
+    #[arg(long)]
+    include_keys: bool,
+
+    #[arg(long)]
+    include_notes: bool,
+}
+
+impl CleanCmd {
+    /// runs `clean`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefixes = self.target_prefixes().await?;
+        if let [prefix] = prefixes.as_slice() {
+            // confirm server down
+            if !self.force {
+                confirm_prefix("Realy down?", prefix, global)?;
+            }
+            return notified("clean", prefix, self.clean(prefix)).await;
+        }
+        if prefixes.is_empty() {
+            log::info!("[CHECKED] prefix check: no environment matched, ok");
+            return Ok(());
+        }
+
+        if !self.force {
+            confirm_count(&format!("Realy down {}?", prefixes.join(", ")), prefixes.len(), global)?;
+        }
+        let results = futures::stream::iter(prefixes.iter())
+            .map(|prefix| async move { (prefix, notified("clean", prefix, self.clean(prefix)).await) })
+            .buffer_unordered(self.parallelism.max(1))
+            .collect::<Vec<_>>()
+            .await;
+        let mut failed_prefixes = Vec::new();
+        for (prefix, result) in results {
+            match result {
+                Ok(()) => log::info!("[DONE] clean of {}, ok", prefix),
+                Err(e) => {
+                    log::error!("[FAILED] clean of {}: {}", prefix, e);
+                    failed_prefixes.push(prefix.clone());
+                },
+            }
+        }
+        if !failed_prefixes.is_empty() {
+            return Err(Error::CleanFailed(failed_prefixes));
+        }
+        Ok(())
+    }
+
+    // the given prefixes and the ones matching the glob
+    async fn target_prefixes(&self) -> Result<Vec<String>, Error> {
+        let mut prefixes = self.prefixes.clone();
+        if let Some(prefix_glob) = &self.prefix_glob {
+            let glob = Glob::new(prefix_glob)?;
+            log::info!("[START] environment search...");
+            let found = service_env::search_prefixes().await?;
+            let matched = found.into_iter().filter(|prefix| glob.matches(prefix)).collect::<Vec<_>>();
+            log::info!("[DONE] environment search: {} of {} matched, ok", matched.len(), prefix_glob);
+            prefixes.extend(matched);
+        }
+        prefixes.sort();
+        prefixes.dedup();
+        Ok(prefixes)
+    }
+
+    async fn clean(&self, prefix: &str) -> Result<(), Error> {
+        log::info!("[START] instance status check...");
+        let vpc_router = PrimaryVpcRouter::try_get(prefix).await?;
+        let switch = PrimarySwitch::try_get(prefix).await?;
+        let server = PrimaryServer::try_get(prefix).await?;
+        let disk = PrimaryServerDisk::try_get(prefix).await?;
+        let simple_monitor = PrimarySimpleMonitor::try_get(prefix).await?;
+        let auto_backup = PrimaryServerDiskAutoBackup::try_get(prefix).await?;
+        let nfs = PrimaryNfs::try_get(prefix).await?;
+        let mobile_gateway = PrimaryMobileGateway::try_get(prefix).await?;
+        let ssh_public_key = if self.all || self.include_keys {
+            PrimaryServerSshPublicKey::try_get(prefix).await?
+        } else {
+            None
+        };
+        let extra_ssh_public_keys = if self.all || self.include_keys {
+            ExtraServerSshPublicKey::all(prefix).await?
+        } else {
+            Vec::new()
+        };
+        let note = if self.all || self.include_notes {
+            PrimaryServerSetupShellNote::try_get(prefix).await?
+        } else {
+            None
+        };
+        let icon = if self.all {
+            PrimaryIcon::try_get(prefix).await?
+        } else {
+            None
+        };
+
+        if let Some(vpc_router) = &vpc_router {
+            loop {
+                match Appliance::power_status(vpc_router.id()).await {
+                    Err(api::Error::ResourceUnknownInstanceStatus) => {
+                        log::info!("[WAIT] vpc router instance status check: unknown, retrying...");
+                        sleep(Duration::from_secs(5)).await;
+                    },
+                    Err(e) => return Err(e.into()),
+                    Ok(InstanceStatus::Up | InstanceStatus::Down) => {
+                        break;
+                    },
+                    Ok(status) => {
+                        log::info!("[WAIT] vpc router instance status check: {}, retrying...", status);
+                        sleep(Duration::from_secs(5)).await;
+                    },
+                }
+            }
+        }
+
+        if let Some(server) = &server {
+            loop {
+                match Server::power_status(server.id()).await {
+                    Err(api::Error::ResourceUnknownInstanceStatus) => {
+                        log::info!("[WAIT] server instance status check: unknown, retrying...");
+                        sleep(Duration::from_secs(5)).await;
+                    },
+                    Err(e) => return Err(e.into()),
+                    Ok(InstanceStatus::Up | InstanceStatus::Down) => {
+                        break;
+                    },
+                    Ok(status) => {
+                        log::info!("[WAIT] server instance status check: {}, retrying...", status);
+                        sleep(Duration::from_secs(5)).await;
+                    },
+                }
+            }
+        }
+        log::info!("[CHECKED] instance status check: ok");
+
+        // delete first, so as not to be notified of the shutdown
+        if let Some(simple_monitor) = simple_monitor {
+            log::info!("[START] simple monitor delete...");
+            SimpleMonitor::delete(simple_monitor.id()).await?;
+            SimpleMonitor::wait_delete(simple_monitor.id()).await?;
+            log::info!("[DONE] simple monitor delete: ok");
+        }
+
+        if let Some(vpc_router) = vpc_router {
+            if Appliance::is_up(vpc_router.id()).await? {
+                log::info!("[START] vpc router down...");
+                Appliance::ensure_down(vpc_router.id()).await?;
+                log::info!("[DONE] vpc router down: ok");
+            }
+            log::info!("[START] vpc router delete...");
+            Appliance::delete(vpc_router.id()).await?;
+            Appliance::wait_delete(vpc_router.id()).await?;
+            log::info!("[DONE] vpc router delete: ok");
+        }
+
+        if let Some(server) = server {
+            if Server::is_up(server.id()).await? {
+                shut_down_server_gracefully(server.id()).await?;
+            }
+            log::info!("[START] server delete...");
+            Server::delete(server.id()).await?;
+            Server::wait_delete(server.id()).await?;
+            log::info!("[DONE] server delete: ok");
+        }
+
+        // the auto backup policy refers to the disk
+        if let Some(auto_backup) = auto_backup {
+            log::info!("[START] auto backup delete...");
+            AutoBackup::delete(auto_backup.id()).await?;
+            AutoBackup::wait_delete(auto_backup.id()).await?;
+            log::info!("[DONE] auto backup delete: ok");
+        }
+
+        // the switch can't be deleted while the servers of the colors are connected, and a half switched primary disk is on one of them
+        for color in [ServerColor::Blue, ServerColor::Green] {
+            delete_replacement(prefix, color).await?;
+        }
+
+        if let Some(disk) = disk {
+            log::info!("[START] disk delete...");
+            Disk::delete(disk.id()).await?;
+            Disk::wait_delete(disk.id()).await?;
+            log::info!("[DONE] disk delete: ok");
+            forget_host_key(prefix).await?;
+        }
+
+        // sims are kept, only detached
+        if let Some(mobile_gateway) = mobile_gateway {
+            log::info!("[START] mobile gateway sims detach...");
+            for sim in Appliance::mobile_gateway_sims(mobile_gateway.id()).await? {
+                if sim.ip().is_some() {
+                    Sim::clear_ip(sim.id()).await?;
+                }
+                Appliance::remove_mobile_gateway_sim(mobile_gateway.id(), sim.id()).await?;
+            }
+            log::info!("[DONE] mobile gateway sims detach: ok");
+            if Appliance::is_up(mobile_gateway.id()).await? {
+                log::info!("[START] mobile gateway down...");
+                Appliance::ensure_down(mobile_gateway.id()).await?;
+                log::info!("[DONE] mobile gateway down: ok");
+            }
+            log::info!("[START] mobile gateway delete...");
+            Appliance::delete(mobile_gateway.id()).await?;
+            Appliance::wait_delete(mobile_gateway.id()).await?;
+            log::info!("[DONE] mobile gateway delete: ok");
+        }
+
+        if let Some(nfs) = nfs {
+            if Appliance::is_up(nfs.id()).await? {
+                log::info!("[START] nfs down...");
+                Appliance::ensure_down(nfs.id()).await?;
+                log::info!("[DONE] nfs down: ok");
+            }
+            log::info!("[START] nfs delete...");
+            Appliance::delete(nfs.id()).await?;
+            Appliance::wait_delete(nfs.id()).await?;
+            log::info!("[DONE] nfs delete: ok");
+        }
+
+        if let Some(switch) = switch {
+            log::info!("[START] switch delete...");
+            Switch::delete(switch.id()).await?;
+            Switch::wait_delete(switch.id()).await?;
+            log::info!("[DONE] switch delete: ok");
+        }
+
+        if let Some(ssh_public_key) = ssh_public_key {
+            log::info!("[START] ssh public key delete...");
+            SshPublicKey::delete(ssh_public_key.id()).await?;
+            SshPublicKey::wait_delete(ssh_public_key.id()).await?;
+            log::info!("[DONE] ssh public key delete: ok");
+        } else if !(self.all || self.include_keys) {
+            log::info!("[NOTE] ssh public key is not deleted for safety, use --include-keys to delete it");
+        }
+        for extra_ssh_public_key in extra_ssh_public_keys {
+            log::info!("[START] extra ssh public key delete: {}...", extra_ssh_public_key.id());
+            SshPublicKey::delete(extra_ssh_public_key.id()).await?;
+            SshPublicKey::wait_delete(extra_ssh_public_key.id()).await?;
+            log::info!("[DONE] extra ssh public key delete: ok");
+        }
+
+        if let Some(note) = note {
+            log::info!("[START] note delete...");
+            Note::delete(note.id()).await?;
+            Note::wait_delete(note.id()).await?;
+            log::info!("[DONE] note delete: ok");
+        } else if !(self.all || self.include_notes) {
+            log::info!("[NOTE] setup shell note is not deleted, use --include-notes to delete it");
+        }
+
+        if let Some(icon) = icon {
+            log::info!("[START] icon delete...");
+            Icon::delete(icon.id()).await?;
+            Icon::wait_delete(icon.id()).await?;
+            log::info!("[DONE] icon delete: ok");
+        }
+
+        log::info!("[DONE] all checks passed, ok");
+
+        Ok(())
+    }
+}
+
+/// finds the resources named with the prefix that the environment doesn't use, e.g. a disk left by a failed update
+#[derive(Debug, Parser)]
+pub struct DoctorCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    // delete orphaned servers, disks and switches
+    #[arg(long)]
+    delete: bool,
+
+    #[arg(long)]
+    force: bool,
+}
+
+impl DoctorCmd {
+    /// runs `doctor`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+
+        // search by name is partial match, so keep only the exact names of the prefix, dev must not match dev-staging-server
+        let mut expected_names = EquipmentKind::all().iter().map(|kind| kind.name(prefix)).collect::<Vec<_>>();
+        expected_names.extend([ServerColor::Blue, ServerColor::Green].iter().map(|color| color.resource_name(prefix)));
+        let is_expected = |name: Option<&str>| name.map(|name| expected_names.iter().any(|expected| expected == name) || ExtraServerSshPublicKey::is_name(prefix, name)).unwrap_or(false);
+        let is_tagged = |tags: &[String]| tags.iter().any(|tag| tag == api::MANAGED_TAG);
+
+        log::info!("[START] resource scan...");
+        let servers = Server::search_by_name(prefix).await?.into_iter().filter(|v| is_expected(v.name())).collect::<Vec<_>>();
+        let disks = Disk::search_by_name(prefix).await?.into_iter().filter(|v| is_expected(v.name())).collect::<Vec<_>>();
+        let switches = Switch::search_by_name(prefix).await?.into_iter().filter(|v| is_expected(v.name())).collect::<Vec<_>>();
+        let appliances = Appliance::search_by_name(prefix).await?.into_iter().filter(|v| is_expected(v.name())).collect::<Vec<_>>();
+        let ssh_public_keys = SshPublicKey::search_by_name(prefix).await?.into_iter().filter(|v| is_expected(v.name())).collect::<Vec<_>>();
+        let notes = Note::search_by_name(prefix).await?.into_iter().filter(|v| is_expected(v.name())).collect::<Vec<_>>();
+        let simple_monitor = PrimarySimpleMonitor::try_get(prefix).await?;
+        let auto_backup = PrimaryServerDiskAutoBackup::try_get(prefix).await?;
+        let icon = PrimaryIcon::try_get(prefix).await?;
+        let migration_archive = MigrationArchive::try_get(prefix).await?;
+        let golden_archives = GoldenArchive::search(prefix).await?;
+        log::info!("[DONE] resource scan: {} servers, {} disks, {} switches, {} appliances, {} ssh public keys, {} notes, {} simple monitors, {} auto backups, {} icons, {} archives",
+            servers.len(), disks.len(), switches.len(), appliances.len(), ssh_public_keys.len(), notes.len(),
+            simple_monitor.iter().count(), auto_backup.iter().count(), icon.iter().count(), migration_archive.iter().count() + golden_archives.len());
+
+        let mut problem_count = 0;
+
+        // the servers and the disks of the colors are left by a replacement which stopped before the switch, or the old ones after it
+        let half_switched_color = try_get_half_switched_color(prefix).await?;
+        let mut orphan_colors = Vec::new();
+        for color in [ServerColor::Blue, ServerColor::Green] {
+            let name = color.resource_name(prefix);
+            let is_left = servers.iter().any(|v| v.name() == Some(name.as_str()) && is_tagged(v.tags()))
+                || disks.iter().any(|v| v.name() == Some(name.as_str()) && is_tagged(v.tags()));
+            if !is_left {
+                continue;
+            }
+            if half_switched_color.is_some() {
+                log::warn!("[MISMATCH] {} server: the switch stopped halfway, run update to finish it", color.name());
+                problem_count += 1;
+            } else {
+                log::warn!("[ORPHAN] {} server: left by a replacement", color.name());
+                orphan_colors.push(color);
+            }
+        }
+        let is_of_orphan_color = |name: Option<&str>| orphan_colors.iter().any(|color| name == Some(color.resource_name(prefix).as_str()));
+
+        // disk attached to server
+        let mut orphan_disks = Vec::new();
+        for disk in &disks {
+            match disk.server_id() {
+                // deleted along with the color
+                None if is_of_orphan_color(disk.name()) => {},
+                None if is_tagged(disk.tags()) => {
+                    log::warn!("[ORPHAN] disk {}: not attached to any server", disk.id());
+                    orphan_disks.push(disk);
+                },
+                None => {
+                    log::warn!("[ORPHAN] disk {}: not attached to any server, left in place without the {} tag", disk.id(), api::MANAGED_TAG);
+                    problem_count += 1;
+                },
+                Some(server_id) if !servers.iter().any(|server| server.id() == server_id) => {
+                    log::warn!("[MISMATCH] disk {}: attached to unmanaged server {}", disk.id(), server_id);
+                    problem_count += 1;
+                },
+                Some(_) => log::info!("[CHECKED] disk {}: attached to server, ok", disk.id()),
+            }
+        }
+
+        // server on switch, switch on router
+        let mut connected_server_ids = Vec::new();
+        let mut orphan_switches = Vec::new();
+        for switch in &switches {
+            let connected_servers = Switch::connected_servers(switch.id()).await?;
+            let connected_appliances = Switch::connected_appliances(switch.id()).await?;
+            connected_server_ids.extend(connected_servers.iter().map(|server| server.id().clone()));
+            if connected_appliances.is_empty() && connected_servers.is_empty() {
+                if is_tagged(switch.tags()) {
+                    log::warn!("[ORPHAN] switch {}: connected to nothing", switch.id());
+                    orphan_switches.push(switch);
+                } else {
+                    log::warn!("[ORPHAN] switch {}: connected to nothing, left in place without the {} tag", switch.id(), api::MANAGED_TAG);
+                    problem_count += 1;
+                }
+            } else if !connected_appliances.iter().any(|appliance| appliances.iter().any(|v| v.id() == appliance.id() && v.is_vpc_router())) {
+                log::warn!("[MISMATCH] switch {}: not connected to managed vpc router", switch.id());
+                problem_count += 1;
+            } else {
+                log::info!("[CHECKED] switch {}: connected to vpc router, ok", switch.id());
+            }
+        }
+        for server in &servers {
+            if connected_server_ids.iter().any(|id| id == server.id()) {
+                log::info!("[CHECKED] server {}: connected to switch, ok", server.id());
+            } else {
+                log::warn!("[MISMATCH] server {}: not connected to managed switch", server.id());
+                problem_count += 1;
+            }
+        }
+        for appliance in &appliances {
+            let mut is_connected = false;
+            for switch in &switches {
+                if Appliance::is_connected_to_switch(appliance.id(), switch.id()).await? {
+                    is_connected = true;
+                    break;
+                }
+            }
+            if is_connected {
+                log::info!("[CHECKED] {} {}: connected to switch, ok", appliance_label(appliance), appliance.id());
+            } else {
+                log::warn!("[MISMATCH] {} {}: not connected to managed switch", appliance_label(appliance), appliance.id());
+                problem_count += 1;
+            }
+        }
+
+        // the common service items and the archives are only reported, an auto backup takes its backups with it
+        if let Some(simple_monitor) = &simple_monitor {
+            if appliances.iter().any(|appliance| appliance.is_vpc_router()) {
+                log::info!("[CHECKED] simple monitor {}: vpc router to watch, ok", simple_monitor.id());
+            } else {
+                log::warn!("[ORPHAN] simple monitor {}: no managed vpc router to watch, left in place", simple_monitor.id());
+                problem_count += 1;
+            }
+        }
+        if let Some(auto_backup) = &auto_backup {
+            if auto_backup.disk_id().is_some_and(|disk_id| disks.iter().any(|disk| disk.id() == disk_id)) {
+                log::info!("[CHECKED] auto backup {}: backing up managed disk, ok", auto_backup.id());
+            } else {
+                log::warn!("[ORPHAN] auto backup {}: no managed disk to back up, left in place", auto_backup.id());
+                problem_count += 1;
+            }
+        }
+        if let Some(icon) = &icon {
+            log::info!("[CHECKED] icon {}: ok", icon.id());
+        }
+        if let Some(migration_archive) = &migration_archive {
+            log::warn!("[NOTE] migration archive {}: left by an unfinished migrate", migration_archive.id());
+        }
+        for golden_archive in &golden_archives {
+            log::info!("[CHECKED] golden archive {}: v{}, ok", golden_archive.id(), golden_archive.version());
+        }
+
+        let orphan_count = orphan_colors.len() + orphan_disks.len() + orphan_switches.len();
+        log::info!("[DONE] doctor: {} orphans, {} other problems", orphan_count, problem_count);
+
+        if !self.delete || orphan_count == 0 {
+            return Ok(());
+        }
+
+        if !self.force {
+            confirm_prefix("Realy delete orphans?", prefix, global)?;
+        }
+
+        for color in orphan_colors {
+            delete_replacement(prefix, color).await?;
+        }
+
+        for disk in orphan_disks {
+            log::info!("[START] orphan disk delete: {}...", disk.id());
+            Disk::delete(disk.id()).await?;
+            Disk::wait_delete(disk.id()).await?;
+            log::info!("[DONE] orphan disk delete: ok");
+        }
+
+        for switch in orphan_switches {
+            log::info!("[START] orphan switch delete: {}...", switch.id());
+            Switch::delete(switch.id()).await?;
+            Switch::wait_delete(switch.id()).await?;
+            log::info!("[DONE] orphan switch delete: ok");
+        }
+
+        Ok(())
+    }
+}
+
+// the class in the logs, the appliances of the prefix aren't only the vpc router
+fn appliance_label(appliance: &Appliance) -> &'static str {
+    match appliance.class() {
+        Some(ApplianceClass::VpcRouter) => "vpc router",
+        Some(ApplianceClass::Nfs) => "nfs",
+        Some(ApplianceClass::MobileGateway) => "mobile gateway",
+        None => "appliance",
+    }
+}
+
+/// brings resources made outside of this tool under the prefix, they are found by name afterwards
+#[derive(Debug, Parser)]
+pub struct AdoptCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[arg(long, value_parser = parse_id::<ServerId>)]
+    server_id: ServerId,
+
+    #[arg(long, value_parser = parse_id::<SwitchId>)]
+    switch_id: SwitchId,
+
+    #[arg(long, value_parser = parse_id::<ApplianceId>)]
+    vpc_router_id: ApplianceId,
+
+    #[arg(long)]
+    force: bool,
+}
+
+impl AdoptCmd {
+    /// runs `adopt`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+
+        log::info!("[START] topology check...");
+        let server = Server::get(&self.server_id).await?;
+        let switch = Switch::get(&self.switch_id).await?;
+        let vpc_router = Appliance::get(&self.vpc_router_id).await?;
+        if !vpc_router.is_vpc_router() {
+            return Err(Error::AdoptNotVpcRouter(vpc_router.id().clone()));
+        }
+        if !Server::is_connected_to_switch(server.id(), switch.id()).await? {
+            return Err(Error::PrimaryServerNotConnectedToSwitch(server.id().clone(), switch.id().clone()));
+        }
+        if !Appliance::is_connected_to_switch(vpc_router.id(), switch.id()).await? {
+            return Err(Error::PrimarySwitchNotConnectedToVpcRouter(switch.id().clone(), vpc_router.id().clone()));
+        }
+        let Some(disk_id) = server.disk_ids().first().cloned().cloned() else {
+            return Err(Error::AdoptServerHasNoDisk(server.id().clone()));
+        };
+        log::info!("[DONE] topology check: server {} with disk {} on switch {} behind vpc router {}, ok", server.id(), disk_id, switch.id(), vpc_router.id());
+
+        // all names are checked before renaming any
+        let candidates = [
+            (ResourceKind::Server, EquipmentKind::PrimaryServer, server.id().to_string(), PrimaryServer::try_get(prefix).await?.map(|v| v.id().to_string())),
+            (ResourceKind::Disk, EquipmentKind::PrimaryServerDisk, disk_id.to_string(), PrimaryServerDisk::try_get(prefix).await?.map(|v| v.id().to_string())),
+            (ResourceKind::Switch, EquipmentKind::PrimarySwitch, switch.id().to_string(), PrimarySwitch::try_get(prefix).await?.map(|v| v.id().to_string())),
+            (ResourceKind::Appliance, EquipmentKind::PrimaryVpcRouter, vpc_router.id().to_string(), PrimaryVpcRouter::try_get(prefix).await?.map(|v| v.id().to_string())),
+        ];
+        let mut renames = Vec::new();
+        for (resource_kind, equipment_kind, id, managed_id) in candidates {
+            let name = equipment_kind.name(prefix);
+            match managed_id {
+                Some(managed_id) if managed_id == id => log::info!("[CHECKED] {} {}: already named {}, ok", resource_kind.single_name(), id, name),
+                Some(managed_id) => return Err(Error::AdoptNameTaken(name, managed_id)),
+                None => renames.push((resource_kind, id, name)),
+            }
+        }
+        if renames.is_empty() {
+            log::info!("[DONE] adopt: already managed, ok");
+            return Ok(());
+        }
+
+        if !self.force {
+            confirm_prefix(&format!("{} resources will be renamed to the naming scheme.", renames.len()), prefix, global)?;
+        }
+        for (resource_kind, id, name) in renames {
+            log::info!("[START] {} {} renaming to {}...", resource_kind.single_name(), id, name);
+            resource_kind.rename(&id, &name).await?;
+            log::info!("[DONE] renamed, ok");
+        }
+
+        // the existing settings are not rewritten by `update`
+        log::info!("[NOTE] the vpc router must forward port {} to {}:22, and the server is reached with the key in the ssh config", PRIMARY_SERVER_FORWARDED_PORT, CONFIG.network.server_ip);
+        log::info!("[DONE] adopt: ok");
+        Ok(())
+    }
+}
+
+/// a kind of the resources of `list`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ListKind {
+    /// servers
+    Server,
+    /// disks
+    Disk,
+    /// switches
+    Switch,
+    /// appliances, e.g. vpc routers
+    Appliance,
+    /// archives
+    Archive,
+    /// notes
+    Note,
+    /// ssh public keys
+    Sshkey,
+    /// iso images
+    Cdrom,
+}
+
+impl ListKind {
+    fn resource_kind(&self) -> ResourceKind {
+        match self {
+            Self::Server => ResourceKind::Server,
+            Self::Disk => ResourceKind::Disk,
+            Self::Switch => ResourceKind::Switch,
+            Self::Appliance => ResourceKind::Appliance,
+            Self::Archive => ResourceKind::Archive,
+            Self::Note => ResourceKind::Note,
+            Self::Sshkey => ResourceKind::SshPublicKey,
+            Self::Cdrom => ResourceKind::Cdrom,
+        }
+    }
+}
+
+/// the resources in the zone, not only the ones of a prefix
+#[derive(Debug, Parser)]
+pub struct ListCmd {
+    // all kinds if not given
+    #[arg(long, value_enum)]
+    kind: Vec<ListKind>,
+
+    // name=substr or tag=tag
+    #[arg(long)]
+    filter: Vec<String>,
+
+    #[arg(long)]
+    json: bool,
+
+    // search is1a, is1b, tk1a and tk1b at once instead of SACLOUD_ZONE, e.g. to find where an old environment is
+    #[arg(long)]
+    all_zones: bool,
+}
+
+impl ListCmd {
+    /// runs `list`
+    pub async fn run(&self) -> Result<(), Error> {
+        let kinds = if self.kind.is_empty() {
+            ListKind::value_variants().to_vec()
+        } else {
+            self.kind.clone()
+        };
+
+        let mut query = SearchQuery::new();
+        for f in &self.filter {
+            query = match f.split_once('=') {
+                Some(("name", name)) => query.name(name),
+                Some(("tag", tag)) => query.tags([tag]),
+                _ => return Err(Error::InvalidListFilter(f.clone())),
+            };
+        }
+
+        // a zone which fails, e.g. without the permission, is skipped with a warning
+        let zones = if self.all_zones { api::ZONES.iter().map(|zone| zone.to_string()).collect() } else { vec![api::zone()?] };
+        let endpoints = zones.iter().map(ApiEndpoint::for_zone).collect::<Result<Vec<_>, _>>()?;
+        let zone_results = futures::future::join_all(endpoints.into_iter().map(|endpoint| {
+            endpoint.scope(Self::search(&kinds, &query))
+        })).await;
+        let mut summaries: Vec<(&str, ResourceSummary)> = Vec::new();
+        for (zone, result) in zones.iter().zip(zone_results) {
+            match result {
+                Ok(zone_summaries) => summaries.extend(zone_summaries.into_iter().map(|summary| (zone.as_str(), summary))),
+                Err(e) if self.all_zones => log::warn!("[NOTE] {} is skipped: {}", zone, e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if self.json {
+            let summaries = if self.all_zones {
+                summaries.iter().map(|(zone, summary)| {
+                    let mut value = serde_json::to_value(summary).expect("must be valid json");
+                    value["Zone"] = Value::from(*zone);
+                    value
+                }).collect::<Vec<_>>()
+            } else {
+                summaries.iter().map(|(_, summary)| serde_json::to_value(summary).expect("must be valid json")).collect()
+            };
+            println!("{}", to_string_pretty(&summaries).expect("must be valid json"));
+            return Ok(());
+        }
+
+        for (zone, summary) in &summaries {
+            if self.all_zones {
+                print!("{}\t", zone);
+            }
+            println!("{}\t{}\t{}\t[{}]\t{}",
+                summary.kind().map(|kind| kind.single_name()).unwrap_or_default(),
+                summary.id(),
+                summary.name().unwrap_or_default(),
+                summary.tags().join(","),
+                summary.status().unwrap_or("-"),
+            );
+        }
+        Ok(())
+    }
+
+    async fn search(kinds: &[ListKind], query: &SearchQuery) -> Result<Vec<ResourceSummary>, Error> {
+        let mut summaries = Vec::new();
+        for kind in kinds {
+            let kind_summaries = kind.resource_kind().search_summaries(query).await?;
+            summaries.extend(kind_summaries.into_iter().filter(|summary| summary.is_user_scope()));
+        }
+        Ok(summaries)
+    }
+}
+
+/// the output of `export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// import blocks for terraform or opentofu
+    Terraform,
+    /// the resources and their ids as json
+    Json,
+}
+
+/// the resources under the prefix with their ids, to be managed by terraform afterwards
+#[derive(Debug, Parser)]
+pub struct ExportCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[arg(long, value_enum, default_value = "terraform")]
+    format: ExportFormat,
+}
+
+impl ExportCmd {
+    /// runs `export`
+    pub async fn run(&self) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+
+        let mut resources: Vec<(EquipmentKind, String)> = Vec::new();
+        if let Some(v) = PrimaryServer::try_get(prefix).await? {
+            resources.push((EquipmentKind::PrimaryServer, v.id().to_string()));
+        }
+        if let Some(v) = PrimaryServerDisk::try_get(prefix).await? {
+            resources.push((EquipmentKind::PrimaryServerDisk, v.id().to_string()));
+        }
+        if let Some(v) = PrimaryServerSshPublicKey::try_get(prefix).await? {
+            resources.push((EquipmentKind::PrimaryServerSshPublicKey, v.id().to_string()));
+        }
+        if let Some(v) = PrimarySwitch::try_get(prefix).await? {
+            resources.push((EquipmentKind::PrimarySwitch, v.id().to_string()));
+        }
+        if let Some(v) = PrimaryVpcRouter::try_get(prefix).await? {
+            resources.push((EquipmentKind::PrimaryVpcRouter, v.id().to_string()));
+        }
+        if let Some(v) = PrimaryServerSetupShellNote::try_get(prefix).await? {
+            resources.push((EquipmentKind::PrimaryServerSetupShellNote, v.id().to_string()));
+        }
+        if let Some(v) = PrimarySimpleMonitor::try_get(prefix).await? {
+            resources.push((EquipmentKind::PrimarySimpleMonitor, v.id().to_string()));
+        }
+        if let Some(v) = PrimaryServerDiskAutoBackup::try_get(prefix).await? {
+            resources.push((EquipmentKind::PrimaryServerDiskAutoBackup, v.id().to_string()));
+        }
+        if let Some(v) = PrimaryNfs::try_get(prefix).await? {
+            resources.push((EquipmentKind::PrimaryNfs, v.id().to_string()));
+        }
+        if let Some(v) = PrimaryMobileGateway::try_get(prefix).await? {
+            resources.push((EquipmentKind::PrimaryMobileGateway, v.id().to_string()));
+        }
+        if let Some(v) = PrimaryIcon::try_get(prefix).await? {
+            resources.push((EquipmentKind::PrimaryIcon, v.id().to_string()));
+        }
+        log::info!("[DONE] resource scan: {} resources", resources.len());
+
+        let zone = api::zone()?;
+        match self.format {
+            ExportFormat::Terraform => print!("{}", service_env::terraform_import_blocks(prefix, &zone, &resources)),
+            ExportFormat::Json => {
+                let values = resources.iter().map(|(kind, id)| json!({
+                    "name": kind.name(prefix),
+                    "id": id,
+                    "zone": &zone,
+                    "terraform_address": kind.terraform_address(prefix),
+                })).collect::<Vec<_>>();
+                println!("{}", to_string_pretty(&values).expect("must be valid json"));
+            },
+        }
+        Ok(())
+    }
+}
+
+/// the state of the prefix for `apply-manifest`, printed if --output is not given
+#[derive(Debug, Parser)]
+pub struct ExportManifestCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+impl ExportManifestCmd {
+    /// runs `export-manifest`
+    pub async fn run(&self) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerNotExists);
+        };
+        let Some(disk) = PrimaryServerDisk::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerDiskNotExists);
+        };
+        let Some(plan_size) = server.plan_size() else {
+            return Err(Error::ManifestServerPlanUnknown(server.id().clone()));
+        };
+        let (Some(disk_plan_id), Some(disk_size_mb)) = (disk.plan_id(), disk.size_mb()) else {
+            return Err(Error::ManifestDiskPlanUnknown(disk.id().clone()));
+        };
+        let simple_monitor = PrimarySimpleMonitor::try_get(prefix).await?.is_some();
+
+        // the content is not in the search result
+        let mut notes = Vec::new();
+        if let Some(note) = PrimaryServerSetupShellNote::try_get(prefix).await? {
+            notes.push((EquipmentKind::PrimaryServerSetupShellNote.name(prefix), Note::get(note.id()).await?));
+        }
+        let notes = notes.iter().map(|(name, note)| (name.clone(), note.content())).collect();
+
+        let (cpu, memory_mb) = plan_size;
+        let manifest_server = ManifestServer { cpu, memory_mb, generation: server.plan_generation(), commitment: server.plan_commitment() };
+        let manifest = Manifest::new(prefix, &api::zone()?, manifest_server, (disk_plan_id.clone(), disk_size_mb), simple_monitor, notes);
+        let json = to_string_pretty(&manifest).expect("must be valid json");
+        match &self.output {
+            Some(output) => {
+                fs::write(output, format!("{}\n", json)).await.map_err(|e| Error::ManifestCouldntWrite(output.clone(), e.to_string()))?;
+                log::info!("[DONE] manifest written to {}, ok", output.display());
+            },
+            None => println!("{}", json),
+        }
+        Ok(())
+    }
+}
+
+/// reproduces the manifest under the prefix, e.g. in another account with the same config
+#[derive(Debug, Parser)]
+pub struct ApplyManifestCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[arg(long)]
+    manifest: PathBuf,
+
+    #[arg(long)]
+    pubkey: Option<PathBuf>,
+
+    #[command(flatten)]
+    ssh: SshArgs,
+
+    #[command(flatten)]
+    scripts: ScriptsArgs,
+
+    // shut down the server for resizing without asking
+    #[arg(long)]
+    force: bool,
+}
+
+impl ApplyManifestCmd {
+    /// runs `apply-manifest`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let manifest = Manifest::load(&self.manifest)?;
+        validate_config()?;
+        let problems = manifest.problems();
+        if !problems.is_empty() {
+            return Err(Error::ManifestHasProblems(problems));
+        }
+        log::info!("[CHECKED] manifest check: exported from {} in {}, ok", manifest.prefix, manifest.zone);
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
+        let scripts_dir = self.scripts.scripts_dir();
+        check_cost(prefix, &api::zone()?, manifest.disk.size_mb, global).await?;
+
+        let firewall_guard = provision(prefix, &self.pubkey, manifest.simple_monitor, None, manifest.disk.size_mb).await?;
+
+        // an existing disk is kept as is
+        let Some(disk) = PrimaryServerDisk::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerDiskNotExists);
+        };
+        if disk.size_mb() != Some(manifest.disk.size_mb) {
+            log::warn!("[NOTE] the disk {} is not {} MB of the manifest, recreate it to follow", disk.id(), manifest.disk.size_mb);
+        }
+
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerNotExists);
+        };
+        let ManifestServer { cpu, memory_mb, generation, commitment } = manifest.server;
+        if server.plan_size() == Some((cpu, memory_mb)) && generation.is_none_or(|generation| server.plan_generation() == Some(generation)) && server.plan_commitment() == commitment {
+            log::info!("[CHECKED] server plan check: already {} cpus, {} GB memory, ok", cpu, memory_mb / 1024);
+        } else {
+            let was_up = shut_down_server_for(server.id(), "resizing", prefix, self.force, global).await?;
+            log::info!("[START] server plan changing...");
+            let new_server = Server::change_plan(server.id(), cpu, memory_mb, generation, commitment).await?;
+            log::info!("[DONE] server plan changed, {} cpus, {} GB memory, new id: {}, ok", cpu, memory_mb / 1024, new_server.id());
+            if was_up {
+                boot_server_again(new_server.id()).await?;
+            }
+        }
+
+        deploy(prefix, &ssh_options, scripts_dir, Some(firewall_guard), None).await
+    }
+}
+
+/// calls the api with the method and the path, and prints the response
+#[derive(Debug, Parser)]
+pub struct ApiCallCmd {
+    method: String,
+
+    // relative to the zone api base url, e.g. server/123456789012/power
+    path: String,
+
+    #[arg(long)]
+    body: Option<PathBuf>,
+
+    // k=v, v is parsed as json if possible, otherwise used as string
+    #[arg(long)]
+    query: Vec<String>,
+}
+
+impl ApiCallCmd {
+    /// runs `api-call`
+    pub async fn run(&self) -> Result<(), Error> {
+        let method = self.method.to_uppercase().parse::<reqwest::Method>().map_err(|_| Error::ApiCallInvalidMethod(self.method.clone()))?;
+
+        let query = if self.query.is_empty() {
+            None
+        } else {
+            let mut query = json!({});
+            for q in &self.query {
+                let Some((key, value)) = q.split_once('=') else {
+                    return Err(Error::ApiCallInvalidQuery(q.clone()));
+                };
+                query[key] = serde_json::from_str(value).unwrap_or_else(|_| Value::from(value));
+            }
+            Some(query)
+        };
+
+        let body = match &self.body {
+            Some(body_path) => {
+                let body = fs::read_to_string(body_path).await.map_err(|e| Error::ApiCallBodyCouldntRead(body_path.clone(), e.to_string()))?;
+                let body = serde_json::from_str(&body).map_err(|e| Error::ApiCallBodyInvalidJson(body_path.clone(), e.to_string()))?;
+                Some(body)
+            },
+            None => None,
+        };
+
+        let value = api::request_api(method, &self.path, &query, &body).await?;
+        println!("{}", to_string_pretty(&value).expect("must be valid json"));
+        Ok(())
+    }
+}
+
+/// prints the latest monitor samples of the server, its disk and its interfaces
+#[derive(Debug, Parser)]
+pub struct MetricsCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    // number of the latest samples to show
+    #[arg(long, default_value_t = 12)]
+    count: usize,
+
+    #[arg(long)]
+    sparkline: bool,
+
+    // ISO 8601, e.g. 2024-01-01T00:00:00+09:00
+    #[arg(long)]
+    start: Option<String>,
+
+    #[arg(long)]
+    end: Option<String>,
+}
+
+impl MetricsCmd {
+    /// runs `metrics`
+    pub async fn run(&self) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let span = MonitorSpan::new(self.start.clone(), self.end.clone());
+
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerNotExists);
+        };
+        let Some(disk) = PrimaryServerDisk::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerDiskNotExists);
+        };
+
+        let samples = monitor::server_cpu(server.id(), &span).await?;
+        let samples = last_samples(&samples, self.count);
+        self.print_series("server cpu-time", samples.iter().map(|s| (s.time.as_str(), s.value.cpu_time)).collect());
+
+        let samples = monitor::disk(disk.id(), &span).await?;
+        let samples = last_samples(&samples, self.count);
+        self.print_series("disk read", samples.iter().map(|s| (s.time.as_str(), s.value.read)).collect());
+        self.print_series("disk write", samples.iter().map(|s| (s.time.as_str(), s.value.write)).collect());
+
+        for interface_id in server.interface_ids() {
+            let samples = monitor::interface(interface_id, &span).await?;
+            let samples = last_samples(&samples, self.count);
+            self.print_series(&format!("interface {} receive", interface_id), samples.iter().map(|s| (s.time.as_str(), s.value.receive)).collect());
+            self.print_series(&format!("interface {} send", interface_id), samples.iter().map(|s| (s.time.as_str(), s.value.send)).collect());
+        }
+        Ok(())
+    }
+
+    fn print_series(&self, label: &str, series: Vec<(&str, Option<f64>)>) {
+        if self.sparkline {
+            let values = series.iter().map(|(_, v)| *v).collect::<Vec<_>>();
+            let last = values.iter().rev().find_map(|v| *v);
+            println!("{}: {} (last: {})", label, sparkline(&values), last.map(|v| format!("{:.3}", v)).unwrap_or("-".to_string()));
+        } else {
+            println!("{}:", label);
+            for (time, value) in series {
+                println!("  {} {}", time, value.map(|v| format!("{:.3}", v)).unwrap_or("-".to_string()));
+            }
+        }
+    }
+}
+
+/// polls the resources of the prefix and serves the gauges for prometheus until ctrl-c
+#[derive(Debug, Parser)]
+pub struct ServeMetricsCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    // host:port, :port listens on all interfaces
+    #[arg(long, default_value = ":9123")]
+    listen: String,
+
+    // the monitor api has 5 minutes resolution, so polling more often only costs api calls
+    #[arg(long, default_value_t = 60)]
+    interval_secs: u64,
+}
+
+impl ServeMetricsCmd {
+    /// runs `serve-metrics`
+    pub async fn run(&self) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let listener = prometheus::listen(&self.listen).await?;
+        log::info!("[DONE] listening on {}, ok", self.listen);
+        let metrics = Arc::new(Mutex::new(String::new()));
+        let server = tokio::spawn(prometheus::serve(listener, metrics.clone()));
+
+        // the gauges of the last successful poll are kept on failure, with the success gauge down
+        let mut last_gauges = Gauges::new();
+        loop {
+            let success = match poll_gauges(prefix).await {
+                Ok(gauges) => {
+                    last_gauges = gauges;
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+                    last_gauges.set("sacloud_poll_timestamp_seconds", "unix time of the last successful poll", &[("prefix", prefix)], now as f64);
+                    log::debug!("[DONE] poll: ok");
+                    true
+                },
+                Err(e) => {
+                    log::warn!("[RETRY] poll failed, retrying in {} seconds...: {}", self.interval_secs, e);
+                    false
+                },
+            };
+            let mut gauges = last_gauges.clone();
+            gauges.set("sacloud_poll_success", "1 if the last poll of the api succeeded", &[("prefix", prefix)], if success { 1.0 } else { 0.0 });
+            *metrics.lock().expect("metrics lock must not be poisoned") = gauges.render();
+
+            tokio::select! {
+                _ = signal::ctrl_c() => break,
+                _ = sleep(Duration::from_secs(self.interval_secs)) => {},
+            }
+        }
+        server.abort();
+        Ok(())
+    }
+}
+
+// the missing resources are just not in the gauges
+async fn poll_gauges(prefix: &str) -> Result<Gauges, Error> {
+    let mut gauges = Gauges::new();
+    let span = MonitorSpan::default();
+
+    if let Some(server) = PrimaryServer::try_get(prefix).await? {
+        let id = server.id().to_string();
+        status_gauges(&mut gauges, prefix, "server", ResourceKind::Server, &id).await?;
+        let samples = monitor::server_cpu(server.id(), &span).await?;
+        if let Some(cpu_time) = samples.iter().rev().find_map(|s| s.value.cpu_time) {
+            gauges.set("sacloud_server_cpu_time", "the latest cpu time of the monitor api", &[("prefix", prefix), ("id", &id)], cpu_time);
+        }
+        for interface_id in server.interface_ids() {
+            let interface_id_string = interface_id.to_string();
+            let labels = [("prefix", prefix), ("id", interface_id_string.as_str())];
+            let samples = monitor::interface(interface_id, &span).await?;
+            if let Some(receive) = samples.iter().rev().find_map(|s| s.value.receive) {
+                gauges.set("sacloud_interface_receive_bps", "the latest receive of the monitor api", &labels, receive);
+            }
+            if let Some(send) = samples.iter().rev().find_map(|s| s.value.send) {
+                gauges.set("sacloud_interface_send_bps", "the latest send of the monitor api", &labels, send);
+            }
+        }
+    }
+    if let Some(disk) = PrimaryServerDisk::try_get(prefix).await? {
+        let id = disk.id().to_string();
+        status_gauges(&mut gauges, prefix, "disk", ResourceKind::Disk, &id).await?;
+        let samples = monitor::disk(disk.id(), &span).await?;
+        if let Some(read) = samples.iter().rev().find_map(|s| s.value.read) {
+            gauges.set("sacloud_disk_read", "the latest read of the monitor api", &[("prefix", prefix), ("id", &id)], read);
+        }
+        if let Some(write) = samples.iter().rev().find_map(|s| s.value.write) {
+            gauges.set("sacloud_disk_write", "the latest write of the monitor api", &[("prefix", prefix), ("id", &id)], write);
+        }
+    }
+    if let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? {
+        status_gauges(&mut gauges, prefix, "vpc_router", ResourceKind::Appliance, &vpc_router.id().to_string()).await?;
+    }
+    if let Some(nfs) = PrimaryNfs::try_get(prefix).await? {
+        status_gauges(&mut gauges, prefix, "nfs", ResourceKind::Appliance, &nfs.id().to_string()).await?;
+    }
+    if let Some(mobile_gateway) = PrimaryMobileGateway::try_get(prefix).await? {
+        status_gauges(&mut gauges, prefix, "mobile_gateway", ResourceKind::Appliance, &mobile_gateway.id().to_string()).await?;
+    }
+    Ok(gauges)
+}
+
+// up only for the resources with the power
+async fn status_gauges(gauges: &mut Gauges, prefix: &str, resource: &str, kind: ResourceKind, id: &str) -> Result<(), Error> {
+    let summary = ResourceSummary::from_value(kind, kind.get(id).await?)?;
+    let labels = [("prefix", prefix), ("resource", resource), ("id", id)];
+    if let Some(availability) = summary.availability() {
+        gauges.set("sacloud_available", "1 if the availability is available", &labels, if availability == "available" { 1.0 } else { 0.0 });
+    }
+    if let Some(instance_status) = summary.instance_status() {
+        gauges.set("sacloud_up", "1 if the instance status is up", &labels, if instance_status == "up" { 1.0 } else { 0.0 });
+    }
+    Ok(())
+}
+
+fn last_samples<T>(samples: &[T], count: usize) -> &[T] {
+    &samples[samples.len().saturating_sub(count)..]
+}
+
+fn sparkline(values: &[Option<f64>]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().flatten().cloned().fold(0.0, f64::max);
+    values.iter().map(|value| match value {
+        None => ' ',
+        Some(_) if max <= 0.0 => BARS[0],
+        Some(v) => BARS[((v / max) * (BARS.len() - 1) as f64).round() as usize],
+    }).collect()
+}
+
+/// the monthly prices of the resources of the prefix in the price list, and their total
+#[derive(Debug, Parser)]
+pub struct CostCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    // show the recent bills of the account too
+    #[arg(long)]
+    bills: bool,
+
+    // save the detail csv of the latest bill
+    #[arg(long)]
+    bill_csv: Option<PathBuf>,
+}
+
+impl CostCmd {
+    /// runs `cost`
+    pub async fn run(&self) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let name_prefix = format!("{}-", prefix);
+        let zone = api::zone()?;
+
+        log::info!("[START] price list fetch...");
+        let prices = billing::service_class_prices().await?;
+        log::info!("[DONE] price list fetch: {} entries, ok", prices.len());
+
+        let mut total_monthly = 0.0;
+        for kind in [ResourceKind::Server, ResourceKind::Disk, ResourceKind::Switch, ResourceKind::Appliance] {
+            let summaries = kind.search_summaries(&SearchQuery::new().name(prefix)).await?;
+            for summary in summaries.iter().filter(|summary| summary.name().map(|name| name.starts_with(&name_prefix)).unwrap_or(false)) {
+                let service_class = summary.service_class().unwrap_or_default();
+                let monthly = billing::find_price(&prices, service_class, &zone).and_then(|price| price.monthly);
+                if let Some(monthly) = monthly {
+                    total_monthly += monthly;
+                } else {
+                    log::warn!("[UNKNOWN] price not found: {} {} {}", kind.single_name(), summary.id(), service_class);
+                }
+                println!("{}\t{}\t{}\t{}\t{}",
+                    kind.single_name(),
+                    summary.id(),
+                    summary.name().unwrap_or_default(),
+                    service_class,
+                    monthly.map(|v| format!("{:.0} JPY/month", v)).unwrap_or("-".to_string()),
+                );
+            }
+        }
+        println!("estimated total: {:.0} JPY/month", total_monthly);
+
+        if !self.bills && self.bill_csv.is_none() {
+            return Ok(());
+        }
+
+        let account_id = billing::account_id().await?;
+        let bills = billing::bills(&account_id).await?;
+        if self.bills {
+            for bill in &bills {
+                println!("bill {}\t{}\t{}\t{}",
+                    bill.id,
+                    bill.date.as_deref().unwrap_or_default(),
+                    bill.amount.map(|v| format!("{:.0} JPY", v)).unwrap_or("-".to_string()),
+                    if bill.paid == Some(true) { "paid" } else { "unpaid" },
+                );
+            }
+        }
+
+        let Some(latest_bill) = bills.iter().max_by(|a, b| a.date.cmp(&b.date)) else {
+            log::info!("[NOTE] no bill found");
+            return Ok(());
+        };
+
+        if self.bills {
+            println!("latest bill {} details:", latest_bill.id);
+            for detail in billing::bill_details(&latest_bill.id).await? {
+                println!("  {}\t{}\t{}\t{}",
+                    detail.service_class_path.as_deref().unwrap_or_default(),
+                    detail.description.as_deref().unwrap_or_default(),
+                    detail.usage.as_deref().unwrap_or_default(),
+                    detail.amount.map(|v| format!("{:.0} JPY", v)).unwrap_or("-".to_string()),
+                );
+            }
+        }
+
+        if let Some(bill_csv_path) = &self.bill_csv {
+            let csv = billing::bill_detail_csv(&latest_bill.id).await?;
+            fs::write(bill_csv_path, csv.body).await.map_err(|e| Error::BillCsvCouldntWrite(bill_csv_path.clone(), e.to_string()))?;
+            log::info!("[DONE] bill csv saved: {}", bill_csv_path.display());
+        }
+
+        Ok(())
+    }
+}
+
+/// the sims behind the mobile gateway of the prefix
+#[derive(Debug, Parser)]
+pub struct SimCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[command(subcommand)]
+    action: SimAction,
+}
+
+/// the actions of `sim`
+#[derive(Debug, Subcommand)]
+pub enum SimAction {
+    /// the sims of the account
+    List,
+    /// register the sim to the account
+    Register {
+        /// the name of the sim
+        #[arg(long)]
+        name: String,
+
+        /// the iccid printed on the sim
+        #[arg(long)]
+        iccid: String,
+
+        /// the passcode printed on the sim
+        #[arg(long, env = "SACLOUD_SIM_PASSCODE")]
+        passcode: String,
+    },
+    /// route the sim traffic into the primary switch's segment via the mobile gateway
+    Attach {
+        /// the name of the sim
+        #[arg(long)]
+        name: String,
+
+        /// the ip of the sim in the segment of the switch
+        #[arg(long)]
+        ip: Ipv4Addr,
+    },
+}
+
+impl SimCmd {
+    /// runs `sim`
+    pub async fn run(&self) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        match &self.action {
+            SimAction::List => {
+                let mobile_gateway = PrimaryMobileGateway::try_get(prefix).await?;
+                let attached_sims = match &mobile_gateway {
+                    Some(mobile_gateway) => Appliance::mobile_gateway_sims(mobile_gateway.id()).await?,
+                    None => Vec::new(),
+                };
+                for sim in Sim::search_all().await? {
+                    let attached_sim = attached_sims.iter().find(|attached_sim| attached_sim.id() == sim.id());
+                    println!("{}\t{}\t{}\t{}",
+                        sim.id(),
+                        sim.name().unwrap_or_default(),
+                        sim.iccid().unwrap_or_default(),
+                        attached_sim.map(|attached_sim| attached_sim.ip().unwrap_or("attached")).unwrap_or("-"),
+                    );
+                }
+            },
+            SimAction::Register { name, iccid, passcode } => {
+                log::info!("[START] sim registering...");
+                let info = SimInfo::builder()
+                    .name(name.clone())
+                    .description(name.clone())
+                    .iccid(iccid.clone())
+                    .passcode(passcode.clone())
+                    .build();
+                let sim = Sim::create(info).await?;
+                log::info!("[DONE] sim registered, id: {}, ok", sim.id());
+            },
+            SimAction::Attach { name, ip } => {
+                let Some(switch) = PrimarySwitch::try_get(prefix).await? else {
+                    return Err(Error::PrimarySwitchNotExists);
+                };
+                let Some(sim) = Sim::get_by_name(name).await? else {
+                    return Err(Error::SimNotExists(name.clone()));
+                };
+
+                let mobile_gateway = if let Some(mobile_gateway) = PrimaryMobileGateway::try_get(prefix).await? {
+                    log::info!("[CHECKED] mobile gateway existence check: already exists, id: {}, ok", mobile_gateway.id());
+                    Appliance::wait_available(mobile_gateway.id()).await?;
+                    log::info!("[CHECKED] mobile gateway availability check: ok");
+                    mobile_gateway
+                } else {
+                    log::info!("[START] mobile gateway existence check: not exists, creating...");
+                    let mobile_gateway = PrimaryMobileGateway::create(prefix).await?;
+                    Appliance::wait_available(mobile_gateway.id()).await?;
+                    log::info!("[DONE] mobile gateway created, id: {}, ok", mobile_gateway.id());
+                    mobile_gateway
+                };
+
+                if !Appliance::is_connected_to_switch(mobile_gateway.id(), switch.id()).await? {
+                    // the interface can be connected only while the appliance is down
+                    if Appliance::is_up(mobile_gateway.id()).await? {
+                        log::info!("[START] mobile gateway down for connecting to switch...");
+                        Appliance::ensure_down(mobile_gateway.id()).await?;
+                        log::info!("[DONE] mobile gateway down, ok");
+                    }
+                    log::info!("[START] switch connection check: connecting mobile gateway...");
+                    Appliance::connect_to_switch(mobile_gateway.id(), switch.id()).await?;
+                    PrimaryMobileGateway::update_config(mobile_gateway.id()).await?;
+                    Appliance::wait_available(mobile_gateway.id()).await?;
+                    log::info!("[DONE] mobile gateway connected to switch, ok");
+                }
+
+                if !Appliance::is_up(mobile_gateway.id()).await? {
+                    log::info!("[START] mobile gateway booting...");
+                    Appliance::ensure_up(mobile_gateway.id()).await?;
+                    log::info!("[DONE] mobile gateway booted, ok");
+                }
+
+                let attached_sims = Appliance::mobile_gateway_sims(mobile_gateway.id()).await?;
+                if attached_sims.iter().any(|attached_sim| attached_sim.id() == sim.id()) {
+                    log::info!("[CHECKED] sim attachment check: already attached, ok");
+                } else {
+                    log::info!("[START] sim attaching to mobile gateway...");
+                    Appliance::add_mobile_gateway_sim(mobile_gateway.id(), sim.id()).await?;
+                    Sim::activate(sim.id()).await?;
+                    log::info!("[DONE] sim attached, ok");
+                }
+
+                log::info!("[START] sim ip assigning...");
+                if attached_sims.iter().any(|attached_sim| attached_sim.id() == sim.id() && attached_sim.ip().is_some()) {
+                    Sim::clear_ip(sim.id()).await?;
+                }
+                Sim::assign_ip(sim.id(), *ip).await?;
+                Appliance::apply_config(mobile_gateway.id()).await?;
+                log::info!("[DONE] sim ip assigned: {}, ok", ip);
+            },
+        }
+        Ok(())
+    }
+}
+
+/// the startup scripts of the account, the setup shell note of each prefix is managed by update
+#[derive(Debug, Parser)]
+pub struct NoteCmd {
+    #[command(subcommand)]
+    action: NoteAction,
+}
+
+/// the actions of `note`
+#[derive(Debug, Subcommand)]
+pub enum NoteAction {
+    /// the notes of the account
+    List {
+        /// the startup scripts published by sakura cloud instead
+        #[arg(long)]
+        official: bool,
+    },
+    /// print the content of the note
+    Show {
+        /// the name of the note
+        #[arg(long)]
+        name: String,
+
+        /// a startup script published by sakura cloud
+        #[arg(long)]
+        official: bool,
+    },
+    /// create or update the note of the name with the content of the file
+    Push {
+        /// the name of the note
+        #[arg(long)]
+        name: String,
+
+        /// the local file of the content
+        #[arg(long)]
+        file: PathBuf,
+    },
+    /// the lines of the remote note prefixed with '-' and the local ones with '+'
+    Diff {
+        /// the name of the note
+        #[arg(long)]
+        name: String,
+
+        /// the local file to compare with
+        #[arg(long)]
+        file: PathBuf,
+    },
+}
+
+impl NoteCmd {
+    /// runs `note`
+    pub async fn run(&self) -> Result<(), Error> {
+        match &self.action {
+            NoteAction::List { official } => {
+                let scope = if *official { ResourceScope::Shared } else { ResourceScope::User };
+                for note in Note::search_all(scope).await? {
+                    println!("{}\t{}\t{}",
+                        note.id(),
+                        note.name().unwrap_or_default(),
+                        note.class().map(|class| class.to_string()).unwrap_or("-".to_string()),
+                    );
+                }
+            },
+            NoteAction::Show { name, official } => {
+                let note = if *official { Note::official_startup_script(name).await? } else { Note::get_by_name(name).await? };
+                let Some(note) = note else {
+                    return Err(Error::NoteNotExists(name.clone()));
+                };
+                // the content is not in the search result
+                let note = Note::get(note.id()).await?;
+                print!("{}", note.content());
+            },
+            NoteAction::Push { name, file } => {
+                let content = read_note_script(file).await?;
+                if let Some(note) = Note::get_by_name(name).await? {
+                    log::info!("[CHECKED] note existence check: already exists, id: {}, ok", note.id());
+                    if Note::update_content_if_needed(note.id(), &content).await? {
+                        log::info!("[DONE] note content updated, ok");
+                    } else {
+                        log::info!("[CHECKED] note content check: already up to date, ok");
+                    }
+                } else {
+                    log::info!("[START] note existence check: not exists, creating...");
+                    let info = NoteInfo::builder()
+                        .name(name.clone())
+                        .class(NoteClass::Shell)
+                        .description(name.clone())
+                        .content(content)
+                        .build();
+                    let note = Note::create(info).await?;
+                    Note::wait_available(note.id()).await?;
+                    log::info!("[DONE] note created, id: {}, ok", note.id());
+                }
+            },
+            NoteAction::Diff { name, file } => {
+                let content = read_note_script(file).await?;
+                let Some(note) = Note::get_by_name(name).await? else {
+                    return Err(Error::NoteNotExists(name.clone()));
+                };
+                let note = Note::get(note.id()).await?;
+                if note.content() == content {
+                    log::info!("[CHECKED] note diff check: no difference, ok");
+                    return Ok(());
+                }
+                println!("--- {} (remote)", name);
+                println!("+++ {} (local)", file.display());
+                for line in line_diff(note.content(), &content) {
+                    println!("{}", line);
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+async fn read_note_script(file: &PathBuf) -> Result<String, Error> {
+    fs::read_to_string(file).await.map_err(|e| Error::NoteScriptCouldntRead(file.clone(), e.to_string()))
+}
+
+// the changed lines and 3 lines around them, like `diff -u` without the line numbers
+fn line_diff(old: &str, new: &str) -> Vec<String> {
+    const CONTEXT: usize = 3;
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    // lcs[i][j] is the length of the longest common lines of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            lines.push(('+', new[j]));
+            j += 1;
+        } else {
+            lines.push(('-', old[i]));
+            i += 1;
+        }
+    }
+
+    let changed = lines.iter().enumerate().filter(|(_, (mark, _))| *mark != ' ').map(|(k, _)| k).collect::<Vec<_>>();
+    let mut output = Vec::new();
+    let mut last_shown = None;
+    for (k, (mark, line)) in lines.iter().enumerate() {
+        if !changed.iter().any(|&c| c.abs_diff(k) <= CONTEXT) {
+            continue;
+        }
+        if last_shown.is_some_and(|last: usize| last + 1 != k) {
+            output.push("@@".to_string());
+        }
+        output.push(format!("{}{}", mark, line));
+        last_shown = Some(k);
+    }
+    output
+}
+
+/// the certificates of the proxy lbs
+#[derive(Debug, Parser)]
+pub struct CertCmd {
+    #[command(subcommand)]
+    action: CertAction,
+}
+
+/// the actions of `cert`
+#[derive(Debug, Subcommand)]
+pub enum CertAction {
+    /// the proxy lbs and their certificates
+    List,
+    /// upload the certificate as the primary one, replacing the current one
+    Rotate {
+        /// the name of the proxy lb
+        #[arg(long)]
+        proxylb: String,
+
+        /// the certificate in pem
+        #[arg(long)]
+        cert: PathBuf,
+
+        /// the private key in pem
+        #[arg(long)]
+        key: PathBuf,
+
+        /// the intermediate certificate in pem
+        #[arg(long)]
+        intermediate: Option<PathBuf>,
+    },
+}
+
+impl CertCmd {
+    /// runs `cert`
+    pub async fn run(&self) -> Result<(), Error> {
+        match &self.action {
+            CertAction::List => {
+                for proxy_lb in ProxyLb::search_all().await? {
+                    let certificates = ProxyLb::certificates(proxy_lb.id()).await?;
+                    let certs = certificates.primary_cert().into_iter().chain(certificates.additional_certs());
+                    for (i, cert) in certs.enumerate() {
+                        println!("{}\t{}\t{}\t{}\t{}\t{}",
+                            proxy_lb.id(),
+                            proxy_lb.name().unwrap_or_default(),
+                            proxy_lb.fqdn().unwrap_or_default(),
+                            if i == 0 { "primary" } else { "additional" },
+                            cert.common_name().unwrap_or_default(),
+                            cert.end_date().unwrap_or_default(),
+                        );
                     }
-                    Err(e) => return Err(e),
+                }
+            },
+            CertAction::Rotate { proxylb, cert, key, intermediate } => {
+                let Some(proxy_lb) = ProxyLb::get_by_name(proxylb).await? else {
+                    return Err(Error::ProxyLbNotExists(proxylb.clone()));
+                };
+
+                let certificates = ProxyLb::certificates(proxy_lb.id()).await?;
+                // private keys of the additional certs are not returned, so they can't be kept
+                if !certificates.additional_certs().is_empty() {
+                    return Err(Error::ProxyLbHasAdditionalCerts(proxylb.clone(), certificates.additional_certs().len()));
+                }
+                if let Some(current_cert) = certificates.primary_cert() {
+                    log::info!("[CHECKED] current certificate: {}, until {}",
+                        current_cert.common_name().unwrap_or_default(), current_cert.end_date().unwrap_or_default());
+                }
+
+                let server_certificate = read_cert_file(cert).await?;
+                let private_key = read_cert_file(key).await?;
+                let intermediate_certificate = match intermediate {
+                    Some(intermediate) => read_cert_file(intermediate).await?,
+                    None => String::new(),
+                };
+
+                log::info!("[START] certificate uploading...");
+                let new_cert = ProxyLbCertificate::new(server_certificate, intermediate_certificate, private_key);
+                ProxyLb::set_certificates(proxy_lb.id(), ProxyLbCertificates::new(new_cert, Vec::new())).await?;
+                log::info!("[DONE] certificate uploaded, ok");
+
+                let certificates = ProxyLb::certificates(proxy_lb.id()).await?;
+                if let Some(new_cert) = certificates.primary_cert() {
+                    log::info!("[CHECKED] new certificate: {}, until {}",
+                        new_cert.common_name().unwrap_or_default(), new_cert.end_date().unwrap_or_default());
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+/// saves the service dirs of the server as tar.gz into the local dir or the bucket
+#[derive(Debug, Parser)]
+pub struct BackupCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[command(flatten)]
+    ssh: SshArgs,
+
+    // service_dirs in config if not given
+    #[arg(long)]
+    remote_dir: Vec<PathBuf>,
+
+    // upload to the bucket of `object_storage` in config, instead of local_dir
+    #[arg(long)]
+    to_bucket: bool,
+
+    #[arg(long)]
+    local_dir: Option<PathBuf>,
+}
+
+impl BackupCmd {
+    /// runs `backup`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
+        let remote_dirs = if self.remote_dir.is_empty() {
+            CONFIG.server.service_dirs.iter().map(PathBuf::from).collect::<Vec<_>>()
+        } else {
+            self.remote_dir.clone()
+        };
+
+        let bucket = if self.to_bucket {
+            let Some(object_storage_config) = &CONFIG.object_storage else {
+                return Err(Error::ObjectStorageNotConfigured);
+            };
+            Some(Bucket::from_config(object_storage_config)?)
+        } else if self.local_dir.is_none() {
+            return Err(Error::BackupLocalDirNotGiven);
+        } else {
+            None
+        };
+
+        let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
+            return Err(Error::PrimaryVpcRouterNotExists);
+        };
+        let public_shared_ip = vpc_router.public_shared_ip()?;
+        let session = Session::connect(public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, &ssh_options).await?;
+
+        let timestamp = object_storage::utc_timestamp();
+        let result = self.backup_dirs(&session, &remote_dirs, bucket.as_ref(), &timestamp).await;
+        let _ = session.close().await;
+        result
+    }
+
+    async fn backup_dirs(&self, session: &Session, remote_dirs: &[PathBuf], bucket: Option<&Bucket>, timestamp: &str) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        for remote_dir in remote_dirs {
+            let dir_name = remote_dir.to_string_lossy().trim_matches('/').replace('/', "_");
+            let file_name = format!("{}-{}.tar.gz", dir_name, timestamp);
+
+            log::info!("[START] backup {}...", remote_dir.display());
+            let mut tar = session.tar_remote_dir(remote_dir).await?;
+            let stdout = tar.stdout()?;
+            match (bucket, &self.local_dir) {
+                (Some(bucket), _) => {
+                    let key = format!("{}/{}", prefix, file_name);
+                    // completed only if tar exits with success, a truncated archive is not left under the key
+                    let size = bucket.upload_stream(&key, stdout, tar.wait()).await?;
+                    log::info!("[DONE] backup {} uploaded to {}/{}, {} bytes, ok", remote_dir.display(), bucket.name(), key, size);
+                },
+                (None, Some(local_dir)) => {
+                    let local_path = local_dir.join(&file_name);
+                    let mut stdout = stdout;
+                    let mut local_file = fs::File::create(&local_path).await.map_err(|e| Error::BackupCouldntWriteLocalFile(local_path.clone(), e.to_string()))?;
+                    let size = tokio::io::copy(&mut stdout, &mut local_file).await.map_err(|e| Error::BackupCouldntWriteLocalFile(local_path.clone(), e.to_string()))?;
+                    tar.wait().await?;
+                    log::info!("[DONE] backup {} saved to {}, {} bytes, ok", remote_dir.display(), local_path.display(), size);
+                },
+                (None, None) => return Err(Error::BackupLocalDirNotGiven),
+            }
+        }
+        Ok(())
+    }
+}
+
+// cdrom sizes accepted by the api
+const CDROM_SIZES_MB: [u64; 2] = [5120, 10240];
+
+/// inserts an iso image into the server, uploading it if needed
+#[derive(Debug, Parser)]
+pub struct MountIsoCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    // uploaded as a new cdrom if the cdrom of the name doesn't exist
+    #[arg(long)]
+    iso: Option<PathBuf>,
+
+    // the file name of the iso if not given
+    #[arg(long)]
+    name: Option<String>,
+
+    // eject the inserted cdrom from the primary server instead
+    #[arg(long)]
+    eject: bool,
+}
+
+impl MountIsoCmd {
+    /// runs `mount-iso`
+    pub async fn run(&self) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerNotExists);
+        };
+
+        if self.eject {
+            let Some(cdrom_id) = server.inserted_cdrom_id() else {
+                return Err(Error::CdromNotInserted);
+            };
+            log::info!("[START] cdrom ejecting...");
+            Server::eject_cdrom(server.id(), cdrom_id).await?;
+            log::info!("[DONE] cdrom {} ejected, ok", cdrom_id);
+            return Ok(());
+        }
+
+        let name = match (&self.name, &self.iso) {
+            (Some(name), _) => name.clone(),
+            (None, Some(iso)) => iso.file_name().map(|name| name.to_string_lossy().to_string()).ok_or(Error::IsoNameNotGiven)?,
+            (None, None) => return Err(Error::IsoNameNotGiven),
+        };
+
+        let cdrom = match Cdrom::get_by_name(&name).await? {
+            Some(cdrom) if cdrom.is_available() => {
+                log::info!("[CHECKED] cdrom {} already exists, ok", name);
+                cdrom
+            },
+            Some(cdrom) => {
+                // the last upload may have failed, so upload again if possible
+                let Some(iso) = &self.iso else {
+                    return Err(Error::CdromNotAvailable(name));
+                };
+                log::info!("[START] cdrom {} isn't available, iso re-uploading...", name);
+                let ftp_server = Cdrom::open_ftp(cdrom.id()).await?;
+                Self::upload(&cdrom, &ftp_server, iso).await?;
+                cdrom
+            },
+            None => {
+                let Some(iso) = &self.iso else {
+                    return Err(Error::CdromNotExists(name));
+                };
+                let iso_size = fs::metadata(iso).await.map_err(|e| Error::IsoCouldntRead(iso.clone(), e.to_string()))?.len();
+                let Some(size_mb) = CDROM_SIZES_MB.into_iter().find(|size_mb| size_mb * 1024 * 1024 >= iso_size) else {
+                    return Err(Error::IsoTooLarge(iso.clone(), iso_size));
+                };
+
+                log::info!("[START] cdrom {} creating...", name);
+                let cdrom_info = CdromInfo::builder()
+                    .name(name.clone())
+                    .description(format!("uploaded by {}", prefix))
+                    .size_mb(size_mb)
+                    .build();
+                let (cdrom, ftp_server) = Cdrom::create(cdrom_info).await?;
+                log::info!("[DONE] cdrom {} created, ok", name);
+
+                Self::upload(&cdrom, &ftp_server, iso).await?;
+                cdrom
+            },
+        };
+
+        if server.inserted_cdrom_id() == Some(cdrom.id()) {
+            log::info!("[CHECKED] cdrom already inserted to the primary server, ok");
+            return Ok(());
+        }
+        if let Some(cdrom_id) = server.inserted_cdrom_id() {
+            log::info!("[START] another cdrom {} ejecting...", cdrom_id);
+            Server::eject_cdrom(server.id(), cdrom_id).await?;
+            log::info!("[DONE] cdrom {} ejected, ok", cdrom_id);
+        }
+
+        log::info!("[START] cdrom inserting to the primary server...");
+        Server::insert_cdrom(server.id(), cdrom.id()).await?;
+        log::info!("[DONE] cdrom {} inserted, ok", cdrom.id());
+        log::info!("[NOTE] reboot the primary server to boot from the cdrom");
+        Ok(())
+    }
+
+    async fn upload(cdrom: &Cdrom, ftp_server: &api::FtpServer, iso: &PathBuf) -> Result<(), Error> {
+        log::info!("[START] iso uploading via ftps...");
+        let result = ftps::upload_file(ftp_server, iso).await;
+        // close anyway, the cdrom can't be used while the ftp server is open
+        Cdrom::close_ftp(cdrom.id()).await?;
+        let size = result?;
+        log::info!("[DONE] iso uploaded, {} bytes, ok", size);
+
+        log::info!("[START] cdrom waiting to be available...");
+        Cdrom::wait_available(cdrom.id()).await?;
+        log::info!("[DONE] cdrom {} available, ok", cdrom.name().unwrap_or_default());
+        Ok(())
+    }
+}
+
+// archive sizes accepted by the api
+const ARCHIVE_SIZES_MB: [u64; 7] = [20480, 40960, 61440, 81920, 102400, 256000, 512000];
+
+/// a raw or qcow2 image uploaded as a new archive, which the disks can be copied from
+#[derive(Debug, Parser)]
+pub struct UploadArchiveCmd {
+    #[arg(long)]
+    image: PathBuf,
+
+    // the file name of the image if not given
+    #[arg(long)]
+    name: Option<String>,
+
+    // the smallest one larger than the image if not given, the virtual size of a qcow2 image may need a larger one
+    #[arg(long)]
+    size_gb: Option<u64>,
+}
+
+impl UploadArchiveCmd {
+    /// runs `upload-archive`
+    pub async fn run(&self) -> Result<(), Error> {
+        let image = &self.image;
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => image.file_name().map(|name| name.to_string_lossy().to_string()).ok_or(Error::ArchiveImageCouldntRead(image.clone(), "no file name".to_string()))?,
+        };
+
+        let archive = match Archive::get_by_name(&name).await? {
+            Some(archive) if archive.is_available() => return Err(Error::ArchiveAlreadyExists(name)),
+            // the last upload may have failed, so upload again
+            Some(archive) => {
+                log::info!("[START] archive {} isn't available, image re-uploading...", name);
+                let ftp_server = Archive::open_ftp(archive.id()).await?;
+                upload_archive_image(&archive, &ftp_server, image).await?;
+                archive
+            },
+            None => {
+                let image_size = fs::metadata(image).await.map_err(|e| Error::ArchiveImageCouldntRead(image.clone(), e.to_string()))?.len();
+                let size_mb = match self.size_gb {
+                    Some(size_gb) => size_gb * 1024,
+                    None => ARCHIVE_SIZES_MB.into_iter().find(|size_mb| size_mb * 1024 * 1024 >= image_size).ok_or(Error::ArchiveImageTooLarge(image.clone(), image_size))?,
+                };
+
+                log::info!("[START] archive {} creating, {} MB...", name, size_mb);
+                let archive_info = ArchiveInfo::builder()
+                    .name(name.clone())
+                    .description(format!("uploaded from {}", image.display()))
+                    .size_mb(size_mb)
+                    .build();
+                let (archive, ftp_server) = Archive::create(archive_info).await?;
+                let Some(ftp_server) = ftp_server else {
+                    return Err(service_env::Error::ArchiveFtpServerNotOpened(archive.id().clone()).into());
+                };
+                log::info!("[DONE] archive {} created, id: {}, ok", name, archive.id());
+
+                upload_archive_image(&archive, &ftp_server, image).await?;
+                archive
+            },
+        };
+        log::info!("[DONE] upload-archive: {}, id: {}, ok", name, archive.id());
+        Ok(())
+    }
+}
+
+/// the image of the archive is saved as is, raw or qcow2 as it was uploaded
+#[derive(Debug, Parser)]
+pub struct DownloadArchiveCmd {
+    // archive name
+    #[arg(long)]
+    name: String,
+
+    #[arg(long)]
+    output: PathBuf,
+}
+
+impl DownloadArchiveCmd {
+    /// runs `download-archive`
+    pub async fn run(&self) -> Result<(), Error> {
+        let Some(archive) = Archive::get_by_name(&self.name).await? else {
+            return Err(Error::ArchiveNotExists(self.name.clone()));
+        };
+        Archive::wait_available(archive.id()).await?;
+        log::info!("[CHECKED] archive {} availability check: ok", self.name);
+
+        log::info!("[START] image downloading via ftps...: {}", self.output.display());
+        let ftp_server = Archive::open_ftp(archive.id()).await?;
+        let result = ftps::download_file_with_progress(&ftp_server, &self.output, transfer_progress_logger("downloading")).await;
+        // close anyway, the archive can't be used while the ftp server is open
+        Archive::close_ftp(archive.id()).await?;
+        let size = result?;
+        log::info!("[DONE] image downloaded, {} bytes, ok", size);
+        Ok(())
+    }
+}
+
+async fn upload_archive_image(archive: &Archive, ftp_server: &api::FtpServer, image: &PathBuf) -> Result<(), Error> {
+    log::info!("[START] image uploading via ftps...");
+    let result = ftps::upload_file_with_progress(ftp_server, image, transfer_progress_logger("uploading")).await;
+    // close anyway, the archive can't be used while the ftp server is open
+    Archive::close_ftp(archive.id()).await?;
+    let size = result?;
+    log::info!("[DONE] image uploaded, {} bytes, ok", size);
+
+    log::info!("[START] archive waiting to be available...");
+    Archive::wait_available(archive.id()).await?;
+    log::info!("[DONE] archive available, ok");
+    Ok(())
+}
+
+const TRANSFER_LOG_INTERVAL: Duration = Duration::from_secs(30);
+
+// the images are gigabytes, so the progress is logged every interval
+fn transfer_progress_logger(action: &'static str) -> impl FnMut(u64, Option<u64>) {
+    let started = Instant::now();
+    let mut last_logged = Instant::now();
+    move |transferred, total| {
+        if last_logged.elapsed() < TRANSFER_LOG_INTERVAL {
+            return;
+        }
+        last_logged = Instant::now();
+        let transferred_mb = transferred / 1024 / 1024;
+        match total.filter(|total| *total > 0) {
+            Some(total) => log::info!("[WAIT] {} {}/{} MB ({}%), elapsed {} secs", action, transferred_mb, total / 1024 / 1024, transferred * 100 / total, started.elapsed().as_secs()),
+            None => log::info!("[WAIT] {} {} MB, elapsed {} secs", action, transferred_mb, started.elapsed().as_secs()),
+        }
+    }
+}
+
+/// a resource of `describe`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DescribeKind {
+    /// the server
+    Server,
+    /// the disk of the server
+    Disk,
+    /// the vpc router
+    Router,
+    /// the switch
+    Switch,
+}
+
+impl DescribeKind {
+    fn resource_kind(&self) -> ResourceKind {
+        match self {
+            Self::Server => ResourceKind::Server,
+            Self::Disk => ResourceKind::Disk,
+            Self::Router => ResourceKind::Appliance,
+            Self::Switch => ResourceKind::Switch,
+        }
+    }
+}
+
+/// one resource of the prefix in detail, what `status` summarizes in a line
+#[derive(Debug, Parser)]
+pub struct DescribeCmd {
+    #[arg(value_enum)]
+    kind: DescribeKind,
+
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    // the api response as is, with the fields not typed here
+    #[arg(long)]
+    raw: bool,
+}
+
+impl DescribeCmd {
+    /// runs `describe`
+    pub async fn run(&self) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let id = match self.kind {
+            DescribeKind::Server => PrimaryServer::try_get(prefix).await?.ok_or(Error::PrimaryServerNotExists)?.id().to_string(),
+            DescribeKind::Disk => PrimaryServerDisk::try_get(prefix).await?.ok_or(Error::PrimaryServerDiskNotExists)?.id().to_string(),
+            DescribeKind::Router => PrimaryVpcRouter::try_get(prefix).await?.ok_or(Error::PrimaryVpcRouterNotExists)?.id().to_string(),
+            DescribeKind::Switch => PrimarySwitch::try_get(prefix).await?.ok_or(Error::PrimarySwitchNotExists)?.id().to_string(),
+        };
+        let value = self.kind.resource_kind().get(&id).await?;
+        if self.raw {
+            println!("{}", to_string_pretty(&value).expect("must be valid json"));
+            return Ok(());
+        }
+
+        let fields = match self.kind {
+            DescribeKind::Server => describe_server(&Server::from_value(value)?),
+            DescribeKind::Disk => describe_disk(&Disk::from_value(value)?),
+            DescribeKind::Router => describe_vpc_router(&Appliance::from_value(value)?),
+            DescribeKind::Switch => describe_switch(&Switch::from_value(value)?).await?,
+        };
+        for (label, value) in fields {
+            println!("{:<16}{}", label, value);
+        }
+        Ok(())
+    }
+}
+
+fn describe_value(value: Option<impl ToString>) -> String {
+    value.map(|value| value.to_string()).unwrap_or("-".to_string())
+}
+
+fn describe_ids<T: ToString>(ids: &[T]) -> String {
+    if ids.is_empty() {
+        return "-".to_string();
+    }
+    ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn describe_interface_switch(switch_id: Option<&SwitchId>, is_shared: bool) -> String {
+    if is_shared {
+        "shared".to_string()
+    } else {
+        describe_value(switch_id)
+    }
+}
+
+// (mac address, switch, ip address), one in a line
+fn describe_interfaces(interfaces: Vec<(Option<&str>, String, Option<Ipv4Addr>)>) -> String {
+    if interfaces.is_empty() {
+        return "-".to_string();
+    }
+    interfaces.into_iter().map(|(mac_address, switch, ip_address)| {
+        format!("{} {} {}", describe_value(mac_address), switch, describe_value(ip_address))
+    }).collect::<Vec<_>>().join(&format!("\n{:<16}", ""))
+}
+
+fn describe_server(server: &Server) -> Vec<(&'static str, String)> {
+    let plan = match server.plan_size() {
+        Some((cpu, memory_mb)) => format!("{} cpu, {} GB memory, generation {}, {}", cpu, memory_mb / 1024, describe_value(server.plan_generation()), describe_value(server.plan_commitment())),
+        None => "-".to_string(),
+    };
+    vec![
+        ("id", server.id().to_string()),
+        ("name", describe_value(server.name())),
+        ("description", describe_value(server.description())),
+        ("tags", format!("[{}]", server.tags().join(","))),
+        ("created at", describe_value(server.created_at())),
+        ("status", describe_value(server.instance_status())),
+        ("availability", describe_value(server.availability())),
+        ("plan", plan),
+        ("host name", describe_value(server.host_name())),
+        ("disks", describe_ids(&server.disk_ids())),
+        ("interfaces", describe_interfaces(server.interfaces().iter().map(|interface| {
+            (interface.mac_address(), describe_interface_switch(interface.switch_id(), interface.is_shared_switch()), interface.ip_address().or(interface.user_ip_address()))
+        }).collect())),
+        ("cdrom", describe_value(server.inserted_cdrom_id())),
+    ]
+}
+
+fn describe_disk(disk: &Disk) -> Vec<(&'static str, String)> {
+    vec![
+        ("id", disk.id().to_string()),
+        ("name", describe_value(disk.name())),
+        ("description", describe_value(disk.description())),
+        ("tags", format!("[{}]", disk.tags().join(","))),
+        ("created at", describe_value(disk.created_at())),
+        ("availability", describe_value(disk.availability())),
+        ("plan", describe_value(disk.plan_name().map(|name| name.to_string()).or(disk.plan_id().map(|id| id.to_string())))),
+        ("size", describe_value(disk.size_mb().map(|size_mb| format!("{} GB", size_mb / 1024)))),
+        ("connection", describe_value(disk.connection())),
+        ("source archive", describe_value(disk.source_archive_id())),
+        ("server", describe_value(disk.server_id())),
+    ]
+}
+
+fn describe_vpc_router(vpc_router: &Appliance) -> Vec<(&'static str, String)> {
+    vec![
+        ("id", vpc_router.id().to_string()),
+        ("name", describe_value(vpc_router.name())),
+        ("description", describe_value(vpc_router.description())),
+        ("tags", format!("[{}]", vpc_router.tags().join(","))),
+        ("created at", describe_value(vpc_router.created_at())),
+        ("status", describe_value(vpc_router.instance_status())),
+        ("availability", describe_value(vpc_router.availability())),
+        ("plan", describe_value(vpc_router.vpc_router_plan_id())),
+        ("public ip", describe_value(vpc_router.public_shared_ip().ok())),
+        ("interfaces", describe_interfaces(vpc_router.interfaces().into_iter().flatten().map(|interface| {
+            (interface.mac_address(), describe_interface_switch(interface.switch_id(), interface.is_shared_switch()), interface.ip_address().or(interface.user_ip_address()))
+        }).collect())),
+        ("firewall", describe_value(vpc_router.is_firewall_enabled().map(|enabled| if enabled { "enabled" } else { "disabled" }))),
+    ]
+}
+
+// the connected resources are not in the switch response
+async fn describe_switch(switch: &Switch) -> Result<Vec<(&'static str, String)>, Error> {
+    let servers = Switch::connected_servers(switch.id()).await?;
+    let appliances = Switch::connected_appliances(switch.id()).await?;
+    Ok(vec![
+        ("id", switch.id().to_string()),
+        ("name", describe_value(switch.name())),
+        ("description", describe_value(switch.description())),
+        ("tags", format!("[{}]", switch.tags().join(","))),
+        ("created at", describe_value(switch.created_at())),
+        ("servers", describe_ids(&servers.iter().map(|server| server.id()).collect::<Vec<_>>())),
+        ("appliances", describe_ids(&appliances.iter().map(|appliance| appliance.id()).collect::<Vec<_>>())),
+    ])
+}
+
+/// the nics of the server of the prefix
+#[derive(Debug, Parser)]
+pub struct NicCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[command(subcommand)]
+    action: NicAction,
+}
+
+/// the actions of `nic`
+#[derive(Debug, Subcommand)]
+pub enum NicAction {
+    /// the nics of the server
+    List,
+    /// the server is shut down while adding, and booted again if it was up
+    Add {
+        /// switch name
+        #[arg(long)]
+        switch: String,
+
+        /// the packet filter to attach
+        #[arg(long, value_parser = parse_id::<PacketFilterId>)]
+        packet_filter: Option<PacketFilterId>,
+
+        /// shut down the server without the confirmation
+        #[arg(long)]
+        force: bool,
+    },
+    /// the server is shut down while removing, and booted again if it was up
+    Remove {
+        /// the nic to remove
+        #[arg(long, value_parser = parse_id::<InterfaceId>)]
+        interface: InterfaceId,
+
+        /// shut down the server without the confirmation
+        #[arg(long)]
+        force: bool,
+    },
+    /// detach the packet filter if not given
+    PacketFilter {
+        /// the nic
+        #[arg(long, value_parser = parse_id::<InterfaceId>)]
+        interface: InterfaceId,
+
+        /// the packet filter to attach
+        #[arg(long, value_parser = parse_id::<PacketFilterId>)]
+        packet_filter: Option<PacketFilterId>,
+    },
+}
+
+impl NicCmd {
+    /// runs `nic`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerNotExists);
+        };
+
+        match &self.action {
+            NicAction::List => {
+                // the interfaces come with the server
+                for (i, interface) in server.interfaces().iter().enumerate() {
+                    println!("{}\t{}\t{}\t{}\t{}\t{}",
+                        i,
+                        interface.id(),
+                        interface.mac_address().unwrap_or_default(),
+                        describe_interface_switch(interface.switch_id(), interface.is_shared_switch()),
+                        describe_value(interface.ip_address().or(interface.user_ip_address())),
+                        describe_value(interface.packet_filter_id()),
+                    );
+                }
+            },
+            NicAction::Add { switch, packet_filter, force } => {
+                let Some(switch) = Switch::get_by_name(switch).await? else {
+                    return Err(Error::NicSwitchNotExists(switch.clone()));
                 };
+
+                let was_up = shut_down_server_for(server.id(), "nic adding", prefix, *force, global).await?;
+
+                log::info!("[START] nic adding...");
+                let interface = Interface::create(server.id()).await?;
+                Interface::connect_to_switch(interface.id(), switch.id()).await?;
+                if let Some(packet_filter) = packet_filter {
+                    Interface::attach_packet_filter(interface.id(), packet_filter).await?;
+                }
+                log::info!("[DONE] nic added, id: {}, connected to switch {}, ok", interface.id(), switch.id());
+
+                if was_up {
+                    boot_server_again(server.id()).await?;
+                }
+            },
+            NicAction::Remove { interface, force } => {
+                let interface_id = interface.clone();
+                let interface_ids = server.interface_ids();
+                if !interface_ids.contains(&&interface_id) {
+                    return Err(Error::NicNotOfPrimaryServer(interface_id));
+                }
+                // the first one is connected to the primary switch
+                if interface_ids.first() == Some(&&interface_id) {
+                    return Err(Error::NicFirstNotRemovable(interface_id));
+                }
+
+                let was_up = shut_down_server_for(server.id(), "nic removing", prefix, *force, global).await?;
+
+                log::info!("[START] nic removing...");
+                let interface = Interface::get(&interface_id).await?;
+                if interface.switch_id().is_some() {
+                    Interface::disconnect_from_switch(&interface_id).await?;
+                }
+                Interface::delete(&interface_id).await?;
+                log::info!("[DONE] nic removed, id: {}, ok", interface_id);
+
+                if was_up {
+                    boot_server_again(server.id()).await?;
+                }
+            },
+            NicAction::PacketFilter { interface, packet_filter } => {
+                let interface_id = interface.clone();
+                if !server.interface_ids().contains(&&interface_id) {
+                    return Err(Error::NicNotOfPrimaryServer(interface_id));
+                }
+
+                match packet_filter {
+                    Some(packet_filter) => {
+                        log::info!("[START] packet filter attaching...");
+                        Interface::attach_packet_filter(&interface_id, packet_filter).await?;
+                        log::info!("[DONE] packet filter {} attached to nic {}, ok", packet_filter, interface_id);
+                    },
+                    None => {
+                        log::info!("[START] packet filter detaching...");
+                        Interface::detach_packet_filter(&interface_id).await?;
+                        log::info!("[DONE] packet filter detached from nic {}, ok", interface_id);
+                    },
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+/// changes the plan of the server, shut down while changing
+#[derive(Debug, Parser)]
+pub struct ResizeServerCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[arg(long)]
+    cpu: u32,
+
+    #[arg(long)]
+    memory_gb: u64,
+
+    // e.g. 100 or 200, the latest one if not given
+    #[arg(long)]
+    generation: Option<u32>,
+
+    // a plan not sharing the cores with the other servers, the standard plan if not given
+    #[arg(long)]
+    dedicated_cpu: bool,
+
+    #[arg(long)]
+    force: bool,
+}
+
+impl ResizeServerCmd {
+    /// runs `resize-server`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let memory_mb = self.memory_gb * 1024;
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerNotExists);
+        };
+
+        let commitment = if self.dedicated_cpu { ServerPlanCommitment::DedicatedCpu } else { ServerPlanCommitment::Standard };
+
+        let generation_matched = self.generation.is_none_or(|generation| server.plan_generation() == Some(generation));
+        if server.plan_size() == Some((self.cpu, memory_mb)) && generation_matched && server.plan_commitment() == commitment {
+            log::info!("[CHECKED] server plan check: already {} cpus, {} GB memory, {}, ok", self.cpu, self.memory_gb, commitment);
+            return Ok(());
+        }
+        if let Some((cpu, memory_mb)) = server.plan_size() {
+            log::info!("[CHECKED] server plan check: currently {} cpus, {} GB memory, generation {:?}, {}", cpu, memory_mb / 1024, server.plan_generation(), server.plan_commitment());
+        }
+
+        let was_up = shut_down_server_for(server.id(), "resizing", prefix, self.force, global).await?;
+
+        log::info!("[START] server plan changing...");
+        let new_server = Server::change_plan(server.id(), self.cpu, memory_mb, self.generation, commitment).await?;
+        log::info!("[DONE] server plan changed, {} cpus, {} GB memory, {}, new id: {}, ok", self.cpu, self.memory_gb, commitment, new_server.id());
+
+        if was_up {
+            boot_server_again(new_server.id()).await?;
+        }
+        Ok(())
+    }
+}
+
+/// the plans to choose server.plan of the config or resize-server, e.g. an older generation is cheaper
+#[derive(Debug, Parser)]
+pub struct ServerPlansCmd {
+    #[arg(long)]
+    cpu: Option<u32>,
+
+    #[arg(long)]
+    memory_gb: Option<u64>,
+
+    #[arg(long)]
+    dedicated_cpu: bool,
+}
+
+impl ServerPlansCmd {
+    /// runs `server-plans`
+    pub async fn run(&self) -> Result<(), Error> {
+        let commitment = if self.dedicated_cpu { ServerPlanCommitment::DedicatedCpu } else { ServerPlanCommitment::Standard };
+        let mut plans = ServerPlan::search_available().await?;
+        plans.retain(|plan| {
+            self.cpu.is_none_or(|cpu| plan.cpu() == cpu)
+                && self.memory_gb.is_none_or(|memory_gb| plan.memory_mb() == memory_gb * 1024)
+                && plan.commitment() == commitment
+        });
+        plans.sort_by_key(|plan| (plan.cpu(), plan.memory_mb(), plan.generation()));
+        for plan in &plans {
+            println!("{}\t{} cpus\t{} GB\tgeneration {}\t{}",
+                plan.id(),
+                plan.cpu(),
+                plan.memory_mb() / 1024,
+                plan.generation().map(|generation| generation.to_string()).unwrap_or("-".to_string()),
+                plan.commitment(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// vnc console for rescue, e.g. when ssh is broken by the setup script
+#[derive(Debug, Parser)]
+pub struct ConsoleCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    // save the .vnc file for vnc viewers
+    #[arg(long)]
+    vnc_file: Option<PathBuf>,
+
+    // open the .vnc file with the default viewer, a temporary file is used if --vnc-file is not given
+    #[arg(long)]
+    launch: bool,
+
+    #[arg(long)]
+    ctrl_alt_delete: bool,
+}
+
+impl ConsoleCmd {
+    /// runs `console`
+    pub async fn run(&self) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerNotExists);
+        };
+
+        let status = Server::power_status(server.id()).await?;
+        log::info!("[CHECKED] server instance status: {}", status);
+
+        if self.ctrl_alt_delete {
+            log::info!("[START] ctrl-alt-delete sending...");
+            Server::send_shortcut_key(server.id(), ShortcutKey::CtrlAltDelete).await?;
+            log::info!("[DONE] ctrl-alt-delete sent, ok");
+        }
+
+        let vnc_proxy = Server::vnc_proxy(server.id()).await?;
+        println!("host: {}", vnc_proxy.host());
+        println!("port: {}", vnc_proxy.port());
+        println!("password: {}", vnc_proxy.password());
+
+        if self.vnc_file.is_none() && !self.launch {
+            return Ok(());
+        }
+        let Some(vnc_file) = vnc_proxy.vnc_file() else {
+            return Err(Error::ConsoleVncFileNotProvided);
+        };
+        let vnc_file_path = self.vnc_file.clone().unwrap_or(env::temp_dir().join(format!("{}-server.vnc", prefix)));
+        fs::write(&vnc_file_path, vnc_file).await.map_err(|e| Error::ConsoleVncFileCouldntWrite(vnc_file_path.clone(), e.to_string()))?;
+        log::info!("[DONE] vnc file saved to {}, ok", vnc_file_path.display());
+
+        if self.launch {
+            let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+            let status = Command::new(opener).arg(&vnc_file_path).status().await.map_err(|e| Error::ConsoleViewerCouldntLaunch(e.to_string()))?;
+            if !status.success() {
+                return Err(Error::ConsoleViewerCouldntLaunch(status.to_string()));
             }
-            println!("----------");
         }
+        Ok(())
+    }
+}
+
+/// journal of the server, or the setup logs written by the startup note
+#[derive(Debug, Parser)]
+pub struct LogsCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    #[command(flatten)]
+    ssh: SshArgs,
+
+    // systemd unit, all units if not given
+    #[arg(long)]
+    unit: Option<String>,
+
+    // until ctrl-c
+    #[arg(long)]
+    follow: bool,
+
+    // e.g. 1h, 30min or 2024-01-01, for the journal only
+    #[arg(long)]
+    since: Option<String>,
+
+    // setup-log.txt and setup-error.txt instead of the journal
+    #[arg(long)]
+    setup: bool,
+}
+
+impl LogsCmd {
+    /// runs `logs`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
+
+        let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? else {
+            return Err(Error::PrimaryVpcRouterNotExists);
+        };
+        let public_shared_ip = vpc_router.public_shared_ip()?;
+        let session = Session::connect(public_shared_ip, PRIMARY_SERVER_FORWARDED_PORT, &ssh_options).await?;
+
+        let result = self.stream_logs(&session).await;
+        let _ = session.close().await;
+        result
+    }
+
+    async fn stream_logs(&self, session: &Session) -> Result<(), Error> {
+        let mut output = if self.setup {
+            if self.follow {
+                session.follow_remote_files(&["setup-log.txt", "setup-error.txt"]).await?
+            } else {
+                session.stream_command(&["tail", "-n", "+1", "setup-log.txt", "setup-error.txt"]).await?
+            }
+        } else {
+            // relative times are passed as -1h, which journalctl accepts
+            let since = self.since.as_ref().map(|since| {
+                let unit = since.trim_start_matches(|c: char| c.is_ascii_digit());
+                let is_relative = unit.len() < since.len() && !unit.is_empty() && unit.chars().all(|c| c.is_ascii_alphabetic());
+                if is_relative { format!("-{}", since) } else { since.clone() }
+            });
+            let mut args = vec!["journalctl", "--no-pager"];
+            if let Some(unit) = &self.unit {
+                args.extend(["--unit", unit]);
+            }
+            if let Some(since) = &since {
+                args.extend(["--since", since]);
+            }
+            if self.follow {
+                args.push("--follow");
+            }
+            session.stream_command(&args).await?
+        };
+
+        let mut lines = BufReader::new(output.stdout()?).lines();
+        loop {
+            tokio::select! {
+                _ = signal::ctrl_c() => return Ok(()),
+                line = lines.next_line() => match line.map_err(|e| Error::SshError(e.into()))? {
+                    Some(line) => println!("{}", line),
+                    None => break,
+                },
+            }
+        }
+        output.wait().await?;
+        Ok(())
+    }
+}
+
+/// reboots the server of the prefix
+#[derive(Debug, Parser)]
+pub struct RebootCmd {
+    #[arg(long, env = "SACLOUD_SERVICE_PREFIX")]
+    prefix: String,
+
+    // upload the setup scripts again and wait for them, the same as update does
+    #[arg(long)]
+    rerun_setup: bool,
+
+    #[command(flatten)]
+    ssh: SshArgs,
+
+    #[command(flatten)]
+    scripts: ScriptsArgs,
+
+    #[arg(long)]
+    force: bool,
+}
+
+impl RebootCmd {
+    /// runs `reboot`
+    pub async fn run(&self, global: &GlobalArgs) -> Result<(), Error> {
+        let prefix = self.prefix.as_str();
+        let Some(server) = PrimaryServer::try_get(prefix).await? else {
+            return Err(Error::PrimaryServerNotExists);
+        };
+
+        if !self.rerun_setup {
+            let was_up = shut_down_server_for(server.id(), "rebooting", prefix, self.force, global).await?;
+            if !was_up {
+                log::info!("[NOTE] server was down, booting only");
+            }
+            return boot_server_again(server.id()).await;
+        }
+
+        if !self.force {
+            confirm_prefix("The server will be rebooted for running the setup script again.", prefix, global)?;
+        }
+
+        let ssh_options = ssh_options(prefix, &self.ssh, global).await?;
+        let scripts_dir = self.scripts.scripts_dir();
+        deploy(prefix, &ssh_options, scripts_dir, None, None).await
+    }
+}
+
+/// the config of --config
+#[derive(Debug, Parser)]
+pub struct ConfigCmd {
+    #[command(subcommand)]
+    action: ConfigAction,
+}
+
+/// the actions of `config`
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// check the config given by --config before a long update
+    Validate,
+}
+
+impl ConfigCmd {
+    /// runs `config`
+    pub async fn run(&self) -> Result<(), Error> {
+        match &self.action {
+            ConfigAction::Validate => {
+                let problems = CONFIG.validate();
+                for problem in &problems {
+                    log::warn!("[INVALID] {}", problem);
+                }
+                log::info!("[DONE] config validate: {} problems", problems.len());
+                if !problems.is_empty() {
+                    return Err(Error::ConfigHasProblems(problems));
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+// セットアップスクリプトのために一旦 Firewall は外す
+async fn open_firewall_for_setup(prefix: &str, vpc_router_id: &ApplianceId) -> Result<FirewallGuard, Error> {
+    log::info!("[START] vpc router config update without firewall for setup script...");
+    // tagged first, so `watch` never sees the firewall off without the tag
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    PrimaryVpcRouter::set_setup_since(vpc_router_id, Some(now)).await?;
+    PrimaryVpcRouter::update_config(prefix, vpc_router_id, false).await?;
+    Appliance::apply_config(vpc_router_id).await?;
+    log::info!("[DONE] vpc router config updated without firewall, ok");
+    Ok(FirewallGuard(prefix.to_string(), vpc_router_id.clone(), ApiEndpoint::current()?, ResourceChooser::current()))
+}
+
+// Guard で戻す
+// the endpoint and the chooser are kept because the runtime in drop is out of the scope, e.g. of the target zone of `migrate`
+struct FirewallGuard(String, ApplianceId, ApiEndpoint, Option<ResourceChooser>);
+impl Drop for FirewallGuard {
+    fn drop(&mut self) {
+        log::info!("[IMPORTANT] ensure vpc router config with firewall...");
+        let prefix = self.0.clone();
+        let vpc_router_id = self.1.clone();
+        let endpoint = self.2.clone();
+        let chooser = self.3.clone().unwrap_or_else(|| ResourceChooser::new(|_, _| None));
+        let handler = thread::spawn(move || {
+            Runtime::new().expect("[FATAL_ERROR] failed to new runtime").block_on(endpoint.scope(chooser.scope(async move {
+                PrimaryVpcRouter::update_config(&prefix, &vpc_router_id, true).await
+                    .expect("[FATAL_ERROR] failed to update vpc router config with firewall");
+                Appliance::apply_config(&vpc_router_id).await
+                    .expect("[FATAL_ERROR] failed to apply vpc router config with firewall");
+                Appliance::wait_available(&vpc_router_id).await
+                    .expect("[FATAL_ERROR] failed to wait vpc router available");
+                PrimaryVpcRouter::set_setup_since(&vpc_router_id, None).await
+                    .expect("[FATAL_ERROR] failed to untag vpc router setup");
+                log::info!("[IMPORTANT] firewall ensured");
+            })))
+        });
+        handler.join().expect("[FATAL_ERROR] failed to join handler");
+    }
+}
+
+// the scripts run on boot, so the server is restarted after uploading them
+async fn start_setup_scripts(server_id: &ServerId, public_shared_ip: Ipv4Addr, port: u16, ssh_options: &SshOptions, scripts_dir: Option<&PathBuf>) -> Result<(), Error> {
+    log::info!("[START] prepare setup script for server...");
+    ServiceScript::prepare_for_server(public_shared_ip, port, ssh_options, scripts_dir.map(PathBuf::as_path)).await?;
+    log::info!("[DONE] setup script prepared, ok");
+
+    log::info!("[START] restart server for running setup script...");
+    shut_down_server_gracefully(server_id).await?;
+    Server::ensure_up(server_id).await?;
+    log::info!("[DONE] server restarted for running setup script, ok");
+    Ok(())
+}
+
+const SETUP_WAIT_ATTEMPTS: u32 = 3;
+const SETUP_WAIT_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+// the script keeps running on the server, so a dropped ssh connection is only waited again
+async fn wait_setup_scripts(public_shared_ip: Ipv4Addr, port: u16, ssh_options: &SshOptions) -> Result<(), Error> {
+    log::info!("[START] wait for server setup script finished...");
+    let mut attempt = 1;
+    let setup_status = loop {
+        match ServiceScript::wait_for_done(public_shared_ip, port, ssh_options).await {
+            Ok(setup_status) => break setup_status,
+            Err(service_script::Error::SshError(e)) if attempt < SETUP_WAIT_ATTEMPTS && !matches!(e, ssh::Error::HostKeyMismatch(..)) => {
+                log::warn!("[RETRY] ssh failed while waiting, retrying in {} seconds...: {}", SETUP_WAIT_RETRY_INTERVAL.as_secs(), e);
+                sleep(SETUP_WAIT_RETRY_INTERVAL).await;
+                attempt += 1;
+            },
+            Err(e) => return Err(e.into()),
+        }
+    };
+    log::info!("[DONE] server setup script finished in {} seconds, ok", setup_status.duration().as_secs());
+    Ok(())
+}
+
+// returns whether the server was up
+async fn shut_down_server_for(server_id: &ServerId, purpose: &str, prefix: &str, force: bool, global: &GlobalArgs) -> Result<bool, Error> {
+    if !Server::is_up(server_id).await? {
+        return Ok(false);
+    }
+    if !force {
+        confirm_prefix(&format!("The server will be shut down for {}.", purpose), prefix, global)?;
+    }
+    shut_down_server_gracefully(server_id).await?;
+    Ok(true)
+}
+
+const GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(120);
+
+// acpi shutdown first, and the hard stop if the os doesn't go down in time
+async fn shut_down_server_gracefully(server_id: &ServerId) -> Result<(), Error> {
+    log::info!("[START] server shutdown...");
+    Server::shutdown(server_id).await?;
+    match timeout(GRACEFUL_SHUTDOWN_TIMEOUT, Server::wait_down(server_id)).await {
+        Ok(result) => {
+            result?;
+            log::info!("[DONE] server shutdown: ok");
+        },
+        Err(_) => {
+            log::warn!("[TIMEOUT] server didn't shut down in {} secs, forcing...", GRACEFUL_SHUTDOWN_TIMEOUT.as_secs());
+            Server::ensure_down(server_id).await?;
+            log::info!("[DONE] server down by force: ok");
+        },
+    }
+    Ok(())
+}
+
+async fn boot_server_again(server_id: &ServerId) -> Result<(), Error> {
+    log::info!("[START] server booting...");
+    Server::ensure_up(server_id).await?;
+    log::info!("[DONE] server booted, ok");
+    Ok(())
+}
+
+async fn read_cert_file(path: &PathBuf) -> Result<String, Error> {
+    fs::read_to_string(path).await.map_err(|e| Error::CertCouldntRead(path.clone(), e.to_string()))
+}
+
+// the start and the result of the command are posted to `notifier` in config
+async fn notified<T>(command: &str, prefix: &str, run: impl Future<Output = Result<T, Error>>) -> Result<T, Error> {
+    notify(NotifyEvent::Start, command, prefix, None).await;
+    let result = run.await;
+    match &result {
+        Ok(_) => notify(NotifyEvent::Finish, command, prefix, None).await,
+        Err(e) => notify(NotifyEvent::Fail, command, prefix, Some(e)).await,
+    }
+    result
+}
+
+// failing to notify doesn't fail the command
+async fn notify(event: NotifyEvent, command: &str, prefix: &str, error: Option<&Error>) {
+    let Some(notifier_config) = CONFIG.notifier.as_ref().filter(|notifier_config| notifier_config.notifies(event)) else {
+        return;
+    };
+    let error = error.map(|e| (e.to_string(), api::redact(&serde_json::to_value(e).expect("must be valid json"))));
+    let zone = api::zone().unwrap_or_default();
+    let payload = notifier::payload(event, command, prefix, &zone, error.as_ref().map(|(message, value)| (message.as_str(), value)));
+    if let Err(e) = notifier::post(&notifier_config.webhook_url, &payload).await {
+        log::warn!("[NOTE] couldn't notify the {} of {}: {}", event.name(), command, e);
+    }
+}
+
+// destructive commands confirm with the prefix, and refuse without --yes if nobody can answer
+fn confirm_prefix(message: &str, prefix: &str, global: &GlobalArgs) -> Result<(), Error> {
+    if global.yes {
+        log::info!("[NOTE] {} confirmed by --yes", message);
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() {
+        return Err(Error::NotInteractive);
+    }
+    println!("{} If ok, input the prefix again:", message);
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    if input.trim() != prefix {
+        return Err(Error::PrefixNotMatched);
+    }
+    Ok(())
+}
+
+// for the commands on several environments, typing all the prefixes is too much
+fn confirm_count(message: &str, count: usize, global: &GlobalArgs) -> Result<(), Error> {
+    if global.yes {
+        log::info!("[NOTE] {} confirmed by --yes", message);
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() {
+        return Err(Error::NotInteractive);
+    }
+    println!("{} If ok, input the number of them:", message);
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    if input.trim() != count.to_string() {
+        return Err(Error::PrefixNotMatched);
+    }
+    Ok(())
+}
+
+// the options of the commands logging in to the server
+#[derive(Debug, Args)]
+struct SshArgs {
+    #[arg(long)]
+    privkey: Option<PathBuf>,
+
+    // authenticate with ssh-agent, --privkey is added to the agent if given
+    #[arg(long)]
+    ssh_agent: bool,
+
+    // forget the stored host key, e.g. after the server is recreated outside of this tool
+    #[arg(long)]
+    accept_new_hostkey: bool,
+
+    // user@host[:port] of a bastion to reach the server's private ip through, instead of the vpc router's port forwarding
+    #[arg(long)]
+    jump: Option<String>,
+}
+
+// the options of the commands running the setup scripts
+#[derive(Debug, Args)]
+struct ScriptsArgs {
+    // a dir of templates overriding root-setup.zsh and user-setup.zsh, preferred to `scripts_dir` in config
+    #[arg(long)]
+    scripts_dir: Option<PathBuf>,
+}
+
+impl ScriptsArgs {
+    fn scripts_dir(&self) -> Option<&PathBuf> {
+        self.scripts_dir.as_ref().or(CONFIG.scripts_dir.as_ref())
+    }
+}
+
+// the privkey and jump options precede the config, ssh-add prompts the passphrase if the key is protected
+async fn ssh_options(prefix: &str, args: &SshArgs, global: &GlobalArgs) -> Result<SshOptions, Error> {
+    let ssh_config = CONFIG.ssh.as_ref();
+    let privkey = args.privkey.clone().or(ssh_config.and_then(|ssh_config| ssh_config.privkey.clone()));
+    let jump = args.jump.clone().or(ssh_config.and_then(|ssh_config| ssh_config.jump.clone()));
+    let accept_new_hostkey = args.accept_new_hostkey;
+
+    let auth = if args.ssh_agent {
+        let auth = SshAuth::agent()?;
+        if let Some(privkey) = &privkey {
+            log::info!("[START] adding the key to ssh-agent...");
+            ssh::add_key_to_agent(privkey).await?;
+            log::info!("[DONE] key added to ssh-agent, ok");
+        }
+        auth
+    } else {
+        SshAuth::KeyFile(privkey.unwrap_or(default_privkey_path()))
+    };
+
+    Ok(SshOptions {
+        user: ssh_config.map(|ssh_config| ssh_config.user.clone()).unwrap_or("ubuntu".to_string()),
+        auth,
+        host_alias: ssh_config.and_then(|ssh_config| ssh_config.host_alias.clone()),
+        known_hosts_path: prepare_known_hosts(prefix, accept_new_hostkey).await?,
+        control_dir: prepare_control_dir(prefix, accept_new_hostkey, !global.no_reuse).await?,
+        jump: match jump {
+            // the private ip is of the current color, see `update --replace-server`
+            Some(host) => Some(SshJump { host, private_ip: service_env::active_server_ip(prefix).await?, private_port: 22 }),
+            None => None,
+        },
+    })
+}
+
+// the given ids first, then the answer on the terminal, never guessed with --yes or without a terminal
+fn resource_chooser(global: &GlobalArgs) -> ResourceChooser {
+    let ids = global.ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+    let yes = global.yes;
+    ResourceChooser::new(move |resource_name, candidates| {
+        let candidate_ids = candidates.iter().map(|candidate| match &candidate["ID"] {
+            Value::String(id) => id.clone(),
+            id => id.to_string(),
+        }).collect::<Vec<_>>();
+        if let Some(i) = candidate_ids.iter().position(|id| ids.contains(id)) {
+            log::info!("[NOTE] {} {} chosen by --id", resource_name, candidate_ids[i]);
+            return Some(i);
+        }
+        if yes || !io::stdin().is_terminal() {
+            return None;
+        }
+        println!("The {} below are found for one:", resource_name);
+        for (i, candidate) in candidates.iter().enumerate() {
+            println!("  [{}] {} {} created at {} tags: {}", i + 1, candidate_ids[i], candidate["Name"].as_str().unwrap_or_default(), candidate["CreatedAt"].as_str().unwrap_or("-"), candidate["Tags"]);
+        }
+        println!("Input the number to use, or empty to abort:");
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).ok()?;
+        input.trim().parse::<usize>().ok().filter(|n| (1..=candidates.len()).contains(n)).map(|n| n - 1)
+    })
+}
+
+fn control_dir_path(prefix: &str) -> PathBuf {
+    home_dir().expect("home dir is prerequisite").join(".sacloud-random-tools/control").join(prefix)
+}
+
+// the master connection verified the old host key, so it's closed together,
+// the commands share a master connection per prefix unless reuse is disabled
+async fn prepare_control_dir(prefix: &str, accept_new_hostkey: bool, reuse: bool) -> Result<Option<PathBuf>, Error> {
+    let path = control_dir_path(prefix);
+    if accept_new_hostkey {
+        ssh::forget_master_connection(&path).await?;
+    }
+    if !reuse {
+        return Ok(None);
+    }
+    fs::create_dir_all(&path).await.map_err(|e| Error::ControlDirCouldntCreate(path.clone(), e.to_string()))?;
+    Ok(Some(path))
+}
+
+// one known_hosts file per prefix, because the vpc router's ip may be reused by another environment
+fn known_hosts_path(prefix: &str) -> PathBuf {
+    home_dir().expect("home dir is prerequisite").join(".sacloud-random-tools/known_hosts").join(prefix)
+}
+
+async fn prepare_known_hosts(prefix: &str, accept_new_hostkey: bool) -> Result<PathBuf, Error> {
+    let path = known_hosts_path(prefix);
+    if accept_new_hostkey {
+        forget_host_key(prefix).await?;
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await.map_err(|e| Error::KnownHostsCouldntUpdate(path.clone(), e.to_string()))?;
+    }
+    Ok(path)
+}
+
+// the host key is generated with the disk, so it must be forgotten when the disk is created or deleted
+async fn forget_host_key(prefix: &str) -> Result<(), Error> {
+    let path = known_hosts_path(prefix);
+    match fs::remove_file(&path).await {
+        Ok(_) => {
+            log::info!("[NOTE] stored host key forgotten: {}", path.display());
+            Ok(())
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(Error::KnownHostsCouldntUpdate(path, e.to_string())),
+    }
+}
+
+const DEFAULT_KEY_NAMES: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+
+// the first existing key in ~/.ssh, the generated key next, and ~/.ssh/id_rsa if none
+fn default_privkey_path() -> PathBuf {
+    let ssh_dir = home_dir().expect("home dir is prerequisite").join(".ssh");
+    if let Some(path) = DEFAULT_KEY_NAMES.into_iter().map(|name| ssh_dir.join(name)).find(|path| path.exists()) {
+        return path;
+    }
+    let generated_key_path = generated_key_path();
+    if generated_key_path.exists() {
+        return generated_key_path;
+    }
+    ssh_dir.join("id_rsa")
+}
+
+// a path unless it looks like a key, e.g. "ssh-ed25519 AAAA... alice@example.com"
+async fn read_extra_pubkey(extra_pubkey: &str) -> Result<String, Error> {
+    if extra_pubkey.starts_with("ssh-") || extra_pubkey.starts_with("ecdsa-") || extra_pubkey.starts_with("sk-") {
+        return Ok(extra_pubkey.to_string());
+    }
+    let path = PathBuf::from(extra_pubkey);
+    fs::read_to_string(&path).await.map_err(|e| Error::PrimarySshPublicKeyGivenButCouldntRead(path, e.to_string()))
+}
+
+fn default_pubkey_path() -> PathBuf {
+    let mut path = default_privkey_path().into_os_string();
+    path.push(".pub");
+    PathBuf::from(path)
+}
+
+// made by --generate-key, not to touch ~/.ssh
+fn generated_key_path() -> PathBuf {
+    home_dir().expect("home dir is prerequisite").join(".sacloud-random-tools/keys/id_ed25519")
+}
+
+// only if no key is given nor found, --generate-key or the answer on the terminal generates one
+async fn ensure_ssh_key(pubkey: &Option<PathBuf>, generate_key: bool) -> Result<(), Error> {
+    if pubkey.is_some() || CONFIG.ssh.as_ref().is_some_and(|ssh_config| ssh_config.pubkey.is_some()) || default_pubkey_path().exists() {
+        return Ok(());
+    }
+    if !generate_key {
+        if !io::stdin().is_terminal() {
+            return Ok(());
+        }
+        println!("No ssh key found in ~/.ssh. Generate an ed25519 key pair in {}? [y/N]", generated_key_path().display());
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+        if !matches!(input.trim(), "y" | "Y" | "yes") {
+            return Ok(());
+        }
+    }
+    let path = generated_key_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await.map_err(|e| Error::SshKeyCouldntGenerate(path.clone(), e.to_string()))?;
     }
+    log::info!("[START] ssh key generation: {}...", path.display());
+    ssh::generate_key(&path, "sacloud-random-tools").await?;
+    log::info!("[DONE] ssh key generated: {}, ok", path.display());
     Ok(())
 }
 
-pub(crate) async fn show_env(prefix: impl AsRef<str>) -> Result<(), Error> {
+/* TODO remove old code
+pub async fn show_env(prefix: impl AsRef<str>) -> Result<(), Error> {
     let prefix = prefix.as_ref();
     match search_ssh_public_key(prefix).await {
         Ok((key_id, key)) => {
@@ -637,4 +5822,3 @@ pub(crate) async fn show_env(prefix: impl AsRef<str>) -> Result<(), Error> {
     Ok(())
 }
 */
-
@@ -0,0 +1,118 @@
+//! Uploads and downloads of the images through the ftps servers the archives and the cdroms open, see [`FtpServer`].
+
+use std::path::Path;
+use serde::Serialize;
+use suppaftp::{
+    async_native_tls::TlsConnector,
+    tokio::{AsyncNativeTlsConnector, AsyncNativeTlsFtpStream},
+    types::FileType,
+};
+use tokio::{fs, io::{AsyncReadExt, AsyncWriteExt}};
+
+use crate::api::FtpServer;
+
+/// errors of the transfers, each with the host name of the ftp server or the local path, and the cause
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+pub enum Error {
+    /// the tls handshake or the binary mode failed too
+    #[error("couldn't connect to {0}: {1}")]
+    ConnectionFailed(String, String),
+    /// the user and the password of the ftp server were refused
+    #[error("couldn't log in to {0}: {1}")]
+    LoginFailed(String, String),
+    /// the local file to upload couldn't be opened or read
+    #[error("couldn't open {0}: {1}")]
+    CouldntOpenSource(String, String),
+    /// the transfer to the ftp server broke off
+    #[error("couldn't upload to {0}: {1}")]
+    UploadFailed(String, String),
+    /// the local file to download into couldn't be created or written
+    #[error("couldn't create {0}: {1}")]
+    CouldntCreateDestination(String, String),
+    /// the ftp server didn't serve exactly one file, or the transfer broke off
+    #[error("couldn't download from {0}: {1}")]
+    DownloadFailed(String, String),
+}
+
+// the progress is reported per chunk
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// the file name on the ftp server doesn't matter, the uploaded file becomes the image
+pub async fn upload_file(ftp_server: &FtpServer, local_path: impl AsRef<Path>) -> Result<u64, Error> {
+    upload_file_with_progress(ftp_server, local_path, |_, _| {}).await
+}
+
+/// on_progress gets the bytes sent so far and the file size
+pub async fn upload_file_with_progress(ftp_server: &FtpServer, local_path: impl AsRef<Path>, mut on_progress: impl FnMut(u64, Option<u64>)) -> Result<u64, Error> {
+    let local_path = local_path.as_ref();
+    let host_name = ftp_server.host_name();
+    let mut file = fs::File::open(local_path).await.map_err(|e| Error::CouldntOpenSource(local_path.display().to_string(), e.to_string()))?;
+    let file_size = file.metadata().await.ok().map(|metadata| metadata.len());
+    let mut stream = connect(ftp_server).await?;
+
+    let file_name = local_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or("image.iso".to_string());
+    log::trace!("[FTPS] uploading...: {}", file_name);
+    let mut upload = stream.put_with_stream(&file_name).await.map_err(|e| Error::UploadFailed(host_name.to_string(), e.to_string()))?;
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut size = 0;
+    loop {
+        let len = file.read(&mut buf).await.map_err(|e| Error::CouldntOpenSource(local_path.display().to_string(), e.to_string()))?;
+        if len == 0 {
+            break;
+        }
+        upload.write_all(&buf[..len]).await.map_err(|e| Error::UploadFailed(host_name.to_string(), e.to_string()))?;
+        size += len as u64;
+        on_progress(size, file_size);
+    }
+    upload.finish().await.map_err(|e| Error::UploadFailed(host_name.to_string(), e.to_string()))?;
+    let _ = stream.quit().await;
+    Ok(size)
+}
+
+/// the ftp server of an archive serves its image as the only file
+pub async fn download_file(ftp_server: &FtpServer, local_path: impl AsRef<Path>) -> Result<u64, Error> {
+    download_file_with_progress(ftp_server, local_path, |_, _| {}).await
+}
+
+/// on_progress gets the bytes received so far and the image size if the server tells it
+pub async fn download_file_with_progress(ftp_server: &FtpServer, local_path: impl AsRef<Path>, mut on_progress: impl FnMut(u64, Option<u64>)) -> Result<u64, Error> {
+    let local_path = local_path.as_ref();
+    let host_name = ftp_server.host_name();
+    let mut stream = connect(ftp_server).await?;
+
+    let file_names = stream.nlst(None).await.map_err(|e| Error::DownloadFailed(host_name.to_string(), e.to_string()))?;
+    let [file_name] = &file_names[..] else {
+        return Err(Error::DownloadFailed(host_name.to_string(), format!("expected one file, found {:?}", file_names)));
+    };
+    let file_size = stream.size(file_name).await.ok().map(|size| size as u64);
+    let mut file = fs::File::create(local_path).await.map_err(|e| Error::CouldntCreateDestination(local_path.display().to_string(), e.to_string()))?;
+
+    log::trace!("[FTPS] downloading...: {}", file_name);
+    let mut download = stream.retr_as_stream(file_name).await.map_err(|e| Error::DownloadFailed(host_name.to_string(), e.to_string()))?;
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut size = 0;
+    loop {
+        let len = download.read(&mut buf).await.map_err(|e| Error::DownloadFailed(host_name.to_string(), e.to_string()))?;
+        if len == 0 {
+            break;
+        }
+        file.write_all(&buf[..len]).await.map_err(|e| Error::CouldntCreateDestination(local_path.display().to_string(), e.to_string()))?;
+        size += len as u64;
+        on_progress(size, file_size);
+    }
+    download.finish().await.map_err(|e| Error::DownloadFailed(host_name.to_string(), e.to_string()))?;
+    file.flush().await.map_err(|e| Error::CouldntCreateDestination(local_path.display().to_string(), e.to_string()))?;
+    let _ = stream.quit().await;
+    Ok(size)
+}
+
+async fn connect(ftp_server: &FtpServer) -> Result<AsyncNativeTlsFtpStream, Error> {
+    let host_name = ftp_server.host_name();
+    log::trace!("[FTPS] connecting...: {}", host_name);
+    let stream = AsyncNativeTlsFtpStream::connect((ftp_server.ip_address(), 21)).await.map_err(|e| Error::ConnectionFailed(host_name.to_string(), e.to_string()))?;
+    let connector = AsyncNativeTlsConnector::from(TlsConnector::new());
+    let mut stream = stream.into_secure(connector, host_name).await.map_err(|e| Error::ConnectionFailed(host_name.to_string(), e.to_string()))?;
+    stream.login(ftp_server.user(), ftp_server.password()).await.map_err(|e| Error::LoginFailed(host_name.to_string(), e.to_string()))?;
+    stream.transfer_type(FileType::Binary).await.map_err(|e| Error::ConnectionFailed(host_name.to_string(), e.to_string()))?;
+    Ok(stream)
+}
@@ -0,0 +1,27 @@
+//! Provisioning of a small personal server environment on Sakura Cloud.
+//!
+//! The `sacloud-random-tools` binary is a thin wrapper of [`cmd::Cmd`], and the modules below can be used
+//! directly to embed the same logic into other programs. The config is loaded once per process with
+//! [`service_env::load_config`], see `config/config.example.json` for the format.
+//!
+//! - [`api`]: the Sakura Cloud api, authenticated with `SACLOUD_ACCESS_TOKEN` and `SACLOUD_SECRET_TOKEN` in `SACLOUD_ZONE`
+//! - [`service_env`]: the config and the resources of the environment named by a prefix
+//! - [`service_script`]: the setup scripts rendered, uploaded and run on the server
+//! - [`ssh`]: ssh sessions, port forwarding and file transfer to the server
+//! - [`object_storage`]: the s3 compatible bucket of `backup --to-bucket`
+//! - [`ftps`]: the ftps upload and download of the archive images
+//! - [`notifier`]: webhook notifications of `update` and `clean`
+//! - [`prometheus`]: the gauges of `serve-metrics` and their http listener
+//! - [`cmd`]: the subcommands of the binary
+
+#![warn(missing_docs)]
+
+pub mod cmd;
+pub mod api;
+pub mod ssh;
+pub mod object_storage;
+pub mod ftps;
+pub mod notifier;
+pub mod prometheus;
+pub mod service_env;
+pub mod service_script;
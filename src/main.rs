@@ -1,34 +1,124 @@
-use std::env;
-use clap::Parser;
+use std::{env, error::Error, fs::File, io::Write, path::PathBuf, process::ExitCode, sync::Mutex, time::{SystemTime, UNIX_EPOCH}};
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
 use serde_json::to_string_pretty;
 
-mod cmd;
-mod api;
-mod ssh;
-mod service_env;
-mod service_script;
+use sacloud_random_tools::{api, cmd, service_env};
 
 #[derive(Debug, Parser)]
 struct Args {
+    // config/config.json in the current dir if not given
+    #[arg(long, global = true, env = "SACLOUD_CONFIG")]
+    config: Option<PathBuf>,
+
+    // json prints the whole error including the api responses
+    #[arg(long, global = true, value_enum, default_value_t = ErrorFormat::Text)]
+    error_format: ErrorFormat,
+
+    #[command(flatten)]
+    global: cmd::GlobalArgs,
+
+    // the trace logs of this tool are appended besides stderr, with the passwords and the keys redacted to share
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
+
     #[command(subcommand)]
     cmd: cmd::Cmd,
 }
 
-#[tokio::main]
-async fn main() {
-    if env::var(env_logger::DEFAULT_FILTER_ENV).is_ok() {
-        env_logger::init();
-    } else {
-        env_logger::builder().filter_level(log::LevelFilter::Info).init();
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ErrorFormat {
+    Text,
+    Json,
+}
+
+impl ErrorFormat {
+    fn log(&self, kind: &str, e: &(impl Error + Serialize)) {
+        match self {
+            ErrorFormat::Text => {
+                let mut message = e.to_string();
+                let mut source = e.source();
+                while let Some(e) = source {
+                    message.push_str(&format!("\n  caused by: {}", e));
+                    source = e.source();
+                }
+                log::error!("{}: {}", kind, message);
+            },
+            // Print the error message as json, so as to show what happens in API
+            ErrorFormat::Json => log::error!("{}: {}", kind, to_string_pretty(&api::redact(&serde_json::to_value(e).unwrap())).unwrap()),
+        }
+    }
+}
+
+// stderr as env_logger is configured, and the file with all levels of this tool
+struct TeeLogger {
+    stderr: env_logger::Logger,
+    file: Mutex<File>,
+}
+
+impl TeeLogger {
+    fn is_own(target: &str) -> bool {
+        target.starts_with(env!("CARGO_CRATE_NAME"))
+    }
+}
+
+impl log::Log for TeeLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.stderr.enabled(metadata) || Self::is_own(metadata.target())
     }
 
+    fn log(&self, record: &log::Record) {
+        if self.stderr.matches(record) {
+            self.stderr.log(record);
+        }
+        if Self::is_own(record.target()) {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+            let mut file = self.file.lock().unwrap();
+            let _ = writeln!(file, "[{}.{:03} {} {}] {}", now.as_secs(), now.subsec_millis(), record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {
+        self.stderr.flush();
+        let _ = self.file.lock().unwrap().flush();
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
     let args = Args::parse();
-    match args.cmd.run().await {
-        Ok(_) => {},
+
+    let mut logger_builder = env_logger::builder();
+    if env::var(env_logger::DEFAULT_FILTER_ENV).is_err() {
+        logger_builder.filter_level(log::LevelFilter::Info);
+    }
+    match &args.log_file {
+        None => logger_builder.init(),
+        Some(log_file) => {
+            let file = match File::options().create(true).append(true).open(log_file) {
+                Ok(file) => file,
+                Err(e) => {
+                    eprintln!("couldn't open the log file {}: {}", log_file.display(), e);
+                    return ExitCode::FAILURE;
+                },
+            };
+            log::set_boxed_logger(Box::new(TeeLogger { stderr: logger_builder.build(), file: Mutex::new(file) })).expect("logger must be set once");
+            log::set_max_level(log::LevelFilter::Trace);
+        },
+    }
+
+    if let Err(e) = service_env::load_config(args.config.as_deref()) {
+        args.error_format.log("Config Error", &e);
+        return ExitCode::FAILURE;
+    }
+
+    // see `ErrorCategory` for the codes
+    match args.cmd.run(&args.global).await {
+        Ok(_) => ExitCode::SUCCESS,
         Err(e) => {
-            // Print the error message as json, so as to show what happens in API
-            log::error!("Uncaught Error: {}", to_string_pretty(&e).unwrap());
-        }
+            args.error_format.log("Uncaught Error", &e);
+            ExitCode::from(e.category().exit_code())
+        },
     }
 }
 
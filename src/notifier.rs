@@ -0,0 +1,94 @@
+//! Webhook notifications of the long running commands, e.g. `update` kicked off from ci.
+//!
+//! The payload has `text` for slack incoming webhooks, and the other fields for the other receivers.
+
+use serde::{Serialize, Deserialize};
+use serde_json::{json, to_string_pretty, Value};
+
+/// errors of posting to the webhook, the command itself isn't failed by them
+#[derive(Debug, Serialize, thiserror::Error)]
+pub enum Error {
+    /// the webhook couldn't be reached
+    #[error("couldn't post to the webhook: {0}")]
+    PostFailed(String),
+    /// the status code and the body of the response
+    #[error("the webhook responded {0}: {1}")]
+    UnexpectedStatus(u16, String),
+}
+
+/// when the webhook is posted, `notifier.events` in config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotifyEvent {
+    /// before the command does anything
+    #[serde(rename = "start")]
+    Start,
+
+    /// after the command succeeded
+    #[serde(rename = "finish")]
+    Finish,
+
+    /// after the command failed, with the error
+    #[serde(rename = "fail")]
+    Fail,
+}
+
+impl NotifyEvent {
+    /// the name in config and in the payload
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Finish => "finish",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+/// the error is the message and the json of `--error-format json`, which has the api responses
+pub fn payload(event: NotifyEvent, command: &str, prefix: &str, zone: &str, error: Option<(&str, &Value)>) -> Value {
+    let mut text = match event {
+        NotifyEvent::Start => format!("`{}` of {} in {} started", command, prefix, zone),
+        NotifyEvent::Finish => format!("`{}` of {} in {} finished", command, prefix, zone),
+        NotifyEvent::Fail => format!("`{}` of {} in {} failed", command, prefix, zone),
+    };
+    if let Some((message, error)) = error {
+        text.push_str(&format!(": {}\n```\n{}\n```", message, to_string_pretty(error).expect("must be valid json")));
+    }
+    json!({
+        "text": text,
+        "event": event.name(),
+        "command": command,
+        "prefix": prefix,
+        "zone": zone,
+        "error": error.map(|(_, error)| error),
+    })
+}
+
+/// posts the payload as json, any 2xx is a success
+pub async fn post(webhook_url: &str, payload: &Value) -> Result<(), Error> {
+    log::trace!("[WEBHOOK] posting...: {}", payload);
+    let res = reqwest::Client::new().post(webhook_url).json(payload).send().await.map_err(|e| Error::PostFailed(e.to_string()))?;
+    let status = res.status();
+    if !status.is_success() {
+        let body = res.text().await.unwrap_or_default();
+        return Err(Error::UnexpectedStatus(status.as_u16(), body));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_text() {
+        let started = payload(NotifyEvent::Start, "update", "dev", "is1a", None);
+        assert_eq!(started["text"], "`update` of dev in is1a started");
+        assert_eq!(started["event"], "start");
+        assert_eq!(started["error"], Value::Null);
+
+        let error = json!({ "ApiError": { "ApiConflict": ["server/1/power", "busy"] } });
+        let failed = payload(NotifyEvent::Fail, "clean", "dev", "is1a", Some(("api call failed", &error)));
+        assert!(failed["text"].as_str().unwrap().starts_with("`clean` of dev in is1a failed: api call failed\n```\n{"), "{}", failed["text"]);
+        assert_eq!(failed["error"], error);
+    }
+}
@@ -0,0 +1,286 @@
+//! The s3 compatible object storage of `backup --to-bucket`, the requests are signed with the aws signature v4 here.
+
+use std::{fmt::Display, future::Future, time::{SystemTime, UNIX_EPOCH}};
+use hmac::{Hmac, Mac};
+use sha2::{Sha256, Digest};
+use reqwest::{Method, StatusCode};
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use url::Url;
+
+use crate::service_env::ObjectStorageConfig;
+
+// the minimum part size of multipart upload is 5MiB except the last part
+const PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// errors of the requests to the bucket, each with the object key or the endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+pub enum Error {
+    /// `object_storage.endpoint` in config is not a url
+    #[error("invalid endpoint: {0}")]
+    InvalidEndpoint(String),
+    /// the request couldn't be sent, e.g. the endpoint is unreachable
+    #[error("{0}: request failed: {1}")]
+    RequestFailed(String, String),
+    /// the key, the status code and the body of the response
+    #[error("{0}: status {1}: {2}")]
+    ResponseFailed(String, u16, String),
+    /// the response of starting a multipart upload had no UploadId
+    #[error("{0}: no upload id in the response")]
+    ResponseWithoutUploadId(String),
+    /// the response of a part upload had no ETag, which completing the upload needs
+    #[error("{0}: no etag in the response")]
+    ResponseWithoutEtag(String),
+    /// the local data to upload couldn't be read
+    #[error("couldn't read the source: {0}")]
+    CouldntReadSource(String),
+    /// the key and the error of the source after its data ended, e.g. the process writing it exited with an error
+    #[error("{0}: the source failed: {1}")]
+    SourceFailed(String, String),
+}
+
+/// a bucket of the object storage with the keys to sign the requests
+pub struct Bucket {
+    endpoint: Url,
+    region: String,
+    name: String,
+    access_key_id: String,
+    secret_access_key: String,
+    client: reqwest::Client,
+}
+
+impl Bucket {
+    /// the endpoint, the region, the bucket and the keys of `object_storage` in config
+    pub fn from_config(config: &ObjectStorageConfig) -> Result<Self, Error> {
+        let endpoint = Url::parse(&config.endpoint).map_err(|e| Error::InvalidEndpoint(e.to_string()))?;
+        Ok(Self {
+            endpoint,
+            region: config.region.clone(),
+            name: config.bucket.clone(),
+            access_key_id: config.access_key_id.clone(),
+            secret_access_key: config.secret_access_key.clone(),
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// the bucket name, e.g. to show where the backup went
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// a single put of the whole body, see `upload_stream` for the large ones
+    pub async fn put_object(&self, key: impl AsRef<str>, body: Vec<u8>) -> Result<(), Error> {
+        let key = key.as_ref();
+        self.request(Method::PUT, key, &[], body).await?;
+        Ok(())
+    }
+
+    /// upload without knowing the size, a multipart upload is used if the data is larger than a part
+    /// the object is only put or completed after source_done succeeds, so a source failing halfway leaves no truncated object
+    pub async fn upload_stream<E: Display>(&self, key: impl AsRef<str>, mut reader: impl AsyncRead + Unpin, source_done: impl Future<Output = Result<(), E>>) -> Result<u64, Error> {
+        let key = key.as_ref();
+        let source_done = async { source_done.await.map_err(|e| Error::SourceFailed(key.to_string(), e.to_string())) };
+
+        let first_part = read_part(&mut reader).await?;
+        if first_part.len() < PART_SIZE {
+            let size = first_part.len() as u64;
+            source_done.await?;
+            self.put_object(key, first_part).await?;
+            return Ok(size);
+        }
+
+        log::trace!("[OBJECT_STORAGE] starting multipart upload...: {}", key);
+        let res = self.request(Method::POST, key, &[("uploads", "")], Vec::new()).await?;
+        let upload_id = xml_element(&res, "UploadId").ok_or(Error::ResponseWithoutUploadId(res.clone()))?;
+
+        let result = self.upload_parts(key, &upload_id, first_part, &mut reader, source_done).await;
+        if result.is_err() {
+            log::trace!("[OBJECT_STORAGE] aborting multipart upload...: {}", key);
+            let _ = self.request(Method::DELETE, key, &[("uploadId", &upload_id)], Vec::new()).await;
+        }
+        result
+    }
+
+    async fn upload_parts(&self, key: &str, upload_id: &str, first_part: Vec<u8>, reader: &mut (impl AsyncRead + Unpin), source_done: impl Future<Output = Result<(), Error>>) -> Result<u64, Error> {
+        let mut etags = Vec::new();
+        let mut size = 0;
+        let mut part = first_part;
+        while !part.is_empty() {
+            let part_number = (etags.len() + 1).to_string();
+            size += part.len() as u64;
+            let etag = self.request_etag(Method::PUT, key, &[("partNumber", &part_number), ("uploadId", upload_id)], part).await?;
+            log::trace!("[OBJECT_STORAGE] uploaded part {}, total {} bytes", part_number, size);
+            etags.push(etag);
+            part = read_part(reader).await?;
+        }
+        // aborted by the caller, the parts are never visible
+        source_done.await?;
+
+        let mut body = String::from("<CompleteMultipartUpload>");
+        for (i, etag) in etags.iter().enumerate() {
+            body.push_str(&format!("<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>", i + 1, etag));
+        }
+        body.push_str("</CompleteMultipartUpload>");
+
+        // complete may fail with 200 status, so check the body
+        let res = self.request(Method::POST, key, &[("uploadId", upload_id)], body.into_bytes()).await?;
+        if xml_element(&res, "Code").is_some() {
+            return Err(Error::ResponseFailed(key.to_string(), StatusCode::OK.as_u16(), res));
+        }
+        Ok(size)
+    }
+
+    async fn request(&self, method: Method, key: &str, query: &[(&str, &str)], body: Vec<u8>) -> Result<String, Error> {
+        let res = self.send(method, key, query, body).await?;
+        res.text().await.map_err(|e| Error::RequestFailed(key.to_string(), e.to_string()))
+    }
+
+    async fn request_etag(&self, method: Method, key: &str, query: &[(&str, &str)], body: Vec<u8>) -> Result<String, Error> {
+        let res = self.send(method, key, query, body).await?;
+        let etag = res.headers().get("ETag").and_then(|etag| etag.to_str().ok()).map(|etag| etag.to_string());
+        etag.ok_or(Error::ResponseWithoutEtag(key.to_string()))
+    }
+
+    async fn send(&self, method: Method, key: &str, query: &[(&str, &str)], body: Vec<u8>) -> Result<reqwest::Response, Error> {
+        // path style, because bucket names may contain dots
+        let path = format!("/{}/{}", uri_encode(&self.name, true), uri_encode(key, false));
+        let mut query = query.iter().map(|(k, v)| (uri_encode(k, true), uri_encode(v, true))).collect::<Vec<_>>();
+        query.sort();
+        let query = query.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join("&");
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+        url.set_query(if query.is_empty() { None } else { Some(&query) });
+
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{}:{}", self.endpoint.host_str().unwrap_or_default(), port),
+            None => self.endpoint.host_str().unwrap_or_default().to_string(),
+        };
+        let (date, date_time) = amz_date(SystemTime::now());
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let canonical_request = format!("{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method, path, query, host, payload_hash, date_time, payload_hash);
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", date_time, scope, hex::encode(Sha256::digest(canonical_request.as_bytes())));
+
+        let signing_key = [self.region.as_str(), "s3", "aws4_request"].iter().fold(
+            hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), date.as_bytes()),
+            |key, data| hmac_sha256(&key, data.as_bytes()),
+        );
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+        let authorization = format!("AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.access_key_id, scope, signature);
+
+        log::trace!("[OBJECT_STORAGE] {} {}", method, url);
+        let res = self.client.request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", date_time)
+            .header("Authorization", authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| Error::RequestFailed(key.to_string(), e.to_string()))?;
+
+        let status = res.status();
+        if !status.is_success() {
+            let text = res.text().await.unwrap_or_default();
+            return Err(Error::ResponseFailed(key.to_string(), status.as_u16(), text));
+        }
+        Ok(res)
+    }
+}
+
+/// e.g. 20240101T000000Z, also used for backup names
+pub fn utc_timestamp() -> String {
+    amz_date(SystemTime::now()).1
+}
+
+async fn read_part(reader: &mut (impl AsyncRead + Unpin)) -> Result<Vec<u8>, Error> {
+    let mut part = Vec::with_capacity(PART_SIZE);
+    while part.len() < PART_SIZE {
+        let n = (&mut *reader).take((PART_SIZE - part.len()) as u64).read_to_end(&mut part).await.map_err(|e| Error::CouldntReadSource(e.to_string()))?;
+        if n == 0 {
+            break;
+        }
+    }
+    Ok(part)
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+    let mut encoded = String::new();
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(b as char),
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+// (yyyymmdd, yyyymmddThhmmssZ) in UTC
+fn amz_date(time: SystemTime) -> (String, String) {
+    let secs = time.duration_since(UNIX_EPOCH).expect("after epoch").as_secs();
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+
+    // civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let date = format!("{:04}{:02}{:02}", year, month, day);
+    let date_time = format!("{}T{:02}{:02}{:02}Z", date, secs_of_day / 3600, secs_of_day % 3600 / 60, secs_of_day % 60);
+    (date, date_time)
+}
+
+fn xml_element(xml: &str, name: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = start + xml[start..].find(&format!("</{}>", name))?;
+    Some(xml[start..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn signing_parts() {
+        assert_eq!(amz_date(UNIX_EPOCH), ("19700101".to_string(), "19700101T000000Z".to_string()));
+        assert_eq!(amz_date(UNIX_EPOCH + Duration::from_secs(1709251199)), ("20240229".to_string(), "20240229T235959Z".to_string()));
+
+        assert_eq!(uri_encode("backup/my dir+1.tar.gz", false), "backup/my%20dir%2B1.tar.gz");
+        assert_eq!(uri_encode("a/b", true), "a%2Fb");
+
+        assert_eq!(xml_element("<R><UploadId>ID</UploadId></R>", "UploadId"), Some("ID".to_string()));
+        assert_eq!(xml_element("<R></R>", "UploadId"), None);
+    }
+
+    #[tokio::test]
+    async fn failed_source_puts_nothing() {
+        // nothing listens on the port, so any request would fail with RequestFailed
+        let bucket = Bucket {
+            endpoint: Url::parse("http://127.0.0.1:9").unwrap(),
+            region: "jp-north-1".to_string(),
+            name: "bucket".to_string(),
+            access_key_id: "id".to_string(),
+            secret_access_key: "secret".to_string(),
+            client: reqwest::Client::new(),
+        };
+        let result = bucket.upload_stream("dev/var.tar.gz", &b"truncated"[..], async { Err("exit status: 2") }).await;
+        assert_eq!(result, Err(Error::SourceFailed("dev/var.tar.gz".to_string(), "exit status: 2".to_string())));
+    }
+}
@@ -0,0 +1,138 @@
+//! Gauges in the prometheus text format, served on `/metrics` by a minimal http listener.
+
+use std::{collections::BTreeMap, sync::{Arc, Mutex}};
+use serde::Serialize;
+use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::{TcpListener, TcpStream}};
+
+/// errors of the listener, the scrapes themselves are served best effort
+#[derive(Debug, Serialize, thiserror::Error)]
+pub enum Error {
+    /// the address and the cause, e.g. already in use
+    #[error("couldn't listen on {0}: {1}")]
+    CouldntListen(String, String),
+}
+
+/// name -> (help, samples), rendered in the name order
+#[derive(Debug, Clone, Default)]
+pub struct Gauges {
+    families: BTreeMap<String, (String, Vec<(String, f64)>)>,
+}
+
+impl Gauges {
+    /// no gauges yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds a sample to the gauge of the name, the help of the first sample is kept
+    pub fn set(&mut self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        let labels = labels.iter().map(|(key, value)| format!("{}=\"{}\"", key, escape_label_value(value))).collect::<Vec<_>>().join(",");
+        let (_, samples) = self.families.entry(name.to_string()).or_insert_with(|| (help.to_string(), Vec::new()));
+        samples.push((labels, value));
+    }
+
+    /// the text format of all the gauges, `# HELP` and `# TYPE` before the samples of each
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        for (name, (help, samples)) in &self.families {
+            text.push_str(&format!("# HELP {} {}\n", name, help));
+            text.push_str(&format!("# TYPE {} gauge\n", name));
+            for (labels, value) in samples {
+                if labels.is_empty() {
+                    text.push_str(&format!("{} {}\n", name, value));
+                } else {
+                    text.push_str(&format!("{}{{{}}} {}\n", name, labels, value));
+                }
+            }
+        }
+        text
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// host:port, :port listens on all interfaces
+pub async fn listen(address: &str) -> Result<TcpListener, Error> {
+    let address = if address.starts_with(':') { format!("0.0.0.0{}", address) } else { address.to_string() };
+    TcpListener::bind(&address).await.map_err(|e| Error::CouldntListen(address.clone(), e.to_string()))
+}
+
+/// the latest rendered text is served as is, the caller replaces it on each poll
+pub async fn serve(listener: TcpListener, metrics: Arc<Mutex<String>>) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                log::warn!("[METRICS] accept failed: {}", e);
+                continue;
+            },
+        };
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &metrics).await {
+                log::debug!("[METRICS] response to {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream, metrics: &Mutex<String>) -> std::io::Result<()> {
+    // only the request line matters
+    let mut buf = [0u8; 1024];
+    let len = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..len]);
+    let request_line = request.lines().next().unwrap_or_default();
+
+    let (status, body) = if request_line.starts_with("GET /metrics ") || request_line.starts_with("GET / ") {
+        ("200 OK", metrics.lock().expect("metrics lock must not be poisoned").clone())
+    } else {
+        ("404 Not Found", "not found\n".to_string())
+    };
+    let response = format!("HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_gauges() {
+        let mut gauges = Gauges::new();
+        gauges.set("sacloud_up", "1 if up", &[("prefix", "dev"), ("resource", "server")], 1.0);
+        gauges.set("sacloud_up", "1 if up", &[("prefix", "dev"), ("resource", "vpc_router")], 0.0);
+        gauges.set("sacloud_poll_success", "1 if the last poll succeeded", &[], 1.0);
+        gauges.set("sacloud_name", "escaped", &[("name", "a\"b")], 0.5);
+        assert_eq!(gauges.render(), [
+            "# HELP sacloud_name escaped",
+            "# TYPE sacloud_name gauge",
+            "sacloud_name{name=\"a\\\"b\"} 0.5",
+            "# HELP sacloud_poll_success 1 if the last poll succeeded",
+            "# TYPE sacloud_poll_success gauge",
+            "sacloud_poll_success 1",
+            "# HELP sacloud_up 1 if up",
+            "# TYPE sacloud_up gauge",
+            "sacloud_up{prefix=\"dev\",resource=\"server\"} 1",
+            "sacloud_up{prefix=\"dev\",resource=\"vpc_router\"} 0",
+            "",
+        ].join("\n"));
+    }
+
+    #[tokio::test]
+    async fn serve_metrics() {
+        let listener = listen("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let metrics = Arc::new(Mutex::new("sacloud_poll_success 1\n".to_string()));
+        tokio::spawn(serve(listener, metrics));
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.ends_with("\r\n\r\nsacloud_poll_success 1\n"), "{}", response);
+    }
+}
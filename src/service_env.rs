@@ -1,53 +1,152 @@
-use std::{borrow::Borrow, net::{IpAddr, Ipv4Addr}, path::PathBuf};
-use once_cell::sync::Lazy;
-use serde_json::json;
+//! The environment named by a prefix: the loaded [`Config`] and the `Primary*` resources found or created by name.
+//! [`load_config`] must be called once before [`CONFIG`] is used, which panics otherwise.
+
+use std::{borrow::Borrow, collections::{BTreeMap, BTreeSet}, fmt, net::{IpAddr, Ipv4Addr}, path::{Path, PathBuf}, fs, env, io::Write, process::{Command, Stdio}};
+use once_cell::sync::{Lazy, OnceCell};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use sha2::{Sha256, Digest};
+use serde_json::{json, Value};
 use serde::{Serialize, Deserialize};
+use url::Url;
 
 use crate::api::{
     self,
-    Server, ServerId, ServerInfo, ServerPlanId,
+    Server, ServerId, ServerInfo, ServerPlan, ServerPlanId, ServerPlanCommitment,
     Disk, DiskId, DiskInfo, DiskPlanId, DiskConnection, DiskConfig,
-    Appliance, ApplianceId, ApplianceInfo, VpcRouterInfo, VpcRouterPlanId,
-    ArchiveId,
+    Appliance, ApplianceId, ApplianceInfo, VpcRouterInfo, VpcRouterPlanId, NfsInfo, NfsPlanId, MobileGatewayInfo, MobileGatewayPlanId,
+    Archive, ArchiveId, ArchiveInfo, FtpServer,
     Switch, SwitchId, SwitchInfo,
     SshPublicKey, SshPublicKeyId, SshPublicKeyInfo,
-    Note, NoteInfo, NoteId, NoteClass,
-    InterfaceDriver,
+    Note, NoteInfo, NoteId, NoteClass, OfficialNoteVariables, Availability,
+    SimpleMonitor, SimpleMonitorInfo, SimpleMonitorHealthCheck, SimpleMonitorProtocol, CommonServiceItemId,
+    AutoBackup, AutoBackupInfo, Weekday,
+    CdromId,
+    Icon, IconId, IconInfo,
+    PrivateHostId,
+    Interface, InterfaceId, InterfaceDriver,
     Ipv4Net, // SingleLineIpv4Net,
 };
+use crate::notifier::NotifyEvent;
 
 static SERVER_PLAN_ID: Lazy<ServerPlanId> = Lazy::new(|| ServerPlanId("100001001".into()));
 static DISK_PLAN_ID: Lazy<DiskPlanId> = Lazy::new(|| DiskPlanId(4.into()));
 
-pub(crate) const PRIMARY_SERVER_FORWARDED_PORT: u16 = 10022;
-const CONFIG_JSON: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/config/config.json"));
+/// the vpc router forwards it to the ssh port of the primary server
+pub const PRIMARY_SERVER_FORWARDED_PORT: u16 = 10022;
+/// the server being created by `update --replace-server` is reached on it until the switch
+pub const REPLACEMENT_SERVER_FORWARDED_PORT: u16 = 10023;
+/// the size of the primary server disk
+pub const PRIMARY_SERVER_DISK_SIZE_MB: u64 = 20480;
+/// prometheus-node-exporter listens on it in the server
+pub const NODE_EXPORTER_PORT: u16 = 9100;
+const DEFAULT_CONFIG_PATH: &str = "config/config.json";
+const EXAMPLE_CONFIG_JSON: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/config/config.example.json"));
 const SETUP_SHELL_NOTE_CONTENT: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/note/setup.sh"));
 
-pub(crate) static CONFIG: Lazy<Config> = Lazy::new(|| { Config::default() });
-
-#[derive(Debug, Serialize)]
-pub(crate) enum Error {
-    ApiError(api::Error),
-}
-
-impl From<api::Error> for Error {
-    fn from(e: api::Error) -> Self {
-        Self::ApiError(e)
-    }
+static LOADED_CONFIG: OnceCell<Config> = OnceCell::new();
+/// panics before load_config, instead of running on the example config
+pub static CONFIG: Lazy<&Config> = Lazy::new(|| { LOADED_CONFIG.get().expect("load_config must be called before CONFIG is used") });
+
+/// the errors of the environment
+#[derive(Debug, Serialize, thiserror::Error)]
+pub enum Error {
+    /// an api call failed
+    #[error("api call failed")]
+    ApiError(#[from] api::Error),
+    /// the config file couldn't be read, with the path and the io error
+    #[error("couldn't read the config {0}: {1}")]
+    ConfigCouldntRead(PathBuf, String),
+    /// the config isn't json of the fields, with the path and what is wrong
+    #[error("invalid config {0}: {1}")]
+    ConfigInvalid(PathBuf, String),
+    /// a secret of the config couldn't be resolved from the keychain or the ${ENV} references, with the field and why
+    #[error("couldn't resolve the secret of {0}: {1}")]
+    ConfigSecretCouldntResolve(String, String),
+    /// load_config was called twice
+    #[error("the config is already loaded")]
+    ConfigAlreadyLoaded,
+    /// no available plan of server.plan in the zone, with the cpus, the memory, the generation and the commitment
+    #[error("no available server plan of {0} cpus, {1} MB memory, generation {2:?}, {3}")]
+    ServerPlanNotFound(u32, u64, Option<u32>, ServerPlanCommitment),
+    /// the archive created for the upload has no ftp server
+    #[error("the blank archive {0} was created without the ftp server")]
+    ArchiveFtpServerNotOpened(ArchiveId),
+    /// the manifest file couldn't be read, with the path and the io error
+    #[error("couldn't read the manifest {0}: {1}")]
+    ManifestCouldntRead(PathBuf, String),
+    /// the manifest isn't json of the fields, with the path and what is wrong
+    #[error("invalid manifest {0}: {1}")]
+    ManifestInvalid(PathBuf, String),
+    /// the disk password couldn't be put in the os keychain, with the keychain account and the error
+    #[error("couldn't store the disk password of {0} in the keychain: {1}")]
+    DiskPasswordCouldntStore(String, String),
+    /// the disk password couldn't be read from the os keychain, with the keychain account and the error
+    #[error("couldn't read the disk password of {0} from the keychain: {1}")]
+    DiskPasswordCouldntRead(String, String),
+    /// no note of a source in server.notes, with the source
+    #[error("{0} not found")]
+    NoteNotFound(String),
+    /// the note of a source in server.notes isn't available yet, with the source and the availability
+    #[error("{0} is not available: {1:?}")]
+    NoteNotAvailable(String, Option<Availability>),
+    /// wg failed or is not installed
+    #[error("couldn't run wg for the key: {0}")]
+    WireGuardKeyCouldntGenerate(String),
+    /// the configured private key is not the base64 of 32 bytes
+    #[error("invalid wireguard private key")]
+    WireGuardKeyInvalid,
 }
 
+/// the resources of the environment, named by the prefix
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) enum EquipmentKind {
+pub enum EquipmentKind {
+    /// the server
     PrimaryServer,
+    /// the disk of the server
     PrimaryServerDisk,
+    /// the ssh public key registered for the server
     PrimaryServerSshPublicKey,
+    /// the switch between the server and the vpc router
     PrimarySwitch,
+    /// the vpc router forwarding to the server
     PrimaryVpcRouter,
+    /// the note of the setup shell
     PrimaryServerSetupShellNote,
+    /// the simple monitor of the server
+    PrimarySimpleMonitor,
+    /// the auto backup of the disk
+    PrimaryServerDiskAutoBackup,
+    /// the nfs appliance
+    PrimaryNfs,
+    /// the mobile gateway
+    PrimaryMobileGateway,
+    /// the icon tagged on the resources
+    PrimaryIcon,
+    /// the archive transferred between the zones
+    MigrationArchive,
 }
 
 impl EquipmentKind {
-    pub(crate) fn name(&self, prefix: impl AsRef<str>) -> String {
+    /// all the kinds, in the order they're listed
+    pub fn all() -> [Self; 12] {
+        [
+            Self::PrimaryServer,
+            Self::PrimaryServerDisk,
+            Self::PrimaryServerSshPublicKey,
+            Self::PrimarySwitch,
+            Self::PrimaryVpcRouter,
+            Self::PrimaryServerSetupShellNote,
+            Self::PrimarySimpleMonitor,
+            Self::PrimaryServerDiskAutoBackup,
+            Self::PrimaryNfs,
+            Self::PrimaryMobileGateway,
+            Self::PrimaryIcon,
+            Self::MigrationArchive,
+        ]
+    }
+
+    /// the resource name of the kind for the prefix
+    pub fn name(&self, prefix: impl AsRef<str>) -> String {
         match self {
             Self::PrimaryServer => format!("{}-server", prefix.as_ref()),
             Self::PrimaryServerDisk => format!("{}-server", prefix.as_ref()),
@@ -55,281 +154,2804 @@ impl EquipmentKind {
             Self::PrimarySwitch => format!("{}-switch", prefix.as_ref()),
             Self::PrimaryVpcRouter => format!("{}-vpc-router", prefix.as_ref()),
             Self::PrimaryServerSetupShellNote => format!("{}-server-setup-shell", prefix.as_ref()),
+            // used as a tag, because simple monitor's name must be the target
+            Self::PrimarySimpleMonitor => format!("{}-simple-monitor", prefix.as_ref()),
+            Self::PrimaryServerDiskAutoBackup => format!("{}-server-auto-backup", prefix.as_ref()),
+            Self::PrimaryNfs => format!("{}-nfs", prefix.as_ref()),
+            Self::PrimaryMobileGateway => format!("{}-mobile-gateway", prefix.as_ref()),
+            Self::PrimaryIcon => format!("{}-icon", prefix.as_ref()),
+            // the same name in both zones while migrating
+            Self::MigrationArchive => format!("{}-migration", prefix.as_ref()),
+        }
+    }
+
+    /// the prefix of the resource of this kind, e.g. dev of dev-switch
+    pub fn prefix_of<'a>(&self, name: &'a str) -> Option<&'a str> {
+        name.strip_suffix(&self.name("")).filter(|prefix| !prefix.is_empty())
+    }
+
+    /// the resource type of the sacloud/sakuracloud provider, none for the transient ones
+    pub fn terraform_type(&self) -> Option<&'static str> {
+        match self {
+            Self::PrimaryServer => Some("sakuracloud_server"),
+            Self::PrimaryServerDisk => Some("sakuracloud_disk"),
+            Self::PrimaryServerSshPublicKey => Some("sakuracloud_ssh_key"),
+            Self::PrimarySwitch => Some("sakuracloud_switch"),
+            Self::PrimaryVpcRouter => Some("sakuracloud_vpc_router"),
+            Self::PrimaryServerSetupShellNote => Some("sakuracloud_note"),
+            Self::PrimarySimpleMonitor => Some("sakuracloud_simple_monitor"),
+            Self::PrimaryServerDiskAutoBackup => Some("sakuracloud_auto_backup"),
+            Self::PrimaryNfs => Some("sakuracloud_nfs"),
+            Self::PrimaryMobileGateway => Some("sakuracloud_mobile_gateway"),
+            Self::PrimaryIcon => Some("sakuracloud_icon"),
+            Self::MigrationArchive => None,
         }
     }
+
+    /// e.g. sakuracloud_server.dev_server, the names are unique per type
+    pub fn terraform_address(&self, prefix: impl AsRef<str>) -> Option<String> {
+        let terraform_type = self.terraform_type()?;
+        let mut local_name: String = self.name(prefix).chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+        if local_name.starts_with(|c: char| c.is_ascii_digit()) {
+            local_name.insert(0, '_');
+        }
+        Some(format!("{}.{}", terraform_type, local_name))
+    }
+}
+
+/// import blocks for terraform 1.5+ or opentofu, the resource blocks are generated by `plan -generate-config-out`
+pub fn terraform_import_blocks(prefix: impl AsRef<str>, zone: impl AsRef<str>, resources: &[(EquipmentKind, String)]) -> String {
+    let prefix = prefix.as_ref();
+    let zone = zone.as_ref();
+    let mut hcl = format!(concat!(
+        "# the resources of {} in {}, run `terraform plan -generate-config-out=generated.tf` to write the resource blocks\n",
+        "\n",
+        "terraform {{\n",
+        "  required_providers {{\n",
+        "    sakuracloud = {{\n",
+        "      source = \"sacloud/sakuracloud\"\n",
+        "    }}\n",
+        "  }}\n",
+        "}}\n",
+        "\n",
+        "provider \"sakuracloud\" {{\n",
+        "  zone = \"{}\"\n",
+        "}}\n",
+    ), prefix, zone, zone);
+    for (kind, id) in resources {
+        let Some(address) = kind.terraform_address(prefix) else {
+            continue;
+        };
+        hcl.push_str(&format!("\nimport {{\n  to = {}\n  id = \"{}\"\n}}\n", address, id));
+    }
+    hcl
 }
 
+/// what `export-manifest` writes and `apply-manifest` reproduces under another prefix, maybe of another account
+/// the config is not included because of the secrets, the same config is expected on both sides
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct Config {
+pub struct Manifest {
+    /// the format of the manifest, Manifest::VERSION when exported
+    #[serde()]
+    pub version: u32,
+
+    /// where it was exported from, e.g. to find the note by name
     #[serde()]
-    pub(crate) packages: Vec<String>,
+    pub prefix: String,
 
+    /// the zone exported from
     #[serde()]
-    pub(crate) git: GitConfig,
+    pub zone: String,
 
+    /// the plan of the server
     #[serde()]
-    pub(crate) forwarding_ports: Vec<ForwardingPortConfig>,
+    pub server: ManifestServer,
 
+    /// the plan and the size of the disk
     #[serde()]
-    pub(crate) server: ServerConfig,
+    pub disk: ManifestDisk,
+
+    /// the firewall rules of the vpc router
+    #[serde()]
+    pub firewall: ManifestFirewall,
+
+    /// `update --simple-monitor` was run
+    #[serde(default)]
+    pub simple_monitor: bool,
+
+    /// the notes of the server, compared by the sha256 of the content
+    #[serde(default)]
+    pub notes: Vec<ManifestNote>,
 }
 
+/// the server plan of the manifest
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct ForwardingPortConfig {
+pub struct ManifestServer {
+    /// the number of cpus
     #[serde()]
-    pub(crate) remote_port: u16,
+    pub cpu: u32,
 
+    /// the memory in MB
     #[serde()]
-    pub(crate) local_port: u16,
+    pub memory_mb: u64,
+
+    /// the latest one is chosen when not recorded
+    #[serde(default)]
+    pub generation: Option<u32>,
+
+    /// the commitment of the plan
+    #[serde(default)]
+    pub commitment: ServerPlanCommitment,
 }
 
+/// the disk of the manifest
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct ServerConfig {
+pub struct ManifestDisk {
+    /// the disk plan, only the one of this tool is supported
+    #[serde()]
+    pub plan_id: DiskPlanId,
+
+    /// the disk size in MB
     #[serde()]
-    pub(crate) service_dirs: Vec<String>,
+    pub size_mb: u64,
+}
 
+/// the local ip rules are not included, they differ by who runs the command
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestFirewall {
+    /// the receive rules as the api takes them
     #[serde()]
-    pub(crate) zshrc_lines: Vec<String>,
+    pub receive: Vec<Value>,
 
+    /// the send rules as the api takes them
     #[serde()]
-    pub(crate) wireguard: WireGuardConfig,
+    pub send: Vec<Value>,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        serde_json::from_str(CONFIG_JSON).unwrap()
+/// a note of the manifest
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestNote {
+    /// the note name, the setup shell note of the prefix
+    #[serde()]
+    pub name: String,
+
+    /// hex, the content itself is embedded in this tool
+    #[serde()]
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// the format this tool reads and writes
+    pub const VERSION: u32 = 1;
+
+    /// a manifest of the environment, the firewall rules made from the loaded config
+    pub fn new(prefix: impl AsRef<str>, zone: impl AsRef<str>, server: ManifestServer, (disk_plan_id, disk_size_mb): (DiskPlanId, u64), simple_monitor: bool, notes: Vec<(String, &str)>) -> Self {
+        let (receive, send) = PrimaryVpcRouter::firewall_rules();
+        Self {
+            version: Self::VERSION,
+            prefix: prefix.as_ref().to_string(),
+            zone: zone.as_ref().to_string(),
+            server,
+            disk: ManifestDisk { plan_id: disk_plan_id, size_mb: disk_size_mb },
+            firewall: ManifestFirewall { receive, send },
+            simple_monitor,
+            notes: notes.into_iter().map(|(name, content)| ManifestNote { name, sha256: hex::encode(Sha256::digest(content.as_bytes())) }).collect(),
+        }
+    }
+
+    /// reads the manifest json
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).map_err(|e| Error::ManifestCouldntRead(path.to_path_buf(), e.to_string()))?;
+        serde_json::from_str(&json).map_err(|e| Error::ManifestInvalid(path.to_path_buf(), e.to_string()))
+    }
+
+    /// what this tool and the loaded config can't reproduce, all problems are returned at once like `Config::validate`
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.version != Self::VERSION {
+            problems.push(format!("version: {} is not supported, only {}", self.version, Self::VERSION));
+        }
+        if self.disk.plan_id != *DISK_PLAN_ID {
+            problems.push(format!("disk.plan_id: {} is not the plan of this tool, {}", self.disk.plan_id, *DISK_PLAN_ID));
+        }
+
+        let (receive, send) = PrimaryVpcRouter::firewall_rules();
+        if self.firewall.receive != receive {
+            problems.push("firewall.receive: differs from the rules of the config, e.g. simple_monitor.source_networks".to_string());
+        }
+        if self.firewall.send != send {
+            problems.push("firewall.send: differs from the rules of the config, e.g. server.wireguard.peer.endpoint".to_string());
+        }
+
+        let setup_shell_note_name = EquipmentKind::PrimaryServerSetupShellNote.name(&self.prefix);
+        let setup_shell_note_sha256 = hex::encode(Sha256::digest(SETUP_SHELL_NOTE_CONTENT.as_bytes()));
+        for note in &self.notes {
+            if note.name != setup_shell_note_name {
+                problems.push(format!("notes: {} is not a note of this tool", note.name));
+            } else if note.sha256 != setup_shell_note_sha256 {
+                problems.push(format!("notes: {} was exported with another setup script, sha256 {}", note.name, note.sha256));
+            }
+        }
+
+        problems
+    }
+}
+
+/// the config of the environment, config/config.json by default
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Config {
+    /// apt packages installed by the setup script
+    #[serde()]
+    pub packages: Vec<String>,
+
+    /// the git user and the local repo
+    #[serde()]
+    pub git: GitConfig,
+
+    /// the ports forwarded by `port-forwarding`
+    #[serde()]
+    pub forwarding_ports: Vec<ForwardingPortConfig>,
+
+    /// if set, `port-forwarding` opens a socks proxy on the local port into the private segment
+    #[serde(default)]
+    pub socks_proxy_port: Option<u16>,
+
+    /// the server and its setup
+    #[serde()]
+    pub server: ServerConfig,
+
+    /// the switch segment behind the vpc router, 192.168.2.0/24 if not set
+    #[serde(default)]
+    pub network: NetworkConfig,
+
+    /// if set, `update` creates a simple monitor of the server
+    #[serde(default)]
+    pub simple_monitor: Option<SimpleMonitorConfig>,
+
+    /// if set, `update` attaches an auto backup policy to the primary server disk
+    #[serde(default)]
+    pub auto_backup: Option<AutoBackupConfig>,
+
+    /// if set, `update` creates an nfs appliance on the switch and the server mounts it
+    #[serde(default)]
+    pub nfs: Option<NfsConfig>,
+
+    /// if set, `update` keeps a shut down copy of the environment in another zone, and `failover` boots it
+    #[serde(default)]
+    pub standby: Option<StandbyConfig>,
+
+    /// s3 compatible object storage for `backup --to-bucket`, the keys are not the api token
+    #[serde(default)]
+    pub object_storage: Option<ObjectStorageConfig>,
+
+    /// ssh user, keys and ssh_config usage, the default keys are probed in ~/.ssh if not set
+    #[serde(default)]
+    pub ssh: Option<SshConfig>,
+
+    /// if set, `update` assigns the icon to the server, the switch and the vpc router on creation
+    #[serde(default)]
+    pub icon: Option<IconConfig>,
+
+    /// root-setup.zsh and user-setup.zsh in this dir override the embedded setup scripts
+    #[serde(default)]
+    pub scripts_dir: Option<PathBuf>,
+
+    /// extra setup script templates run after the user setup
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
+
+    /// if set, the setup script installs docker and runs the compose file on every `update`
+    #[serde(default)]
+    pub docker: Option<DockerConfig>,
+
+    /// for features.unattended_upgrades
+    #[serde(default)]
+    pub unattended_upgrades: UnattendedUpgradesConfig,
+
+    /// for features.swap
+    #[serde(default)]
+    pub swap: SwapConfig,
+
+    /// e.g. { "vm.swappiness": "10" }, written to /etc/sysctl.d on every `update`
+    #[serde(default)]
+    pub sysctl: BTreeMap<String, String>,
+
+    /// for features.hardening
+    #[serde(default)]
+    pub hardening: HardeningConfig,
+
+    /// for features.monitoring_agent
+    #[serde(default)]
+    pub monitoring_agent: MonitoringAgentConfig,
+
+    /// installed and enabled after the hooks, restarted only if the unit or its files changed
+    #[serde(default)]
+    pub systemd_units: Vec<SystemdUnitConfig>,
+
+    /// unix users besides ubuntu, the ones removed from here lose the keys and sudo on the next `update`
+    #[serde(default)]
+    pub users: Vec<UserConfig>,
+
+    /// if set, `update` and `clean` post to the webhook when they start, finish or fail
+    #[serde(default)]
+    pub notifier: Option<NotifierConfig>,
+
+    /// if set, the deploy fails unless the url responds 200 after the setup scripts finish
+    #[serde(default)]
+    pub health_check: Option<HealthCheckConfig>,
+
+    /// `update` asks before going on if the estimated monthly cost of the environment exceeds it, in JPY
+    #[serde(default)]
+    pub monthly_budget: Option<f64>,
+
+    /// the optional parts of the provisioning, e.g. {"wireguard": false, "swap": true}
+    #[serde(default)]
+    pub features: FeaturesConfig,
+}
+
+/// the setup steps and the vpc router rules of each are only with it enabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Feature {
+    /// the server goes out through server.wireguard.peer
+    #[serde(rename = "wireguard")]
+    Wireguard,
+
+    /// the compose project of docker in config
+    #[serde(rename = "docker")]
+    Docker,
+
+    /// node_exporter
+    #[serde(rename = "monitoring_agent")]
+    MonitoringAgent,
+
+    /// the swap file of swap.size_mb
+    #[serde(rename = "swap")]
+    Swap,
+
+    /// unattended-upgrades with unattended_upgrades.reboot_time
+    #[serde(rename = "unattended_upgrades")]
+    UnattendedUpgrades,
+
+    /// fail2ban, sshd_config and ufw in the server
+    #[serde(rename = "hardening")]
+    Hardening,
+}
+
+impl Feature {
+    /// all the features
+    pub const ALL: [Feature; 6] = [Feature::Wireguard, Feature::Docker, Feature::MonitoringAgent, Feature::Swap, Feature::UnattendedUpgrades, Feature::Hardening];
+
+    /// the name in config.json
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Wireguard => "wireguard",
+            Self::Docker => "docker",
+            Self::MonitoringAgent => "monitoring_agent",
+            Self::Swap => "swap",
+            Self::UnattendedUpgrades => "unattended_upgrades",
+            Self::Hardening => "hardening",
+        }
+    }
+
+    // the ones provisioned before the toggles
+    fn enabled_by_default(&self) -> bool {
+        matches!(self, Self::Wireguard | Self::Docker)
+    }
+}
+
+/// the missing ones are the defaults
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FeaturesConfig(BTreeMap<Feature, bool>);
+
+impl FeaturesConfig {
+    /// whether the feature is on, the default of the feature if it's not in the config
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        self.0.get(&feature).copied().unwrap_or(feature.enabled_by_default())
+    }
+
+    /// the features that are on, in the order of Feature::ALL
+    pub fn enabled(&self) -> Vec<Feature> {
+        Feature::ALL.into_iter().filter(|feature| self.is_enabled(*feature)).collect()
+    }
+}
+
+/// a port forwarded by `port-forwarding`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForwardingPortConfig {
+    /// the port on the server side
+    #[serde()]
+    pub remote_port: u16,
+
+    /// the port on the local side
+    #[serde()]
+    pub local_port: u16,
+
+    /// which side listens
+    #[serde(default)]
+    pub direction: ForwardingDirection,
+
+    /// the host seen from the server, e.g. another host in the private segment, only for the local direction
+    #[serde(default = "ForwardingPortConfig::default_host")]
+    pub remote_host: String,
+
+    /// the address the local port listens on, only for the local direction
+    #[serde(default = "ForwardingPortConfig::default_host")]
+    pub bind_address: String,
+}
+
+impl ForwardingPortConfig {
+    /// the loopback, the default of remote_host and bind_address
+    pub fn default_host() -> String {
+        "127.0.0.1".to_string()
+    }
+}
+
+/// the side of a forwarding port that listens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForwardingDirection {
+    /// the local port is forwarded to the remote port, like ssh -L
+    #[default]
+    #[serde(rename = "local")]
+    Local,
+
+    /// the remote port on the server is forwarded to the local port, like ssh -R
+    #[serde(rename = "remote")]
+    Remote,
+}
+
+/// the simple monitor of the server
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimpleMonitorConfig {
+    /// tcp by default
+    #[serde(default = "SimpleMonitorConfig::default_protocol")]
+    pub protocol: SimpleMonitorProtocol,
+
+    /// for http, the port forwarded by the vpc router
+    #[serde(default)]
+    pub port: Option<u16>,
+
+    /// for http, the path requested
+    #[serde(default)]
+    pub path: Option<String>,
+
+    /// the interval of the checks in seconds, the api default if not set
+    #[serde(default)]
+    pub delay_loop: Option<u64>,
+
+    /// notify the account's email on the changes of the health
+    #[serde(default)]
+    pub notify_email: bool,
+
+    /// notify the slack webhook on the changes of the health
+    #[serde(default)]
+    pub slack_webhook_url: Option<String>,
+
+    /// source networks of the simple monitor, allowed by the vpc router firewall
+    #[serde(default)]
+    pub source_networks: Vec<String>,
+}
+
+impl SimpleMonitorConfig {
+    fn default_protocol() -> SimpleMonitorProtocol {
+        SimpleMonitorProtocol::Tcp
+    }
+}
+
+/// the auto backup of the primary server disk
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AutoBackupConfig {
+    /// the days of the backups, sunday by default
+    #[serde(default = "AutoBackupConfig::default_weekdays")]
+    pub weekdays: Vec<Weekday>,
+
+    /// number of archives to keep, 1 to 10
+    #[serde(default = "AutoBackupConfig::default_generations")]
+    pub generations: u8,
+}
+
+impl AutoBackupConfig {
+    fn default_weekdays() -> Vec<Weekday> {
+        vec![Weekday::Sun]
+    }
+
+    fn default_generations() -> u8 {
+        3
+    }
+}
+
+/// the standby environment in another zone
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StandbyConfig {
+    /// e.g. is1b, the ids in config like private_host_id must exist in this zone too
+    pub zone: String,
+
+    /// if set, the a record follows the active vpc router
+    #[serde(default)]
+    pub dns: Option<StandbyDnsConfig>,
+}
+
+/// the dns record of the active vpc router
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StandbyDnsConfig {
+    /// the dns zone registered in the sakura cloud dns, e.g. example.com
+    pub zone: String,
+
+    /// relative to the zone, e.g. dev
+    pub name: String,
+
+    /// the ttl of the record in seconds, 60 by default
+    #[serde(default = "StandbyDnsConfig::default_ttl")]
+    pub ttl: u32,
+}
+
+impl StandbyDnsConfig {
+    fn default_ttl() -> u32 {
+        60
+    }
+}
+
+/// the nfs appliance on the switch
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NfsConfig {
+    /// the plan determines the storage class and the size
+    #[serde()]
+    pub plan_id: NfsPlanId,
+
+    /// where the server mounts it, /mnt/nfs by default
+    #[serde(default = "NfsConfig::default_mount_point")]
+    pub mount_point: String,
+}
+
+impl NfsConfig {
+    fn default_mount_point() -> String {
+        "/mnt/nfs".to_string()
     }
 }
 
+/// the object storage bucket of `backup --to-bucket`
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct GitConfig {
+pub struct ObjectStorageConfig {
+    /// the s3 endpoint, <https://s3.isk01.sakurastorage.jp> by default
+    #[serde(default = "ObjectStorageConfig::default_endpoint")]
+    pub endpoint: String,
+
+    /// the signing region, jp-north-1 by default
+    #[serde(default = "ObjectStorageConfig::default_region")]
+    pub region: String,
+
+    /// the bucket the backups are put in
     #[serde()]
-    pub(crate) user: String,
+    pub bucket: String,
 
+    /// the access key id, resolved like the other secrets
     #[serde()]
-    pub(crate) email: String,
+    pub access_key_id: String,
 
+    /// the secret access key, resolved like the other secrets
     #[serde()]
-    pub(crate) local_repo_path: PathBuf,
+    pub secret_access_key: String,
+}
+
+impl ObjectStorageConfig {
+    fn default_endpoint() -> String {
+        "https://s3.isk01.sakurastorage.jp".to_string()
+    }
+
+    fn default_region() -> String {
+        "jp-north-1".to_string()
+    }
 }
 
+/// how ssh reaches the server
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct WireGuardConfig {
+pub struct SshConfig {
+    /// the login user, ubuntu by default
+    #[serde(default = "SshConfig::default_user")]
+    pub user: String,
+
+    /// the private key, probed in ~/.ssh if not set
+    #[serde(default)]
+    pub privkey: Option<PathBuf>,
+
+    /// the public key registered with the new disk, probed in ~/.ssh if not set
+    #[serde(default)]
+    pub pubkey: Option<PathBuf>,
+
+    /// the team members' public keys, a file or the key itself, registered as {prefix}-pub-key-1, -2, ... with the new disk
+    #[serde(default)]
+    pub extra_pubkeys: Vec<String>,
+
+    /// Host in ~/.ssh/config whose HostName is the vpc router's ip, e.g. to go through ProxyJump
+    #[serde(default)]
+    pub host_alias: Option<String>,
+
+    /// `user@host[:port]` of a bastion in the private network, the server is reached at its private ip through it
+    #[serde(default)]
+    pub jump: Option<String>,
+}
+
+impl SshConfig {
+    fn default_user() -> String {
+        "ubuntu".to_string()
+    }
+}
+
+/// the icon of the resources
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IconConfig {
+    /// an existing icon, preferred to png
+    #[serde(default)]
+    pub id: Option<IconId>,
+
+    /// uploaded as `<prefix>-icon` if id is not given
+    #[serde(default)]
+    pub png: Option<PathBuf>,
+}
+
+/// the compose project run by docker
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DockerConfig {
+    /// local path, pushed on every `update`, only the changed services are recreated
     #[serde()]
-    pub(crate) interface: WireGuardInterfaceConfig,
+    pub compose_file: PathBuf,
+
+    /// where the compose file is put on the server, /home/ubuntu/compose by default
+    #[serde(default = "DockerConfig::default_project_dir")]
+    pub project_dir: String,
+}
+
+impl DockerConfig {
+    fn default_project_dir() -> String {
+        "/home/ubuntu/compose".to_string()
+    }
+}
+
+/// the swap file of features.swap
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SwapConfig {
+    /// the swap file is recreated on `update` if changed
+    #[serde(default = "SwapConfig::default_size_mb")]
+    pub size_mb: u64,
+}
+
+impl Default for SwapConfig {
+    fn default() -> Self {
+        Self { size_mb: Self::default_size_mb() }
+    }
+}
+
+impl SwapConfig {
+    fn default_size_mb() -> u64 {
+        2048
+    }
+}
+
+/// the unattended upgrades of features.unattended_upgrades
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UnattendedUpgradesConfig {
+    /// e.g. 04:00 in the timezone of the server, the server reboots then if an upgrade needs it, never if not set
+    #[serde(default)]
+    pub reboot_time: Option<String>,
+}
+
+/// the node_exporter of features.monitoring_agent
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MonitoringAgentConfig {
+    /// networks of the prometheus scraping the server, node_exporter isn't exposed if empty
+    #[serde(default)]
+    pub scrape_sources: Vec<String>,
+
+    /// the global port of the vpc router forwarded to node_exporter
+    #[serde(default = "MonitoringAgentConfig::default_forwarded_port")]
+    pub forwarded_port: u16,
+}
+
+impl Default for MonitoringAgentConfig {
+    fn default() -> Self {
+        Self { scrape_sources: Vec::new(), forwarded_port: Self::default_forwarded_port() }
+    }
+}
+
+impl MonitoringAgentConfig {
+    fn default_forwarded_port() -> u16 {
+        19100
+    }
+}
+
+/// the fail2ban, sshd_config and ufw of features.hardening
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HardeningConfig {
+    /// tcp ports the ufw in the server allows besides ssh, e.g. the ones the vpc router forwards for the simple monitor
+    #[serde(default)]
+    pub allowed_ports: Vec<u16>,
+
+    /// e.g. the office network, never banned by fail2ban
+    #[serde(default)]
+    pub fail2ban_ignore_networks: Vec<String>,
+
+    /// the MaxAuthTries of sshd, 6 as sshd by default, every key in ssh-agent is one try so too few locks out the users with many keys
+    #[serde(default = "HardeningConfig::default_max_auth_tries")]
+    pub max_auth_tries: u32,
+}
+
+impl Default for HardeningConfig {
+    fn default() -> Self {
+        Self { allowed_ports: Vec::new(), fail2ban_ignore_networks: Vec::new(), max_auth_tries: Self::default_max_auth_tries() }
+    }
+}
+
+impl HardeningConfig {
+    fn default_max_auth_tries() -> u32 {
+        6
+    }
+}
 
+/// a unix user of the server
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserConfig {
+    /// the login name
     #[serde()]
-    pub(crate) peer: WireGuardPeerConfig,
+    pub name: String,
+
+    /// the whole authorized_keys of the user, e.g. `["ssh-ed25519 AAAA... alice@laptop"]`
+    #[serde(default)]
+    pub authorized_keys: Vec<String>,
+
+    /// passwordless sudo
+    #[serde(default)]
+    pub sudo: bool,
+
+    /// supplementary groups, e.g. ["docker"]
+    #[serde(default)]
+    pub groups: Vec<String>,
+}
+
+impl UserConfig {
+    /// the authorized_keys file content, a key per line
+    pub fn authorized_keys_file(&self) -> String {
+        self.authorized_keys.iter().map(|key| format!("{}\n", key)).collect()
+    }
 }
 
+/// a systemd service installed on the server
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct WireGuardInterfaceConfig {
+pub struct SystemdUnitConfig {
+    /// `<name>.service`
+    #[serde()]
+    pub name: String,
+
+    /// the ExecStart of the unit
     #[serde()]
-    pub(crate) private_key: String,
+    pub exec_start: String,
+
+    /// the User of the unit, ubuntu by default
+    #[serde(default = "SystemdUnitConfig::default_user")]
+    pub user: String,
 
-    // 雑
+    /// the WorkingDirectory of the unit
+    #[serde(default)]
+    pub working_directory: Option<String>,
+
+    /// the Environment of the unit
+    #[serde(default)]
+    pub environment: BTreeMap<String, String>,
+
+    /// uploaded before the unit is started, e.g. the binary or its config
+    #[serde(default)]
+    pub files: Vec<SystemdUnitFileConfig>,
+}
+
+/// a file uploaded for a systemd unit
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SystemdUnitFileConfig {
+    /// the local path to upload
     #[serde()]
-    pub(crate) address: Vec<String>,
+    pub local: PathBuf,
 
+    /// absolute path on the server, owned by the user of the unit
     #[serde()]
-    pub(crate) dns: Vec<IpAddr>,
+    pub remote: String,
+
+    /// the octal mode, 644 by default
+    #[serde(default = "SystemdUnitFileConfig::default_mode")]
+    pub mode: String,
+}
+
+impl SystemdUnitConfig {
+    fn default_user() -> String {
+        "ubuntu".to_string()
+    }
+
+    /// the unit file, restarted always and wanted by multi-user.target
+    pub fn unit_file(&self) -> String {
+        let mut unit_file = String::new();
+        unit_file.push_str("[Unit]\n");
+        unit_file.push_str(&format!("Description={}\n", self.name));
+        unit_file.push_str("Wants=network-online.target\n");
+        unit_file.push_str("After=network-online.target\n");
+        unit_file.push_str("\n[Service]\n");
+        unit_file.push_str(&format!("User={}\n", self.user));
+        if let Some(working_directory) = &self.working_directory {
+            unit_file.push_str(&format!("WorkingDirectory={}\n", working_directory));
+        }
+        for (key, value) in &self.environment {
+            let assignment = format!("{}={}", key, value).replace('\\', "\\\\").replace('"', "\\\"");
+            unit_file.push_str(&format!("Environment=\"{}\"\n", assignment));
+        }
+        unit_file.push_str(&format!("ExecStart={}\n", self.exec_start));
+        unit_file.push_str("Restart=always\n");
+        unit_file.push_str("\n[Install]\n");
+        unit_file.push_str("WantedBy=multi-user.target\n");
+        unit_file
+    }
+}
+
+impl SystemdUnitFileConfig {
+    fn default_mode() -> String {
+        "644".to_string()
+    }
 }
 
+/// the health check after the setup scripts
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct WireGuardPeerConfig {
+pub struct HealthCheckConfig {
+    /// e.g. http://{ip}:8080/healthz, {ip} is the public ip of the vpc router
     #[serde()]
-    pub(crate) public_key: String,
+    pub url: String,
+
+    /// requested by curl on the server instead, e.g. http://127.0.0.1:8080/healthz of a port not forwarded
+    #[serde(default)]
+    pub from_server: bool,
+
+    /// the number of the requests before failing, 30 by default
+    #[serde(default = "HealthCheckConfig::default_attempts")]
+    pub attempts: u32,
+
+    /// the seconds between the requests, 10 by default
+    #[serde(default = "HealthCheckConfig::default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+impl HealthCheckConfig {
+    fn default_attempts() -> u32 {
+        30
+    }
+
+    fn default_interval_secs() -> u64 {
+        10
+    }
 
+    /// the url with {ip} replaced by the public ip
+    pub fn url(&self, public_shared_ip: Ipv4Addr) -> String {
+        self.url.replace("{ip}", &public_shared_ip.to_string())
+    }
+}
+
+/// the webhook notified on `update` and `clean`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotifierConfig {
+    /// e.g. a slack incoming webhook, `${ENV_VAR}` and `keychain:<service>:<account>` are resolved on load
     #[serde()]
-    pub(crate) endpoint: IpAddr,
+    pub webhook_url: String,
+
+    /// all events if empty
+    #[serde(default)]
+    pub events: Vec<NotifyEvent>,
 }
 
-#[derive(Debug)]
-pub(crate) struct PrimaryServer {
-    server: Server,
+impl NotifierConfig {
+    /// whether the event is posted
+    pub fn notifies(&self, event: NotifyEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// a setup script template run after the user setup
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HookConfig {
+    /// also the marker names, `hook_<name>_done` and `hook_<name>_failed` in the home dir
+    #[serde()]
+    pub name: String,
+
+    /// an upon template rendered with the same values as the setup scripts
+    #[serde()]
+    pub template: PathBuf,
+
+    /// the user it runs as, root by default
+    #[serde(default = "HookConfig::default_user")]
+    pub user: String,
+
+    /// ascending, the hooks with the same order run as listed
+    #[serde(default)]
+    pub order: i32,
+}
+
+impl HookConfig {
+    fn default_user() -> String {
+        "root".to_string()
+    }
+
+    /// the order to run
+    pub fn sorted(hooks: &[HookConfig]) -> Vec<&HookConfig> {
+        let mut hooks = hooks.iter().collect::<Vec<_>>();
+        hooks.sort_by_key(|hook| hook.order);
+        hooks
+    }
+}
+
+/// the switch segment behind the vpc router
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// the subnet is router_ip/mask_len
+    #[serde(default = "NetworkConfig::default_mask_len")]
+    pub mask_len: u8,
+
+    /// also the default route of the server and the nfs
+    #[serde(default = "NetworkConfig::default_router_ip")]
+    pub router_ip: Ipv4Addr,
+
+    /// the server's ip, 192.168.2.2 by default
+    #[serde(default = "NetworkConfig::default_server_ip")]
+    pub server_ip: Ipv4Addr,
+
+    /// the servers of `update --replace-server` take server_ip and this in turn
+    #[serde(default = "NetworkConfig::default_replacement_server_ip")]
+    pub replacement_server_ip: Ipv4Addr,
+
+    /// the nfs appliance's ip, 192.168.2.3 by default
+    #[serde(default = "NetworkConfig::default_nfs_ip")]
+    pub nfs_ip: Ipv4Addr,
+
+    /// the mobile gateway's ip, 192.168.2.4 by default
+    #[serde(default = "NetworkConfig::default_mobile_gateway_ip")]
+    pub mobile_gateway_ip: Ipv4Addr,
+}
+
+impl NetworkConfig {
+    fn default_mask_len() -> u8 {
+        24
+    }
+
+    fn default_router_ip() -> Ipv4Addr {
+        Ipv4Addr::new(192, 168, 2, 1)
+    }
+
+    fn default_server_ip() -> Ipv4Addr {
+        Ipv4Addr::new(192, 168, 2, 2)
+    }
+
+    fn default_nfs_ip() -> Ipv4Addr {
+        Ipv4Addr::new(192, 168, 2, 3)
+    }
+
+    fn default_mobile_gateway_ip() -> Ipv4Addr {
+        Ipv4Addr::new(192, 168, 2, 4)
+    }
+
+    fn default_replacement_server_ip() -> Ipv4Addr {
+        Ipv4Addr::new(192, 168, 2, 5)
+    }
+
+    /// the router ip and the mask length
+    pub fn subnet(&self) -> (Ipv4Addr, u8) {
+        (self.router_ip, self.mask_len)
+    }
+
+    // e.g. 192.168.2.0/24
+    fn subnet_string(&self) -> String {
+        let mask = u32::MAX.checked_shl(32 - self.mask_len as u32).unwrap_or(0);
+        format!("{}/{}", Ipv4Addr::from(u32::from(self.router_ip) & mask), self.mask_len)
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            mask_len: Self::default_mask_len(),
+            router_ip: Self::default_router_ip(),
+            server_ip: Self::default_server_ip(),
+            nfs_ip: Self::default_nfs_ip(),
+            mobile_gateway_ip: Self::default_mobile_gateway_ip(),
+            replacement_server_ip: Self::default_replacement_server_ip(),
+        }
+    }
+}
+
+/// the server and its setup
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// dirs created and owned by ubuntu on the server
+    #[serde()]
+    pub service_dirs: Vec<String>,
+
+    /// lines appended to the .zshrc of ubuntu unless they are there
+    #[serde()]
+    pub zshrc_lines: Vec<String>,
+
+    /// required by features.wireguard
+    #[serde(default)]
+    pub wireguard: Option<WireGuardConfig>,
+
+    /// dedicated host to create the server on, the server plan must fit in it
+    #[serde(default)]
+    pub private_host_id: Option<PrivateHostId>,
+
+    /// the plan of a new server, 1 cpu and 1 GB if not set
+    #[serde(default)]
+    pub plan: Option<ServerPlanConfig>,
+
+    /// the root password of a new disk for the console login, kept in the keychain (see show-password)
+    #[serde(default)]
+    pub generate_disk_password: bool,
+
+    /// variables of the startup notes, e.g. {"updatepackage": true}
+    #[serde(default)]
+    pub note_variables: NoteVariablesConfig,
+
+    /// note_variables overridden by the prefix, e.g. {"dev": {"usacloud": true}}
+    #[serde(default)]
+    pub note_variables_by_prefix: BTreeMap<String, NoteVariablesConfig>,
+
+    /// notes run after the setup shell note in the order, e.g. [{"official": "...", "variables": {"updatepackage": true}}]
+    #[serde(default)]
+    pub notes: Vec<NoteConfig>,
+
+    /// the host name instead of the resource name, set by the setup script too so that it follows the config
+    #[serde(default)]
+    pub host_name: Option<String>,
+
+    /// e.g. Asia/Tokyo, the one of the archive if not set
+    #[serde(default)]
+    pub timezone: Option<String>,
+
+    /// e.g. ja_JP.UTF-8, generated if missing
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+impl ServerConfig {
+    /// the host_name, or the resource name if not set
+    pub fn host_name(&self, resource_name: &str) -> String {
+        self.host_name.clone().unwrap_or_else(|| resource_name.to_string())
+    }
+
+    /// the note_variables with the ones of the prefix over them
+    pub fn note_variables(&self, prefix: &str) -> NoteVariablesConfig {
+        match self.note_variables_by_prefix.get(prefix) {
+            Some(overrides) => self.note_variables.merge(overrides),
+            None => self.note_variables.clone(),
+        }
+    }
+}
+
+/// the official ones are typed, the others are passed to the notes as is
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoteVariablesConfig {
+    /// the variables the official notes take
+    #[serde(flatten)]
+    pub official: OfficialNoteVariables,
+
+    /// the other variables
+    #[serde(flatten)]
+    pub others: BTreeMap<String, Value>,
+}
+
+impl NoteVariablesConfig {
+    /// the variables of other are overwritten
+    pub fn merge(&self, other: &Self) -> Self {
+        let mut others = self.others.clone();
+        others.extend(other.others.clone());
+        Self { official: self.official.merge(&other.official), others }
+    }
+
+    /// the variables as a json object
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).expect("must be valid json")
+    }
+}
+
+/// a note run on the server after the setup shell note
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteConfig {
+    /// the note to run
+    #[serde(flatten)]
+    pub source: NoteSourceConfig,
+
+    /// over note_variables of the prefix
+    #[serde(default)]
+    pub variables: NoteVariablesConfig,
+}
+
+/// where a note of the config is found
+#[derive(Debug, Serialize, Deserialize)]
+pub enum NoteSourceConfig {
+    /// a startup script published by sakura cloud
+    #[serde(rename = "official")]
+    Official(String),
+
+    /// a note of the account
+    #[serde(rename = "name")]
+    Name(String),
+
+    /// a note of the account by id
+    #[serde(rename = "id")]
+    Id(NoteId),
+}
+
+impl fmt::Display for NoteSourceConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Official(name) => write!(f, "official note {:?}", name),
+            Self::Name(name) => write!(f, "note {:?}", name),
+            Self::Id(id) => write!(f, "note {}", id),
+        }
+    }
+}
+
+/// the plan of a new server
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerPlanConfig {
+    /// the number of cpus
+    #[serde()]
+    pub cpu: u32,
+
+    /// the memory in GB
+    #[serde()]
+    pub memory_gb: u64,
+
+    /// e.g. 100 or 200, the latest one if not set, the older ones may be cheaper
+    #[serde(default)]
+    pub generation: Option<u32>,
+
+    /// the commitment of the plan, standard by default
+    #[serde(default)]
+    pub commitment: ServerPlanCommitment,
+}
+
+impl Config {
+    /// reads the config json and resolves its secrets
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).map_err(|e| Error::ConfigCouldntRead(path.to_path_buf(), e.to_string()))?;
+        // the message of serde_json has the line and the column
+        let mut config: Self = serde_json::from_str(&json).map_err(|e| Error::ConfigInvalid(path.to_path_buf(), e.to_string()))?;
+        config.resolve_secrets()?;
+        Ok(config)
+    }
+
+    // secrets are kept out of config.json by ${ENV_VAR}, keychain:<service>:<account> or a file
+    fn resolve_secrets(&mut self) -> Result<(), Error> {
+        // not needed without the feature
+        if let Some(wireguard) = self.server.wireguard.as_mut().filter(|_| self.features.is_enabled(Feature::Wireguard)) {
+            let interface = &mut wireguard.interface;
+            if let Some(private_key_file) = &interface.private_key_file {
+                let private_key = fs::read_to_string(private_key_file)
+                    .map_err(|e| Error::ConfigSecretCouldntResolve("server.wireguard.interface.private_key_file".to_string(), e.to_string()))?;
+                interface.private_key = private_key.trim().to_string();
+            }
+            interface.private_key = resolve_secret("server.wireguard.interface.private_key", &interface.private_key)?;
+        }
+
+        if let Some(object_storage) = &mut self.object_storage {
+            object_storage.access_key_id = resolve_secret("object_storage.access_key_id", &object_storage.access_key_id)?;
+            object_storage.secret_access_key = resolve_secret("object_storage.secret_access_key", &object_storage.secret_access_key)?;
+        }
+
+        if let Some(notifier) = &mut self.notifier {
+            notifier.webhook_url = resolve_secret("notifier.webhook_url", &notifier.webhook_url)?;
+        }
+        Ok(())
+    }
+
+    /// only with features.wireguard
+    pub fn wireguard(&self) -> Option<&WireGuardConfig> {
+        self.server.wireguard.as_ref().filter(|_| self.features.is_enabled(Feature::Wireguard))
+    }
+
+    /// only with features.docker
+    pub fn docker(&self) -> Option<&DockerConfig> {
+        self.docker.as_ref().filter(|_| self.features.is_enabled(Feature::Docker))
+    }
+
+    /// none if node_exporter isn't exposed to any scrape source
+    pub fn monitoring_agent(&self) -> Option<&MonitoringAgentConfig> {
+        Some(&self.monitoring_agent).filter(|monitoring_agent| self.features.is_enabled(Feature::MonitoringAgent) && !monitoring_agent.scrape_sources.is_empty())
+    }
+
+    /// the tcp ports the ufw of features.hardening allows besides ssh
+    pub fn guest_allowed_ports(&self) -> Vec<u16> {
+        let mut ports = self.hardening.allowed_ports.clone();
+        if self.monitoring_agent().is_some() {
+            ports.push(NODE_EXPORTER_PORT);
+        }
+        ports
+    }
+
+    /// the constraints serde can't check, all problems are returned at once
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let network = &self.network;
+        let subnet = network.subnet_string();
+        // a /30 has no room for the nfs and the mobile gateway
+        if !(8..=29).contains(&network.mask_len) {
+            problems.push(format!("network.mask_len: {} is not in 8 to 29", network.mask_len));
+        } else {
+            let hosts = [
+                ("router_ip", network.router_ip),
+                ("server_ip", network.server_ip),
+                ("nfs_ip", network.nfs_ip),
+                ("mobile_gateway_ip", network.mobile_gateway_ip),
+                ("replacement_server_ip", network.replacement_server_ip),
+            ];
+            let mask = u32::MAX << (32 - network.mask_len as u32);
+            for (i, (name, ip)) in hosts.iter().enumerate() {
+                let host_part = u32::from(*ip) & !mask;
+                if !networks_overlap((*ip, 32), network.subnet()) {
+                    problems.push(format!("network.{}: {} is not in {}", name, ip, subnet));
+                } else if host_part == 0 || host_part == !mask {
+                    problems.push(format!("network.{}: {} is the network or the broadcast address", name, ip));
+                }
+                if let Some((other, _)) = hosts[..i].iter().find(|(_, other_ip)| other_ip == ip) {
+                    problems.push(format!("network.{}: {} is also network.{}", name, ip, other));
+                }
+            }
+        }
+
+        if self.features.is_enabled(Feature::Wireguard) && self.server.wireguard.is_none() {
+            problems.push("server.wireguard: required by features.wireguard".to_string());
+        }
+        if let Some(wireguard) = self.wireguard() {
+            if !is_wireguard_key(&wireguard.interface.private_key) {
+                problems.push("server.wireguard.interface.private_key: not a base64 encoded 32 bytes key".to_string());
+            }
+            if !is_wireguard_key(&wireguard.peer.public_key) {
+                problems.push("server.wireguard.peer.public_key: not a base64 encoded 32 bytes key".to_string());
+            }
+            let mut wireguard_networks = Vec::new();
+            for address in &wireguard.interface.address {
+                // ipv6 addresses are passed through as is
+                if address.contains(':') {
+                    continue;
+                }
+                let Some(network) = parse_ipv4_network(address) else {
+                    problems.push(format!("server.wireguard.interface.address: invalid network: {}", address));
+                    continue;
+                };
+                if networks_overlap(network, self.network.subnet()) {
+                    problems.push(format!("server.wireguard.interface.address: {} overlaps the switch segment {}", address, subnet));
+                }
+                if wireguard_networks.iter().any(|other| networks_overlap(network, *other)) {
+                    problems.push(format!("server.wireguard.interface.address: {} overlaps another address", address));
+                }
+                wireguard_networks.push(network);
+            }
+        }
+
+        let mut local_ports = Vec::new();
+        let mut remote_ports = Vec::new();
+        for forwarding_port in &self.forwarding_ports {
+            if forwarding_port.local_port == 0 || forwarding_port.remote_port == 0 {
+                problems.push(format!("forwarding_ports: port 0 is not forwardable: {} -> {}", forwarding_port.remote_port, forwarding_port.local_port));
+            }
+            let (listen_ports, listen_port) = match forwarding_port.direction {
+                ForwardingDirection::Local => (&mut local_ports, forwarding_port.local_port),
+                ForwardingDirection::Remote => (&mut remote_ports, forwarding_port.remote_port),
+            };
+            if listen_ports.contains(&listen_port) {
+                problems.push(format!("forwarding_ports: {} port {} is listened twice", if forwarding_port.direction == ForwardingDirection::Local { "local" } else { "remote" }, listen_port));
+            }
+            listen_ports.push(listen_port);
+        }
+        if let Some(socks_proxy_port) = self.socks_proxy_port {
+            if local_ports.contains(&socks_proxy_port) {
+                problems.push(format!("socks_proxy_port: {} collides with forwarding_ports", socks_proxy_port));
+            }
+        }
+        if remote_ports.contains(&22) {
+            problems.push("forwarding_ports: remote port 22 is used by sshd".to_string());
+        }
+
+        if let Some(simple_monitor) = &self.simple_monitor {
+            for source_network in &simple_monitor.source_networks {
+                if parse_ipv4_network(source_network).is_none() {
+                    problems.push(format!("simple_monitor.source_networks: invalid network: {}", source_network));
+                }
+            }
+        }
+
+        if let Some(auto_backup) = &self.auto_backup {
+            if !(1..=10).contains(&auto_backup.generations) {
+                problems.push(format!("auto_backup.generations: {} is not in 1 to 10", auto_backup.generations));
+            }
+        }
+
+        if let Some(nfs) = &self.nfs {
+            if !nfs.mount_point.starts_with('/') {
+                problems.push(format!("nfs.mount_point: {} is not absolute", nfs.mount_point));
+            }
+        }
+
+        if let Some(standby) = &self.standby {
+            if let Some(dns) = &standby.dns {
+                if dns.name.is_empty() || dns.name.ends_with('.') {
+                    problems.push(format!("standby.dns.name: must be relative to the zone: {}", dns.name));
+                }
+            }
+        }
+
+        let mut hook_names = Vec::new();
+        for hook in &self.hooks {
+            if hook.name.is_empty() || !hook.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                problems.push(format!("hooks: name must be alphanumeric, - or _: {}", hook.name));
+            }
+            if hook_names.contains(&&hook.name) {
+                problems.push(format!("hooks: name is duplicated: {}", hook.name));
+            }
+            hook_names.push(&hook.name);
+        }
+
+        let mut unit_names = Vec::new();
+        for unit in &self.systemd_units {
+            if unit.name.is_empty() || !unit.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '@') {
+                problems.push(format!("systemd_units: name must be alphanumeric, -, _ or @: {}", unit.name));
+            }
+            if unit_names.contains(&&unit.name) {
+                problems.push(format!("systemd_units: name is duplicated: {}", unit.name));
+            }
+            unit_names.push(&unit.name);
+            for file in &unit.files {
+                if !file.remote.starts_with('/') {
+                    problems.push(format!("systemd_units.files: {} is not absolute", file.remote));
+                }
+                if u32::from_str_radix(&file.mode, 8).is_err() {
+                    problems.push(format!("systemd_units.files: mode {} is not octal", file.mode));
+                }
+            }
+        }
+
+        let mut user_names = Vec::new();
+        for user in &self.users {
+            let valid = (1..=32).contains(&user.name.len()) && user.name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_') && user.name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+            if !valid {
+                problems.push(format!("users: name must be lowercase alphanumeric, - or _: {}", user.name));
+            }
+            if user.name == "root" || user.name == "ubuntu" {
+                problems.push(format!("users: {} is managed by the setup script itself", user.name));
+            }
+            if user_names.contains(&&user.name) {
+                problems.push(format!("users: name is duplicated: {}", user.name));
+            }
+            user_names.push(&user.name);
+            for key in &user.authorized_keys {
+                if key.contains('\n') || key.split_whitespace().count() < 2 {
+                    problems.push(format!("users.{}.authorized_keys: not a single line public key: {}", user.name, key));
+                }
+            }
+            for group in &user.groups {
+                if group.is_empty() || !group.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                    problems.push(format!("users.{}.groups: name must be alphanumeric, - or _: {}", user.name, group));
+                }
+            }
+        }
+
+        if let Some(docker) = &self.docker {
+            if !docker.project_dir.starts_with('/') {
+                problems.push(format!("docker.project_dir: {} is not absolute", docker.project_dir));
+            }
+        }
+
+        if let Some(host_name) = &self.server.host_name {
+            let valid = host_name.len() <= 253 && host_name.split('.').all(|label| {
+                (1..=63).contains(&label.len()) && !label.starts_with('-') && !label.ends_with('-') && label.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            });
+            if !valid {
+                problems.push(format!("server.host_name: {} is not a host name", host_name));
+            }
+        }
+        if let Some(timezone) = &self.server.timezone {
+            if timezone.is_empty() || timezone.starts_with('/') || timezone.contains("..") || !timezone.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '_' | '+' | '-')) {
+                problems.push(format!("server.timezone: {} is not a timezone name, e.g. Asia/Tokyo", timezone));
+            }
+        }
+        if let Some(locale) = &self.server.locale {
+            if locale.is_empty() || !locale.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '@' | '-')) {
+                problems.push(format!("server.locale: {} is not a locale name, e.g. ja_JP.UTF-8", locale));
+            }
+        }
+
+        if self.swap.size_mb == 0 {
+            problems.push("swap.size_mb: 0, disable features.swap instead".to_string());
+        }
+
+        for (key, value) in &self.sysctl {
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/')) {
+                problems.push(format!("sysctl: invalid key: {}", key));
+            }
+            if value.is_empty() || value.contains('\n') {
+                problems.push(format!("sysctl.{}: not a single line value", key));
+            }
+        }
+
+        if let Some(reboot_time) = &self.unattended_upgrades.reboot_time {
+            let valid = reboot_time.split_once(':').map(|(hour, minute)| {
+                hour.len() == 2 && minute.len() == 2 && matches!(hour.parse::<u8>(), Ok(hour) if hour < 24) && matches!(minute.parse::<u8>(), Ok(minute) if minute < 60)
+            });
+            if valid != Some(true) {
+                problems.push(format!("unattended_upgrades.reboot_time: {} is not HH:MM", reboot_time));
+            }
+        }
+
+        for scrape_source in &self.monitoring_agent.scrape_sources {
+            if parse_ipv4_network(scrape_source).is_none() {
+                problems.push(format!("monitoring_agent.scrape_sources: invalid network: {}", scrape_source));
+            }
+        }
+        if [PRIMARY_SERVER_FORWARDED_PORT, REPLACEMENT_SERVER_FORWARDED_PORT].contains(&self.monitoring_agent.forwarded_port) {
+            problems.push(format!("monitoring_agent.forwarded_port: {} is used by ssh", self.monitoring_agent.forwarded_port));
+        }
+
+        for ignore_network in &self.hardening.fail2ban_ignore_networks {
+            if parse_ipv4_network(ignore_network).is_none() {
+                problems.push(format!("hardening.fail2ban_ignore_networks: invalid network: {}", ignore_network));
+            }
+        }
+        if self.hardening.max_auth_tries == 0 {
+            problems.push("hardening.max_auth_tries: must be 1 or more".to_string());
+        }
+
+        if let Some(health_check) = &self.health_check {
+            if !matches!(Url::parse(&health_check.url(Ipv4Addr::LOCALHOST)), Ok(url) if url.scheme() == "https" || url.scheme() == "http") {
+                problems.push(format!("health_check.url: {} is not an http(s) url", health_check.url));
+            }
+            if health_check.attempts == 0 {
+                problems.push("health_check.attempts: must be 1 or more".to_string());
+            }
+        }
+
+        if let Some(notifier) = &self.notifier {
+            if !matches!(Url::parse(&notifier.webhook_url), Ok(url) if url.scheme() == "https" || url.scheme() == "http") {
+                problems.push("notifier.webhook_url: not an http(s) url".to_string());
+            }
+        }
+
+        if let Some(icon) = &self.icon {
+            if icon.id.is_none() && icon.png.is_none() {
+                problems.push("icon: either id or png is required".to_string());
+            }
+        }
+
+        problems
+    }
+}
+
+fn resolve_secret(field: &str, value: &str) -> Result<String, Error> {
+    let error = |reason: String| Error::ConfigSecretCouldntResolve(field.to_string(), reason);
+
+    if let Some(keychain) = value.strip_prefix("keychain:") {
+        let Some((service, account)) = keychain.split_once(':') else {
+            return Err(error(format!("keychain:<service>:<account> expected: {}", value)));
+        };
+        return keychain_password(service, account).map_err(error);
+    }
+
+    let mut resolved = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            return Err(error(format!("unclosed ${{: {}", value)));
+        };
+        let name = &rest[start + 2..start + len];
+        let env_value = env::var(name).map_err(|e| error(format!("{}: {}", name, e)))?;
+        resolved.push_str(&rest[..start]);
+        resolved.push_str(&env_value);
+        rest = &rest[start + len + 1..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
+
+// security on macos, secret-tool of libsecret otherwise
+fn keychain_password(service: &str, account: &str) -> Result<String, String> {
+    let output = if cfg!(target_os = "macos") {
+        Command::new("security").args(["find-generic-password", "-s", service, "-a", account, "-w"]).output()
+    } else {
+        Command::new("secret-tool").args(["lookup", "service", service, "account", account]).output()
+    };
+    let output = output.map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("{}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim_end_matches('\n').to_string())
+}
+
+fn store_keychain_password(service: &str, account: &str, password: &str) -> Result<(), String> {
+    let output = if cfg!(target_os = "macos") {
+        Command::new("security").args(["add-generic-password", "-U", "-s", service, "-a", account, "-w", password]).output()
+    } else {
+        // the password is read from stdin, not to be seen in the process list
+        let child = Command::new("secret-tool")
+            .args(["store", &format!("--label={} {}", service, account), "service", service, "account", account])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        child.and_then(|mut child| {
+            child.stdin.take().expect("stdin must be piped").write_all(password.as_bytes())?;
+            child.wait_with_output()
+        })
+    };
+    let output = output.map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("{}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+const DISK_PASSWORD_KEYCHAIN_SERVICE: &str = "sacloud-random-tools-disk-password";
+
+// one password per prefix and zone, the disks copied from the primary one have the same password
+fn disk_password_account(prefix: &str) -> Result<String, Error> {
+    Ok(format!("{}@{}", prefix, api::zone()?))
+}
+
+/// stores the root password of the disks of the prefix in the keychain
+pub fn store_disk_password(prefix: impl AsRef<str>, password: &str) -> Result<(), Error> {
+    let account = disk_password_account(prefix.as_ref())?;
+    store_keychain_password(DISK_PASSWORD_KEYCHAIN_SERVICE, &account, password).map_err(|e| Error::DiskPasswordCouldntStore(account, e))
+}
+
+/// the root password of the disks of the prefix from the keychain
+pub fn disk_password(prefix: impl AsRef<str>) -> Result<String, Error> {
+    let account = disk_password_account(prefix.as_ref())?;
+    keychain_password(DISK_PASSWORD_KEYCHAIN_SERVICE, &account).map_err(|e| Error::DiskPasswordCouldntRead(account, e))
+}
+
+/// the setup shell note and then the notes of the config with the variables, all of them must be available before the disk uses them
+pub async fn disk_notes(prefix: impl AsRef<str>, setup_shell_note_id: impl Borrow<NoteId>) -> Result<Vec<(NoteId, Value)>, Error> {
+    let prefix = prefix.as_ref();
+    let mut pairs = vec![(setup_shell_note_id.borrow().clone(), CONFIG.server.note_variables(prefix).to_value())];
+    for note_config in &CONFIG.server.notes {
+        let note = match &note_config.source {
+            NoteSourceConfig::Official(name) => Note::official_startup_script(name).await?,
+            NoteSourceConfig::Name(name) => Note::get_by_name(name).await?,
+            NoteSourceConfig::Id(id) => Some(Note::get(id).await?),
+        };
+        let Some(note) = note else {
+            return Err(Error::NoteNotFound(note_config.source.to_string()));
+        };
+        if !note.is_available() {
+            return Err(Error::NoteNotAvailable(note_config.source.to_string(), note.availability()));
+        }
+        let variables = CONFIG.server.note_variables(prefix).merge(&note_config.variables);
+        pairs.push((note.id().clone(), variables.to_value()));
+    }
+    Ok(pairs)
+}
+
+/// the prefixes of the environments in the zone, found by the names of the switches, the vpc routers and the servers
+/// created by this tool, a half created or half cleaned environment still has one of them
+pub async fn search_prefixes() -> Result<BTreeSet<String>, Error> {
+    let summaries = search_environment_summaries().await?;
+    Ok(summaries.into_iter().map(|(prefix, _)| prefix).collect())
+}
+
+async fn search_environment_summaries() -> Result<Vec<(String, api::ResourceSummary)>, Error> {
+    let mut summaries = Vec::new();
+    for (resource_kind, equipment_kind) in [
+        (api::ResourceKind::Switch, EquipmentKind::PrimarySwitch),
+        (api::ResourceKind::Appliance, EquipmentKind::PrimaryVpcRouter),
+        (api::ResourceKind::Server, EquipmentKind::PrimaryServer),
+    ] {
+        // the same names created outside of this tool, e.g. web-server, are not environments
+        let query = api::SearchQuery::new().tags([api::MANAGED_TAG]);
+        for summary in resource_kind.search_summaries(&query).await? {
+            if let Some(prefix) = summary.name().and_then(|name| equipment_kind.prefix_of(name)) {
+                summaries.push((prefix.to_string(), summary));
+            }
+        }
+    }
+    Ok(summaries)
+}
+
+// `update --ttl` tags the switch, the vpc router and the server with the unix time, and `reap` cleans the environment after it
+const EXPIRES_AT_TAG_PREFIX: &str = "expires-at-";
+
+/// the earliest expiry in the tags as the unix time, none without the tag
+pub fn expires_at_of(tags: &[String]) -> Option<u64> {
+    tags.iter().filter_map(|tag| tag.strip_prefix(EXPIRES_AT_TAG_PREFIX)?.parse().ok()).min()
+}
+
+fn with_expires_at(tags: &[String], expires_at: u64) -> Vec<String> {
+    let mut tags = tags.iter().filter(|tag| !tag.starts_with(EXPIRES_AT_TAG_PREFIX)).cloned().collect::<Vec<_>>();
+    tags.push(format!("{}{}", EXPIRES_AT_TAG_PREFIX, expires_at));
+    tags
+}
+
+/// the later update extends or shortens the expiry
+pub async fn set_expires_at(prefix: impl AsRef<str>, expires_at: u64) -> Result<(), Error> {
+    let prefix = prefix.as_ref();
+    let mut resources = Vec::new();
+    if let Some(switch) = PrimarySwitch::try_get(prefix).await? {
+        resources.push((api::ResourceKind::Switch, switch.id().to_string()));
+    }
+    if let Some(vpc_router) = PrimaryVpcRouter::try_get(prefix).await? {
+        resources.push((api::ResourceKind::Appliance, vpc_router.id().to_string()));
+    }
+    if let Some(server) = PrimaryServer::try_get(prefix).await? {
+        resources.push((api::ResourceKind::Server, server.id().to_string()));
+    }
+    for (kind, id) in resources {
+        let resource_value = kind.get(&id).await?;
+        let tags = serde_json::from_value::<Vec<String>>(resource_value["Tags"].clone()).unwrap_or_default();
+        kind.update(&id, json!({ "Tags": with_expires_at(&tags, expires_at) })).await?;
+    }
+    Ok(())
+}
+
+/// the ip the server reports on the switch, the one of its color in config until the disk edit sets it
+pub async fn active_server_ip(prefix: impl AsRef<str>) -> Result<Ipv4Addr, Error> {
+    let prefix = prefix.as_ref();
+    let Some(server) = PrimaryServer::try_get(prefix).await? else {
+        return Ok(CONFIG.network.server_ip);
+    };
+    let switch_ip = match PrimarySwitch::try_get(prefix).await? {
+        Some(switch) => server.switch_ip(switch.id()),
+        None => None,
+    };
+    Ok(switch_ip.unwrap_or(server.private_ip()))
+}
+
+/// the earliest expiry of each prefix in the zone, the ones without --ttl are not included
+pub async fn search_expires_at() -> Result<BTreeMap<String, u64>, Error> {
+    let mut expires_at_by_prefix = BTreeMap::<String, u64>::new();
+    for (prefix, summary) in search_environment_summaries().await? {
+        if let Some(expires_at) = expires_at_of(summary.tags()) {
+            let earliest = expires_at_by_prefix.entry(prefix).or_insert(expires_at);
+            *earliest = (*earliest).min(expires_at);
+        }
+    }
+    Ok(expires_at_by_prefix)
+}
+
+/// the archives named with the prefix, except the migration archive which `migrate` resumes from
+/// and the golden archives, which `bake` prunes by itself
+pub async fn search_archives(prefix: impl AsRef<str>) -> Result<Vec<Archive>, Error> {
+    let prefix = prefix.as_ref();
+    let name_prefix = format!("{}-", prefix);
+    let migration_archive_name = EquipmentKind::MigrationArchive.name(prefix);
+    let archives = Archive::search_by_name(&name_prefix).await?;
+    Ok(archives.into_iter()
+        .filter(|archive| archive.name().map(|name| {
+            name.starts_with(&name_prefix) && name != migration_archive_name && GoldenArchive::version_of(prefix, name).is_none()
+        }).unwrap_or(false))
+        .collect())
+}
+
+/// (kept, pruned), the newest `keep` ones and the ones younger than keep_days are kept, so are the ones of unknown age
+pub fn partition_by_retention(archives: Vec<Archive>, keep: usize, keep_days: Option<u64>, now: u64) -> (Vec<Archive>, Vec<Archive>) {
+    let mut archives = archives.into_iter()
+        .map(|archive| (archive.created_at().and_then(api::unix_time), archive))
+        .collect::<Vec<_>>();
+    // the newest first, the unknown ones last
+    archives.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    let mut kept = Vec::new();
+    let mut pruned = Vec::new();
+    for (i, (created_at, archive)) in archives.into_iter().enumerate() {
+        let young = match (created_at, keep_days) {
+            (None, _) => true,
+            (Some(created_at), Some(keep_days)) => now.saturating_sub(created_at) < keep_days * 24 * 60 * 60,
+            (Some(_), None) => false,
+        };
+        if i < keep || young {
+            kept.push(archive);
+        } else {
+            pruned.push(archive);
+        }
+    }
+    (kept, pruned)
+}
+
+/// 20 alphanumerics with a lower, an upper and a digit at least, which every password policy accepts
+pub fn generate_password() -> String {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    loop {
+        let mut password = String::new();
+        while password.len() < 20 {
+            let mut bytes = [0u8; 32];
+            getrandom::getrandom(&mut bytes).expect("the os random source must be available");
+            // the rejection keeps the distribution uniform
+            for byte in bytes.into_iter().filter(|byte| (*byte as usize) < CHARS.len() * 4) {
+                if password.len() < 20 {
+                    password.push(CHARS[byte as usize % CHARS.len()] as char);
+                }
+            }
+        }
+        if password.chars().any(|c| c.is_ascii_lowercase()) && password.chars().any(|c| c.is_ascii_uppercase()) && password.chars().any(|c| c.is_ascii_digit()) {
+            return password;
+        }
+    }
+}
+
+fn is_wireguard_key(key: &str) -> bool {
+    matches!(BASE64_STANDARD.decode(key), Ok(bytes) if bytes.len() == 32)
+}
+
+// a.b.c.d/n, the host part is allowed
+fn parse_ipv4_network(network: &str) -> Option<(Ipv4Addr, u8)> {
+    let (ip, mask_len) = network.split_once('/')?;
+    let ip = ip.parse().ok()?;
+    let mask_len = mask_len.parse().ok().filter(|mask_len| *mask_len <= 32)?;
+    Some((ip, mask_len))
+}
+
+fn networks_overlap((ip1, mask_len1): (Ipv4Addr, u8), (ip2, mask_len2): (Ipv4Addr, u8)) -> bool {
+    let mask_len = mask_len1.min(mask_len2);
+    let mask = u32::MAX.checked_shl(32 - mask_len as u32).unwrap_or(0);
+    u32::from(ip1) & mask == u32::from(ip2) & mask
+}
+
+// the example config, only for trying commands without the config file
+impl Default for Config {
+    fn default() -> Self {
+        serde_json::from_str(EXAMPLE_CONFIG_JSON).expect("example config must be valid")
+    }
+}
+
+/// config/config.json in the current dir is used if the path is not given, and the example if it doesn't exist either
+pub fn load_config(path: Option<&Path>) -> Result<(), Error> {
+    let config = match path {
+        Some(path) => Config::load(path)?,
+        None if Path::new(DEFAULT_CONFIG_PATH).exists() => Config::load(DEFAULT_CONFIG_PATH)?,
+        None => {
+            log::warn!("[NOTE] {} not found, the example config is used", DEFAULT_CONFIG_PATH);
+            Config::default()
+        },
+    };
+    LOADED_CONFIG.set(config).map_err(|_| Error::ConfigAlreadyLoaded)
+}
+
+// the example config for the tests, which share one process
+#[cfg(test)]
+pub(crate) fn load_example_config() {
+    LOADED_CONFIG.get_or_init(Config::default);
+}
+
+/// the git user of the server and the local repo
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitConfig {
+    /// the user.name of git
+    #[serde()]
+    pub user: String,
+
+    /// the user.email of git
+    #[serde()]
+    pub email: String,
+
+    /// the local path of the repo
+    #[serde()]
+    pub local_repo_path: PathBuf,
+}
+
+/// the wireguard tunnel the server goes out through
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WireGuardConfig {
+    /// the wg0.conf interface of the server
+    #[serde()]
+    pub interface: WireGuardInterfaceConfig,
+
+    /// the peer the server connects to
+    #[serde()]
+    pub peer: WireGuardPeerConfig,
+}
+
+impl WireGuardConfig {
+    /// wg0.conf of another device behind the same peer, the same as the server's without the ssh routing
+    pub fn client_conf(&self, private_key: &str, addresses: &[String], allowed_ips: &[String]) -> String {
+        let dns = self.interface.dns.iter().map(|dns| dns.to_string()).collect::<Vec<_>>();
+        let mut conf = String::new();
+        conf.push_str("[Interface]\n");
+        conf.push_str(&format!("PrivateKey = {}\n", private_key));
+        conf.push_str(&format!("Address = {}\n", addresses.join(", ")));
+        if !dns.is_empty() {
+            conf.push_str(&format!("DNS = {}\n", dns.join(", ")));
+        }
+        conf.push_str("MTU = 1280\n");
+        conf.push_str("\n[Peer]\n");
+        conf.push_str(&format!("PublicKey = {}\n", self.peer.public_key));
+        conf.push_str(&format!("Endpoint = {}:51820\n", self.peer.endpoint));
+        conf.push_str("PersistentKeepalive = 25\n");
+        conf.push_str(&format!("AllowedIPs = {}\n", allowed_ips.join(", ")));
+        conf
+    }
+
+    /// the same ip as the server's would take over its tunnel
+    pub fn client_address_problems(&self, addresses: &[String]) -> Vec<String> {
+        let server_ips = self.interface.address.iter().filter_map(|address| address.split_once('/').map(|(ip, _)| ip)).collect::<Vec<_>>();
+        let mut problems = Vec::new();
+        for address in addresses {
+            if !address.contains(':') && parse_ipv4_network(address).is_none() {
+                problems.push(format!("--address: invalid network: {}", address));
+            }
+            if address.split_once('/').map(|(ip, _)| server_ips.contains(&ip)).unwrap_or(false) {
+                problems.push(format!("--address: {} is the address of the server", address));
+            }
+        }
+        problems
+    }
+}
+
+/// (private key, public key) by `wg genkey` and `wg pubkey`
+pub fn generate_wireguard_key_pair() -> Result<(String, String), Error> {
+    let output = Command::new("wg").arg("genkey").output().map_err(|e| Error::WireGuardKeyCouldntGenerate(e.to_string()))?;
+    if !output.status.success() {
+        return Err(Error::WireGuardKeyCouldntGenerate(format!("{}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    let private_key = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let public_key = wireguard_public_key(&private_key)?;
+    Ok((private_key, public_key))
+}
+
+/// the public key of the private key by `wg pubkey`, an error unless it's the base64 of 32 bytes
+pub fn wireguard_public_key(private_key: &str) -> Result<String, Error> {
+    if !is_wireguard_key(private_key) {
+        return Err(Error::WireGuardKeyInvalid);
+    }
+    // the private key is read from stdin, not to be seen in the process list
+    let child = Command::new("wg")
+        .arg("pubkey")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let output = child.and_then(|mut child| {
+        child.stdin.take().expect("stdin must be piped").write_all(private_key.as_bytes())?;
+        child.wait_with_output()
+    }).map_err(|e| Error::WireGuardKeyCouldntGenerate(e.to_string()))?;
+    if !output.status.success() {
+        return Err(Error::WireGuardKeyCouldntGenerate(format!("{}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// the interface of the wg0.conf of the server
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WireGuardInterfaceConfig {
+    /// `${ENV_VAR}` and `keychain:<service>:<account>` are resolved on load
+    #[serde(default)]
+    pub private_key: String,
+
+    /// preferred to private_key, e.g. the output of wg genkey
+    #[serde(default)]
+    pub private_key_file: Option<PathBuf>,
+
+    /// the Address of the wg0.conf of the server, e.g. 10.0.0.2/32
+    #[serde()]
+    pub address: Vec<String>,
+
+    /// the DNS of the wg0.conf
+    #[serde()]
+    pub dns: Vec<IpAddr>,
+}
+
+/// the peer of the wg0.conf of the server
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WireGuardPeerConfig {
+    /// the public key of the peer
+    #[serde()]
+    pub public_key: String,
+
+    /// the endpoint of the peer, the port is 51820
+    #[serde()]
+    pub endpoint: IpAddr,
+}
+
+/// the server and its disk are replaced by the other color with `update --replace-server`, an untagged server is blue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerColor {
+    /// the color of an untagged server
+    Blue,
+    /// the other color
+    Green,
+}
+
+impl ServerColor {
+    const TAG_PREFIX: &'static str = "color-";
+
+    /// the color in the tags and the resource names
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Blue => "blue",
+            Self::Green => "green",
+        }
+    }
+
+    /// the other color
+    pub fn other(&self) -> Self {
+        match self {
+            Self::Blue => Self::Green,
+            Self::Green => Self::Blue,
+        }
+    }
+
+    /// the private ip of the server of this color
+    pub fn ip(&self) -> Ipv4Addr {
+        match self {
+            Self::Blue => CONFIG.network.server_ip,
+            Self::Green => CONFIG.network.replacement_server_ip,
+        }
+    }
+
+    /// e.g. dev-server-green, the replacement until the switch and the old one after it
+    pub fn resource_name(&self, prefix: impl AsRef<str>) -> String {
+        format!("{}-{}", EquipmentKind::PrimaryServer.name(prefix), self.name())
+    }
+
+    fn tag(&self) -> String {
+        format!("{}{}", Self::TAG_PREFIX, self.name())
+    }
+
+    fn of_tags(tags: &[String]) -> Self {
+        if tags.iter().any(|tag| *tag == Self::Green.tag()) { Self::Green } else { Self::Blue }
+    }
+}
+
+/// the server of the prefix
+#[derive(Debug)]
+pub struct PrimaryServer {
+    server: Server,
+}
+
+impl PrimaryServer {
+    const KIND: EquipmentKind = EquipmentKind::PrimaryServer;
+
+    /// the server of the prefix, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let prefix = prefix.as_ref();
+        let name = Self::KIND.name(prefix);
+
+        let server = Server::get_by_name(&name).await?;
+        Ok(server.map(|server| Self { server }))
+    }
+
+    /// the server of the color, none if it doesn't exist
+    pub async fn try_get_replacement(prefix: impl AsRef<str>, color: ServerColor) -> Result<Option<Self>, Error> {
+        let server = Server::get_by_name(color.resource_name(prefix)).await?;
+        Ok(server.map(|server| Self { server }))
+    }
+
+    /// creates the server of the prefix on the switch, blue
+    pub async fn create(prefix: impl AsRef<str>, switch_id: impl Borrow<SwitchId>, icon_id: Option<&IconId>) -> Result<Self, Error> {
+        let prefix = prefix.as_ref();
+        Self::create_named(prefix, Self::KIND.name(prefix), ServerColor::Blue, switch_id.borrow(), icon_id).await
+    }
+
+    /// alongside the current server, with the other color's ip
+    pub async fn create_replacement(prefix: impl AsRef<str>, color: ServerColor, switch_id: impl Borrow<SwitchId>, icon_id: Option<&IconId>) -> Result<Self, Error> {
+        let prefix = prefix.as_ref();
+        Self::create_named(prefix, color.resource_name(prefix), color, switch_id.borrow(), icon_id).await
+    }
+
+    // the host name is of the primary name, so it's kept by the replacement
+    async fn create_named(prefix: &str, name: String, color: ServerColor, switch_id: &SwitchId, icon_id: Option<&IconId>) -> Result<Self, Error> {
+        let server_plan_id = Self::plan_id().await?;
+
+        let mut server_info_builder = ServerInfo::builder()
+            .name(name.clone())
+            .server_plan(server_plan_id)
+            .description(name.clone())
+            .host_name(CONFIG.server.host_name(&Self::KIND.name(prefix)))
+            .tags(vec![color.tag()])
+            .connected_switch_ids(vec![switch_id.clone()])
+            .interface_driver(InterfaceDriver::Virtio)
+            .wait_disk_migration(true);
+        if let Some(icon_id) = icon_id {
+            server_info_builder = server_info_builder.icon_id(icon_id.clone());
+        }
+        if let Some(private_host_id) = &CONFIG.server.private_host_id {
+            server_info_builder = server_info_builder.private_host_id(private_host_id.clone());
+        }
+        let server_info = server_info_builder.build();
+
+        let server = Server::create(server_info).await?;
+
+        Ok(Self { server })
+    }
+
+    async fn plan_id() -> Result<ServerPlanId, Error> {
+        if CONFIG.server.plan.is_none() {
+            return Ok(SERVER_PLAN_ID.clone());
+        }
+        Ok(Self::plan().await?.id().clone())
+    }
+
+    /// the plan a new server is created with
+    pub async fn plan() -> Result<ServerPlan, Error> {
+        let Some(plan) = &CONFIG.server.plan else {
+            let server_plan = ServerPlan::search_available().await?.into_iter().find(|server_plan| server_plan.id() == &*SERVER_PLAN_ID);
+            return server_plan.ok_or(Error::ServerPlanNotFound(1, 1024, None, ServerPlanCommitment::Standard));
+        };
+        let memory_mb = plan.memory_gb * 1024;
+        match ServerPlan::find(plan.cpu, memory_mb, plan.generation, plan.commitment).await? {
+            Some(server_plan) => Ok(server_plan),
+            None => Err(Error::ServerPlanNotFound(plan.cpu, memory_mb, plan.generation, plan.commitment)),
+        }
+    }
+
+    /// the replacement takes the primary name, and the old one is kept by its color until it's deleted
+    pub async fn promote(&self, prefix: impl AsRef<str>) -> Result<(), Error> {
+        api::ResourceKind::Server.rename(self.id().to_string(), Self::KIND.name(prefix)).await?;
+        Ok(())
+    }
+
+    /// renames the server after its color
+    pub async fn retire(&self, prefix: impl AsRef<str>) -> Result<(), Error> {
+        api::ResourceKind::Server.rename(self.id().to_string(), self.color().resource_name(prefix)).await?;
+        Ok(())
+    }
+
+    /// the id of the server
+    pub fn id(&self) -> &ServerId {
+        self.server.id()
+    }
+
+    /// the name of the server
+    pub fn name(&self) -> Option<&str> {
+        self.server.name()
+    }
+
+    /// the color in the tags of the server
+    pub fn color(&self) -> ServerColor {
+        ServerColor::of_tags(self.server.tags())
+    }
+
+    /// the private ip of the color of the server
+    pub fn private_ip(&self) -> Ipv4Addr {
+        self.color().ip()
+    }
+
+    /// the ids of the interfaces of the server
+    pub fn interface_ids(&self) -> Vec<&InterfaceId> {
+        self.server.interface_ids()
+    }
+
+    /// the interfaces of the server
+    pub fn interfaces(&self) -> &[Interface] {
+        self.server.interfaces()
+    }
+
+    /// the ip of the server on the switch
+    pub fn switch_ip(&self, switch_id: &SwitchId) -> Option<Ipv4Addr> {
+        self.server.user_ip_address_on(switch_id)
+    }
+
+    /// the id of the cdrom inserted in the server
+    pub fn inserted_cdrom_id(&self) -> Option<&CdromId> {
+        self.server.inserted_cdrom_id()
+    }
+
+    /// the cpus and the memory MB of the plan
+    pub fn plan_size(&self) -> Option<(u32, u64)> {
+        self.server.plan_size()
+    }
+
+    /// the generation of the plan
+    pub fn plan_generation(&self) -> Option<u32> {
+        self.server.plan_generation()
+    }
+
+    /// the commitment of the plan, standard if the api doesn't tell
+    pub fn plan_commitment(&self) -> ServerPlanCommitment {
+        self.server.plan_commitment().unwrap_or_default()
+    }
+}
+
+/// the disk of the primary server
+#[derive(Debug)]
+pub struct PrimaryServerDisk {
+    disk: Disk,
+}
+
+impl PrimaryServerDisk {
+    const KIND: EquipmentKind = EquipmentKind::PrimaryServerDisk;
+
+    /// the disk of the prefix, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let prefix = prefix.as_ref();
+        let name = Self::KIND.name(prefix);
+
+        let disk = Disk::get_by_name(&name).await?;
+        Ok(disk.map(|disk| Self { disk }))
+    }
+
+    /// the disk of the color, none if it doesn't exist
+    pub async fn try_get_replacement(prefix: impl AsRef<str>, color: ServerColor) -> Result<Option<Self>, Error> {
+        let disk = Disk::get_by_name(color.resource_name(prefix)).await?;
+        Ok(disk.map(|disk| Self { disk }))
+    }
+
+    /// named and addressed after the server, so the replacement's disk is the replacement too
+    pub async fn create_for_server(
+        prefix: impl AsRef<str>,
+        server: &PrimaryServer,
+        archive_id: impl Borrow<ArchiveId>,
+        notes: Vec<(NoteId, Value)>,
+        ssh_public_key_ids: &[SshPublicKeyId],
+        size_mb: u64,
+        password: Option<&str>,
+    ) -> Result<Self, Error> {
+        let prefix = prefix.as_ref();
+        let archive_id = archive_id.borrow();
+        let name = server.name().map(str::to_string).unwrap_or(Self::KIND.name(prefix));
+
+        let info = DiskInfo::builder()
+            .name(name.clone())
+            .description(name.clone())
+            .plan_id(DISK_PLAN_ID.clone())
+            .source_archive_id(archive_id.clone())
+            .size_mb(size_mb)
+            .connection(DiskConnection::Virtio)
+            .server_id(server.id().clone())
+            .build();
+
+        let mut config = DiskConfig::builder()
+            .host_name(CONFIG.server.host_name(&Self::KIND.name(prefix)))
+            .ssh_key_ids(ssh_public_key_ids.to_vec())
+            .user_ip_address(server.private_ip())
+            .user_subnet(Ipv4Net::new(CONFIG.network.router_ip, CONFIG.network.mask_len))
+            .change_partition_uuid(false)
+            .enable_dhcp(false)
+            .disable_pw_auth(true)
+            .note_id_and_variables_pairs(notes);
+        // the ssh password auth is still disabled, the password is only for the console
+        if let Some(password) = password {
+            config = config.password(password);
+        }
+        let config = config.build();
+
+        let disk = Disk::create(info, config).await?;
+
+        Ok(Self { disk })
+
+    }
+
+    /// renamed along with the server, see PrimaryServer::promote
+    pub async fn promote(&self, prefix: impl AsRef<str>) -> Result<(), Error> {
+        api::ResourceKind::Disk.rename(self.id().to_string(), Self::KIND.name(prefix)).await?;
+        Ok(())
+    }
+
+    /// renames the disk after the color
+    pub async fn retire(&self, prefix: impl AsRef<str>, color: ServerColor) -> Result<(), Error> {
+        api::ResourceKind::Disk.rename(self.id().to_string(), color.resource_name(prefix)).await?;
+        Ok(())
+    }
+
+    /// the id of the disk
+    pub fn id(&self) -> &DiskId {
+        self.disk.id()
+    }
+
+    /// the server the disk is attached to, none if it's detached
+    pub fn server_id(&self) -> Option<&ServerId> {
+        self.disk.server_id()
+    }
+
+    /// the plan id of the disk
+    pub fn plan_id(&self) -> Option<&DiskPlanId> {
+        self.disk.plan_id()
+    }
+
+    /// the size of the disk in MB
+    pub fn size_mb(&self) -> Option<u64> {
+        self.disk.size_mb()
+    }
+}
+
+
+/// the ssh public key registered with the new disks
+#[derive(Debug)]
+pub struct PrimaryServerSshPublicKey {
+    ssh_public_key: SshPublicKey,
+}
+
+impl PrimaryServerSshPublicKey {
+    const KIND: EquipmentKind = EquipmentKind::PrimaryServerSshPublicKey;
+
+    /// the key of the prefix, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let prefix = prefix.as_ref();
+        let kind = EquipmentKind::PrimaryServerSshPublicKey;
+        let name = kind.name(prefix);
+
+        let ssh_public_key = SshPublicKey::get_by_name(&name).await?;
+        Ok(ssh_public_key.map(|ssh_public_key| Self { ssh_public_key }))
+    }
+
+    /// registers the key of the prefix
+    pub async fn create(prefix: impl AsRef<str>, public_key: impl AsRef<str>) -> Result<Self, Error> {
+        Self::create_named(Self::KIND.name(prefix), public_key).await
+    }
+
+    // the new key of rotate-key is registered under this name until it's verified on the server
+    fn rotating_name(prefix: impl AsRef<str>) -> String {
+        format!("{}-rotating", Self::KIND.name(prefix))
+    }
+
+    /// the key being rotated by rotate-key, none if it doesn't exist
+    pub async fn try_get_rotating(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let ssh_public_key = SshPublicKey::get_by_name(Self::rotating_name(prefix)).await?;
+        Ok(ssh_public_key.map(|ssh_public_key| Self { ssh_public_key }))
+    }
+
+    /// registers the new key of rotate-key
+    pub async fn create_rotating(prefix: impl AsRef<str>, public_key: impl AsRef<str>) -> Result<Self, Error> {
+        Self::create_named(Self::rotating_name(prefix), public_key).await
+    }
+
+    /// the rotating key takes the name of the primary one
+    pub async fn promote(&self, prefix: impl AsRef<str>) -> Result<(), Error> {
+        api::ResourceKind::SshPublicKey.rename(self.id().to_string(), Self::KIND.name(prefix)).await?;
+        Ok(())
+    }
+
+    /// the replaced key is kept under another name, so it's not used for the new disks
+    pub async fn retire(&self, prefix: impl AsRef<str>) -> Result<(), Error> {
+        api::ResourceKind::SshPublicKey.rename(self.id().to_string(), format!("{}-retired-{}", Self::KIND.name(prefix), self.id())).await?;
+        Ok(())
+    }
+
+    async fn create_named(name: String, public_key: impl AsRef<str>) -> Result<Self, Error> {
+        let info = SshPublicKeyInfo::builder()
+            .name(name.clone())
+            .description(name.clone())
+            .public_key(public_key.as_ref())
+            .build();
+        let ssh_public_key = SshPublicKey::create(info).await?;
+        Ok(Self { ssh_public_key })
+    }
+
+    /// the id of the key
+    pub fn id(&self) -> &SshPublicKeyId {
+        self.ssh_public_key.id()
+    }
+
+    /// the public key
+    pub fn public_key(&self) -> &str {
+        self.ssh_public_key.public_key()
+    }
+}
+
+/// the keys of `ssh.extra_pubkeys`, numbered from 1 in the order of the config
+#[derive(Debug)]
+pub struct ExtraServerSshPublicKey {
+    ssh_public_key: SshPublicKey,
+}
+
+impl ExtraServerSshPublicKey {
+    /// the name of the extra key of the number
+    pub fn name(prefix: impl AsRef<str>, number: usize) -> String {
+        format!("{}-{}", EquipmentKind::PrimaryServerSshPublicKey.name(prefix), number)
+    }
+
+    /// whether the name is of an extra key of the prefix
+    pub fn is_name(prefix: impl AsRef<str>, name: &str) -> bool {
+        let key_name = EquipmentKind::PrimaryServerSshPublicKey.name(prefix);
+        matches!(name.strip_prefix(&key_name).and_then(|rest| rest.strip_prefix('-')), Some(number) if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+    }
+
+    /// the extra key of the number, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>, number: usize) -> Result<Option<Self>, Error> {
+        let ssh_public_key = SshPublicKey::get_by_name(Self::name(prefix, number)).await?;
+        Ok(ssh_public_key.map(|ssh_public_key| Self { ssh_public_key }))
+    }
+
+    /// also the ones removed from the config since
+    pub async fn all(prefix: impl AsRef<str>) -> Result<Vec<Self>, Error> {
+        let prefix = prefix.as_ref();
+        let ssh_public_keys = SshPublicKey::search_by_name(EquipmentKind::PrimaryServerSshPublicKey.name(prefix)).await?;
+        Ok(ssh_public_keys.into_iter()
+            .filter(|ssh_public_key| ssh_public_key.name().is_some_and(|name| Self::is_name(prefix, name)))
+            .map(|ssh_public_key| Self { ssh_public_key })
+            .collect())
+    }
+
+    /// registers the extra key of the number
+    pub async fn create(prefix: impl AsRef<str>, number: usize, public_key: impl AsRef<str>) -> Result<Self, Error> {
+        let name = Self::name(prefix, number);
+        let info = SshPublicKeyInfo::builder()
+            .name(name.clone())
+            .description(name.clone())
+            .public_key(public_key.as_ref())
+            .build();
+        let ssh_public_key = SshPublicKey::create(info).await?;
+        Ok(Self { ssh_public_key })
+    }
+
+    /// the id of the key
+    pub fn id(&self) -> &SshPublicKeyId {
+        self.ssh_public_key.id()
+    }
+
+    /// the public key
+    pub fn public_key(&self) -> &str {
+        self.ssh_public_key.public_key()
+    }
+}
+
+
+/// the switch between the server and the vpc router
+#[derive(Debug)]
+pub struct PrimarySwitch {
+    switch: Switch,
+}
+
+impl PrimarySwitch {
+    const KIND: EquipmentKind = EquipmentKind::PrimarySwitch;
+
+    /// the switch of the prefix, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let prefix = prefix.as_ref();
+        let name = Self::KIND.name(prefix);
+
+        let switch = Switch::get_by_name(&name).await?;
+        Ok(switch.map(|switch| Self { switch }))
+    }
+
+    /// creates the switch of the prefix
+    pub async fn create(prefix: impl AsRef<str>, icon_id: Option<&IconId>) -> Result<Self, Error> {
+        let prefix = prefix.as_ref();
+        let name = Self::KIND.name(prefix);
+
+        let mut info_builder = SwitchInfo::builder()
+            .name(name.clone())
+            .description(name.clone());
+        if let Some(icon_id) = icon_id {
+            info_builder = info_builder.icon_id(icon_id.clone());
+        }
+        let info = info_builder.build();
+        let switch = Switch::create(info).await?;
+        Ok(Self { switch })
+    }
+
+    /// the id of the switch
+    pub fn id(&self) -> &SwitchId {
+        self.switch.id()
+    }
+}
+
+
+// `update` tags the vpc router with the unix time it turned the firewall off for the setup scripts, and `watch` leaves it off while tagged
+const SETUP_SINCE_TAG_PREFIX: &str = "setup-since-";
+
+/// the vpc router in front of the server
+#[derive(Debug)]
+pub struct PrimaryVpcRouter {
+    appliance: Appliance,
+}
+
+impl PrimaryVpcRouter {
+    const KIND: EquipmentKind = EquipmentKind::PrimaryVpcRouter;
+
+    /// the vpc router of the prefix, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let prefix = prefix.as_ref();
+        let name = Self::KIND.name(prefix);
+
+        let appliance = Appliance::get_by_name(&name).await?;
+        Ok(appliance.map(|appliance| Self { appliance }))
+    }
+
+    /// creates the vpc router of the prefix
+    pub async fn create(prefix: impl AsRef<str>, icon_id: Option<&IconId>) -> Result<Self, Error> {
+        let prefix = prefix.as_ref();
+        let name = Self::KIND.name(prefix);
+
+        let mut info_builder = ApplianceInfo::builder()
+            .name(name.clone())
+            .description(name.clone())
+            .vpc_router(
+                VpcRouterInfo::builder()
+                    .plan_id(VpcRouterPlanId::new(1))
+                    .remark(
+                        json!({
+                            "Router": { "VPCRouterVersion": 2 },
+                            "Servers": [ {} ],
+                            "Switch": { "Scope": "shared" },
+                        })
+                    )
+                    .settings(
+                        json!({
+                            "Router": {
+                                "InternetConnection": { "Enabled": "True" },
+                            },
+                        })
+                    )
+                    .build()
+            );
+        if let Some(icon_id) = icon_id {
+            info_builder = info_builder.icon_id(icon_id.clone());
+        }
+        let info = info_builder.build();
+        let appliance = Appliance::create(info).await?;
+
+        Ok(Self { appliance })
+    }
+
+    /// the rules from config, without the local ip allowed in front of them on `update_config`
+    pub fn firewall_rules() -> (Vec<Value>, Vec<Value>) {
+        let mut firewall_receive_config = Vec::new();
+        let mut firewall_send_config = Vec::new();
+
+        if let Some(simple_monitor_config) = &CONFIG.simple_monitor {
+            for source_network in &simple_monitor_config.source_networks {
+                firewall_receive_config.push(json!({ "Protocol": "ip", "SourceNetwork": source_network, "Action": "allow", "Description": "simple monitor" }));
+            }
+        }
+
+        for feature in CONFIG.features.enabled() {
+            match feature {
+                Feature::Wireguard => if let Some(wireguard) = CONFIG.wireguard() {
+                    firewall_send_config.push(json!({ "Protocol": "udp", "DestinationNetwork": format!("{}/32", wireguard.peer.endpoint), "DestinationPort": "51820", "Action": "allow", "Description": "wireguard" }));
+                },
+                // matched after the port forwarding, so the private port
+                Feature::MonitoringAgent => if let Some(monitoring_agent) = CONFIG.monitoring_agent() {
+                    for scrape_source in &monitoring_agent.scrape_sources {
+                        firewall_receive_config.push(json!({ "Protocol": "tcp", "SourceNetwork": scrape_source, "DestinationPort": NODE_EXPORTER_PORT.to_string(), "Action": "allow", "Description": "monitoring agent" }));
+                    }
+                },
+                Feature::Docker | Feature::Swap | Feature::UnattendedUpgrades | Feature::Hardening => {},
+            }
+        }
+
+        firewall_receive_config.push(json!({ "Protocol": "ip", "Action": "deny", "Description": "otherwise" }));
+        firewall_send_config.push(json!({ "Protocol": "ip", "Action": "deny", "Description": "otherwise" }));
+
+        (firewall_receive_config, firewall_send_config)
+    }
+
+    /// the replacement is forwarded only while `update --replace-server` sets it up
+    pub fn port_forwarding_rules(server_ip: Ipv4Addr, replacement_server_ip: Option<Ipv4Addr>) -> Vec<Value> {
+        let mut rules = vec![json!({ "Protocol": "tcp", "GlobalPort": PRIMARY_SERVER_FORWARDED_PORT.to_string(), "PrivateAddress": server_ip.to_string(), "PrivatePort": "22" })];
+        if let Some(replacement_server_ip) = replacement_server_ip {
+            rules.push(json!({ "Protocol": "tcp", "GlobalPort": REPLACEMENT_SERVER_FORWARDED_PORT.to_string(), "PrivateAddress": replacement_server_ip.to_string(), "PrivatePort": "22", "Description": "replacement server" }));
+        }
+        if let Some(monitoring_agent) = CONFIG.monitoring_agent() {
+            rules.push(json!({ "Protocol": "tcp", "GlobalPort": monitoring_agent.forwarded_port.to_string(), "PrivateAddress": server_ip.to_string(), "PrivatePort": NODE_EXPORTER_PORT.to_string(), "Description": "monitoring agent" }));
+        }
+        rules
+    }
+
+    /// the forwarded addresses follow the servers of the prefix, see ServerColor
+    pub async fn update_config(prefix: impl AsRef<str>, vpc_router_id: impl Borrow<ApplianceId>, firewall_enabled: bool) -> Result<(), Error> {
+        let prefix = prefix.as_ref();
+        let vpc_router_id = vpc_router_id.borrow();
+        let (mut firewall_receive_config, mut firewall_send_config) = Self::firewall_rules();
+
+        let (server_ip, replacement_server_ip) = match PrimaryServer::try_get(prefix).await? {
+            Some(server) => {
+                let replacement = PrimaryServer::try_get_replacement(prefix, server.color().other()).await?;
+                (server.private_ip(), replacement.map(|replacement| replacement.private_ip()))
+            },
+            None => (CONFIG.network.server_ip, None),
+        };
+
+        if let Some(local_ip) = public_ip::addr_v4().await {
+            firewall_receive_config.insert(0, json!({ "Protocol": "ip", "SourceNetwork": format!("{}/32", local_ip), "Action": "allow", "Description": "local" }));
+            firewall_send_config.insert(0, json!({ "Protocol": "ip", "DestinationNetwork": format!("{}/32", local_ip), "Action": "allow", "Description": "local" }));
+        }
+
+        let info = ApplianceInfo::builder()
+            .vpc_router_info(
+                VpcRouterInfo::builder()
+                    .settings(
+                        json!({
+                            "Router": {
+                                "Interfaces": [
+                                    null,
+                                    { "IPAddress": [ CONFIG.network.router_ip.to_string() ], "NetworkMaskLen": CONFIG.network.mask_len },
+                                ],
+                                "Firewall": {
+                                    "Config": [
+                                    {
+                                        "Receive": firewall_receive_config,
+                                        "Send": firewall_send_config,
+                                    },
+                                    ],
+                                    "Enabled": if firewall_enabled { "True" } else { "False" },
+                                },
+                                "PortForwarding": {
+                                    "Config": Self::port_forwarding_rules(server_ip, replacement_server_ip),
+                                    "Enabled": "True",
+                                },
+                                "WireGuardServer": {
+                                    "Config": { "IPAddress": "", "Peers": [] },
+                                    "Enabled": "False"
+                                },
+                                "PPTPServer": { "Enabled": "False" },
+                                "L2TPIPsecServer": { "Enabled": "False" },
+                            }
+                        })
+                )
+                .build()
+            )
+            .build();
+
+        Appliance::update(vpc_router_id, info).await?;
+        Ok(())
+    }
+
+    /// tags the unix time the firewall is turned off for the setup scripts, none to untag it after the firewall is back
+    pub async fn set_setup_since(vpc_router_id: impl Borrow<ApplianceId>, setup_since: Option<u64>) -> Result<(), Error> {
+        let vpc_router_id = vpc_router_id.borrow().to_string();
+        let resource_value = api::ResourceKind::Appliance.get(&vpc_router_id).await?;
+        let mut tags = serde_json::from_value::<Vec<String>>(resource_value["Tags"].clone()).unwrap_or_default();
+        tags.retain(|tag| !tag.starts_with(SETUP_SINCE_TAG_PREFIX));
+        tags.extend(setup_since.map(|setup_since| format!("{}{}", SETUP_SINCE_TAG_PREFIX, setup_since)));
+        api::ResourceKind::Appliance.update(&vpc_router_id, json!({ "Tags": tags })).await?;
+        Ok(())
+    }
+
+    /// the unix time `update` turned the firewall off for the setup scripts, none unless it's running
+    pub fn setup_since(&self) -> Option<u64> {
+        self.appliance.tags().iter().filter_map(|tag| tag.strip_prefix(SETUP_SINCE_TAG_PREFIX)?.parse().ok()).max()
+    }
+
+    /// the id of the vpc router
+    pub fn id(&self) -> &ApplianceId {
+        self.appliance.id()
+    }
+
+    /// the shared ip of the vpc router on the internet
+    pub fn public_shared_ip(&self) -> Result<Ipv4Addr, Error> {
+        let ip = self.appliance.public_shared_ip()?;
+        Ok(ip)
+    }
+}
+
+/// the note of the setup shell, run by the new disks
+#[derive(Debug)]
+pub struct PrimaryServerSetupShellNote {
+    note: Note,
 }
 
-impl PrimaryServer {
-    const KIND: EquipmentKind = EquipmentKind::PrimaryServer;
+impl PrimaryServerSetupShellNote {
+    const KIND: EquipmentKind = EquipmentKind::PrimaryServerSetupShellNote;
 
-    pub(crate) async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+    /// the note of the prefix, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
         let prefix = prefix.as_ref();
         let name = Self::KIND.name(prefix);
 
-        let server = Server::get_by_name(&name).await?;
-        Ok(server.map(|server| Self { server }))
+        let note = Note::get_by_name(&name).await?;
+        Ok(note.map(|note| Self { note }))
+    }
+    
+    /// updates the note to the embedded setup shell if it differs
+    pub async fn update_content_if_needed(id: impl Borrow<NoteId>) -> Result<(), Error> {
+        Note::update_content_if_needed(id, SETUP_SHELL_NOTE_CONTENT).await?;
+        Ok(())
     }
 
-    pub(crate) async fn create(prefix: impl AsRef<str>, switch_id: impl Borrow<SwitchId>) -> Result<Self, Error> {
+    /// creates the note of the prefix with the embedded setup shell
+    pub async fn create(prefix: impl AsRef<str>) -> Result<Self, Error> {
         let prefix = prefix.as_ref();
-        let switch_id = switch_id.borrow();
         let name = Self::KIND.name(prefix);
 
-        let server_info = ServerInfo::builder()
+        let info = NoteInfo::builder()
             .name(name.clone())
-            .server_plan(SERVER_PLAN_ID.clone())
+            .class(NoteClass::Shell)
             .description(name.clone())
-            .host_name(name.clone())
-            .connected_switch_ids(vec![switch_id.clone()])
-            .interface_driver(InterfaceDriver::Virtio)
-            .wait_disk_migration(true)
+            .content(SETUP_SHELL_NOTE_CONTENT)
             .build();
 
-        let server = Server::create(server_info).await?;
-
-        Ok(Self { server })
+        let note = Note::create(info).await?;
+        Ok(Self { note })
     }
 
-    pub(crate) fn id(&self) -> &ServerId {
-        self.server.id()
+    /// the id of the note
+    pub fn id(&self) -> &NoteId {
+        &self.note.id()
     }
 
+    /// the content of the note
+    pub fn content(&self) -> &str {
+        self.note.content()
+    }
 }
 
+
+/// the simple monitor of the vpc router, found by its tag
 #[derive(Debug)]
-pub(crate) struct PrimaryServerDisk {
-    disk: Disk,
+pub struct PrimarySimpleMonitor {
+    simple_monitor: SimpleMonitor,
 }
 
-impl PrimaryServerDisk {
-    const KIND: EquipmentKind = EquipmentKind::PrimaryServerDisk;
+impl PrimarySimpleMonitor {
+    const KIND: EquipmentKind = EquipmentKind::PrimarySimpleMonitor;
 
-    pub(crate) async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+    /// the simple monitor of the prefix, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
         let prefix = prefix.as_ref();
-        let name = Self::KIND.name(prefix);
+        let tag = Self::KIND.name(prefix);
 
-        let disk = Disk::get_by_name(&name).await?;
-        Ok(disk.map(|disk| Self { disk }))
+        let simple_monitor = SimpleMonitor::get_by_tag(&tag).await?;
+        Ok(simple_monitor.map(|simple_monitor| Self { simple_monitor }))
     }
 
-    pub(crate) async fn create_for_server(
-        prefix: impl AsRef<str>,
-        server_id: impl Borrow<ServerId>,
-        archive_id: impl Borrow<ArchiveId>,
-        startup_shell_note_id: impl Borrow<NoteId>,
-        ssh_public_key_id: impl Borrow<SshPublicKeyId>,
-    ) -> Result<Self, Error> {
-        let prefix = prefix.as_ref();
-        let server_id = server_id.borrow();
-        let archive_id = archive_id.borrow();
-        let startup_shell_note_id = startup_shell_note_id.borrow();
-        let ssh_public_key_id = ssh_public_key_id.borrow();
-        let name = Self::KIND.name(prefix);
-
-        let info = DiskInfo::builder()
-            .name(name.clone())
-            .description(name.clone())
-            .plan_id(DISK_PLAN_ID.clone())
-            .source_archive_id(archive_id.clone())
-            .size_mb(20480)
-            .connection(DiskConnection::Virtio)
-            .server_id(server_id.clone())
-            .build();
-
-        let config = DiskConfig::builder()
-            .host_name(name.clone())
-            .ssh_key_ids(vec![ssh_public_key_id.clone()])
-            .user_ip_address(Ipv4Addr::new(192, 168, 2, 2))
-            .user_subnet(Ipv4Net::new(Ipv4Addr::new(192, 168, 2, 1), 24))
-            .change_partition_uuid(false)
-            .enable_dhcp(false)
-            .disable_pw_auth(true)
-            .setup_shell_note(startup_shell_note_id.clone(), json!({}))
-            .build();
-
-        let disk = Disk::create(info, config).await?;
+    /// creates the simple monitor of the public ip
+    pub async fn create(prefix: impl AsRef<str>, public_shared_ip: Ipv4Addr) -> Result<Self, Error> {
+        let info = Self::info(prefix, public_shared_ip);
+        let simple_monitor = SimpleMonitor::create(info).await?;
+        Ok(Self { simple_monitor })
+    }
 
-        Ok(Self { disk })
+    /// updates the simple monitor to the config
+    pub async fn update(id: impl Borrow<CommonServiceItemId>, prefix: impl AsRef<str>, public_shared_ip: Ipv4Addr) -> Result<(), Error> {
+        let info = Self::info(prefix, public_shared_ip);
+        SimpleMonitor::update(id, info).await?;
+        Ok(())
+    }
 
+    fn info(prefix: impl AsRef<str>, public_shared_ip: Ipv4Addr) -> SimpleMonitorInfo {
+        let prefix = prefix.as_ref();
+        let tag = Self::KIND.name(prefix);
+        let config = CONFIG.simple_monitor.as_ref();
+
+        let protocol = config.map(|config| config.protocol).unwrap_or(SimpleMonitorProtocol::Tcp);
+        let health_check = match protocol {
+            SimpleMonitorProtocol::Ping => SimpleMonitorHealthCheck::ping(),
+            SimpleMonitorProtocol::Tcp => SimpleMonitorHealthCheck::tcp(config.and_then(|config| config.port).unwrap_or(PRIMARY_SERVER_FORWARDED_PORT)),
+            SimpleMonitorProtocol::Http => SimpleMonitorHealthCheck::http(
+                config.and_then(|config| config.port).unwrap_or(80),
+                config.and_then(|config| config.path.clone()).unwrap_or("/".to_string()),
+                200,
+            ),
+        };
+
+        let mut builder = SimpleMonitorInfo::builder()
+            .description(tag.clone())
+            .tags(vec![tag.clone()])
+            .target(public_shared_ip.to_string())
+            .health_check(health_check)
+            .notify_email(config.map(|config| config.notify_email).unwrap_or(true));
+        if let Some(delay_loop) = config.and_then(|config| config.delay_loop) {
+            builder = builder.delay_loop(delay_loop);
+        }
+        if let Some(slack_webhook_url) = config.and_then(|config| config.slack_webhook_url.clone()) {
+            builder = builder.slack_webhook_url(slack_webhook_url);
+        }
+        builder.build()
     }
 
-    pub(crate) fn id(&self) -> &DiskId {
-        self.disk.id()
+    /// the id of the simple monitor
+    pub fn id(&self) -> &CommonServiceItemId {
+        self.simple_monitor.id()
     }
 }
 
 
+/// the auto backup of the primary server disk
 #[derive(Debug)]
-pub(crate) struct PrimaryServerSshPublicKey {
-    ssh_public_key: SshPublicKey,
+pub struct PrimaryServerDiskAutoBackup {
+    auto_backup: AutoBackup,
 }
 
-impl PrimaryServerSshPublicKey {
-    const KIND: EquipmentKind = EquipmentKind::PrimaryServerSshPublicKey;
+impl PrimaryServerDiskAutoBackup {
+    const KIND: EquipmentKind = EquipmentKind::PrimaryServerDiskAutoBackup;
 
-    pub(crate) async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+    /// the auto backup of the prefix, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
         let prefix = prefix.as_ref();
-        let kind = EquipmentKind::PrimaryServerSshPublicKey;
-        let name = kind.name(prefix);
+        let name = Self::KIND.name(prefix);
 
-        let ssh_public_key = SshPublicKey::get_by_name(&name).await?;
-        Ok(ssh_public_key.map(|ssh_public_key| Self { ssh_public_key }))
+        let auto_backup = AutoBackup::get_by_name(&name).await?;
+        Ok(auto_backup.map(|auto_backup| Self { auto_backup }))
+    }
+
+    /// creates the auto backup of the disk
+    pub async fn create(prefix: impl AsRef<str>, disk_id: impl Borrow<DiskId>, config: &AutoBackupConfig) -> Result<Self, Error> {
+        let info = Self::info(prefix, disk_id, config);
+        let auto_backup = AutoBackup::create(info).await?;
+        Ok(Self { auto_backup })
+    }
+
+    /// updates the auto backup to the disk and the config
+    pub async fn update(id: impl Borrow<CommonServiceItemId>, prefix: impl AsRef<str>, disk_id: impl Borrow<DiskId>, config: &AutoBackupConfig) -> Result<(), Error> {
+        let info = Self::info(prefix, disk_id, config);
+        AutoBackup::update(id, info).await?;
+        Ok(())
     }
 
-    pub(crate) async fn create(prefix: impl AsRef<str>, public_key: impl AsRef<str>) -> Result<Self, Error> {
+    fn info(prefix: impl AsRef<str>, disk_id: impl Borrow<DiskId>, config: &AutoBackupConfig) -> AutoBackupInfo {
+        let prefix = prefix.as_ref();
         let name = Self::KIND.name(prefix);
-        let info = SshPublicKeyInfo::builder()
+
+        AutoBackupInfo::builder()
             .name(name.clone())
             .description(name.clone())
-            .public_key(public_key.as_ref())
-            .build();
-        let ssh_public_key = SshPublicKey::create(info).await?;
-        Ok(Self { ssh_public_key })
+            .tags(vec![name.clone()])
+            .disk_id(disk_id)
+            .weekdays(config.weekdays.clone())
+            .generations(config.generations)
+            .build()
     }
 
-    pub(crate) fn id(&self) -> &SshPublicKeyId {
-        self.ssh_public_key.id()
+    /// the id of the auto backup
+    pub fn id(&self) -> &CommonServiceItemId {
+        self.auto_backup.id()
     }
 
-    pub(crate) fn public_key(&self) -> &str {
-        self.ssh_public_key.public_key()
+    /// the id of the disk backed up
+    pub fn disk_id(&self) -> Option<&DiskId> {
+        self.auto_backup.disk_id()
     }
 }
 
 
+/// the nfs appliance on the switch
 #[derive(Debug)]
-pub(crate) struct PrimarySwitch {
-    switch: Switch,
+pub struct PrimaryNfs {
+    appliance: Appliance,
 }
 
-impl PrimarySwitch {
-    const KIND: EquipmentKind = EquipmentKind::PrimarySwitch;
+impl PrimaryNfs {
+    const KIND: EquipmentKind = EquipmentKind::PrimaryNfs;
 
-    pub(crate) async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+    /// the nfs of the prefix, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
         let prefix = prefix.as_ref();
         let name = Self::KIND.name(prefix);
 
-        let switch = Switch::get_by_name(&name).await?;
-        Ok(switch.map(|switch| Self { switch }))
+        let appliance = Appliance::get_by_name(&name).await?;
+        Ok(appliance.map(|appliance| Self { appliance }))
     }
 
-    pub(crate) async fn create(prefix: impl AsRef<str>) -> Result<Self, Error> {
+    /// creates the nfs on the switch
+    pub async fn create(prefix: impl AsRef<str>, switch_id: impl Borrow<SwitchId>, config: &NfsConfig) -> Result<Self, Error> {
         let prefix = prefix.as_ref();
         let name = Self::KIND.name(prefix);
 
-        let info = SwitchInfo::builder()
+        let info = ApplianceInfo::builder()
             .name(name.clone())
             .description(name.clone())
+            .nfs(
+                NfsInfo::builder()
+                    .plan_id(config.plan_id.clone())
+                    .switch_id(switch_id)
+                    .ip_address(CONFIG.network.nfs_ip)
+                    .network_mask_len(CONFIG.network.mask_len)
+                    .default_route(CONFIG.network.router_ip)
+                    .build()
+            )
             .build();
-        let switch = Switch::create(info).await?;
-        Ok(Self { switch })
+        let appliance = Appliance::create(info).await?;
+
+        Ok(Self { appliance })
     }
 
-    pub(crate) fn id(&self) -> &SwitchId {
-        self.switch.id()
+    /// the id of the nfs
+    pub fn id(&self) -> &ApplianceId {
+        self.appliance.id()
     }
 }
 
 
+/// the mobile gateway routing the sim traffic
 #[derive(Debug)]
-pub(crate) struct PrimaryVpcRouter {
+pub struct PrimaryMobileGateway {
     appliance: Appliance,
 }
 
-impl PrimaryVpcRouter {
-    const KIND: EquipmentKind = EquipmentKind::PrimaryVpcRouter;
+impl PrimaryMobileGateway {
+    const KIND: EquipmentKind = EquipmentKind::PrimaryMobileGateway;
 
-    pub(crate) async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+    /// the mobile gateway of the prefix, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
         let prefix = prefix.as_ref();
         let name = Self::KIND.name(prefix);
 
@@ -337,27 +2959,27 @@ impl PrimaryVpcRouter {
         Ok(appliance.map(|appliance| Self { appliance }))
     }
 
-    pub(crate) async fn create(prefix: impl AsRef<str>) -> Result<Self, Error> {
+    /// creates the mobile gateway of the prefix
+    pub async fn create(prefix: impl AsRef<str>) -> Result<Self, Error> {
         let prefix = prefix.as_ref();
         let name = Self::KIND.name(prefix);
 
         let info = ApplianceInfo::builder()
             .name(name.clone())
             .description(name.clone())
-            .vpc_router(
-                VpcRouterInfo::builder()
-                    .plan_id(VpcRouterPlanId::new(1))
+            .mobile_gateway(
+                MobileGatewayInfo::builder()
+                    .plan_id(MobileGatewayPlanId::new(1))
                     .remark(
                         json!({
-                            "Router": { "VPCRouterVersion": 2 },
                             "Servers": [ {} ],
                             "Switch": { "Scope": "shared" },
                         })
                     )
                     .settings(
                         json!({
-                            "Router": {
-                                "InternetConnection": { "Enabled": "True" },
+                            "MobileGateway": {
+                                "InternetConnection": { "Enabled": "False" },
                             },
                         })
                     )
@@ -369,119 +2991,537 @@ impl PrimaryVpcRouter {
         Ok(Self { appliance })
     }
 
-    pub(crate) async fn update_config(vpc_router_id: impl Borrow<ApplianceId>, firewall_enabled: bool) -> Result<(), Error> {
-        let vpc_router_id = vpc_router_id.borrow();
-        let mut firewall_receive_config = Vec::new();
-        let mut firewall_send_config = Vec::new();
-
-        if let Some(local_ip) = public_ip::addr_v4().await {
-            firewall_receive_config.push(json!({ "Protocol": "ip", "SourceNetwork": format!("{}/32", local_ip), "Action": "allow", "Description": "local" }));
-            firewall_send_config.push(json!({ "Protocol": "ip", "DestinationNetwork": format!("{}/32", local_ip), "Action": "allow", "Description": "local" }));
-        }
-
-        let wireguard_peer_endpoint_ip = CONFIG.server.wireguard.peer.endpoint;
-        firewall_send_config.push(json!({ "Protocol": "udp", "DestinationNetwork": format!("{}/32", wireguard_peer_endpoint_ip), "DestinationPort": "51820", "Action": "allow", "Description": "wireguard" }));
-
-        firewall_receive_config.push(json!({ "Protocol": "ip", "Action": "deny", "Description": "otherwise" }));
-        firewall_send_config.push(json!({ "Protocol": "ip", "Action": "deny", "Description": "otherwise" }));
-
+    /// sim traffic is routed into the primary switch's segment
+    pub async fn update_config(mobile_gateway_id: impl Borrow<ApplianceId>) -> Result<(), Error> {
         let info = ApplianceInfo::builder()
-            .vpc_router_info(
-                VpcRouterInfo::builder()
+            .mobile_gateway_info(
+                MobileGatewayInfo::builder()
                     .settings(
                         json!({
-                            "Router": {
+                            "MobileGateway": {
                                 "Interfaces": [
                                     null,
-                                    { "IPAddress": [ "192.168.2.1" ], "NetworkMaskLen": 24 },
+                                    { "IPAddress": [ CONFIG.network.mobile_gateway_ip.to_string() ], "NetworkMaskLen": CONFIG.network.mask_len },
                                 ],
-                                "Firewall": {
-                                    "Config": [
-                                    {
-                                        "Receive": firewall_receive_config,
-                                        "Send": firewall_send_config,
-                                    },
-                                    ],
-                                    "Enabled": if firewall_enabled { "True" } else { "False" },
-                                },
-                                "PortForwarding": {
-                                    "Config": [ { "Protocol": "tcp", "GlobalPort": PRIMARY_SERVER_FORWARDED_PORT.to_string(), "PrivateAddress": "192.168.2.2", "PrivatePort": "22" } ],
-                                    "Enabled": "True",
-                                },
-                                "WireGuardServer": {
-                                    "Config": { "IPAddress": "", "Peers": [] },
-                                    "Enabled": "False"
-                                },
-                                "PPTPServer": { "Enabled": "False" },
-                                "L2TPIPsecServer": { "Enabled": "False" },
-                            }
+                                "InternetConnection": { "Enabled": "False" },
+                                "InterDeviceCommunication": { "Enabled": "True" },
+                            },
                         })
-                )
-                .build()
+                    )
+                    .build()
             )
             .build();
 
-        Appliance::update(vpc_router_id, info).await?;
+        Appliance::update(mobile_gateway_id, info).await?;
         Ok(())
     }
 
-    pub(crate) fn id(&self) -> &ApplianceId {
+    /// the id of the mobile gateway
+    pub fn id(&self) -> &ApplianceId {
         self.appliance.id()
     }
+}
 
-    pub(crate) fn public_shared_ip(&self) -> Result<Ipv4Addr, Error> {
-        let ip = self.appliance.public_shared_ip()?;
-        Ok(ip)
+
+/// the icon of the resources
+#[derive(Debug)]
+pub struct PrimaryIcon {
+    icon: Icon,
+}
+
+impl PrimaryIcon {
+    const KIND: EquipmentKind = EquipmentKind::PrimaryIcon;
+
+    /// the icon of the prefix, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let prefix = prefix.as_ref();
+        let name = Self::KIND.name(prefix);
+
+        let icon = Icon::get_by_name(&name).await?;
+        Ok(icon.map(|icon| Self { icon }))
+    }
+
+    /// uploads the png as the icon of the prefix
+    pub async fn create(prefix: impl AsRef<str>, png: impl AsRef<[u8]>) -> Result<Self, Error> {
+        let prefix = prefix.as_ref();
+        let name = Self::KIND.name(prefix);
+
+        let info = IconInfo::builder()
+            .name(name.clone())
+            .png(png)
+            .build();
+        let icon = Icon::create(info).await?;
+        Ok(Self { icon })
+    }
+
+    /// the id of the icon
+    pub fn id(&self) -> &IconId {
+        self.icon.id()
+    }
+
+    /// the url of the icon image
+    pub fn url(&self) -> Option<&str> {
+        self.icon.url()
     }
 }
 
+/// the snapshot of the primary server disk in the source zone, and its copy in the target zone
 #[derive(Debug)]
-pub(crate) struct PrimaryServerSetupShellNote {
-    note: Note,
+pub struct MigrationArchive {
+    archive: Archive,
 }
 
-impl PrimaryServerSetupShellNote {
-    const KIND: EquipmentKind = EquipmentKind::PrimaryServerSetupShellNote;
+impl MigrationArchive {
+    const KIND: EquipmentKind = EquipmentKind::MigrationArchive;
 
-    pub(crate) async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+    /// the archive of the prefix, none if it doesn't exist
+    pub async fn try_get(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
         let prefix = prefix.as_ref();
         let name = Self::KIND.name(prefix);
 
-        let note = Note::get_by_name(&name).await?;
-        Ok(note.map(|note| Self { note }))
+        let archive = Archive::get_by_name(&name).await?;
+        Ok(archive.map(|archive| Self { archive }))
     }
-    
-    pub(crate) async fn update_content_if_needed(id: impl Borrow<NoteId>) -> Result<(), Error> {
-        let id = id.borrow();
-        let note = Note::get(id).await?;
-        if note.content() == SETUP_SHELL_NOTE_CONTENT {
-            return Ok(());
-        }
 
-        let info = NoteInfo::builder()
-            .content(SETUP_SHELL_NOTE_CONTENT)
+    /// archives the disk as the archive of the prefix
+    pub async fn create_from_disk(prefix: impl AsRef<str>, disk_id: impl Borrow<DiskId>) -> Result<Self, Error> {
+        let prefix = prefix.as_ref();
+        let disk_id = disk_id.borrow();
+        let name = Self::KIND.name(prefix);
+
+        let info = ArchiveInfo::builder()
+            .name(name.clone())
+            .description(name.clone())
+            .source_disk_id(disk_id.clone())
             .build();
-        Note::update(id, info).await?;
-        Ok(())
+        let (archive, _) = Archive::create(info).await?;
+        Ok(Self { archive })
     }
 
-    pub(crate) async fn create(prefix: impl AsRef<str>) -> Result<Self, Error> {
+    /// the ftp server is opened to upload the image
+    pub async fn create_blank(prefix: impl AsRef<str>, size_mb: u64) -> Result<(Self, FtpServer), Error> {
         let prefix = prefix.as_ref();
         let name = Self::KIND.name(prefix);
 
-        let info = NoteInfo::builder()
+        let info = ArchiveInfo::builder()
             .name(name.clone())
-            .class(NoteClass::Shell)
             .description(name.clone())
-            .content(SETUP_SHELL_NOTE_CONTENT)
+            .size_mb(size_mb)
             .build();
+        let (archive, ftp_server) = Archive::create(info).await?;
+        let Some(ftp_server) = ftp_server else {
+            return Err(Error::ArchiveFtpServerNotOpened(archive.id().clone()));
+        };
+        Ok((Self { archive }, ftp_server))
+    }
 
-        let note = Note::create(info).await?;
-        Ok(Self { note })
+    /// the id of the archive
+    pub fn id(&self) -> &ArchiveId {
+        self.archive.id()
     }
 
-    pub(crate) fn id(&self) -> &NoteId {
-        &self.note.id()
+    /// the size of the archive in MB
+    pub fn size_mb(&self) -> Option<u64> {
+        self.archive.size_mb()
+    }
+
+    /// whether the archive is ready to use
+    pub fn is_available(&self) -> bool {
+        self.archive.is_available()
+    }
+}
+
+/// the disk after the first setup, `update` copies new disks from the latest one so the setup scripts have little to do
+#[derive(Debug)]
+pub struct GoldenArchive {
+    archive: Archive,
+    version: u32,
+}
+
+impl GoldenArchive {
+    // e.g. dev-golden-v3
+    fn name(prefix: impl AsRef<str>, version: u32) -> String {
+        format!("{}-golden-v{}", prefix.as_ref(), version)
+    }
+
+    fn version_of(prefix: impl AsRef<str>, name: &str) -> Option<u32> {
+        let version = name.strip_prefix(&format!("{}-golden-v", prefix.as_ref()))?;
+        if version.is_empty() || !version.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        version.parse().ok()
+    }
+
+    /// the newest first, including the ones still being created
+    pub async fn search(prefix: impl AsRef<str>) -> Result<Vec<Self>, Error> {
+        let prefix = prefix.as_ref();
+        let archives = Archive::search_by_name(format!("{}-golden-v", prefix)).await?;
+        let mut golden_archives = archives.into_iter()
+            .filter_map(|archive| {
+                let version = Self::version_of(prefix, archive.name()?)?;
+                Some(Self { archive, version })
+            })
+            .collect::<Vec<_>>();
+        golden_archives.sort_by_key(|golden_archive| std::cmp::Reverse(golden_archive.version));
+        Ok(golden_archives)
+    }
+
+    /// the newest available one, none if there's none
+    pub async fn try_get_latest(prefix: impl AsRef<str>) -> Result<Option<Self>, Error> {
+        let golden_archives = Self::search(prefix).await?;
+        Ok(golden_archives.into_iter().find(|golden_archive| golden_archive.is_available()))
+    }
+
+    /// the next version of the existing ones
+    pub async fn create_from_disk(prefix: impl AsRef<str>, disk_id: impl Borrow<DiskId>) -> Result<Self, Error> {
+        let prefix = prefix.as_ref();
+        let disk_id = disk_id.borrow();
+        let version = Self::search(prefix).await?.first().map(|latest| latest.version + 1).unwrap_or(1);
+        let name = Self::name(prefix, version);
+
+        let info = ArchiveInfo::builder()
+            .name(name.clone())
+            .description(name.clone())
+            .source_disk_id(disk_id.clone())
+            .build();
+        let (archive, _) = Archive::create(info).await?;
+        Ok(Self { archive, version })
+    }
+
+    /// the id of the archive
+    pub fn id(&self) -> &ArchiveId {
+        self.archive.id()
+    }
+
+    /// the number in the name, e.g. 3 of dev-golden-v3
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// whether the archive is ready to use
+    pub fn is_available(&self) -> bool {
+        self.archive.is_available()
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terraform_imports() {
+        assert_eq!(EquipmentKind::PrimaryServer.terraform_address("dev").as_deref(), Some("sakuracloud_server.dev_server"));
+        assert_eq!(EquipmentKind::PrimaryVpcRouter.terraform_address("1st").as_deref(), Some("sakuracloud_vpc_router._1st_vpc_router"));
+        assert_eq!(EquipmentKind::MigrationArchive.terraform_address("dev"), None);
+
+        let hcl = terraform_import_blocks("dev", "is1a", &[
+            (EquipmentKind::PrimaryServer, "113000000001".to_string()),
+            (EquipmentKind::MigrationArchive, "113000000002".to_string()),
+        ]);
+        assert!(hcl.contains("  zone = \"is1a\"\n"), "{}", hcl);
+        assert!(hcl.contains("import {\n  to = sakuracloud_server.dev_server\n  id = \"113000000001\"\n}\n"), "{}", hcl);
+        assert!(!hcl.contains("113000000002"), "{}", hcl);
+    }
+
+    #[test]
+    fn extra_ssh_public_key_names() {
+        assert_eq!(ExtraServerSshPublicKey::name("dev", 2), "dev-pub-key-2");
+        assert!(ExtraServerSshPublicKey::is_name("dev", "dev-pub-key-1"));
+        assert!(ExtraServerSshPublicKey::is_name("dev", "dev-pub-key-12"));
+        assert!(!ExtraServerSshPublicKey::is_name("dev", "dev-pub-key"));
+        assert!(!ExtraServerSshPublicKey::is_name("dev", "dev-pub-key-"));
+        assert!(!ExtraServerSshPublicKey::is_name("dev", "dev-pub-key-old"));
+        assert!(!ExtraServerSshPublicKey::is_name("dev", "dev2-pub-key-1"));
+    }
+
+    #[test]
+    fn note_variables_overrides() {
+        let config: ServerConfig = serde_json::from_value(json!({
+            "service_dirs": [],
+            "zshrc_lines": [],
+            "note_variables": { "usacloud": false, "updatepackage": true, "hostname_suffix": "a" },
+            "note_variables_by_prefix": { "dev": { "usacloud": true, "hostname_suffix": "b" } },
+        })).unwrap();
+        assert_eq!(config.note_variables("prod").official, OfficialNoteVariables { usacloud: Some(false), updatepackage: Some(true) });
+        assert_eq!(config.note_variables("dev").to_value(), json!({ "usacloud": true, "updatepackage": true, "hostname_suffix": "b" }));
+        assert_eq!(NoteVariablesConfig::default().to_value(), json!({}));
+    }
+
+    #[test]
+    fn note_configs() {
+        let notes: Vec<NoteConfig> = serde_json::from_value(json!([
+            { "official": "update packages", "variables": { "updatepackage": true } },
+            { "name": "dev-extra" },
+            { "id": "113" },
+        ])).unwrap();
+        assert!(matches!(&notes[0].source, NoteSourceConfig::Official(name) if name == "update packages"));
+        assert_eq!(notes[0].variables.official.updatepackage, Some(true));
+        assert_eq!(notes[1].variables, NoteVariablesConfig::default());
+        assert_eq!(notes[2].source.to_string(), "note 113");
+    }
+
+    #[test]
+    fn prefix_of_names() {
+        assert_eq!(EquipmentKind::PrimarySwitch.prefix_of("dev-switch"), Some("dev"));
+        assert_eq!(EquipmentKind::PrimaryVpcRouter.prefix_of("test-1-vpc-router"), Some("test-1"));
+        assert_eq!(EquipmentKind::PrimaryServer.prefix_of("-server"), None);
+        assert_eq!(EquipmentKind::PrimaryServer.prefix_of("dev-switch"), None);
+    }
+
+    #[test]
+    fn golden_archive_names() {
+        assert_eq!(GoldenArchive::name("dev", 3), "dev-golden-v3");
+        assert_eq!(GoldenArchive::version_of("dev", "dev-golden-v12"), Some(12));
+        assert_eq!(GoldenArchive::version_of("dev", "dev-golden-v"), None);
+        assert_eq!(GoldenArchive::version_of("dev", "dev-golden-v1-old"), None);
+        assert_eq!(GoldenArchive::version_of("dev", "dev-2-golden-v1"), None);
+    }
+
+    #[test]
+    fn server_colors() {
+        load_example_config();
+        assert_eq!(ServerColor::of_tags(&[]), ServerColor::Blue);
+        assert_eq!(ServerColor::of_tags(&["web".to_string(), "color-green".to_string()]), ServerColor::Green);
+        assert_eq!(ServerColor::Green.other(), ServerColor::Blue);
+        assert_eq!(ServerColor::Green.resource_name("dev"), "dev-server-green");
+        assert_eq!(EquipmentKind::PrimaryServer.prefix_of(&ServerColor::Blue.resource_name("dev")), None);
+
+        let rules = PrimaryVpcRouter::port_forwarding_rules(Ipv4Addr::new(192, 168, 2, 5), Some(Ipv4Addr::new(192, 168, 2, 2)));
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0]["PrivateAddress"], "192.168.2.5");
+        assert_eq!(rules[1]["GlobalPort"], REPLACEMENT_SERVER_FORWARDED_PORT.to_string());
+        assert_eq!(rules[1]["PrivateAddress"], "192.168.2.2");
+    }
+
+    #[test]
+    fn expires_at_tags() {
+        let tags = vec!["web".to_string(), api::MANAGED_TAG.to_string(), "expires-at-1700000000".to_string()];
+        assert_eq!(expires_at_of(&tags), Some(1700000000));
+        let tags = with_expires_at(&tags, 1800000000);
+        assert_eq!(tags, ["web", api::MANAGED_TAG, "expires-at-1800000000"]);
+        assert_eq!(expires_at_of(&["expires-at-".to_string(), "expires-at-x".to_string()]), None);
+        assert_eq!(expires_at_of(&["expires-at-2".to_string(), "expires-at-1".to_string()]), Some(1));
+    }
+
+    #[test]
+    fn archive_retention() {
+        let archive = |id: &str, created_at: Option<&str>| Archive::from_value(json!({ "ID": id, "Name": format!("dev-{}", id), "CreatedAt": created_at })).unwrap();
+        let archives = vec![
+            archive("1", Some("2024-01-01T00:00:00+09:00")),
+            archive("2", Some("2024-01-03T00:00:00+09:00")),
+            archive("3", None),
+            archive("4", Some("2024-01-02T00:00:00+09:00")),
+            archive("5", Some("2024-01-04T00:00:00+09:00")),
+        ];
+        let now = api::unix_time("2024-01-05T00:00:00+09:00").unwrap();
+        let ids = |archives: &[Archive]| archives.iter().map(|archive| archive.id().to_string()).collect::<Vec<_>>();
+
+        let (kept, pruned) = partition_by_retention(archives.clone(), 2, None, now);
+        assert_eq!((ids(&kept), ids(&pruned)), (vec!["5", "2", "3"].into_iter().map(String::from).collect(), vec!["4".to_string(), "1".to_string()]));
+
+        // exactly 4 days old is beyond 4 days
+        let (kept, pruned) = partition_by_retention(archives.clone(), 1, Some(4), now);
+        assert_eq!((ids(&kept), ids(&pruned)), (vec!["5", "2", "4", "3"].into_iter().map(String::from).collect(), vec!["1".to_string()]));
+
+        let (kept, pruned) = partition_by_retention(archives, 0, None, now);
+        assert_eq!((ids(&kept), ids(&pruned).len()), (vec!["3".to_string()], 4));
+    }
+
+    #[test]
+    fn wireguard_client_conf() {
+        let mut config = Config::default();
+        let wireguard = config.server.wireguard.as_mut().unwrap();
+        wireguard.interface.address = vec!["10.0.0.2/24".to_string()];
+        wireguard.interface.dns = vec!["10.0.0.1".parse().unwrap()];
+        wireguard.peer.public_key = "PEER".to_string();
+        wireguard.peer.endpoint = "192.0.2.1".parse().unwrap();
+        let wireguard = config.wireguard().unwrap();
+        assert_eq!(wireguard.client_conf("KEY", &["10.0.0.3/24".to_string()], &["10.0.0.0/24".to_string()]), [
+            "[Interface]",
+            "PrivateKey = KEY",
+            "Address = 10.0.0.3/24",
+            "DNS = 10.0.0.1",
+            "MTU = 1280",
+            "",
+            "[Peer]",
+            "PublicKey = PEER",
+            "Endpoint = 192.0.2.1:51820",
+            "PersistentKeepalive = 25",
+            "AllowedIPs = 10.0.0.0/24",
+            "",
+        ].join("\n"));
+        assert!(wireguard.client_address_problems(&["10.0.0.3/24".to_string(), "fd00::3/64".to_string()]).is_empty());
+        assert_eq!(wireguard.client_address_problems(&["10.0.0.2/32".to_string(), "10.0.0".to_string()]), [
+            "--address: 10.0.0.2/32 is the address of the server",
+            "--address: invalid network: 10.0.0",
+        ]);
+    }
+
+    #[test]
+    fn features_defaults() {
+        let features: FeaturesConfig = serde_json::from_value(json!({ "docker": false, "swap": true })).unwrap();
+        assert_eq!(features.enabled(), [Feature::Wireguard, Feature::Swap]);
+        assert_eq!(FeaturesConfig::default().enabled(), [Feature::Wireguard, Feature::Docker]);
+        assert!(serde_json::from_value::<FeaturesConfig>(json!({ "unknown": true })).is_err());
+    }
+
+    #[test]
+    fn generated_password() {
+        let password = generate_password();
+        assert_eq!(password.len(), 20);
+        assert!(password.chars().all(|c| c.is_ascii_alphanumeric()), "{}", password);
+        assert!(password.chars().any(|c| c.is_ascii_lowercase()), "{}", password);
+        assert!(password.chars().any(|c| c.is_ascii_uppercase()), "{}", password);
+        assert!(password.chars().any(|c| c.is_ascii_digit()), "{}", password);
+        assert_ne!(password, generate_password());
+    }
+
+    #[test]
+    fn manifest_problems() {
+        load_example_config();
+        let manifest = Manifest::new("dev", "is1a", ManifestServer { cpu: 2, memory_mb: 4096, generation: None, commitment: ServerPlanCommitment::Standard }, (DISK_PLAN_ID.clone(), 40960), false, vec![
+            (EquipmentKind::PrimaryServerSetupShellNote.name("dev"), SETUP_SHELL_NOTE_CONTENT),
+        ]);
+        assert!(manifest.problems().is_empty(), "{:?}", manifest.problems());
+
+        let mut manifest: Manifest = serde_json::from_value(serde_json::to_value(&manifest).unwrap()).unwrap();
+        manifest.version = 2;
+        manifest.disk.plan_id = DiskPlanId(2.into());
+        manifest.firewall.send.clear();
+        manifest.notes[0].sha256 = "00".to_string();
+        manifest.notes.push(ManifestNote { name: "dev-other".to_string(), sha256: "00".to_string() });
+        assert_eq!(manifest.problems().len(), 5);
+    }
+
+    #[test]
+    fn example_config() {
+        let config = Config::default();
+        assert_eq!(config.forwarding_ports[0].local_port, 8080);
+        assert_eq!(config.ssh.map(|ssh_config| ssh_config.user), Some("ubuntu".to_string()));
+
+        assert!(matches!(Config::load("config/not-exists.json"), Err(Error::ConfigCouldntRead(..))));
+    }
+
+    #[test]
+    fn config_validate() {
+        let mut config = Config::default();
+        assert_eq!(config.validate().len(), 2);
+
+        // the wireguard keys are not needed without the feature
+        config.features = serde_json::from_value(json!({ "wireguard": false })).unwrap();
+        assert!(config.validate().is_empty());
+        config.features = FeaturesConfig::default();
+
+        let wireguard = config.server.wireguard.as_mut().unwrap();
+        wireguard.interface.private_key = BASE64_STANDARD.encode([0u8; 32]);
+        wireguard.peer.public_key = BASE64_STANDARD.encode([1u8; 32]);
+        assert!(config.validate().is_empty());
+
+        config.unattended_upgrades.reboot_time = Some("4:00".to_string());
+        assert_eq!(config.validate(), ["unattended_upgrades.reboot_time: 4:00 is not HH:MM"]);
+        config.unattended_upgrades.reboot_time = Some("04:00".to_string());
+
+        config.swap.size_mb = 0;
+        config.sysctl.insert("vm.swappiness".to_string(), "10".to_string());
+        config.sysctl.insert("vm swappiness".to_string(), "10".to_string());
+        assert_eq!(config.validate(), ["swap.size_mb: 0, disable features.swap instead", "sysctl: invalid key: vm swappiness"]);
+        config.swap.size_mb = 4096;
+        config.sysctl.remove("vm swappiness");
+
+        config.server.host_name = Some("Web_1".to_string());
+        config.server.timezone = Some("../etc/passwd".to_string());
+        config.server.locale = Some("ja_JP.UTF-8".to_string());
+        assert_eq!(config.validate(), ["server.host_name: Web_1 is not a host name", "server.timezone: ../etc/passwd is not a timezone name, e.g. Asia/Tokyo"]);
+        config.server.host_name = Some("web-1.example.com".to_string());
+        config.server.timezone = Some("Asia/Tokyo".to_string());
+        assert_eq!(config.server.host_name("dev-server"), "web-1.example.com");
+
+        let user = |name: &str, authorized_keys: &[&str]| UserConfig { name: name.to_string(), authorized_keys: authorized_keys.iter().map(|key| key.to_string()).collect(), sudo: false, groups: Vec::new() };
+        config.users = vec![user("alice", &["ssh-ed25519 AAAA alice@laptop"]), user("ubuntu", &[]), user("alice", &["AAAA"])];
+        assert_eq!(config.validate(), [
+            "users: ubuntu is managed by the setup script itself",
+            "users: name is duplicated: alice",
+            "users.alice.authorized_keys: not a single line public key: AAAA",
+        ]);
+        config.users.truncate(1);
+        assert_eq!(config.users[0].authorized_keys_file(), "ssh-ed25519 AAAA alice@laptop\n");
+
+        config.health_check = serde_json::from_value(json!({ "url": "ftp://{ip}/healthz", "attempts": 0 })).unwrap();
+        assert_eq!(config.validate(), ["health_check.url: ftp://{ip}/healthz is not an http(s) url", "health_check.attempts: must be 1 or more"]);
+        config.health_check = serde_json::from_value(json!({ "url": "http://{ip}:8080/healthz" })).unwrap();
+        assert!(config.validate().is_empty());
+        assert_eq!(config.health_check.as_ref().unwrap().url(Ipv4Addr::new(203, 0, 113, 1)), "http://203.0.113.1:8080/healthz");
+
+        config.hardening.fail2ban_ignore_networks = vec!["203.0.113.0/24".to_string(), "office".to_string()];
+        assert_eq!(config.validate(), ["hardening.fail2ban_ignore_networks: invalid network: office"]);
+        config.hardening.fail2ban_ignore_networks.pop();
+        config.hardening.max_auth_tries = 0;
+        assert_eq!(config.validate(), ["hardening.max_auth_tries: must be 1 or more"]);
+        config.hardening.max_auth_tries = 6;
+
+        config.monitoring_agent.scrape_sources = vec!["198.51.100.10/32".to_string()];
+        config.monitoring_agent.forwarded_port = PRIMARY_SERVER_FORWARDED_PORT;
+        assert_eq!(config.validate(), ["monitoring_agent.forwarded_port: 10022 is used by ssh"]);
+        config.monitoring_agent.forwarded_port = 19100;
+        assert!(config.monitoring_agent().is_none());
+        config.features = serde_json::from_value(json!({ "monitoring_agent": true })).unwrap();
+        assert_eq!(config.guest_allowed_ports(), [NODE_EXPORTER_PORT]);
+        config.features = FeaturesConfig::default();
+
+        config.server.wireguard.as_mut().unwrap().interface.address.push("192.168.2.128/25".to_string());
+        config.forwarding_ports.push(config.forwarding_ports[0].clone());
+        config.socks_proxy_port = Some(8080);
+        assert_eq!(config.validate().len(), 3);
+
+        // moving the segment away resolves the wireguard overlap
+        config.network.mask_len = 29;
+        config.network.router_ip = Ipv4Addr::new(172, 16, 0, 1);
+        config.network.server_ip = Ipv4Addr::new(172, 16, 0, 2);
+        config.network.nfs_ip = Ipv4Addr::new(172, 16, 0, 7);
+        config.network.mobile_gateway_ip = Ipv4Addr::new(172, 16, 0, 2);
+        config.network.replacement_server_ip = Ipv4Addr::new(172, 16, 0, 3);
+        assert_eq!(config.validate().len(), 4);
+        assert_eq!(config.network.subnet_string(), "172.16.0.0/29");
+
+        assert!(networks_overlap((Ipv4Addr::new(10, 0, 0, 2), 32), (Ipv4Addr::new(10, 0, 0, 0), 24)));
+        assert!(!networks_overlap((Ipv4Addr::new(10, 0, 1, 2), 32), (Ipv4Addr::new(10, 0, 0, 0), 24)));
+        assert!(networks_overlap((Ipv4Addr::new(10, 0, 1, 2), 0), (Ipv4Addr::new(192, 168, 0, 0), 24)));
+        assert_eq!(parse_ipv4_network("10.0.0.1/33"), None);
+    }
+
+    #[test]
+    fn systemd_unit_file() {
+        let unit: SystemdUnitConfig = serde_json::from_value(json!({
+            "name": "app",
+            "exec_start": "/home/ubuntu/app --port 8080",
+            "environment": { "GREETING": "say \"hi\"" },
+        })).unwrap();
+        assert_eq!(unit.unit_file(), [
+            "[Unit]",
+            "Description=app",
+            "Wants=network-online.target",
+            "After=network-online.target",
+            "",
+            "[Service]",
+            "User=ubuntu",
+            "Environment=\"GREETING=say \\\"hi\\\"\"",
+            "ExecStart=/home/ubuntu/app --port 8080",
+            "Restart=always",
+            "",
+            "[Install]",
+            "WantedBy=multi-user.target",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn secret_interpolation() {
+        env::set_var("SACLOUD_RANDOM_TOOLS_TEST_SECRET", "secret");
+        assert_eq!(resolve_secret("f", "${SACLOUD_RANDOM_TOOLS_TEST_SECRET}").unwrap(), "secret");
+        assert_eq!(resolve_secret("f", "a${SACLOUD_RANDOM_TOOLS_TEST_SECRET}b$c").unwrap(), "asecretb$c");
+        assert_eq!(resolve_secret("f", "plain").unwrap(), "plain");
+        assert!(resolve_secret("f", "${SACLOUD_RANDOM_TOOLS_TEST_NOT_SET}").is_err());
+        assert!(resolve_secret("f", "${UNCLOSED").is_err());
+        assert!(resolve_secret("f", "keychain:service-only").is_err());
+    }
+}
@@ -1,35 +1,84 @@
-use std::{fmt::Write as _, net::Ipv4Addr, path::Path, time::{Duration, Instant}};
-use once_cell::sync::Lazy;
-use serde::{Serialize};
-use tokio::time::sleep;
+//! The setup scripts rendered from the config, uploaded to the server and run on boot.
+//! [`ServiceScript::prepare_for_server`] uploads them, and [`ServiceScript::wait_for_done`] waits for the status file.
+
+use std::{fmt::Write as _, net::Ipv4Addr, path::{Path, PathBuf}, fs, time::{Duration, Instant}};
+use serde::{Serialize, Deserialize};
+use tokio::{io::{AsyncBufReadExt, BufReader}, time::sleep};
 
 use crate::{
+    api,
     service_env::{
         CONFIG,
-        PRIMARY_SERVER_FORWARDED_PORT,
+        Feature,
+        HookConfig,
     },
+    object_storage::utc_timestamp,
     ssh::{
         self,
-        Session,
+        Session, SshOptions,
     },
 };
 
-static TEMPLATE_ENGINE: Lazy<upon::Engine> = Lazy::new(|| {
-    let mut engine = upon::Engine::new();
-    engine.set_default_formatter(&escape_shell);
-    engine.add_template(ServiceScript::RootSetup.as_str(), include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/service_script/root-setup.zsh"))).unwrap();
-    engine.add_template(ServiceScript::UserSetup.as_str(), include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/service_script/user-setup.zsh"))).unwrap();
-    engine
-});
+const ROOT_SETUP_TEMPLATE: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/service_script/root-setup.zsh"));
+const USER_SETUP_TEMPLATE: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/service_script/user-setup.zsh"));
+
+// root-setup.zsh includes the ones of the enabled features, and runs them after the systemd units
+const FEATURES_TEMPLATE_NAME: &str = "features";
+const SWAP_TEMPLATE: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/service_script/features/swap.zsh"));
+const UNATTENDED_UPGRADES_TEMPLATE: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/service_script/features/unattended-upgrades.zsh"));
+const MONITORING_AGENT_TEMPLATE: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/service_script/features/monitoring-agent.zsh"));
+const HARDENING_TEMPLATE: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/service_script/features/hardening.zsh"));
+
+// written by the startup script in the note, truncated on every boot
+const SETUP_LOG_FILES: [&str; 2] = ["setup-log.txt", "setup-error.txt"];
+
+// root-setup.zsh copies it into the project dir of docker compose
+const COMPOSE_FILE: &str = "docker-compose.yaml";
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
-pub(crate) enum Error {
+// written by root-setup.zsh on every step
+const SETUP_STATUS_FILE: &str = "root_setup_status.json";
+
+/// the errors of the setup scripts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+pub enum Error {
+    /// the template engine rejected a template or its data
+    #[error("couldn't render the setup script: {0}")]
     RenderError(String),
+    /// the template couldn't be read, with the path and the io error
+    #[error("couldn't read the template {0}: {1}")]
+    TemplateCouldntRead(PathBuf, String),
+    /// the compose file couldn't be read, with the path and the io error
+    #[error("couldn't read the compose file {0}: {1}")]
+    ComposeFileCouldntRead(PathBuf, String),
+    /// the unit file couldn't be read, with the path and the io error
+    #[error("couldn't read the unit file {0}: {1}")]
+    UnitFileCouldntRead(PathBuf, String),
+    /// the hook whose failed marker the script left
+    #[error("the hook {0} failed")]
+    HookFailed(String),
+    /// the script exited while the status was still running
+    #[error("the setup script stopped without finishing")]
     IllegallyStopped,
-    Failed,
+    /// the script finished with a non-zero code
+    #[error("the setup step {step} failed with code {code}")]
+    StepFailed {
+        /// the step written in the status file
+        step: String,
+        /// the exit code written in the status file
+        code: i32,
+    },
+    /// the status file isn't the json of SetupStatus
+    #[error("invalid setup status: {0}")]
+    StatusInvalid(String),
+    /// the script didn't start in 2 minutes
+    #[error("the setup script didn't start in time")]
     TimeoutToStart,
+    /// the script didn't finish in 10 minutes
+    #[error("the setup script didn't finish in time")]
     TimeoutToFinish,
-    SshError(ssh::Error),
+    /// an ssh error while putting or waiting for the scripts
+    #[error("ssh failed")]
+    SshError(#[from] ssh::Error),
 }
 
 impl From<upon::Error> for Error {
@@ -38,63 +87,88 @@ impl From<upon::Error> for Error {
     }
 }
 
-impl From<ssh::Error> for Error {
-    fn from(e: ssh::Error) -> Self {
-        Error::SshError(e)
-    }
-}
-
-pub(crate) enum ServiceScript {
+/// the setup scripts put on the server
+pub enum ServiceScript {
+    /// root-setup.zsh, run as root
     RootSetup,
+    /// user-setup.zsh, run as ubuntu by root-setup.zsh
     UserSetup,
 }
 
 impl ServiceScript {
-    pub(crate) async fn prepare_for_server(ip: Ipv4Addr, user: impl AsRef<str>, pubkey_path: impl AsRef<Path>) -> Result<(), Error> {
-        let render_params = upon::value!{
-            git: &CONFIG.git,
-            packages: &CONFIG.packages,
-            service_dirs: &CONFIG.server.service_dirs,
-            zshrc_lines: &CONFIG.server.zshrc_lines,
-            wireguard: &CONFIG.server.wireguard,
-            public_shared_ip: ip,
-        };
-        let root_setup_script = Self::RootSetup.render(&render_params)?;
-        log::trace!("[SETUP_SCRIPT] rendered root_setup.zsh: {}", root_setup_script);
-        let user_setup_script = Self::UserSetup.render(&render_params)?;
-        log::trace!("[SETUP_SCRIPT] rendered user_setup.zsh: {}", user_setup_script);
+    /// the templates in scripts_dir override the embedded ones with the same file name
+    /// the port is the one the vpc router forwards to the server, e.g. PRIMARY_SERVER_FORWARDED_PORT
+    pub async fn prepare_for_server(ip: Ipv4Addr, port: u16, ssh_options: &SshOptions, scripts_dir: Option<&Path>) -> Result<(), Error> {
+        let mut engine = template_engine(scripts_dir)?;
+        let hooks = HookConfig::sorted(&CONFIG.hooks);
+        let units = &CONFIG.systemd_units;
+        let unit_files = units.iter().flat_map(|unit| unit.files.iter().map(move |file| (unit, file))).collect::<Vec<_>>();
+        let render_params = render_params(ip);
+        let secrets = api::redacted_strings(&serde_json::to_value(&render_params).unwrap());
+        let root_setup_script = Self::RootSetup.render(&engine, &render_params)?;
+        log::trace!("[SETUP_SCRIPT] rendered root_setup.zsh: {}", api::redact_text(&root_setup_script, &secrets));
+        let user_setup_script = Self::UserSetup.render(&engine, &render_params)?;
+        log::trace!("[SETUP_SCRIPT] rendered user_setup.zsh: {}", api::redact_text(&user_setup_script, &secrets));
+        // put beside the scripts
+        let mut extra_files = Vec::new();
+        for hook in &hooks {
+            let template = fs::read_to_string(&hook.template).map_err(|e| Error::TemplateCouldntRead(hook.template.clone(), e.to_string()))?;
+            let template_name = format!("hook_{}", hook.name);
+            engine.add_template(template_name.clone(), template)?;
+            let hook_script = engine.template(&template_name).render(&render_params).to_string()?;
+            log::trace!("[SETUP_SCRIPT] rendered hook-{}.zsh: {}", hook.name, api::redact_text(&hook_script, &secrets));
+            extra_files.push((format!("hook-{}.zsh", hook.name), hook_script.into_bytes()));
+        }
+        for unit in units {
+            extra_files.push((format!("systemd-{}.service", unit.name), unit.unit_file().into_bytes()));
+        }
+        // the remote paths are passed to the script in the same order
+        for (i, (_, file)) in unit_files.iter().enumerate() {
+            let content = fs::read(&file.local).map_err(|e| Error::UnitFileCouldntRead(file.local.clone(), e.to_string()))?;
+            extra_files.push((format!("systemd-file-{}", i + 1), content));
+        }
+        for user in &CONFIG.users {
+            extra_files.push((format!("authorized-keys-{}", user.name), user.authorized_keys_file().into_bytes()));
+        }
+        if let Some(docker_config) = CONFIG.docker() {
+            let compose = fs::read_to_string(&docker_config.compose_file).map_err(|e| Error::ComposeFileCouldntRead(docker_config.compose_file.clone(), e.to_string()))?;
+            extra_files.push((COMPOSE_FILE.to_string(), compose.into_bytes()));
+        }
         let root_setup_script = root_setup_script.as_bytes();
         let user_setup_script = user_setup_script.as_bytes();
 
         log::trace!("[SETUP_SCRIPT] connecting to server for put scripts...: {}", ip);
-        let session = Session::connect(ip, PRIMARY_SERVER_FORWARDED_PORT, user, pubkey_path).await?;
+        let session = Session::connect(ip, port, ssh_options).await?;
 
         // ensure the close of the session, but I want to use `?` operator
         // currently, async drop is not supported in rust
-        async fn with_session(session: &Session, root_setup_script: &[u8], user_setup_script: &[u8]) -> Result<(), Error> {
-            session.put_file("root-setup.zsh", root_setup_script).await?;
-            session.put_file("user-setup.zsh", user_setup_script).await?;
-            session.put_file("root_setup_not_yet_started_once", &b""[..]).await?;
-            session.put_file("root_setup_not_yet_finished_once", &b""[..]).await?;
-            session.put_file("root_setup_not_yet_success_once", &b""[..]).await?;
+        async fn with_session(session: &Session, root_setup_script: &[u8], user_setup_script: &[u8], extra_files: &[(String, Vec<u8>)]) -> Result<(), Error> {
+            session.put_file(ServiceScript::RootSetup.file_name(), root_setup_script).await?;
+            session.put_file(ServiceScript::UserSetup.file_name(), user_setup_script).await?;
+            for (file_name, content) in extra_files {
+                session.put_file(file_name, &content[..]).await?;
+            }
+            let pending_status = serde_json::to_vec(&SetupStatus::pending()).expect("must be serializable");
+            session.put_file(SETUP_STATUS_FILE, &pending_status[..]).await?;
             Ok(())
         }
-        let result = with_session(&session, root_setup_script, user_setup_script).await;
+        let result = with_session(&session, root_setup_script, user_setup_script, &extra_files).await;
         let _ = session.close().await;
         result
     }
 
-    pub(crate) async fn wait_for_done(ip: Ipv4Addr, user: impl AsRef<str>, pubkey_path: impl AsRef<Path>) -> Result<(), Error> {
+    /// waits for root-setup.zsh to start and finish, streaming the remote logs and downloading them on an error
+    pub async fn wait_for_done(ip: Ipv4Addr, port: u16, ssh_options: &SshOptions) -> Result<SetupStatus, Error> {
         log::trace!("[SETUP_SCRIPT] connecting to server for waiting for scripts done...: {}", ip);
 
-        let session = Session::connect(ip, PRIMARY_SERVER_FORWARDED_PORT, &user, &pubkey_path).await?;
-        async fn with_session(session: &Session) -> Result<(), Error> {
+        let session = Session::connect(ip, port, ssh_options).await?;
+        async fn with_session(session: &Session) -> Result<SetupStatus, Error> {
             let start_waiting = Instant::now();
 
             // 2 分以内にプロセスが開始された痕跡がなければタイムアウト
             loop {
                 let exists_process = session.process_exists("root-setup.zsh").await?;
-                let started = !session.file_exists("root_setup_not_yet_started_once").await?;
+                let started = SetupStatus::read(session).await?.state != SetupState::Pending;
 
                 if exists_process || started {
                     log::trace!("[SETUP_SCRIPT] root-setup.zsh process started");
@@ -110,27 +184,30 @@ impl ServiceScript {
             }
 
             let start_waiting = Instant::now();
-            loop {
+            let status = loop {
+                // the status is read after the process check, because it's written before the exit
                 let exists_process = session.process_exists("root-setup.zsh").await?;
-                let started = !session.file_exists("root_setup_not_yet_started_once").await?;
-                let finished = !session.file_exists("root_setup_not_yet_finished_once").await?;
-                let success = !session.file_exists("root_setup_not_yet_success_once").await?;
-
-                // プロセスが終わっていて、プロセスを開始した痕跡があるまでループ
-                if !exists_process && started {
-                    log::trace!("[SETUP_SCRIPT] root-setup.zsh process disappeared");
-                    if !finished {
-                        log::trace!("[SETUP_SCRIPT] root-setup.zsh process illegally stopped");
-                        // 正常に終了できてない
-                        return Err(Error::IllegallyStopped);
-                    }
-                    if !success {
-                        log::trace!("[SETUP_SCRIPT] root-setup.zsh process failed");
-                        // 正常に終了できていない
-                        return Err(Error::Failed);
+                let status = SetupStatus::read(session).await?;
+
+                if status.state == SetupState::Finished {
+                    let code = status.code.unwrap_or(-1);
+                    if code != 0 {
+                        log::trace!("[SETUP_SCRIPT] root-setup.zsh process failed: {} {}", status.step, code);
+                        for hook in &CONFIG.hooks {
+                            if session.file_exists(format!("hook_{}_failed", hook.name)).await? {
+                                return Err(Error::HookFailed(hook.name.clone()));
+                            }
+                        }
+                        return Err(Error::StepFailed { step: status.step, code });
                     }
                     log::trace!("[SETUP_SCRIPT] root-setup.zsh process successfully finished");
-                    break;
+                    break status;
+                }
+
+                // 終了を書かずにプロセスが消えた
+                if !exists_process && status.state == SetupState::Running {
+                    log::trace!("[SETUP_SCRIPT] root-setup.zsh process illegally stopped: {}", status.step);
+                    return Err(Error::IllegallyStopped);
                 }
 
                 if start_waiting.elapsed() > Duration::from_secs(60 * 10) {
@@ -139,30 +216,251 @@ impl ServiceScript {
                 }
 
                 sleep(Duration::from_secs(5)).await;
-            }
+            };
             log::trace!("[SETUP_SCRIPT] waiting for scripts done, done");
-            Ok(())
+            Ok(status)
+        }
+        // the remote logs are streamed while waiting
+        async fn with_session_streaming_logs(session: &Session) -> Result<SetupStatus, Error> {
+            let mut tail = session.follow_remote_files(&SETUP_LOG_FILES).await?;
+            let mut lines = BufReader::new(tail.stdout()?).lines();
+            let stream_logs = async {
+                while let Ok(Some(line)) = lines.next_line().await {
+                    log::info!("[REMOTE] {}", line);
+                }
+                std::future::pending::<()>().await
+            };
+            tokio::select! {
+                result = with_session(session) => result,
+                _ = stream_logs => unreachable!("pending forever"),
+            }
+        }
+        let result = with_session_streaming_logs(&session).await;
+        if result.is_err() {
+            download_logs(&session).await;
         }
-        let result = with_session(&session).await;
         let _ = session.close().await;
         result
     }
 
-    pub(crate) fn as_str(&self) -> &'static str {
+    /// the template name of the script
+    pub fn as_str(&self) -> &'static str {
         match self {
             ServiceScript::RootSetup => "root_setup",
             ServiceScript::UserSetup => "user_setup",
         }
     }
 
-    pub(crate) fn render(&self, data: impl Serialize) -> Result<String, Error> {
-        log::trace!("[SETUP_SCRIPT] rendering script: {} {}", self.as_str(), serde_json::to_string_pretty(&data).unwrap());
-        let script = TEMPLATE_ENGINE.template(self.as_str()).render(data).to_string()?;
-        log::trace!("[SETUP_SCRIPT] rendered script: {}", script);
+    /// the file name of the script on the server
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ServiceScript::RootSetup => "root-setup.zsh",
+            ServiceScript::UserSetup => "user-setup.zsh",
+        }
+    }
+
+    fn embedded_template(&self) -> &'static str {
+        match self {
+            ServiceScript::RootSetup => ROOT_SETUP_TEMPLATE,
+            ServiceScript::UserSetup => USER_SETUP_TEMPLATE,
+        }
+    }
+
+    /// renders the script with the data, logging it with the secrets redacted
+    pub fn render(&self, engine: &upon::Engine, data: impl Serialize) -> Result<String, Error> {
+        // the wireguard private key is in the data
+        let data_value = serde_json::to_value(&data).unwrap();
+        let secrets = api::redacted_strings(&data_value);
+        log::trace!("[SETUP_SCRIPT] rendering script: {} {}", self.as_str(), serde_json::to_string_pretty(&api::redact(&data_value)).unwrap());
+        let script = engine.template(self.as_str()).render(data).to_string()?;
+        log::trace!("[SETUP_SCRIPT] rendered script: {}", api::redact_text(&script, &secrets));
         Ok(script)
     }
 }
 
+/// the state of the setup scripts in the status file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SetupState {
+    /// the scripts haven't started
+    #[serde(rename = "pending")]
+    Pending,
+
+    /// a step is running
+    #[serde(rename = "running")]
+    Running,
+
+    /// the scripts exited, with the code in the status
+    #[serde(rename = "finished")]
+    Finished,
+}
+
+/// the step is the running one, or the failed one when finished with non-zero code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetupStatus {
+    state: SetupState,
+    step: String,
+    code: Option<i32>,
+
+    // unix time on the server
+    started_at: u64,
+    updated_at: u64,
+}
+
+impl SetupState {
+    /// the state as the status file writes it
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Finished => "finished",
+        }
+    }
+}
+
+impl SetupStatus {
+    fn pending() -> Self {
+        Self {
+            state: SetupState::Pending,
+            step: "none".to_string(),
+            code: None,
+            started_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    /// reads the status file in the home dir over sftp
+    pub async fn read(session: &Session) -> Result<Self, Error> {
+        let json = session.read_remote_file(SETUP_STATUS_FILE).await?;
+        serde_json::from_slice(&json).map_err(|e| Error::StatusInvalid(format!("{}: {}", e, String::from_utf8_lossy(&json))))
+    }
+
+    /// the state of the scripts
+    pub fn state(&self) -> SetupState {
+        self.state
+    }
+
+    /// the running step, or the last one when finished
+    pub fn step(&self) -> &str {
+        &self.step
+    }
+
+    /// the exit code, none until finished
+    pub fn code(&self) -> Option<i32> {
+        self.code
+    }
+
+    /// the time from the start to the last update of the status
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.updated_at.saturating_sub(self.started_at))
+    }
+}
+
+// into the current dir, failures are only logged so as not to hide the original error
+async fn download_logs(session: &Session) {
+    let timestamp = utc_timestamp();
+    for remote_path in SETUP_LOG_FILES {
+        let local_path = PathBuf::from(format!("{}-{}", timestamp, remote_path));
+        match session.read_remote_file(remote_path).await {
+            Ok(content) => match tokio::fs::write(&local_path, content).await {
+                Ok(_) => log::info!("[NOTE] setup log downloaded: {}", local_path.display()),
+                Err(e) => log::warn!("[NOTE] setup log couldn't be written: {}: {}", local_path.display(), e),
+            },
+            Err(e) => log::warn!("[NOTE] setup log couldn't be downloaded: {}: {}", remote_path, e),
+        }
+    }
+}
+
+// the values the setup scripts and the hooks are rendered with
+fn render_params(ip: Ipv4Addr) -> upon::Value {
+    let hooks = HookConfig::sorted(&CONFIG.hooks);
+    let units = &CONFIG.systemd_units;
+    let unit_files = units.iter().flat_map(|unit| unit.files.iter().map(move |file| (unit, file))).collect::<Vec<_>>();
+    upon::value!{
+        git: &CONFIG.git,
+        packages: &CONFIG.packages,
+        service_dirs: &CONFIG.server.service_dirs,
+        zshrc_lines: &CONFIG.server.zshrc_lines,
+        host_name: &CONFIG.server.host_name,
+        timezone: &CONFIG.server.timezone,
+        locale: &CONFIG.server.locale,
+        wireguard: CONFIG.wireguard(),
+        nfs: &CONFIG.nfs,
+        nfs_ip: CONFIG.network.nfs_ip,
+        docker: CONFIG.docker(),
+        unattended_upgrades: &CONFIG.unattended_upgrades,
+        swap: &CONFIG.swap,
+        sysctl: &CONFIG.sysctl,
+        hardening: &CONFIG.hardening,
+        guest_allowed_ports: CONFIG.guest_allowed_ports(),
+        router_ip: CONFIG.network.router_ip,
+        feature_steps: CONFIG.features.enabled().into_iter().filter(|feature| feature_template(*feature).is_some()).map(|feature| format!("feature_{}", feature.name())).collect::<Vec<_>>(),
+        unit_names: units.iter().map(|unit| unit.name.clone()).collect::<Vec<_>>(),
+        unit_file_units: unit_files.iter().map(|(unit, _)| unit.name.clone()).collect::<Vec<_>>(),
+        unit_file_users: unit_files.iter().map(|(unit, _)| unit.user.clone()).collect::<Vec<_>>(),
+        unit_file_paths: unit_files.iter().map(|(_, file)| file.remote.clone()).collect::<Vec<_>>(),
+        unit_file_modes: unit_files.iter().map(|(_, file)| file.mode.clone()).collect::<Vec<_>>(),
+        public_shared_ip: ip,
+        hook_names: hooks.iter().map(|hook| hook.name.clone()).collect::<Vec<_>>(),
+        hook_users: hooks.iter().map(|hook| hook.user.clone()).collect::<Vec<_>>(),
+        user_names: CONFIG.users.iter().map(|user| user.name.clone()).collect::<Vec<_>>(),
+        user_sudos: CONFIG.users.iter().map(|user| if user.sudo { 1 } else { 0 }).collect::<Vec<_>>(),
+        user_groups: CONFIG.users.iter().map(|user| user.groups.join(",")).collect::<Vec<_>>(),
+    }
+}
+
+fn template_engine(scripts_dir: Option<&Path>) -> Result<upon::Engine<'static>, Error> {
+    if let Some(scripts_dir) = scripts_dir.filter(|scripts_dir| !scripts_dir.is_dir()) {
+        return Err(Error::TemplateCouldntRead(scripts_dir.to_path_buf(), "not a directory".to_string()));
+    }
+    let mut engine = upon::Engine::new();
+    engine.set_default_formatter(&escape_shell);
+    for script in [ServiceScript::RootSetup, ServiceScript::UserSetup] {
+        let path = scripts_dir.map(|scripts_dir| scripts_dir.join(script.file_name())).filter(|path| path.exists());
+        let template = match path {
+            Some(path) => {
+                log::info!("[NOTE] {} is overridden by {}", script.file_name(), path.display());
+                fs::read_to_string(&path).map_err(|e| Error::TemplateCouldntRead(path.clone(), e.to_string()))?
+            },
+            None => script.embedded_template().to_string(),
+        };
+        engine.add_template(script.as_str(), template)?;
+    }
+
+    // the fragments are overridden by features/<name>.zsh in scripts_dir
+    let mut features_template = String::new();
+    for feature in CONFIG.features.enabled() {
+        let Some(embedded_template) = feature_template(feature) else {
+            continue;
+        };
+        let path = scripts_dir.map(|scripts_dir| scripts_dir.join("features").join(feature_file_name(feature))).filter(|path| path.exists());
+        match path {
+            Some(path) => {
+                log::info!("[NOTE] features/{} is overridden by {}", feature_file_name(feature), path.display());
+                features_template.push_str(&fs::read_to_string(&path).map_err(|e| Error::TemplateCouldntRead(path.clone(), e.to_string()))?);
+            },
+            None => features_template.push_str(embedded_template),
+        }
+        features_template.push('\n');
+    }
+    engine.add_template(FEATURES_TEMPLATE_NAME, features_template)?;
+    Ok(engine)
+}
+
+// none for the ones in root-setup.zsh itself
+fn feature_template(feature: Feature) -> Option<&'static str> {
+    match feature {
+        Feature::Wireguard | Feature::Docker => None,
+        Feature::Swap => Some(SWAP_TEMPLATE),
+        Feature::UnattendedUpgrades => Some(UNATTENDED_UPGRADES_TEMPLATE),
+        Feature::MonitoringAgent => Some(MONITORING_AGENT_TEMPLATE),
+        Feature::Hardening => Some(HARDENING_TEMPLATE),
+    }
+}
+
+fn feature_file_name(feature: Feature) -> String {
+    format!("{}.zsh", feature.name().replace('_', "-"))
+}
+
 fn escape_shell(formatter: &mut upon::fmt::Formatter<'_>, value: &upon::Value) -> upon::fmt::Result {
     match value {
         upon::Value::None => return Err("Value::None is not supported in shell script template".into()),
@@ -198,3 +496,28 @@ fn escape_shell(formatter: &mut upon::fmt::Formatter<'_>, value: &upon::Value) -
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service_env::load_example_config;
+
+    // with the example config, a template upon can't compile fails here instead of on the server
+    #[test]
+    fn render_embedded_templates() {
+        load_example_config();
+        let mut engine = template_engine(None).unwrap();
+        let render_params = render_params(Ipv4Addr::new(203, 0, 113, 1));
+        for script in [ServiceScript::RootSetup, ServiceScript::UserSetup] {
+            script.render(&engine, &render_params).unwrap();
+        }
+
+        // all of them, most of the features are disabled in the example config
+        for feature in Feature::ALL {
+            let Some(template) = feature_template(feature) else {
+                continue;
+            };
+            engine.add_template(feature_file_name(feature), template).unwrap();
+            engine.template(&feature_file_name(feature)).render(&render_params).to_string().unwrap();
+        }
+    }
+}
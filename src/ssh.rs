@@ -1,26 +1,87 @@
-use std::{time::Duration, path::Path, net::Ipv4Addr, time::Instant};
+//! ssh sessions to the server through the vpc router's port forwarding, built on the system openssh.
+//! [`Session::connect`] takes [`SshOptions`], and the session runs commands, forwards ports and transfers files.
+
+use std::{time::Duration, path::{Path, PathBuf}, net::Ipv4Addr, time::{Instant, SystemTime}, env, io::SeekFrom, collections::BTreeSet, ffi::OsString, sync::Arc};
 use shell_escape::unix::escape;
-use openssh::{self, SessionBuilder, Stdio, KnownHosts, ForwardType, Socket};
+use openssh::{self, SessionBuilder, Stdio, KnownHosts, ForwardType, Socket, RemoteChild, ChildStdout, ControlPersist};
 use openssh_sftp_client::{self, Sftp};
 use openssh_sftp_protocol_error::ErrorCode as SftpErrorKind;
-use tokio::{io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader, AsyncBufReadExt}, time::{timeout, interval}, net::TcpStream, fs::File};
-use serde::Serialize;
+use tokio::{io::{AsyncRead, AsyncReadExt, AsyncWriteExt, AsyncSeekExt, BufReader, AsyncBufReadExt}, time::{timeout, interval}, net::TcpStream, fs::{File, OpenOptions}, process::Command};
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
 use regex::Regex;
-use futures::StreamExt;
+use futures::{StreamExt, stream::FuturesUnordered};
 use bytes::BytesMut;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
-pub(crate) enum Error {
+// reads in flight per downloading file
+const PIPELINED_READS: usize = 16;
+
+// the sftp client lowers it to the limit the server announces
+const READ_CHUNK_SIZE: u64 = 256 * 1024;
+
+const PART_SUFFIX: &str = ".part";
+const PART_STATE_SUFFIX: &str = ".part.json";
+
+/// the errors of the ssh sessions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+pub enum Error {
+    /// an io error, locally or of the openssh process
+    #[error("io error: {0}")]
     IoError(String),
+    /// an error of openssh, its message and its debug form
+    #[error("ssh error: {0}")]
     OpensshError(String, String),
+    /// an error of the sftp client, its message and its debug form
+    #[error("sftp error: {0}")]
     OpensshSftpError(String, String),
+    /// ps printed not even the header line
+    #[error("ps printed no line")]
     PsCommandNoLineOutput,
+    /// the remote process was spawned without a piped stdout
+    #[error("couldn't take the stdout of the remote process")]
     CouldntTakeRemoteProcessStdout,
+    /// a line of ps doesn't have the columns of the header
+    #[error("couldn't parse a line of ps")]
     ParseFailedPsOutputLine,
+    /// the sftp server didn't tell the file type
+    #[error("couldn't get the remote file type")]
     CouldntGetRemoteFileType,
+    /// the sftp server didn't tell the file size
+    #[error("couldn't get the remote file size")]
+    CouldntGetRemoteFileSize,
+    /// the glob pattern is not valid
+    #[error("invalid glob pattern: {0}")]
+    InvalidGlob(String),
+    /// the file, the size on one end and the size on the other after a transfer
+    #[error("{0} is {1} bytes on one end but {2} bytes on the other")]
+    TransferSizeMismatch(String, u64, u64),
+    /// the file, the sha256 on one end and the sha256 on the other after a transfer
+    #[error("the sha256 of {0} is {1} on one end but {2} on the other")]
+    TransferChecksumMismatch(String, String, String),
+    /// the sftp server failed to read the file
+    #[error("couldn't read the remote file: {0}")]
     CouldntReadRemoteFile(String),
+    /// the local file couldn't be written
+    #[error("couldn't write the local file: {0}")]
     CouldntWriteLocalFile(String),
+    /// a file was expected at the path
+    #[error("{0} exists but is not a file")]
     PathExistsButNotFile(String),
+    /// the command and its exit status or stderr
+    #[error("`{0}` failed: {1}")]
+    RemoteCommandFailed(String, String),
+    /// the agent auth was chosen without SSH_AUTH_SOCK
+    #[error("ssh-agent is not running, SSH_AUTH_SOCK is not set")]
+    SshAgentNotRunning,
+    /// the key file and the stderr of ssh-add
+    #[error("ssh-add {0} failed: {1}")]
+    SshAddFailed(String, String),
+    /// the arguments and the stderr of ssh-keygen
+    #[error("ssh-keygen {0} failed: {1}")]
+    SshKeygenFailed(String, String),
+    /// the host and the known_hosts file which has another key for it
+    #[error("the host key of {0} doesn't match {1}, pass --accept-new-hostkey if the server is recreated")]
+    HostKeyMismatch(String, String),
 }
 
 impl From<std::io::Error> for Error {
@@ -41,58 +102,377 @@ impl From<openssh_sftp_client::Error> for Error {
     }
 }
 
-pub(crate) struct Session {
-    session: openssh::Session,
+/// ssh runs in batch mode, so passphrase protected keys must be in the agent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SshAuth {
+    /// a private key file passed to ssh with -i
+    KeyFile(PathBuf),
+    /// the keys in the running ssh-agent
+    Agent,
+}
+
+impl SshAuth {
+    /// the agent auth, an error unless SSH_AUTH_SOCK is set
+    pub fn agent() -> Result<Self, Error> {
+        if env::var_os("SSH_AUTH_SOCK").is_none() {
+            return Err(Error::SshAgentNotRunning);
+        }
+        Ok(Self::Agent)
+    }
+}
+
+/// how to reach the server besides the ip and the port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshOptions {
+    /// the user to log in as
+    pub user: String,
+    /// the private key file or the agent
+    pub auth: SshAuth,
+
+    /// a Host in ssh_config to connect via instead of the ip, so its options like ProxyJump are applied
+    pub host_alias: Option<String>,
+
+    /// the host key is added on the first connect and verified on the later connects
+    pub known_hosts_path: PathBuf,
+
+    /// the master connection is left running here after the session closes, and the next session to the same destination reuses it
+    pub control_dir: Option<PathBuf>,
+
+    /// reach the server's private address through a bastion instead of the vpc router's port forwarding
+    pub jump: Option<SshJump>,
+}
+
+/// a bastion to reach the server through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshJump {
+    /// `user@host[:port]` as ssh -J takes
+    pub host: String,
+
+    /// the server's address in the private network, which the bastion can reach
+    pub private_ip: Ipv4Addr,
+    /// the ssh port in the private network
+    pub private_port: u16,
+}
+
+// the master connection idles this long after the last session before exiting
+const CONTROL_PERSIST_SECS: usize = 10 * 60;
+
+const MASTER_STATE_FILE: &str = "master.json";
+
+// which master connection is left in the control dir
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct MasterState {
+    destination: String,
+    ctl: PathBuf,
+}
+
+impl MasterState {
+    fn destination(ip: Ipv4Addr, port: u16, options: &SshOptions) -> String {
+        let destination = format!("{}@{}:{}", options.user, options.host_alias.clone().unwrap_or(ip.to_string()), port);
+        match &options.jump {
+            Some(jump) => format!("{} via {}", destination, jump.host),
+            None => destination,
+        }
+    }
+
+    async fn load(control_dir: &Path) -> Option<Self> {
+        let content = tokio::fs::read(control_dir.join(MASTER_STATE_FILE)).await.ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    async fn save(&self, control_dir: &Path) -> Result<(), Error> {
+        let content = serde_json::to_vec(self).expect("serializable");
+        tokio::fs::write(control_dir.join(MASTER_STATE_FILE), content).await?;
+        Ok(())
+    }
+
+    // the master log is next to the socket in the dir openssh creates
+    fn resume(&self) -> openssh::Session {
+        let log = self.ctl.with_file_name("log");
+        openssh::Session::resume(self.ctl.clone().into_boxed_path(), Some(log.into_boxed_path()))
+    }
+}
+
+/// terminates the master connection left in the control dir, e.g. after the host key changed
+pub async fn forget_master_connection(control_dir: impl AsRef<Path>) -> Result<(), Error> {
+    let control_dir = control_dir.as_ref();
+    if let Some(state) = MasterState::load(control_dir).await {
+        log::trace!("[SSH] closing cached master connection...: {}", state.destination);
+        let _ = state.resume().close().await;
+        if let Some(dir) = state.ctl.parent() {
+            let _ = tokio::fs::remove_dir_all(dir).await;
+        }
+    }
+    match tokio::fs::remove_file(control_dir.join(MASTER_STATE_FILE)).await {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// ssh-add prompts the passphrase on the terminal if the key is protected
+pub async fn add_key_to_agent(privkey_path: impl AsRef<Path>) -> Result<(), Error> {
+    let privkey_path = privkey_path.as_ref();
+    log::trace!("[SSH] adding key to agent...: {}", privkey_path.display());
+    let status = Command::new("ssh-add").arg(privkey_path).status().await
+        .map_err(|e| Error::SshAddFailed(privkey_path.display().to_string(), e.to_string()))?;
+    if !status.success() {
+        return Err(Error::SshAddFailed(privkey_path.display().to_string(), status.to_string()));
+    }
+    Ok(())
+}
+
+/// an ed25519 key pair without passphrase, path.pub is the public key
+pub async fn generate_key(privkey_path: impl AsRef<Path>, comment: &str) -> Result<(), Error> {
+    let privkey_path = privkey_path.as_ref();
+    log::trace!("[SSH] generating key...: {}", privkey_path.display());
+    let output = Command::new("ssh-keygen").args(["-q", "-t", "ed25519", "-N", "", "-C", comment, "-f"]).arg(privkey_path).output().await
+        .map_err(|e| Error::SshKeygenFailed(privkey_path.display().to_string(), e.to_string()))?;
+    if !output.status.success() {
+        return Err(Error::SshKeygenFailed(privkey_path.display().to_string(), format!("{}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(())
+}
+
+/// the filters and the concurrency of sync_remote_dir
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncOptions {
+    /// number of files downloaded at once
+    pub parallelism: usize,
+
+    /// compare with sha256sum on the server besides the size, it reads every file once more on both ends
+    pub verify_sha256: bool,
+
+    /// glob patterns matched against the file names, all files are included if empty
+    pub include: Vec<String>,
+
+    /// glob patterns matched against the file names, applied after the include and the DEFAULT_EXCLUDE
+    pub exclude: Vec<String>,
+}
+
+/// editor and temporary files that are being written
+pub const DEFAULT_EXCLUDE: &[&str] = &["*.tmp", "*.swp", "*~", ".nfs*"];
+
+/// a glob supporting *, ? and [...], a file name never contains / so * matches anything
+#[derive(Debug, Clone)]
+pub struct Glob(Regex);
+
+impl Glob {
+    /// compiles the pattern, an error for an unclosed [ or a class the regex rejects
+    pub fn new(pattern: &str) -> Result<Self, Error> {
+        let mut regex = String::from("^");
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' => regex.push_str(".*"),
+                '?' => regex.push('.'),
+                '[' => {
+                    let mut class = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(']') if !class.is_empty() && class != "!" => break,
+                            Some(c) => class.push(c),
+                            None => return Err(Error::InvalidGlob(pattern.to_string())),
+                        }
+                    }
+                    let (negated, class) = match class.strip_prefix('!') {
+                        Some(class) => (true, class),
+                        None => (false, class.as_str()),
+                    };
+                    regex.push('[');
+                    if negated {
+                        regex.push('^');
+                    }
+                    regex.push_str(&class.replace('\\', "\\\\").replace('[', "\\[").replace('&', "\\&").replace('~', "\\~"));
+                    regex.push(']');
+                },
+                c => regex.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        regex.push('$');
+        Regex::new(&regex).map(Glob).map_err(|_| Error::InvalidGlob(pattern.to_string()))
+    }
+
+    /// whether the filename matches the whole pattern
+    pub fn matches(&self, filename: &str) -> bool {
+        self.0.is_match(filename)
+    }
+}
+
+struct SyncFilter {
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+}
+
+impl SyncFilter {
+    fn new(options: &SyncOptions) -> Result<Self, Error> {
+        let include = options.include.iter().map(|pattern| Glob::new(pattern)).collect::<Result<_, _>>()?;
+        let exclude = DEFAULT_EXCLUDE.iter().copied().chain(options.exclude.iter().map(|pattern| pattern.as_str())).map(Glob::new).collect::<Result<_, _>>()?;
+        Ok(SyncFilter { include, exclude })
+    }
+
+    fn matches(&self, filename: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|glob| glob.matches(filename)))
+            && !self.exclude.iter().any(|glob| glob.matches(filename))
+    }
+}
+
+// saved next to the .part file while downloading, so an interrupted download resumes where it stopped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PartialDownload {
+    // the remote file is regarded as changed if these differ
+    size: u64,
+    modified: Option<u32>,
+
+    // everything before is written to the .part file
+    offset: u64,
+}
+
+impl PartialDownload {
+    // a broken state is the same as no state, the download starts over
+    async fn load(path: &Path) -> Option<Self> {
+        let content = tokio::fs::read(path).await.ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    async fn save(&self, path: &Path) -> Result<(), Error> {
+        let content = serde_json::to_vec(self).expect("serializable");
+        tokio::fs::write(path, content).await.map_err(|e| Error::CouldntWriteLocalFile(e.to_string()))
+    }
+}
+
+fn partial_download_path(local_path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(local_path.as_os_str());
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+fn is_partial_download_path(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    path.ends_with(PART_SUFFIX) || path.ends_with(PART_STATE_SUFFIX)
+}
+
+async fn local_sha256(path: &Path) -> Result<String, Error> {
+    let mut file = File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1024 * 1024];
+    loop {
+        let len = file.read(&mut buf).await?;
+        if len == 0 {
+            break;
+        }
+        hasher.update(&buf[..len]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// an ssh session and its sftp subsystem over one master connection
+pub struct Session {
+    // shared with the sftp subsystem, which runs over the same master connection
+    session: Arc<openssh::Session>,
+
+    // the master connection is detached instead of closed
+    keeps_master: bool,
     sftp: Sftp,
 }
 
 impl Session {
-    pub(crate) async fn connect(ip: Ipv4Addr, port: u16, user: impl AsRef<str>, pubkey_path: impl AsRef<Path>) -> Result<Self, Error> {
+    /// ip and port are the vpc router's port forwarding, which is replaced with the private address if the jump is given
+    pub async fn connect(ip: Ipv4Addr, port: u16, options: &SshOptions) -> Result<Self, Error> {
+        let (ip, port) = match &options.jump {
+            Some(jump) => (jump.private_ip, jump.private_port),
+            None => (ip, port),
+        };
         log::trace!("[SSH] connecting to server...: {}:{}", ip, port);
 
-        // main session
-        let session = Self::new_session(ip, port, &user, &pubkey_path).await?;
+        let session = match Self::resume_session(ip, port, options).await {
+            Some(session) => session,
+            None => Self::new_session(ip, port, options).await?,
+        };
+        let session = Arc::new(session);
 
-        // sftp session
-        // I considered to use the same session for both using Arc, but session close will occur moving the session itself and it's not useful with Arc
-        // So, I decided to use different session for sftp
-        // It's not simple, TODO fix it
-        let sftp_session = Self::new_session(ip, port, &user, &pubkey_path).await?;
-        
         log::trace!("[SSH] starting sftp subsystem...");
-        let sftp = Sftp::from_session(sftp_session, Default::default()).await?;
+        let sftp = Sftp::from_clonable_session(session.clone(), Default::default()).await?;
 
         log::trace!("[SSH] connected to server: {}:{}", ip, port);
         Ok(Self {
             session,
             sftp,
+            keeps_master: options.control_dir.is_some(),
         })
     }
 
-    async fn new_session(ip: Ipv4Addr, port: u16, user: impl AsRef<str>, pubkey_path: impl AsRef<Path>) -> Result<openssh::Session, Error> {
+    // the cached master connection is used only if it's for the same destination and still alive
+    async fn resume_session(ip: Ipv4Addr, port: u16, options: &SshOptions) -> Option<openssh::Session> {
+        let control_dir = options.control_dir.as_ref()?;
+        let state = MasterState::load(control_dir).await?;
+        if state.destination == MasterState::destination(ip, port, options) {
+            let session = state.resume();
+            if session.check().await.is_ok() {
+                log::trace!("[SSH] reusing master connection: {}", state.destination);
+                return Some(session);
+            }
+            log::trace!("[SSH] cached master connection is gone: {}", state.destination);
+        }
+        let _ = forget_master_connection(control_dir).await;
+        None
+    }
+
+    async fn new_session(ip: Ipv4Addr, port: u16, options: &SshOptions) -> Result<openssh::Session, Error> {
         let start_time = Instant::now();
         let mut interval = interval(Duration::from_secs(20));
 
-        let pubkey_path = pubkey_path.as_ref();
-        let user = user.as_ref();
+        let user = options.user.as_str();
+        // the host alias names the vpc router, which isn't the destination through the bastion
+        let destination = match &options.jump {
+            Some(_) => ip.to_string(),
+            None => options.host_alias.clone().unwrap_or(ip.to_string()),
+        };
 
         let session = loop {
-            log::trace!("[SSH] waiting for ssh to be connectable...: {}:{}", ip, port);
-            wait_for_ssh_connectable(ip, port).await?;
+            // the private address isn't reachable from here, the connect below retries instead
+            if options.jump.is_none() {
+                log::trace!("[SSH] waiting for ssh to be connectable...: {}:{}", ip, port);
+                wait_for_ssh_connectable(ip, port).await?;
+            }
 
-            let session = SessionBuilder::default()
+            let mut builder = SessionBuilder::default();
+            builder
                 .user(user.to_string())
                 .port(port)
-                .keyfile(pubkey_path)
                 .connect_timeout(Duration::from_secs(20))
-                .known_hosts_check(KnownHosts::Accept)
-                .server_alive_interval(Duration::from_secs(60))
-                .connect(ip.to_string())
-                .await;
+                .known_hosts_check(KnownHosts::Add)
+                .user_known_hosts_file(&options.known_hosts_path)
+                .server_alive_interval(Duration::from_secs(60));
+            // the dirs of the masters that exited after idling are cleaned up here
+            if let Some(control_dir) = &options.control_dir {
+                builder
+                    .control_directory(control_dir)
+                    .control_persist(ControlPersist::IdleFor(CONTROL_PERSIST_SECS.try_into().expect("non zero")))
+                    .clean_history_control_directory(true);
+            }
+            // without the keyfile, ssh tries the identities in the agent
+            if let SshAuth::KeyFile(privkey_path) = &options.auth {
+                builder.keyfile(privkey_path);
+            }
+            if let Some(jump) = &options.jump {
+                builder.jump_hosts([jump.host.as_str()]);
+            }
+            let session = builder.connect(&destination).await;
             match session {
-                Ok(session) => break session,
+                Ok(session) => {
+                    if let Some(control_dir) = &options.control_dir {
+                        let state = MasterState { destination: MasterState::destination(ip, port, options), ctl: session.control_socket().to_path_buf() };
+                        state.save(control_dir).await?;
+                    }
+                    break session;
+                },
+                // retrying never succeeds, the server may be replaced or spoofed
+                Err(e) if is_host_key_mismatch(&e) => {
+                    return Err(Error::HostKeyMismatch(destination, options.known_hosts_path.display().to_string()));
+                },
                 Err(e) => {
-                    log::trace!("[SSH] couldn't connect to server: {} {} {} {}", ip.to_string(), port, user, pubkey_path.display());
+                    log::trace!("[SSH] couldn't connect to server: {} {} {} {:?}", destination, port, user, options.auth);
                     log::trace!("[SSH] error: {}", e);
                     if start_time.elapsed() > Duration::from_secs(60 * 5) {
                         return Err(e.into());
@@ -105,7 +485,8 @@ impl Session {
         Ok(session)
     }
 
-    pub(crate) async fn sync_remote_dir(&self, remote_dir_path: impl AsRef<Path>, local_dir_path: impl AsRef<Path>) -> Result<(), Error> {
+    /// downloads the files of the remote dir which pass the options to the local dir
+    pub async fn sync_remote_dir(&self, remote_dir_path: impl AsRef<Path>, local_dir_path: impl AsRef<Path>, options: &SyncOptions) -> Result<(), Error> {
         let remote_dir_path = remote_dir_path.as_ref();
         let local_dir_path = local_dir_path.as_ref();
         log::trace!("[SSH] syncing remote file...: {} -> {}", remote_dir_path.display(), local_dir_path.display());
@@ -116,7 +497,9 @@ impl Session {
         let dir_entry_stream = remote_dir.read_dir();
         let mut dir_entry_stream = Box::pin(dir_entry_stream);
 
+        let filter = SyncFilter::new(options)?;
         log::trace!("[SSH] iterating remote dir...: {}", remote_dir_path.display());
+        let mut filenames = Vec::new();
         while let Some(entry) = dir_entry_stream.next().await {
             let entry = entry?;
             let filename = entry.filename();
@@ -124,58 +507,194 @@ impl Session {
                 log::trace!("[SSH] skipping special file...: {}", filename.display());
                 continue;
             }
-            let local_path = local_dir_path.join(filename);
-            let remote_path = remote_dir_path.join(filename);
-
-            log::info!("[SSH] syncing remote file...: {} -> {}", remote_path.display(), local_path.display());
-            let mut remote_file = self.sftp.open(&remote_path).await?;
-            let mut local_file = File::create(&local_path).await?;
-            let chunk_size = 4096usize;
-            let mut buf = BytesMut::with_capacity(chunk_size);
-            let mut total = 0;
-            let mut last_log_time = Instant::now();
-            loop {
-                let maybe_buf = remote_file.read(chunk_size as u32, buf).await.map_err(|e| Error::CouldntReadRemoteFile(e.to_string()))?;
-                let Some(b) = maybe_buf else {
-                    break;
-                };
-                buf = b;
-
-                // if my understanding is correct, the below assertions should never fail
-                assert!(buf.len() <= chunk_size);
-                assert!(buf.capacity() == chunk_size);
-
-                total += buf.len();
-
-                local_file.write_all(&buf).await.map_err(|e| Error::CouldntWriteLocalFile(e.to_string()))?;
-                if last_log_time.elapsed() > Duration::from_secs(5) {
-                    log::trace!("[SSH] downloaded {} {} bytes", remote_path.display(), total);
-                    last_log_time = Instant::now();
-                }
-
-                buf.truncate(0); // reset cursor to first bytes, but not causes allocation
-                assert!(buf.len() <= 0);
-                assert!(buf.capacity() == chunk_size);
+            // leftovers of an interrupted sync with the same local dir as the remote dir
+            if is_partial_download_path(filename) {
+                log::trace!("[SSH] skipping partial download...: {}", filename.display());
+                continue;
             }
-            local_file.flush().await.map_err(|e| Error::CouldntWriteLocalFile(e.to_string()))?;
+            if !filter.matches(&filename.to_string_lossy()) {
+                log::trace!("[SSH] skipping filtered file...: {}", filename.display());
+                continue;
+            }
+            filenames.push(filename.to_path_buf());
+        }
+
+        let downloads = filenames.iter().map(|filename| self.download_file(remote_dir_path.join(filename), local_dir_path.join(filename), options.verify_sha256));
+        let mut downloads = futures::stream::iter(downloads).buffer_unordered(options.parallelism.max(1));
+        while let Some(result) = downloads.next().await {
+            result?;
         }
         log::info!("[SSH] done syncing remote dir: {} -> {}", remote_dir_path.display(), local_dir_path.display());
 
         Ok(())
     }
 
-    pub(crate) async fn forward_remote_port(&self, remote_port: u16, local_port: u16) -> Result<(), Error> {
-        log::trace!("[SSH] forwarding remote port...: {} -> {}", remote_port, local_port);
+    // downloads into a .part file next to the local path, which is renamed into place once it's verified
+    // keeps several reads in flight, each chunk is written at its own offset as it arrives
+    async fn download_file(&self, remote_path: PathBuf, local_path: PathBuf, verify_sha256: bool) -> Result<(), Error> {
+        log::info!("[SSH] syncing remote file...: {} -> {}", remote_path.display(), local_path.display());
+        let mut remote_file = self.sftp.open(&remote_path).await?;
+        let metadata = remote_file.metadata().await?;
+        let size = metadata.len().ok_or(Error::CouldntGetRemoteFileSize)?;
+        let modified = metadata.modified().map(|modified| modified.into_raw());
+
+        let part_path = partial_download_path(&local_path, PART_SUFFIX);
+        let state_path = partial_download_path(&local_path, PART_STATE_SUFFIX);
+        let resume_offset = match PartialDownload::load(&state_path).await {
+            Some(state) if state.size == size && state.modified == modified && tokio::fs::try_exists(&part_path).await? => state.offset,
+            _ => 0,
+        };
+        let mut local_file = if 0 < resume_offset {
+            log::info!("[SSH] resuming download from {} bytes: {}", resume_offset, remote_path.display());
+            OpenOptions::new().write(true).open(&part_path).await?
+        } else {
+            File::create(&part_path).await?
+        };
+        let mut chunk_size = READ_CHUNK_SIZE;
+
+        let read_chunk = |offset: u64, len: u64| {
+            let mut remote_file = remote_file.clone();
+            async move {
+                remote_file.seek(SeekFrom::Start(offset)).await.map_err(|e| Error::CouldntReadRemoteFile(e.to_string()))?;
+                let buf = remote_file.read(len as u32, BytesMut::with_capacity(len as usize)).await.map_err(|e| Error::CouldntReadRemoteFile(e.to_string()))?;
+                Ok::<_, Error>((offset, len, buf))
+            }
+        };
+
+        let mut reads = FuturesUnordered::new();
+        // start offsets of the reads in flight, everything before the first one is written
+        let mut in_flight = BTreeSet::new();
+        let mut next_offset = resume_offset;
+        let mut end = resume_offset;
+        let mut last_save_time = Instant::now();
+        loop {
+            while reads.len() < PIPELINED_READS && next_offset < size {
+                let len = chunk_size.min(size - next_offset);
+                reads.push(read_chunk(next_offset, len));
+                in_flight.insert(next_offset);
+                next_offset += len;
+            }
+            let Some(result) = reads.next().await else {
+                break;
+            };
+            let (offset, len, buf) = result?;
+            in_flight.remove(&offset);
+            // the file shrank while downloading, nothing is left from here
+            let Some(buf) = buf else {
+                continue;
+            };
+
+            local_file.seek(SeekFrom::Start(offset)).await.map_err(|e| Error::CouldntWriteLocalFile(e.to_string()))?;
+            local_file.write_all(&buf).await.map_err(|e| Error::CouldntWriteLocalFile(e.to_string()))?;
+            let read_len = buf.len() as u64;
+            end = end.max(offset + read_len);
+
+            // the server may return less than requested, the rest is read again and the later reads fit its limit
+            if 0 < read_len && read_len < len {
+                reads.push(read_chunk(offset + read_len, len - read_len));
+                in_flight.insert(offset + read_len);
+                chunk_size = chunk_size.min(read_len);
+            }
+
+            if last_save_time.elapsed() > Duration::from_secs(5) {
+                let written = in_flight.first().copied().unwrap_or(next_offset).min(size);
+                log::trace!("[SSH] downloaded {} {}/{} bytes", remote_path.display(), written, size);
+                // the state must not claim more than what is on the disk
+                local_file.sync_data().await.map_err(|e| Error::CouldntWriteLocalFile(e.to_string()))?;
+                PartialDownload { size, modified, offset: written }.save(&state_path).await?;
+                last_save_time = Instant::now();
+            }
+        }
+        local_file.set_len(end).await.map_err(|e| Error::CouldntWriteLocalFile(e.to_string()))?;
+        local_file.flush().await.map_err(|e| Error::CouldntWriteLocalFile(e.to_string()))?;
+        drop(local_file);
+
+        // a changed remote file can't be resumed, so the next run starts over
+        let result = self.verify_download(&remote_path, &part_path, size, verify_sha256).await;
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&state_path).await;
+            let _ = tokio::fs::remove_file(&part_path).await;
+            return result;
+        }
+        tokio::fs::rename(&part_path, &local_path).await?;
+        let _ = tokio::fs::remove_file(&state_path).await;
+        Ok(())
+    }
+
+    async fn verify_download(&self, remote_path: &Path, part_path: &Path, size: u64, verify_sha256: bool) -> Result<(), Error> {
+        let local_size = tokio::fs::metadata(part_path).await?.len();
+        if local_size != size {
+            return Err(Error::TransferSizeMismatch(remote_path.display().to_string(), size, local_size));
+        }
+        if !verify_sha256 {
+            return Ok(());
+        }
+        log::trace!("[SSH] verifying sha256...: {}", remote_path.display());
+        let remote_sha256 = self.remote_sha256(remote_path).await?;
+        let local_sha256 = local_sha256(part_path).await?;
+        if local_sha256 != remote_sha256 {
+            return Err(Error::TransferChecksumMismatch(remote_path.display().to_string(), remote_sha256, local_sha256));
+        }
+        Ok(())
+    }
+
+    async fn remote_sha256(&self, remote_path: &Path) -> Result<String, Error> {
+        let command = format!("sha256sum {}", escape(remote_path.to_string_lossy()));
+        let output = self.session.raw_command(&command).output().await?;
+        if !output.status.success() {
+            return Err(Error::RemoteCommandFailed(command, String::from_utf8_lossy(&output.stderr).trim().to_string()));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.split_whitespace().next().unwrap_or_default().to_string())
+    }
+
+    /// fails if the master connection is gone, e.g. after the laptop sleeps
+    pub async fn check(&self) -> Result<(), Error> {
+        self.session.check().await?;
+        Ok(())
+    }
+
+    /// forwards the remote host and port to the local bind address and port
+    pub async fn forward_remote_port(&self, remote_host: &str, remote_port: u16, bind_address: &str, local_port: u16) -> Result<(), Error> {
+        log::trace!("[SSH] forwarding remote port...: {}:{} -> {}:{}", remote_host, remote_port, bind_address, local_port);
         self.session.request_port_forward(
             ForwardType::Local,
-            Socket::new("127.0.0.1", local_port),
+            Socket::new(bind_address, local_port),
+            Socket::new(remote_host, remote_port),
+        ).await?;
+        log::trace!("[SSH] forwarded remote port: {}:{} -> {}:{}", remote_host, remote_port, bind_address, local_port);
+        Ok(())
+    }
+
+    /// forwards the remote port on the loopback of the server to the local port on the loopback
+    pub async fn forward_local_port(&self, local_port: u16, remote_port: u16) -> Result<(), Error> {
+        log::trace!("[SSH] forwarding local port...: {} -> {}", local_port, remote_port);
+        self.session.request_port_forward(
+            ForwardType::Remote,
             Socket::new("127.0.0.1", remote_port),
+            Socket::new("127.0.0.1", local_port),
         ).await?;
-        log::trace!("[SSH] forwarded remote port: {} -> {}", remote_port, local_port);
+        log::trace!("[SSH] forwarded local port: {} -> {}", local_port, remote_port);
+        Ok(())
+    }
+
+    /// openssh doesn't support dynamic forwarding, so it's requested to the master through the control socket
+    pub async fn forward_dynamic(&self, local_port: u16) -> Result<(), Error> {
+        log::trace!("[SSH] opening socks proxy...: {}", local_port);
+        let output = Command::new("ssh")
+            .arg("-S").arg(self.session.control_socket())
+            .args(["-O", "forward", "-D", &format!("127.0.0.1:{}", local_port), "none"])
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(Error::RemoteCommandFailed("ssh -O forward -D".to_string(), String::from_utf8_lossy(&output.stderr).trim().to_string()));
+        }
+        log::trace!("[SSH] opened socks proxy: {}", local_port);
         Ok(())
     }
 
-    pub(crate) async fn put_file(&self, remote_path: impl AsRef<Path>, data: impl AsyncRead + Unpin) -> Result<(), Error> {
+    /// writes the data to the remote path
+    pub async fn put_file(&self, remote_path: impl AsRef<Path>, data: impl AsyncRead + Unpin) -> Result<(), Error> {
         let remote_path = remote_path.as_ref();
         log::trace!("[SSH] putting file...: {}", remote_path.display());
         let mut remote_file = self.sftp.create(remote_path).await?;
@@ -184,6 +703,8 @@ impl Session {
         log::trace!("[SSH] copying data to remote file...");
         let mut buf = [0; 4096];
         let mut data = BufReader::new(data);
+        let mut size = 0u64;
+        let mut hasher = Sha256::new();
         loop {
             log::trace!("[SSH] reading data...");
             let n = data.read(&mut buf[..]).await?;
@@ -196,16 +717,32 @@ impl Session {
             log::trace!("[SSH] writing data...");
             remote_file.write_all(&buf[..n]).await?;
             log::trace!("[SSH] wrote {} bytes", n);
+            size += n as u64;
+            hasher.update(&buf[..n]);
         };
         log::trace!("[SSH] syncing remote file...");
         remote_file.sync_all().await?;
         log::trace!("[SSH] done syncing remote file");
+        remote_file.close().await?;
+
+        // a dropped session may leave the remote file truncated without any error
+        log::trace!("[SSH] verifying remote file...: {}", remote_path.display());
+        let remote_size = self.sftp.fs().metadata(remote_path).await?.len().ok_or(Error::CouldntGetRemoteFileSize)?;
+        if remote_size != size {
+            return Err(Error::TransferSizeMismatch(remote_path.display().to_string(), size, remote_size));
+        }
+        let local_sha256 = hex::encode(hasher.finalize());
+        let remote_sha256 = self.remote_sha256(remote_path).await?;
+        if remote_sha256 != local_sha256 {
+            return Err(Error::TransferChecksumMismatch(remote_path.display().to_string(), local_sha256, remote_sha256));
+        }
 
         log::trace!("[SSH] put file: {}", remote_path.display());
         Ok(())
     }
 
-    pub(crate) async fn file_exists(&self, remote_path: impl AsRef<Path>) -> Result<bool, Error> {
+    /// whether the remote path exists, an error if it exists but is not a file
+    pub async fn file_exists(&self, remote_path: impl AsRef<Path>) -> Result<bool, Error> {
         let remote_path = remote_path.as_ref();
         log::trace!("[SSH] checking file exists...: {}", remote_path.display());
         let metadata = match self.sftp.fs().metadata(remote_path).await {
@@ -230,7 +767,21 @@ impl Session {
         Ok(true)
     }
 
-    pub(crate) async fn process_exists(&self, process_name: &str) -> Result<bool, Error> {
+    /// none if the file doesn't exist
+    pub async fn remote_modified_at(&self, remote_path: impl AsRef<Path>) -> Result<Option<SystemTime>, Error> {
+        let remote_path = remote_path.as_ref();
+        log::trace!("[SSH] getting modified time...: {}", remote_path.display());
+        let metadata = match self.sftp.fs().metadata(remote_path).await {
+            Ok(metadata) => metadata,
+            Err(openssh_sftp_client::Error::IOError(e)) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(openssh_sftp_client::Error::SftpError(SftpErrorKind::NoSuchFile, _)) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(metadata.modified().map(|modified| modified.as_system_time()))
+    }
+
+    /// whether the command of a process in ps auwx contains process_name
+    pub async fn process_exists(&self, process_name: &str) -> Result<bool, Error> {
         log::trace!("[SSH] checking process exists...: {}", process_name);
         // example for showing executing command and parsing output
 
@@ -271,15 +822,82 @@ impl Session {
         Ok(found)
     }
 
-    pub(crate) async fn close(self) -> Result<(), Error> {
+    /// gzipped tar of the remote dir is streamed from the stdout
+    pub async fn tar_remote_dir(&self, remote_dir_path: impl AsRef<Path>) -> Result<RemoteOutput<'_>, Error> {
+        let remote_dir_path = remote_dir_path.as_ref();
+        log::trace!("[SSH] starting tar...: {}", remote_dir_path.display());
+        let command = format!("tar czf - -C {} .", escape(remote_dir_path.to_string_lossy()));
+        let child = self.session.raw_command(&command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .await?;
+        Ok(RemoteOutput { command, child })
+    }
+
+    /// follows the files even if they are truncated or recreated, the lines are streamed from the stdout
+    pub async fn follow_remote_files(&self, remote_paths: &[&str]) -> Result<RemoteOutput<'_>, Error> {
+        log::trace!("[SSH] following remote files...: {:?}", remote_paths);
+        let command = format!("tail -n +1 -F {}", remote_paths.iter().map(|path| escape((*path).into()).to_string()).collect::<Vec<_>>().join(" "));
+        let child = self.session.raw_command(&command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .await?;
+        Ok(RemoteOutput { command, child })
+    }
+
+    /// the args are escaped, the stderr goes to the local stderr
+    pub async fn stream_command(&self, args: &[&str]) -> Result<RemoteOutput<'_>, Error> {
+        let command = args.iter().map(|arg| escape((*arg).into()).to_string()).collect::<Vec<_>>().join(" ");
+        log::trace!("[SSH] starting command...: {}", command);
+        let child = self.session.raw_command(&command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .await?;
+        Ok(RemoteOutput { command, child })
+    }
+
+    /// reads the whole remote file
+    pub async fn read_remote_file(&self, remote_path: impl AsRef<Path>) -> Result<Vec<u8>, Error> {
+        let remote_path = remote_path.as_ref();
+        log::trace!("[SSH] reading remote file...: {}", remote_path.display());
+        let content = self.sftp.fs().read(remote_path).await.map_err(|e| Error::CouldntReadRemoteFile(e.to_string()))?;
+        Ok(content.to_vec())
+    }
+
+    /// closes the sftp subsystem and the session, leaving the master running if it's kept
+    pub async fn close(self) -> Result<(), Error> {
         log::trace!("[SSH] closing session...");
-        self.session.close().await?;
+        // the sftp subsystem releases its clone of the session when it exits
         self.sftp.close().await?;
+        match Arc::try_unwrap(self.session) {
+            // the master exits by itself after idling
+            Ok(session) if self.keeps_master => {
+                session.detach();
+            },
+            Ok(session) => session.close().await?,
+            // dropping the last clone closes the master connection anyway
+            Err(_) => log::trace!("[SSH] session is still shared, leaving it to be dropped"),
+        }
         log::trace!("[SSH] closed session");
         Ok(())
     }
 }
 
+// openssh only gives the stderr of ssh as the connect error
+fn is_host_key_mismatch(e: &openssh::Error) -> bool {
+    let openssh::Error::Connect(e) = e else {
+        return false;
+    };
+    let message = e.to_string();
+    message.contains("REMOTE HOST IDENTIFICATION HAS CHANGED") || message.contains("Host key verification failed")
+}
+
 async fn wait_for_ssh_connectable(ip: Ipv4Addr, port: u16) -> Result<(), Error> {
     
     // lightweight ssh connection check than connect
@@ -303,5 +921,103 @@ async fn wait_for_ssh_connectable(ip: Ipv4Addr, port: u16) -> Result<(), Error>
     }
 }
 
+// the type and the base64 of a public key line, the options before them and the comment after them are ignored
+fn public_key_material(line: &str) -> Option<(&str, &str)> {
+    let mut fields = line.split_whitespace().skip_while(|field| !(field.starts_with("ssh-") || field.starts_with("ecdsa-") || field.starts_with("sk-")));
+    Some((fields.next()?, fields.next()?))
+}
 
+/// authorized_keys content with the key appended unless it's already there
+pub fn add_authorized_key(content: &str, public_key: &str) -> String {
+    let public_key = public_key.trim();
+    let material = public_key_material(public_key);
+    if material.is_some() && content.lines().any(|line| public_key_material(line) == material) {
+        return content.to_string();
+    }
+    let mut content = content.to_string();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(public_key);
+    content.push('\n');
+    content
+}
 
+/// authorized_keys content without the lines of the same key, unchanged if the key can't be parsed
+pub fn remove_authorized_key(content: &str, public_key: &str) -> String {
+    let material = public_key_material(public_key);
+    content.lines()
+        .filter(|line| material.is_none() || public_key_material(line) != material)
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// a remote process whose stdout is streamed
+pub struct RemoteOutput<'s> {
+    command: String,
+    child: RemoteChild<'s>,
+}
+
+impl<'s> RemoteOutput<'s> {
+    /// takes the stdout of the process, an error after the first call
+    pub fn stdout(&mut self) -> Result<ChildStdout, Error> {
+        self.child.stdout().take().ok_or(Error::CouldntTakeRemoteProcessStdout)
+    }
+
+    /// waits for the process, an error unless it exits with success
+    pub async fn wait(self) -> Result<(), Error> {
+        let status = self.child.wait().await?;
+        if !status.success() {
+            return Err(Error::RemoteCommandFailed(self.command, status.to_string()));
+        }
+        log::trace!("[SSH] done: {}", self.command);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authorized_keys() {
+        let content = "ssh-ed25519 AAAAold old@example.com\nfrom=\"10.0.0.0/8\" ssh-rsa AAAAteam team@example.com";
+        let added = add_authorized_key(content, "ssh-ed25519 AAAAnew new@example.com\n");
+        assert_eq!(added, "ssh-ed25519 AAAAold old@example.com\nfrom=\"10.0.0.0/8\" ssh-rsa AAAAteam team@example.com\nssh-ed25519 AAAAnew new@example.com\n");
+        assert_eq!(add_authorized_key(&added, "ssh-ed25519 AAAAnew another comment"), added);
+        assert_eq!(add_authorized_key("", "ssh-ed25519 AAAAnew"), "ssh-ed25519 AAAAnew\n");
+
+        let removed = remove_authorized_key(&added, "ssh-ed25519 AAAAold");
+        assert_eq!(removed, "from=\"10.0.0.0/8\" ssh-rsa AAAAteam team@example.com\nssh-ed25519 AAAAnew new@example.com\n");
+        assert_eq!(remove_authorized_key(&removed, "ssh-rsa AAAAteam"), "ssh-ed25519 AAAAnew new@example.com\n");
+    }
+
+    #[test]
+    fn sync_filter() {
+        let glob = Glob::new("*.tar.gz").unwrap();
+        assert!(glob.matches("result.tar.gz"));
+        assert!(!glob.matches("result.tar.gz.bak"));
+        assert!(!glob.matches("resultXtarXgz"));
+
+        let glob = Glob::new("log-[0-9]?.[!t]*").unwrap();
+        assert!(glob.matches("log-1a.csv"));
+        assert!(!glob.matches("log-1a.txt"));
+        assert!(!glob.matches("log-xa.csv"));
+        assert!(Glob::new("log-[0-9").is_err());
+
+        let options = SyncOptions { parallelism: 1, verify_sha256: false, include: vec!["*.tar.gz".to_string(), "*.log".to_string()], exclude: vec!["debug*".to_string()] };
+        let filter = SyncFilter::new(&options).unwrap();
+        assert!(filter.matches("result.tar.gz"));
+        assert!(filter.matches("run.log"));
+        assert!(!filter.matches("debug.log"));
+        assert!(!filter.matches("result.csv"));
+
+        // the temporary files are excluded by default
+        let options = SyncOptions { parallelism: 1, verify_sha256: false, include: vec![], exclude: vec![] };
+        let filter = SyncFilter::new(&options).unwrap();
+        assert!(filter.matches("result.csv"));
+        assert!(!filter.matches("result.csv.tmp"));
+        assert!(!filter.matches(".result.csv.swp"));
+        assert!(!filter.matches("result.csv~"));
+    }
+}